@@ -0,0 +1,9 @@
+-- NOTE(alex): One row for each login, a bearer token is only good while its row is here (`logout`
+-- deletes it), so a token can't be made up, or outlive the session it was handed out for.
+create table if not exists Session (
+    id integer primary key,
+    user_id int not null,
+    token text not null unique,
+    created_at int not null,
+    foreign key (user_id) references User(id) on delete cascade
+);
@@ -1,26 +1,34 @@
 use actix_identity::{CookieIdentityPolicy, IdentityService, RequestIdentity};
 use actix_session::CookieSession;
 use actix_web::{
-    dev::ServiceRequest, error::ErrorUnauthorized, get, middleware, App, Error, HttpResponse,
-    HttpServer, Responder,
+    dev::ServiceRequest,
+    error::{ErrorForbidden, ErrorUnauthorized},
+    get, middleware, web, App, Error, HttpMessage, HttpResponse, HttpServer, Responder,
 };
 use actix_web_httpauth::extractors::{basic::Config, bearer::BearerAuth};
 use errors::AppError;
 use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
 use tasks::routes::task_service;
 use time::Duration;
-use users::{models::LoggedUser, routes::user_service};
+use users::{
+    models::{InsertUser, LoggedUser, User},
+    routes::user_service,
+};
+use validation::ValidationConfig;
 
 use crate::users::errors::UserError;
 
 mod errors;
+mod migrations;
 mod tasks;
 mod users;
+mod validation;
 
 const WELCOME_MSG: &'static str = include_str!("./../strings/welcome.txt");
-
-/// WARNING(alex): This query drops every table before creating them.
-const CREATE_DATABASE: &'static str = include_str!("./../queries/create_database.sql");
+const COOKIE_KEY_VAR: &'static str = "AUTHORIZATION_COOKIE_KEY";
+/// NOTE(alex): The cookie middlewares derive their keys from it, and refuse anything shorter.
+const MIN_COOKIE_KEY_LENGTH: usize = 32;
+const ADMIN_PASSWORD_VAR: &'static str = "AUTHORIZATION_ADMIN_PASSWORD";
 
 #[get("/")]
 async fn index() -> Result<impl Responder, AppError> {
@@ -30,16 +38,27 @@ async fn index() -> Result<impl Responder, AppError> {
     Ok(response)
 }
 
-/// NOTE(alex): This function should be part of some setup script, it's here for convenience. It
-/// could be moved to the `build.rs`, by adding `sqlx` and `tokio` as `dev-dependencies`:
-async fn create_database(db_pool: &SqlitePool) -> Result<String, AppError> {
-    let mut connection = db_pool.acquire().await?;
+/// Opens the database, creating the file if it doesn't exist yet, and brings its schema up to date.
+///
+/// NOTE(alex): This refuses to work with a database that was migrated by a newer binary.
+async fn setup_database() -> Result<SqlitePool, AppError> {
+    let db_options = sqlx::sqlite::SqliteConnectOptions::new()
+        .filename(env!("DATABASE_FILE"))
+        .create_if_missing(true);
 
-    let result = sqlx::query(CREATE_DATABASE)
-        .execute(&mut connection)
+    let database_pool = SqlitePoolOptions::new()
+        .max_connections(5)
+        .connect_with(db_options)
         .await?;
 
-    Ok(result.rows_affected().to_string())
+    let num_applied = migrations::run(&database_pool).await?;
+    log::info!(
+        "Database is at version {}, applied {} migrations.",
+        migrations::latest_version(),
+        num_applied
+    );
+
+    Ok(database_pool)
 }
 
 pub(crate) async fn validator(
@@ -49,51 +68,149 @@ pub(crate) async fn validator(
     if let Some(identity) = req.get_identity() {
         let logged_user: LoggedUser = serde_json::from_str(&identity)?;
 
-        // NOTE(alex) Return `Ok(request)` if the token match our logged user's token, otherwise it
-        // returns an `Err`.
-        (credentials.token() == logged_user.token.to_string())
-            .then(|| req)
-            .ok_or(ErrorUnauthorized(UserError::InvalidToken))
+        // NOTE(alex) Return `Ok(request)` if the token match our logged user's token, and its
+        // session is still there, otherwise it returns an `Err`. The `LoggedUser` is put into the
+        // request extensions, so routes may extract it to know who is making the request.
+        if credentials.token() != logged_user.token {
+            return Err(ErrorUnauthorized(UserError::InvalidToken));
+        }
+
+        let has_session = match req.app_data::<web::Data<SqlitePool>>() {
+            Some(db_pool) => logged_user
+                .has_session(db_pool.get_ref())
+                .await
+                .map_err(Error::from)?,
+            None => false,
+        };
+
+        if has_session {
+            req.extensions_mut().insert(logged_user);
+            Ok(req)
+        } else {
+            Err(ErrorUnauthorized(UserError::InvalidToken))
+        }
     } else {
         Err(ErrorUnauthorized(UserError::NotLoggedIn))
     }
 }
 
+/// Same as `validator`, but only lets an admin through.
+///
+/// NOTE(alex): Use it as `HttpAuthentication::bearer(admin_validator)` on routes that act on
+/// everyone's data, such as deleting or listing users.
+pub(crate) async fn admin_validator(
+    req: ServiceRequest,
+    credentials: BearerAuth,
+) -> Result<ServiceRequest, Error> {
+    let req = validator(req, credentials).await?;
+
+    // NOTE(alex): The role is not in the identity cookie, so it can't outlive a demotion.
+    let user_id = req
+        .extensions()
+        .get::<LoggedUser>()
+        .map(|logged_user| logged_user.id);
+    let db_pool = req.app_data::<web::Data<SqlitePool>>().cloned();
+
+    let is_admin = match (user_id, db_pool) {
+        (Some(user_id), Some(db_pool)) => User::is_admin(db_pool.get_ref(), user_id)
+            .await
+            .map_err(Error::from)?,
+        _ => false,
+    };
+
+    if is_admin {
+        Ok(req)
+    } else {
+        Err(ErrorForbidden(UserError::Forbidden))
+    }
+}
+
+/// Reads the key of the identity, and session, cookies from `COOKIE_KEY_VAR`.
+///
+/// WARNING(alex): Whoever knows this key can make up an identity cookie for any user, so there is no
+/// default, the server refuses to start without one.
+fn cookie_key() -> std::io::Result<Vec<u8>> {
+    match std::env::var(COOKIE_KEY_VAR) {
+        Ok(key) if key.len() >= MIN_COOKIE_KEY_LENGTH => Ok(key.into_bytes()),
+        Ok(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "`{}` must be at least {} bytes long!",
+                COOKIE_KEY_VAR, MIN_COOKIE_KEY_LENGTH
+            ),
+        )),
+        Err(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("`{}` is not set, refusing to start!", COOKIE_KEY_VAR),
+        )),
+    }
+}
+
+/// `--create-admin <username>`, with the password in `ADMIN_PASSWORD_VAR`, so it doesn't end up in
+/// the shell history, or the process list.
+async fn create_admin(db_pool: &SqlitePool, username: Option<String>) -> std::io::Result<User> {
+    match (username, std::env::var(ADMIN_PASSWORD_VAR)) {
+        (Some(valid_username), Ok(valid_password)) => InsertUser {
+            valid_username,
+            valid_password,
+        }
+        .insert_admin(db_pool)
+        .await
+        .map_err(to_io_error),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "`--create-admin <username>` reads the password from `{}`!",
+                ADMIN_PASSWORD_VAR
+            ),
+        )),
+    }
+}
+
+fn to_io_error(fail: AppError) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::Other, fail.to_string())
+}
+
 #[actix_web::main]
 pub async fn main() -> std::io::Result<()> {
     env_logger::init();
 
-    let db_options = sqlx::sqlite::SqliteConnectOptions::new()
-        .filename(env!("DATABASE_FILE"))
-        .create_if_missing(true);
+    let database_pool = setup_database().await.map_err(to_io_error)?;
 
-    let database_pool = SqlitePoolOptions::new()
-        .max_connections(5)
-        .connect_with(db_options)
-        .await
-        .unwrap();
+    // NOTE(alex): Brings the database schema up to date, without starting the server.
+    if std::env::args().any(|arg| arg == "--migrate-only") {
+        return Ok(());
+    }
+
+    // NOTE(alex): The only way of getting an admin, see `InsertUser::insert_admin`.
+    let mut args = std::env::args().skip_while(|arg| arg != "--create-admin");
+    if args.next().is_some() {
+        let admin = create_admin(&database_pool, args.next()).await?;
+        log::info!("Created admin `{}` with id `{}`.", admin.username, admin.id);
 
-    if option_env!("NEW_DATABASE").is_some() {
-        create_database(&database_pool).await.unwrap();
+        return Ok(());
     }
 
+    let cookie_key = cookie_key()?;
     let data = actix_web::web::Data::new(database_pool);
+    let validation_config = actix_web::web::Data::new(ValidationConfig::default());
 
     HttpServer::new(move || {
         App::new()
             .app_data(data.clone())
+            .app_data(validation_config.clone())
             .app_data(Config::default().realm("Restricted area, login first!"))
             .service(index)
             .configure(task_service)
             .configure(user_service)
             .wrap(IdentityService::new(
-                CookieIdentityPolicy::new(&[0; 32])
+                CookieIdentityPolicy::new(&cookie_key)
                     .name("auth-cookie")
                     .login_deadline(Duration::seconds(120))
                     .secure(false),
             ))
             .wrap(
-                CookieSession::signed(&[0; 32])
+                CookieSession::signed(&cookie_key)
                     .name("session-cookie")
                     .secure(false)
                     // WARNING(alex): This uses the `time` crate, not `std::time`!
@@ -0,0 +1,25 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub(crate) enum MigrationError {
+    #[error(
+        "Database schema is at version `{database_version}`, but this binary only knows up to \
+        version `{binary_version}`!"
+    )]
+    SchemaTooNew {
+        database_version: i64,
+        binary_version: i64,
+    },
+
+    #[error("Migration `{version}` failed with `{reason}`!")]
+    Failed { version: i64, reason: String },
+
+    #[error(
+        "Database was created before migrations existed, `{table}` has no `{column}` column, \
+        start from a new database!"
+    )]
+    LegacySchema {
+        table: &'static str,
+        column: &'static str,
+    },
+}
@@ -0,0 +1,2 @@
+insert into _migrations (version, name, applied_at)
+values ($1, $2, $3);
\ No newline at end of file
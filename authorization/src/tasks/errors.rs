@@ -5,6 +5,12 @@ pub(crate) enum TaskError {
     #[error("`title` field of `Task` cannot be empty!")]
     EmptyTitle,
 
+    #[error("`title` field of `Task` cannot be longer than {0} characters!")]
+    TitleLength(usize),
+
+    #[error("`details` field of `Task` cannot be longer than {0} characters!")]
+    DetailsLength(usize),
+
     #[error("Could not find any `Task` for id: `{0}`!")]
     NotFound(i64),
 
@@ -14,3 +20,14 @@ pub(crate) enum TaskError {
     #[error("Could not find any `Task`!")]
     Empty,
 }
+
+impl TaskError {
+    /// The field of the request at fault, for the validation errors.
+    pub(crate) fn field(&self) -> Option<&'static str> {
+        match self {
+            TaskError::EmptyTitle | TaskError::TitleLength(_) => Some("title"),
+            TaskError::DetailsLength(_) => Some("details"),
+            _ => None,
+        }
+    }
+}
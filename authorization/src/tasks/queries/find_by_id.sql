@@ -1,3 +1,4 @@
 select *
 from Task
-where Task.id = $1
\ No newline at end of file
+where
+    Task.id = $1 and Task.owner_id = $2
\ No newline at end of file
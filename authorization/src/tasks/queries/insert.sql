@@ -1,2 +1,2 @@
-insert into Task (title, details)
-values ($1, $2);
\ No newline at end of file
+insert into Task (title, details, owner_id)
+values ($1, $2, $3);
\ No newline at end of file
@@ -4,26 +4,31 @@ use actix_web_httpauth::middleware::HttpAuthentication;
 use sqlx::SqlitePool;
 
 use super::{errors::*, models::*};
-use crate::{errors::AppError, validator};
+use crate::{errors::AppError, users::models::LoggedUser, validation::Validated, validator};
 
 #[post("/tasks", wrap = "HttpAuthentication::bearer(validator)")]
 async fn insert(
     db_pool: web::Data<SqlitePool>,
-    input: InsertTask,
+    logged_user: LoggedUser,
+    Validated(input): Validated<InsertTask>,
 ) -> Result<impl Responder, AppError> {
-    let task = input.insert(db_pool.get_ref()).await?;
+    let task = input.insert(db_pool.get_ref(), logged_user.id).await?;
     Ok(HttpResponse::Created().json(task))
 }
 
 #[put("/tasks", wrap = "HttpAuthentication::bearer(validator)")]
 async fn update(
     db_pool: web::Data<SqlitePool>,
-    input: UpdateTask,
+    logged_user: LoggedUser,
+    Validated(input): Validated<UpdateTask>,
 ) -> Result<impl Responder, AppError> {
-    let num_modified = input.update(db_pool.get_ref()).await?;
+    let task_id = input.id;
+    let num_modified = input.update(db_pool.get_ref(), logged_user.id).await?;
 
+    // NOTE(alex): A `Task` that belongs to someone else is reported as not found, we don't want to
+    // leak which ids exist.
     if num_modified == 0 {
-        Ok(HttpResponse::NotModified().body("No tasks were updated."))
+        Err(TaskError::NotFound(task_id).into())
     } else {
         Ok(HttpResponse::Ok().body(format!("Updated {} tasks.", num_modified)))
     }
@@ -32,12 +37,13 @@ async fn update(
 #[delete("/tasks/{id}", wrap = "HttpAuthentication::bearer(validator)")]
 async fn delete(
     db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
     id: web::Path<i64>,
 ) -> Result<impl Responder, AppError> {
-    let num_modified = Task::delete(db_pool.get_ref(), *id).await?;
+    let num_modified = Task::delete(db_pool.get_ref(), *id, logged_user.id).await?;
 
     if num_modified == 0 {
-        Ok(HttpResponse::NotModified().body("No tasks were deleted."))
+        Err(TaskError::NotFound(*id).into())
     } else {
         Ok(HttpResponse::Ok().body(format!("Deleted {} tasks.", num_modified)))
     }
@@ -46,23 +52,31 @@ async fn delete(
 #[post("/tasks/{id}/done", wrap = "HttpAuthentication::bearer(validator)")]
 async fn done(
     db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
     id: web::Path<i64>,
 ) -> Result<impl Responder, AppError> {
-    let done_id = Task::done(db_pool.get_ref(), *id).await?;
+    let done_id = Task::done(db_pool.get_ref(), *id, logged_user.id).await?;
 
-    if done_id == 0 {
-        Ok(HttpResponse::NotModified().body(format!("Task with id {} not done.", id)))
-    } else {
-        Ok(HttpResponse::Created().body(done_id.to_string()))
+    match done_id {
+        Some(done_id) => Ok(HttpResponse::Created().body(done_id.to_string())),
+        None => Err(TaskError::NotFound(*id).into()),
     }
 }
 
 #[delete("/tasks/{id}/undo", wrap = "HttpAuthentication::bearer(validator)")]
 async fn undo(
     db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
     id: web::Path<i64>,
 ) -> Result<impl Responder, AppError> {
-    let num_modified = Task::undo(db_pool.get_ref(), *id).await?;
+    if Task::find_by_id(db_pool.get_ref(), *id, logged_user.id)
+        .await?
+        .is_none()
+    {
+        return Err(TaskError::NotFound(*id).into());
+    }
+
+    let num_modified = Task::undo(db_pool.get_ref(), *id, logged_user.id).await?;
 
     if num_modified == 0 {
         Ok(HttpResponse::NotModified().body(format!("Task with id {} not undone.", id)))
@@ -71,9 +85,12 @@ async fn undo(
     }
 }
 
-#[get("/tasks")]
-async fn find_all(db_pool: web::Data<SqlitePool>) -> Result<impl Responder, AppError> {
-    let tasks = Task::find_all(db_pool.get_ref()).await?;
+#[get("/tasks", wrap = "HttpAuthentication::bearer(validator)")]
+async fn find_all(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+) -> Result<impl Responder, AppError> {
+    let tasks = Task::find_all(db_pool.get_ref(), logged_user.id).await?;
 
     if tasks.is_empty() {
         Err(TaskError::Empty.into())
@@ -82,9 +99,12 @@ async fn find_all(db_pool: web::Data<SqlitePool>) -> Result<impl Responder, AppE
     }
 }
 
-#[get("/tasks/ongoing")]
-async fn find_ongoing(db_pool: web::Data<SqlitePool>) -> Result<impl Responder, AppError> {
-    let tasks = Task::find_ongoing(db_pool.get_ref()).await?;
+#[get("/tasks/ongoing", wrap = "HttpAuthentication::bearer(validator)")]
+async fn find_ongoing(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+) -> Result<impl Responder, AppError> {
+    let tasks = Task::find_ongoing(db_pool.get_ref(), logged_user.id).await?;
 
     if tasks.is_empty() {
         Err(TaskError::Empty.into())
@@ -93,12 +113,18 @@ async fn find_ongoing(db_pool: web::Data<SqlitePool>) -> Result<impl Responder,
     }
 }
 
-#[get("/tasks")]
+#[get("/tasks", wrap = "HttpAuthentication::bearer(validator)")]
 async fn find_by_pattern(
     db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
     pattern: web::Query<QueryTask>,
 ) -> Result<impl Responder, AppError> {
-    let tasks = Task::find_by_pattern(db_pool.get_ref(), &format!("%{}%", pattern.title)).await?;
+    let tasks = Task::find_by_pattern(
+        db_pool.get_ref(),
+        &format!("%{}%", pattern.title),
+        logged_user.id,
+    )
+    .await?;
 
     if tasks.is_empty() {
         Err(TaskError::Empty.into())
@@ -116,12 +142,13 @@ async fn find_by_pattern(
 // is the equivalent of the `[^/]+` regex.
 ///
 /// There is a 3rd way of sorts, which boils down to: avoid possible route conflicting paths.
-#[get("/tasks/{id:\\d+}")]
+#[get("/tasks/{id:\\d+}", wrap = "HttpAuthentication::bearer(validator)")]
 async fn find_by_id(
     db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
     id: web::Path<i64>,
 ) -> Result<impl Responder, AppError> {
-    let task = Task::find_by_id(db_pool.get_ref(), *id).await?;
+    let task = Task::find_by_id(db_pool.get_ref(), *id, logged_user.id).await?;
 
     match task {
         Some(task) => Ok(HttpResponse::Found().json(task)),
@@ -131,9 +158,10 @@ async fn find_by_id(
 
 const FAVORITE_TASK_STR: &'static str = "favorite_task";
 
-#[post("/tasks/favorite/{id}")]
+#[post("/tasks/favorite/{id}", wrap = "HttpAuthentication::bearer(validator)")]
 async fn favorite(
     db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
     session: Session,
     id: web::Path<i64>,
 ) -> Result<impl Responder, AppError> {
@@ -144,7 +172,7 @@ async fn favorite(
             // NOTE(alex): Just remove the task, this is basically "unfavorite".
             Ok(HttpResponse::NoContent().body(format!("Task {} unfavorited", old_favorite.id)))
         } else {
-            match Task::find_by_id(&db_pool, *id).await? {
+            match Task::find_by_id(&db_pool, *id, logged_user.id).await? {
                 Some(task) => {
                     session.insert(FAVORITE_TASK_STR, task.clone())?;
                     Ok(HttpResponse::Found().json(task))
@@ -153,7 +181,7 @@ async fn favorite(
             }
         }
     } else {
-        match Task::find_by_id(&db_pool, *id).await? {
+        match Task::find_by_id(&db_pool, *id, logged_user.id).await? {
             Some(task) => {
                 session.insert(FAVORITE_TASK_STR, task.clone())?;
                 Ok(HttpResponse::Found().json(task))
@@ -190,13 +218,24 @@ pub(crate) fn task_service(cfg: &mut web::ServiceConfig) {
 // `integration` project!
 #[cfg(test)]
 mod tests {
+    use std::str::FromStr;
+
     use actix_identity::{CookieIdentityPolicy, IdentityService};
-    use actix_web::{cookie::Cookie, http::StatusCode, test, web, web::ServiceConfig, App};
-    use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
+    use actix_web::{
+        cookie::Cookie,
+        http::{header, StatusCode},
+        test, web,
+        web::ServiceConfig,
+        App,
+    };
+    use sqlx::{
+        sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+        Pool, Sqlite,
+    };
     use time::Duration;
 
     use crate::{
-        create_database,
+        migrations,
         tasks::{
             models::{InsertTask, Task, UpdateTask},
             routes::{
@@ -213,9 +252,9 @@ mod tests {
     };
 
     pub async fn setup_data() -> web::Data<Pool<Sqlite>> {
-        let db_options = sqlx::sqlite::SqliteConnectOptions::new()
-            .filename(env!("DATABASE_FILE"))
-            .create_if_missing(true);
+        // NOTE(alex): Migrations never drop anything, so every test gets its own in-memory
+        // database. It only lives as long as its single connection does.
+        let db_options = SqliteConnectOptions::from_str("sqlite::memory:").unwrap();
 
         let database_pool = SqlitePoolOptions::new()
             .max_connections(1)
@@ -223,11 +262,54 @@ mod tests {
             .await
             .unwrap();
 
-        create_database(&database_pool).await.unwrap();
+        migrations::run(&database_pool).await.unwrap();
 
         web::Data::new(database_pool)
     }
 
+    // NOTE(alex): Registers a new `User`, and logs it in, returning the identity cookie and the
+    // bearer token that protected routes require.
+    macro_rules! register_and_login {
+        ($app: expr, $username: expr, $password: expr) => {{
+            let new_user = InsertUser {
+                valid_username: $username.to_string(),
+                valid_password: $password.to_string(),
+            };
+            let register_user_request = test::TestRequest::post()
+                .uri("/users/register")
+                .set_json(&new_user)
+                .to_request();
+            let register_user_service_response =
+                test::call_service(&mut $app, register_user_request).await;
+            assert!(register_user_service_response.status().is_success());
+
+            let user: User = test::read_body_json(register_user_service_response).await;
+
+            let login_user = LoginUser {
+                username: user.username,
+                password: new_user.valid_password,
+            };
+            let login_request = test::TestRequest::post()
+                .uri("/users/login")
+                .set_json(&login_user)
+                .to_request();
+            let login_service_response = test::call_service(&mut $app, login_request).await;
+            assert!(login_service_response.status().is_success());
+
+            let cookies = login_service_response.response().cookies();
+            let cookies_str = cookies
+                .flat_map(|cookie| cookie.to_string().chars().collect::<Vec<_>>())
+                .collect::<String>();
+
+            let logged_user: LoggedUser = test::read_body_json(login_service_response).await;
+
+            let bearer_token = format!("Bearer {}", logged_user.token);
+            let cookies = Cookie::parse_encoded(cookies_str).unwrap();
+
+            (cookies, bearer_token)
+        }};
+    }
+
     // WARNING(alex): This macro doesn't check if there is an user register already, or if some user
     // is logged in, so the tests must be run with:
     // cargo test -- --test-threads=1
@@ -258,44 +340,7 @@ mod tests {
                 );
             let mut app = test::init_service(app).await;
 
-            let (cookies, bearer_token) = {
-                let new_user = InsertUser {
-                    valid_username: "spike".to_string(),
-                    valid_password: "vicious".to_string(),
-                };
-                let register_user_request = test::TestRequest::post()
-                    .uri("/users/register")
-                    .set_json(&new_user)
-                    .to_request();
-                let register_user_service_response =
-                    test::call_service(&mut app, register_user_request).await;
-                assert!(register_user_service_response.status().is_success());
-
-                let user: User = test::read_body_json(register_user_service_response).await;
-
-                let login_user = LoginUser {
-                    username: user.username,
-                    password: user.password,
-                };
-                let login_request = test::TestRequest::post()
-                    .uri("/users/login")
-                    .set_json(&login_user)
-                    .to_request();
-                let login_service_response = test::call_service(&mut app, login_request).await;
-                assert!(login_service_response.status().is_success());
-
-                let cookies = login_service_response.response().cookies();
-                let cookies_str = cookies
-                    .flat_map(|cookie| cookie.to_string().chars().collect::<Vec<_>>())
-                    .collect::<String>();
-
-                let logged_user: LoggedUser = test::read_body_json(login_service_response).await;
-
-                let bearer_token = format!("Bearer {}", logged_user.token);
-                let cookies = Cookie::parse_encoded(cookies_str).unwrap();
-
-                (cookies, bearer_token)
-            };
+            let (cookies, bearer_token) = register_and_login!(app, "spike", "vicious");
 
             (app, bearer_token, cookies)
         }};
@@ -360,7 +405,7 @@ mod tests {
 
             let login_user = LoginUser {
                 username: user.username,
-                password: user.password,
+                password: new_user.valid_password,
             };
             let login_request = test::TestRequest::post()
                 .uri("/users/login")
@@ -422,6 +467,39 @@ mod tests {
         assert!(response.status().is_client_error());
     }
 
+    #[actix_rt::test]
+    pub async fn test_task_insert_form_body() {
+        let configure = |cfg: &mut ServiceConfig| {
+            cfg.service(task_insert);
+        };
+
+        let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+        let request = test::TestRequest::post()
+            .uri("/tasks")
+            .insert_header(("Authorization".to_string(), bearer_token.clone()))
+            .insert_header((header::CONTENT_TYPE, "application/x-www-form-urlencoded"))
+            .cookie(cookies.clone())
+            .set_payload("non_empty_title=Re-watch+Cowboy+Bebop&details=Good+show.")
+            .to_request();
+        let response = test::call_service(&mut app, request).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        // NOTE(alex): Over the default `max_body_bytes`.
+        let request = test::TestRequest::post()
+            .uri("/tasks")
+            .insert_header(("Authorization".to_string(), bearer_token))
+            .insert_header((header::CONTENT_TYPE, "application/x-www-form-urlencoded"))
+            .cookie(cookies)
+            .set_payload(format!(
+                "non_empty_title=Re-watch+Cowboy+Bebop&details={}",
+                "Good+show.".repeat(2000)
+            ))
+            .to_request();
+        let response = test::call_service(&mut app, request).await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
     #[actix_rt::test]
     pub async fn test_task_update_valid_task() {
         let configure = |cfg: &mut ServiceConfig| {
@@ -515,7 +593,7 @@ mod tests {
             .to_request();
         let response = test::call_service(&mut app, request).await;
 
-        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
 
     #[actix_rt::test]
@@ -581,7 +659,11 @@ mod tests {
         let _ = pre_insert_task!(bearer_token, cookies, app);
 
         // NOTE(alex): Find all
-        let request = test::TestRequest::get().uri("/tasks").to_request();
+        let request = test::TestRequest::get()
+            .uri("/tasks")
+            .insert_header(("Authorization".to_string(), bearer_token))
+            .cookie(cookies)
+            .to_request();
         let response = test::call_service(&mut app, request).await;
 
         assert_eq!(response.status(), StatusCode::FOUND);
@@ -609,7 +691,11 @@ mod tests {
         assert!(task_done_response.status().is_success());
 
         // NOTE(alex): Find ongoing tasks only
-        let request = test::TestRequest::get().uri("/tasks/ongoing").to_request();
+        let request = test::TestRequest::get()
+            .uri("/tasks/ongoing")
+            .insert_header(("Authorization".to_string(), bearer_token))
+            .cookie(cookies)
+            .to_request();
         let response = test::call_service(&mut app, request).await;
 
         assert_eq!(response.status(), StatusCode::FOUND);
@@ -629,6 +715,8 @@ mod tests {
         // NOTE(alex): Find tasks with title pattern
         let request = test::TestRequest::get()
             .uri(&format!("/tasks{}", title_pattern))
+            .insert_header(("Authorization".to_string(), bearer_token))
+            .cookie(cookies)
             .to_request();
         let response = test::call_service(&mut app, request).await;
 
@@ -648,12 +736,90 @@ mod tests {
         // NOTE(alex): Find with id
         let request = test::TestRequest::get()
             .uri(&format!("/tasks/{}", task.id))
+            .insert_header(("Authorization".to_string(), bearer_token))
+            .cookie(cookies)
             .to_request();
         let response = test::call_service(&mut app, request).await;
 
         assert_eq!(response.status(), StatusCode::FOUND);
     }
 
+    #[actix_rt::test]
+    pub async fn test_task_find_by_id_of_another_user() {
+        let configure = |cfg: &mut ServiceConfig| {
+            cfg.service(task_insert);
+            cfg.service(task_find_by_id);
+        };
+
+        let (mut app, bearer_token, cookies) = setup_app!(configure);
+        let task = pre_insert_task!(bearer_token, cookies, app);
+
+        let (other_cookies, other_bearer_token) = register_and_login!(app, "jet", "black");
+
+        // NOTE(alex): Find with id, as a user that doesn't own the task
+        let request = test::TestRequest::get()
+            .uri(&format!("/tasks/{}", task.id))
+            .insert_header(("Authorization".to_string(), other_bearer_token))
+            .cookie(other_cookies)
+            .to_request();
+        let response = test::call_service(&mut app, request).await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    pub async fn test_task_update_of_another_user() {
+        let configure = |cfg: &mut ServiceConfig| {
+            cfg.service(task_insert);
+            cfg.service(task_update);
+        };
+
+        let (mut app, bearer_token, cookies) = setup_app!(configure);
+        let task = pre_insert_task!(bearer_token, cookies, app);
+
+        let (other_cookies, other_bearer_token) = register_and_login!(app, "jet", "black");
+
+        let update_task = UpdateTask {
+            id: task.id,
+            new_title: "Feed Ein".to_string(),
+            details: "Not a good show.".to_string(),
+        };
+
+        // NOTE(alex): Update, as a user that doesn't own the task
+        let request = test::TestRequest::put()
+            .uri("/tasks")
+            .insert_header(("Authorization".to_string(), other_bearer_token))
+            .cookie(other_cookies)
+            .set_json(&update_task)
+            .to_request();
+        let response = test::call_service(&mut app, request).await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    pub async fn test_task_delete_of_another_user() {
+        let configure = |cfg: &mut ServiceConfig| {
+            cfg.service(task_insert);
+            cfg.service(task_delete);
+        };
+
+        let (mut app, bearer_token, cookies) = setup_app!(configure);
+        let task = pre_insert_task!(bearer_token, cookies, app);
+
+        let (other_cookies, other_bearer_token) = register_and_login!(app, "jet", "black");
+
+        // NOTE(alex): Delete, as a user that doesn't own the task
+        let request = test::TestRequest::delete()
+            .uri(&format!("/tasks/{}", task.id))
+            .insert_header(("Authorization".to_string(), other_bearer_token))
+            .cookie(other_cookies)
+            .to_request();
+        let response = test::call_service(&mut app, request).await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
     #[actix_rt::test]
     pub async fn test_task_favorite() {
         let configure = |cfg: &mut ServiceConfig| {
@@ -1,5 +1,6 @@
 pub(crate) mod errors;
 pub(crate) mod models;
+pub(crate) mod password;
 pub(crate) mod routes;
 
 const FIND_ALL: &'static str = include_str!("./users/queries/find_all.sql");
@@ -8,3 +9,8 @@ const INSERT: &'static str = include_str!("./users/queries/insert.sql");
 const UPDATE: &'static str = include_str!("./users/queries/update.sql");
 const DELETE: &'static str = include_str!("./users/queries/delete.sql");
 const LOGIN: &'static str = include_str!("./users/queries/login.sql");
+const FIND_ROLE: &'static str = include_str!("./users/queries/find_role.sql");
+const UPDATE_PASSWORD: &'static str = include_str!("./users/queries/update_password.sql");
+const INSERT_SESSION: &'static str = include_str!("./users/queries/insert_session.sql");
+const FIND_SESSION: &'static str = include_str!("./users/queries/find_session.sql");
+const DELETE_SESSION: &'static str = include_str!("./users/queries/delete_session.sql");
@@ -1,23 +1,42 @@
 use actix_web::{
-    body::BoxBody,
-    dev::{JsonBody, Payload},
-    FromRequest, HttpRequest, HttpResponse, Responder,
+    body::BoxBody, dev::Payload, FromRequest, HttpMessage, HttpRequest, HttpResponse, Responder,
 };
-use futures::{future::LocalBoxFuture, FutureExt};
+use futures::future::{ready, Ready};
+use rand_core::{OsRng, RngCore};
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, SqlitePool};
+use time::OffsetDateTime;
 
-use super::{errors::*, *};
-use crate::errors::AppError;
+use super::{errors::*, password::*, *};
+use crate::{
+    errors::AppError,
+    validation::{length, FieldErrors, Validate, ValidationConfig},
+};
 
 pub(crate) const MIN_USERNAME_LENGTH: usize = 3;
+/// Extended result code of a `unique` constraint failing.
+const SQLITE_CONSTRAINT_UNIQUE: &str = "2067";
 pub(crate) const MIN_PASSWORD_LENGTH: usize = 4;
+/// NOTE(alex): Sent as hex, so the token itself is twice as long.
+const SESSION_TOKEN_BYTES: usize = 32;
+
+/// NOTE(alex): Stored as lowercase text in the `role` column.
+#[derive(Hash, PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub(crate) enum Role {
+    Admin,
+    User,
+}
 
 #[derive(Hash, PartialEq, Eq, Clone, Debug, Serialize, Deserialize, FromRow)]
 pub(crate) struct User {
     pub(crate) id: i64,
     pub(crate) username: String,
-    pub(crate) password: String,
+    /// NOTE(alex): The Argon2id PHC string, never sent back in a response.
+    #[serde(skip)]
+    pub(crate) password_hash: String,
+    pub(crate) role: Role,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -42,76 +61,125 @@ pub(crate) struct LoginUser {
 pub(crate) struct LoggedUser {
     pub(crate) id: i64,
     pub(crate) username: String,
-    pub(crate) password: String,
-    pub(crate) token: u64,
+    pub(crate) token: String,
 }
 
 impl InsertUser {
     pub(crate) async fn insert(self, db_pool: &SqlitePool) -> Result<User, AppError> {
+        self.insert_as(db_pool, Role::User).await
+    }
+
+    /// NOTE(alex): Only `--create-admin` calls this, registering through the route always makes an
+    /// `user`, so a fresh deploy can't be taken over by whoever registers first.
+    pub(crate) async fn insert_admin(self, db_pool: &SqlitePool) -> Result<User, AppError> {
+        self.validate(&ValidationConfig::default())?
+            .insert_as(db_pool, Role::Admin)
+            .await
+    }
+
+    async fn insert_as(self, db_pool: &SqlitePool, role: Role) -> Result<User, AppError> {
+        let password_hash = hash_password(&self.valid_password)?;
+
         let mut connection = db_pool.acquire().await?;
-        let result = sqlx::query(INSERT)
+        let id: i64 = sqlx::query_scalar(INSERT)
             .bind(&self.valid_username)
-            .bind(&self.valid_password)
-            .execute(&mut connection)
-            .await?;
+            .bind(&password_hash)
+            .bind(role)
+            .fetch_one(&mut connection)
+            .await
+            .map_err(|fail| username_taken(fail, &self.valid_username))?;
 
         let user = User {
-            id: result.last_insert_rowid(),
+            id,
             username: self.valid_username,
-            password: self.valid_password,
+            password_hash,
+            role,
         };
 
         Ok(user)
     }
-
-    fn validate(self) -> Result<Self, UserError> {
-        if self.valid_username.trim().is_empty() {
-            Err(UserError::EmptyUsername)
-        } else if self.valid_username.len() < MIN_USERNAME_LENGTH {
-            Err(UserError::UsernameLength)
-        } else if self.valid_username.contains(" ") {
-            Err(UserError::UsernameInvalidCharacter)
-        } else if self.valid_password.trim().is_empty() {
-            Err(UserError::EmptyPassword)
-        } else if self.valid_password.len() < MIN_PASSWORD_LENGTH {
-            Err(UserError::PasswordLength)
-        } else if self.valid_password.contains(" ") {
-            Err(UserError::PasswordInvalidCharacter)
-        } else {
-            Ok(self)
-        }
-    }
 }
 
 impl UpdateUser {
     pub(crate) async fn update(self, db_pool: &SqlitePool) -> Result<u64, AppError> {
+        let password_hash = hash_password(&self.valid_password)?;
+
         let mut connection = db_pool.acquire().await?;
         let result = sqlx::query(UPDATE)
             .bind(&self.valid_username)
-            .bind(&self.valid_password)
+            .bind(&password_hash)
             .bind(&self.id)
             .execute(&mut connection)
-            .await?;
+            .await
+            .map_err(|fail| username_taken(fail, &self.valid_username))?;
 
         Ok(result.rows_affected())
     }
+}
 
-    fn validate(self) -> Result<Self, UserError> {
-        if self.valid_username.trim().is_empty() {
-            Err(UserError::EmptyUsername)
-        } else if self.valid_username.len() < MIN_USERNAME_LENGTH {
-            Err(UserError::UsernameLength)
-        } else if self.valid_username.contains(" ") {
-            Err(UserError::UsernameInvalidCharacter)
-        } else if self.valid_password.trim().is_empty() {
-            Err(UserError::EmptyPassword)
-        } else if self.valid_password.len() < MIN_PASSWORD_LENGTH {
-            Err(UserError::PasswordLength)
-        } else if self.valid_password.contains(" ") {
-            Err(UserError::PasswordInvalidCharacter)
-        } else {
-            Ok(self)
+/// NOTE(alex): `User.username` is unique (`login` relies on it), so an insert, or update, with a
+/// username that some other user has fails on the index, and that is the client's fault.
+fn username_taken(fail: sqlx::Error, username: &str) -> AppError {
+    match &fail {
+        sqlx::Error::Database(database_error)
+            if database_error.code().as_deref() == Some(SQLITE_CONSTRAINT_UNIQUE)
+                && database_error.message().contains("User.username") =>
+        {
+            UserError::UsernameTaken(username.to_string()).into()
         }
+        _ => fail.into(),
+    }
+}
+
+impl Validate for InsertUser {
+    fn check(&self, config: &ValidationConfig, errors: &mut FieldErrors) {
+        check_username(&self.valid_username, config, errors);
+        check_password(&self.valid_password, config, errors);
+    }
+}
+
+impl Validate for UpdateUser {
+    fn check(&self, config: &ValidationConfig, errors: &mut FieldErrors) {
+        check_username(&self.valid_username, config, errors);
+        check_password(&self.valid_password, config, errors);
+    }
+}
+
+fn check_username(username: &str, config: &ValidationConfig, errors: &mut FieldErrors) {
+    let username_length = length(username);
+
+    if username.trim().is_empty() {
+        errors.push(UserError::EmptyUsername);
+    } else if username_length < MIN_USERNAME_LENGTH || username_length > config.max_username_length
+    {
+        errors.push(UserError::UsernameLength {
+            min: MIN_USERNAME_LENGTH,
+            max: config.max_username_length,
+        });
+    } else if !username
+        .chars()
+        .all(|character| config.username_charset.allows(character))
+    {
+        errors.push(UserError::UsernameInvalidCharacter(config.username_charset));
+    }
+}
+
+fn check_password(password: &str, config: &ValidationConfig, errors: &mut FieldErrors) {
+    let password_length = length(password);
+
+    if password.trim().is_empty() {
+        errors.push(UserError::EmptyPassword);
+    } else if password_length < config.min_password_length
+        || password_length > config.max_password_length
+    {
+        errors.push(UserError::PasswordLength {
+            min: config.min_password_length,
+            max: config.max_password_length,
+        });
+    } else if password.chars().any(char::is_whitespace) {
+        errors.push(UserError::PasswordInvalidCharacter);
+    } else if !config.password_complexity.allows(password) {
+        errors.push(UserError::WeakPassword(config.password_complexity));
     }
 }
 
@@ -143,25 +211,96 @@ impl User {
         Ok(result)
     }
 
-    pub(crate) fn to_logged(self, token: u64) -> LoggedUser {
-        LoggedUser {
+    /// `false` for an user that doesn't exist.
+    ///
+    /// NOTE(alex): Always read from the database, the identity cookie only says who the user is,
+    /// so a demoted admin loses access right away.
+    pub(crate) async fn is_admin(db_pool: &SqlitePool, user_id: i64) -> Result<bool, AppError> {
+        let role: Option<Role> = sqlx::query_scalar(FIND_ROLE)
+            .bind(user_id)
+            .fetch_optional(db_pool)
+            .await?;
+
+        Ok(role == Some(Role::Admin))
+    }
+
+    /// Starts a new session for the user, with a random token that `validator` will only take
+    /// while the session lasts.
+    pub(crate) async fn start_session(self, db_pool: &SqlitePool) -> Result<LoggedUser, AppError> {
+        let mut token = [0u8; SESSION_TOKEN_BYTES];
+        OsRng.fill_bytes(&mut token);
+        let token = token
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+
+        let mut connection = db_pool.acquire().await?;
+        sqlx::query(INSERT_SESSION)
+            .bind(self.id)
+            .bind(&token)
+            .bind(OffsetDateTime::now_utc().unix_timestamp())
+            .execute(&mut connection)
+            .await?;
+
+        Ok(LoggedUser {
             id: self.id,
             username: self.username,
-            password: self.password,
             token,
-        }
+        })
+    }
+}
+
+impl LoggedUser {
+    /// Whether the session of this token is still there (and belongs to this user).
+    pub(crate) async fn has_session(&self, db_pool: &SqlitePool) -> Result<bool, AppError> {
+        let result = sqlx::query_scalar(FIND_SESSION)
+            .bind(&self.token)
+            .bind(self.id)
+            .fetch_one(db_pool)
+            .await?;
+
+        Ok(result)
+    }
+
+    pub(crate) async fn end_session(&self, db_pool: &SqlitePool) -> Result<u64, AppError> {
+        let mut connection = db_pool.acquire().await?;
+        let result = sqlx::query(DELETE_SESSION)
+            .bind(&self.token)
+            .execute(&mut connection)
+            .await?;
+
+        Ok(result.rows_affected())
     }
 }
 
 impl LoginUser {
     pub(crate) async fn login(self, db_pool: &SqlitePool) -> Result<Option<User>, AppError> {
-        let result = sqlx::query_as(LOGIN)
-            .bind(self.username)
-            .bind(self.password)
+        let user: Option<User> = sqlx::query_as(LOGIN)
+            .bind(&self.username)
             .fetch_optional(db_pool)
             .await?;
 
-        Ok(result)
+        match user {
+            Some(mut user) if verify_password(&self.password, &user.password_hash)? => {
+                // NOTE(alex): The password is correct, so this is our chance to upgrade a hash
+                // that was created with outdated parameters.
+                if needs_rehash(&user.password_hash)? {
+                    let password_hash = hash_password(&self.password)?;
+
+                    let mut connection = db_pool.acquire().await?;
+                    sqlx::query(UPDATE_PASSWORD)
+                        .bind(&password_hash)
+                        .bind(user.id)
+                        .execute(&mut connection)
+                        .await?;
+
+                    user.password_hash = password_hash;
+                }
+
+                Ok(Some(user))
+            }
+            _ => Ok(None),
+        }
     }
 }
 
@@ -183,34 +322,20 @@ impl Responder for User {
     }
 }
 
-impl FromRequest for InsertUser {
+/// NOTE(alex): Only available on routes wrapped by `validator`, which is responsible for putting the
+/// `LoggedUser` in the request extensions.
+impl FromRequest for LoggedUser {
     type Error = AppError;
 
-    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
-
-    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-        JsonBody::new(req, payload, None, false)
-            .limit(4056)
-            .map(|res: Result<InsertUser, _>| match res {
-                Ok(insert_user) => insert_user.validate().map_err(|fail| AppError::from(fail)),
-                Err(fail) => Err(AppError::from(fail)),
-            })
-            .boxed_local()
-    }
-}
-
-impl FromRequest for UpdateUser {
-    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
 
-    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let logged_user = req
+            .extensions()
+            .get::<LoggedUser>()
+            .cloned()
+            .ok_or_else(|| UserError::NotLoggedIn.into());
 
-    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-        JsonBody::new(req, payload, None, false)
-            .limit(4056)
-            .map(|res: Result<UpdateUser, _>| match res {
-                Ok(update_user) => update_user.validate().map_err(|fail| AppError::from(fail)),
-                Err(fail) => Err(AppError::from(fail)),
-            })
-            .boxed_local()
+        ready(logged_user)
     }
 }
@@ -0,0 +1,2 @@
+delete from Session
+where Session.token = $1;
\ No newline at end of file
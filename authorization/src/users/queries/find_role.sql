@@ -0,0 +1,3 @@
+select User.role
+from User
+where User.id = $1
\ No newline at end of file
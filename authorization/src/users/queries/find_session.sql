@@ -0,0 +1,5 @@
+select exists (
+        select 1
+        from Session
+        where Session.token = $1 and Session.user_id = $2
+    );
\ No newline at end of file
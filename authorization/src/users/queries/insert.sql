@@ -1,2 +1,4 @@
-insert into User (username, password)
-values ($1, $2);
\ No newline at end of file
+-- NOTE(alex): Registering always makes an `user`, admins are only created by `--create-admin`.
+insert into User (username, password_hash, role)
+values ($1, $2, $3)
+returning User.id;
@@ -0,0 +1,2 @@
+insert into Session (user_id, token, created_at)
+values ($1, $2, $3);
\ No newline at end of file
@@ -1,4 +1,4 @@
 update User
 set username = $1,
-    password = $2
+    password_hash = $2
 where User.id = $3
\ No newline at end of file
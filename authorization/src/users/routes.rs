@@ -5,14 +5,14 @@ use sqlx::SqlitePool;
 
 use super::{
     errors::UserError,
-    models::{InsertUser, LoginUser, UpdateUser, User},
+    models::{InsertUser, LoggedUser, LoginUser, UpdateUser, User},
 };
-use crate::{errors::AppError, validator};
+use crate::{admin_validator, errors::AppError, validation::Validated, validator};
 
 #[post("/users/register")]
 async fn insert(
     db_pool: web::Data<SqlitePool>,
-    input: InsertUser,
+    Validated(input): Validated<InsertUser>,
 ) -> Result<impl Responder, AppError> {
     let user = input.insert(db_pool.get_ref()).await?;
     Ok(HttpResponse::Created().json(user))
@@ -21,8 +21,14 @@ async fn insert(
 #[put("/users", wrap = "HttpAuthentication::bearer(validator)")]
 async fn update(
     db_pool: web::Data<SqlitePool>,
-    input: UpdateUser,
+    logged_user: LoggedUser,
+    Validated(input): Validated<UpdateUser>,
 ) -> Result<impl Responder, AppError> {
+    // NOTE(alex): Users may only edit their own account, admins may edit anyone's.
+    if input.id != logged_user.id && !User::is_admin(db_pool.get_ref(), logged_user.id).await? {
+        return Err(UserError::Forbidden.into());
+    }
+
     let num_modified = input.update(db_pool.get_ref()).await?;
 
     if num_modified == 0 {
@@ -32,7 +38,10 @@ async fn update(
     }
 }
 
-#[delete("/users/{id:\\d+}", wrap = "HttpAuthentication::bearer(validator)")]
+#[delete(
+    "/users/{id:\\d+}",
+    wrap = "HttpAuthentication::bearer(admin_validator)"
+)]
 async fn delete(
     db_pool: web::Data<SqlitePool>,
     id: web::Path<i64>,
@@ -46,7 +55,7 @@ async fn delete(
     }
 }
 
-#[get("/users")]
+#[get("/users", wrap = "HttpAuthentication::bearer(admin_validator)")]
 async fn find_all(db_pool: web::Data<SqlitePool>) -> Result<impl Responder, AppError> {
     let users = User::find_all(db_pool.get_ref()).await?;
 
@@ -70,17 +79,6 @@ async fn find_by_id(
     }
 }
 
-pub(crate) fn create_auth_token(user: &User) -> u64 {
-    use std::{
-        collections::hash_map::DefaultHasher,
-        hash::{Hash, Hasher},
-    };
-
-    let mut hasher = DefaultHasher::new();
-    user.hash(&mut hasher);
-    hasher.finish()
-}
-
 #[post("/users/login")]
 async fn login(
     db_pool: web::Data<SqlitePool>,
@@ -91,15 +89,14 @@ async fn login(
     let user = login_user.login(&db_pool).await?;
     match user {
         Some(user) => {
-            let auth_token = create_auth_token(&user);
-            let logged_user = user.to_logged(auth_token);
+            let logged_user = user.start_session(&db_pool).await?;
 
             // NOTE(alex): We'll use this identity cookie to check if the user is logged in for
             // routes that require it.
             identity.remember(serde_json::to_string_pretty(&logged_user)?);
 
             let response: HttpResponse = HttpResponse::Ok()
-                .append_header(("X-Auth-Token", auth_token.to_string()))
+                .append_header(("X-Auth-Token", logged_user.token.clone()))
                 .json(logged_user);
             Ok(response)
         }
@@ -108,9 +105,17 @@ async fn login(
 }
 
 #[delete("/users/logout", wrap = "HttpAuthentication::bearer(validator)")]
-async fn logout(identity: Identity) -> impl Responder {
+async fn logout(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    identity: Identity,
+) -> Result<impl Responder, AppError> {
+    // NOTE(alex): Forgetting the cookie isn't enough, a copy of it would still work, the token
+    // must be gone from the database too.
+    logged_user.end_session(&db_pool).await?;
     identity.forget();
-    HttpResponse::Ok().body("Logged out.")
+
+    Ok(HttpResponse::Ok().body("Logged out."))
 }
 
 pub(crate) fn user_service(cfg: &mut web::ServiceConfig) {
@@ -127,33 +132,41 @@ pub(crate) fn user_service(cfg: &mut web::ServiceConfig) {
 // `integration` project!
 #[cfg(test)]
 mod tests {
+    use std::str::FromStr;
+
     use actix_identity::{CookieIdentityPolicy, IdentityService};
     use actix_session::CookieSession;
     use actix_web::{
         cookie::Cookie,
+        dev::Service,
         http::StatusCode,
         test,
         web::{self, ServiceConfig},
         App,
     };
-    use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
+    use sqlx::{
+        sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+        Pool, Sqlite,
+    };
     use time::Duration;
 
     use crate::{
-        create_database,
+        errors::FieldError,
+        migrations,
         users::{
-            models::{InsertUser, LoggedUser, LoginUser, UpdateUser, User},
+            models::{InsertUser, LoggedUser, LoginUser, Role, UpdateUser, User},
             routes::{
                 delete as user_delete, find_all as user_find_all, find_by_id as user_find_by_id,
                 insert as user_insert, login, logout, update as user_update,
             },
         },
+        validation::{PasswordComplexity, ValidationConfig},
     };
 
     pub async fn setup_data() -> web::Data<Pool<Sqlite>> {
-        let db_options = sqlx::sqlite::SqliteConnectOptions::new()
-            .filename(env!("DATABASE_FILE"))
-            .create_if_missing(true);
+        // NOTE(alex): Migrations never drop anything, so every test gets its own in-memory
+        // database. It only lives as long as its single connection does.
+        let db_options = SqliteConnectOptions::from_str("sqlite::memory:").unwrap();
 
         let database_pool = SqlitePoolOptions::new()
             .max_connections(1)
@@ -161,7 +174,7 @@ mod tests {
             .await
             .unwrap();
 
-        create_database(&database_pool).await.unwrap();
+        migrations::run(&database_pool).await.unwrap();
 
         web::Data::new(database_pool)
     }
@@ -194,23 +207,18 @@ mod tests {
             let mut app = test::init_service(app).await;
 
             let (cookies, bearer_token) = {
-                let new_user = InsertUser {
+                // NOTE(alex): "spike" is the admin, created the same way `--create-admin` does it.
+                let user = InsertUser {
                     valid_username: "spike".to_string(),
                     valid_password: "vicious".to_string(),
-                };
-                let register_user_request = test::TestRequest::post()
-                    .uri("/users/register")
-                    .set_json(&new_user)
-                    .to_request();
-                let register_user_service_response =
-                    test::call_service(&mut app, register_user_request).await;
-                assert!(register_user_service_response.status().is_success());
-
-                let user: User = test::read_body_json(register_user_service_response).await;
+                }
+                .insert_admin(data.get_ref())
+                .await
+                .unwrap();
 
                 let login_user = LoginUser {
                     username: user.username,
-                    password: user.password,
+                    password: "vicious".to_string(),
                 };
                 let login_request = test::TestRequest::post()
                     .uri("/users/login")
@@ -236,6 +244,35 @@ mod tests {
         }};
     }
 
+    // NOTE(alex): Logs in an already registered `User`, returning the identity cookie and the bearer
+    // token.
+    macro_rules! login {
+        ($app: expr, $username: expr, $password: expr) => {{
+            let login_user = LoginUser {
+                username: $username.to_string(),
+                password: $password.to_string(),
+            };
+            let login_request = test::TestRequest::post()
+                .uri("/users/login")
+                .set_json(&login_user)
+                .to_request();
+            let login_service_response = test::call_service(&mut $app, login_request).await;
+            assert!(login_service_response.status().is_success());
+
+            let cookies = login_service_response.response().cookies();
+            let cookies_str = cookies
+                .flat_map(|cookie| cookie.to_string().chars().collect::<Vec<_>>())
+                .collect::<String>();
+
+            let logged_user: LoggedUser = test::read_body_json(login_service_response).await;
+
+            let bearer_token = format!("Bearer {}", logged_user.token);
+            let cookies = Cookie::parse_encoded(cookies_str).unwrap();
+
+            (cookies, bearer_token)
+        }};
+    }
+
     macro_rules! pre_insert_user {
         ($app: expr) => {{
             let insert_user = InsertUser {
@@ -299,6 +336,38 @@ mod tests {
         assert!(response.status().is_client_error());
     }
 
+    #[actix_rt::test]
+    pub async fn test_user_insert_reports_every_invalid_field() {
+        let data = setup_data().await;
+        let app = App::new()
+            .app_data(data.clone())
+            .app_data(web::Data::new(ValidationConfig {
+                password_complexity: PasswordComplexity::Mixed,
+                ..Default::default()
+            }))
+            .configure(|cfg| {
+                cfg.service(user_insert);
+            });
+        let mut app = test::init_service(app).await;
+
+        let invalid_insert_user = InsertUser {
+            valid_username: "yu".to_string(),
+            valid_password: "toguro".to_string(),
+        };
+
+        let request = test::TestRequest::post()
+            .uri("/users/register")
+            .set_json(&invalid_insert_user)
+            .to_request();
+        let response = test::call_service(&mut app, request).await;
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let errors: Vec<FieldError> = test::read_body_json(response).await;
+        let fields: Vec<_> = errors.iter().map(|fail| fail.field.as_str()).collect();
+        assert_eq!(fields, vec!["username", "password"]);
+        assert!(errors[0].message.contains("between 3 and 32"));
+    }
+
     #[actix_rt::test]
     pub async fn test_user_update_valid_user() {
         let configure = |cfg: &mut ServiceConfig| {
@@ -312,7 +381,7 @@ mod tests {
         let update_user = UpdateUser {
             id: user.id,
             valid_username: format!("{}_urameshi", user.username),
-            valid_password: format!("{}_young", user.password),
+            valid_password: "toguro_young".to_string(),
         };
 
         // NOTE(alex): Update
@@ -340,7 +409,7 @@ mod tests {
         let update_user = UpdateUser {
             id: user.id,
             valid_username: " \n\t".to_string(),
-            valid_password: format!("{}_young", user.password),
+            valid_password: "toguro_young".to_string(),
         };
 
         // NOTE(alex): Update
@@ -355,6 +424,36 @@ mod tests {
         assert!(response.status().is_client_error());
     }
 
+    #[actix_rt::test]
+    pub async fn test_user_update_another_user_as_non_admin() {
+        let configure = |cfg: &mut ServiceConfig| {
+            cfg.service(user_insert);
+            cfg.service(user_update);
+        };
+
+        let (mut app, _, _) = setup_app!(configure);
+        let user = pre_insert_user!(app);
+        let (cookies, bearer_token) = login!(app, "yusuke", "toguro");
+
+        // NOTE(alex): "spike" was created first, so it has the id before ours.
+        let update_user = UpdateUser {
+            id: user.id - 1,
+            valid_username: "faye".to_string(),
+            valid_password: "valentine".to_string(),
+        };
+
+        // NOTE(alex): Update
+        let request = test::TestRequest::put()
+            .uri("/users")
+            .insert_header(("Authorization".to_string(), bearer_token))
+            .cookie(cookies)
+            .set_json(&update_user)
+            .to_request();
+        let response = test::call_service(&mut app, request).await;
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
     #[actix_rt::test]
     pub async fn test_user_delete_existing_user() {
         let configure = |cfg: &mut ServiceConfig| {
@@ -376,6 +475,31 @@ mod tests {
         assert!(response.status().is_success());
     }
 
+    #[actix_rt::test]
+    pub async fn test_user_delete_as_non_admin() {
+        let configure = |cfg: &mut ServiceConfig| {
+            cfg.service(user_insert);
+            cfg.service(user_delete);
+        };
+
+        let (mut app, _, _) = setup_app!(configure);
+        let user = pre_insert_user!(app);
+        let (cookies, bearer_token) = login!(app, "yusuke", "toguro");
+
+        // NOTE(alex): Delete
+        let request = test::TestRequest::delete()
+            .uri(&format!("/users/{}", user.id))
+            .insert_header(("Authorization".to_string(), bearer_token))
+            .cookie(cookies)
+            .to_request();
+        let error = app.call(request).await.unwrap_err();
+
+        assert_eq!(
+            error.as_response_error().status_code(),
+            StatusCode::FORBIDDEN
+        );
+    }
+
     #[actix_rt::test]
     pub async fn test_user_delete_non_existant_user() {
         let configure = |cfg: &mut ServiceConfig| {
@@ -404,16 +528,84 @@ mod tests {
             cfg.service(user_find_all);
         };
 
-        let (mut app, _, _) = setup_app!(configure);
+        let (mut app, bearer_token, cookies) = setup_app!(configure);
         let _ = pre_insert_user!(app);
 
         // NOTE(alex): Find all
-        let request = test::TestRequest::get().uri("/users").to_request();
+        let request = test::TestRequest::get()
+            .uri("/users")
+            .insert_header(("Authorization".to_string(), bearer_token))
+            .cookie(cookies)
+            .to_request();
         let response = test::call_service(&mut app, request).await;
 
         assert_eq!(response.status(), StatusCode::FOUND);
     }
 
+    #[actix_rt::test]
+    pub async fn test_user_find_all_as_non_admin() {
+        let configure = |cfg: &mut ServiceConfig| {
+            cfg.service(user_insert);
+            cfg.service(user_find_all);
+        };
+
+        // NOTE(alex): Registering never makes an admin, so "yusuke" is a regular user.
+        let (mut app, _, _) = setup_app!(configure);
+        let _ = pre_insert_user!(app);
+        let (cookies, bearer_token) = login!(app, "yusuke", "toguro");
+
+        // NOTE(alex): Find all
+        let request = test::TestRequest::get()
+            .uri("/users")
+            .insert_header(("Authorization".to_string(), bearer_token))
+            .cookie(cookies)
+            .to_request();
+        let error = app.call(request).await.unwrap_err();
+
+        assert_eq!(
+            error.as_response_error().status_code(),
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    #[actix_rt::test]
+    pub async fn test_user_register_first_is_not_admin() {
+        let configure = |cfg: &mut ServiceConfig| {
+            cfg.service(user_insert);
+            cfg.service(user_find_all);
+        };
+
+        let data = setup_data().await;
+        let app = App::new()
+            .app_data(data.clone())
+            .configure(configure)
+            .service(login)
+            .wrap(IdentityService::new(
+                CookieIdentityPolicy::new(&[0; 32])
+                    .name("auth-cookie")
+                    .login_deadline(Duration::minutes(10))
+                    .secure(false),
+            ));
+        let mut app = test::init_service(app).await;
+
+        // NOTE(alex): An empty database, so "yusuke" is the first to register.
+        let user = pre_insert_user!(app);
+        assert_eq!(user.role, Role::User);
+
+        let (cookies, bearer_token) = login!(app, "yusuke", "toguro");
+        let request = test::TestRequest::get()
+            .uri("/users")
+            .insert_header(("Authorization".to_string(), bearer_token))
+            .cookie(cookies)
+            .to_request();
+        let error = app.call(request).await.unwrap_err();
+
+        assert_eq!(
+            error.as_response_error().status_code(),
+            StatusCode::FORBIDDEN
+        );
+    }
+
     #[actix_rt::test]
     pub async fn test_user_find_by_id() {
         let configure = |cfg: &mut ServiceConfig| {
@@ -443,16 +635,46 @@ mod tests {
         let (mut app, _, _) = setup_app!(configure);
         let user = pre_insert_user!(app);
 
+        let login_user = LoginUser {
+            username: user.username,
+            password: "toguro".to_string(),
+        };
+
         // NOTE(alex): Login
         let request = test::TestRequest::post()
             .uri("/users/login")
-            .set_json(&user)
+            .set_json(&login_user)
             .to_request();
         let response = test::call_service(&mut app, request).await;
 
         assert!(response.status().is_success());
     }
 
+    #[actix_rt::test]
+    pub async fn test_user_login_with_wrong_password() {
+        let configure = |cfg: &mut ServiceConfig| {
+            cfg.service(user_insert);
+            cfg.service(login);
+        };
+
+        let (mut app, _, _) = setup_app!(configure);
+        let user = pre_insert_user!(app);
+
+        let login_user = LoginUser {
+            username: user.username,
+            password: "sensui".to_string(),
+        };
+
+        // NOTE(alex): Login
+        let request = test::TestRequest::post()
+            .uri("/users/login")
+            .set_json(&login_user)
+            .to_request();
+        let response = test::call_service(&mut app, request).await;
+
+        assert!(response.status().is_client_error());
+    }
+
     #[actix_rt::test]
     pub async fn test_user_logout() {
         let configure = |cfg: &mut ServiceConfig| {
@@ -490,14 +712,13 @@ mod tests {
 
         let login_user = LoginUser {
             username: user.username,
-            password: user.password,
+            password: new_user.valid_password,
         };
         let login_request = test::TestRequest::post()
             .uri("/users/login")
             .set_json(&login_user)
             .to_request();
-        let login_service_response =
-            test::call_service(&mut app, login_request).await;
+        let login_service_response = test::call_service(&mut app, login_request).await;
         assert!(login_service_response.status().is_success());
 
         let cookies = login_service_response.response().cookies();
@@ -513,11 +734,24 @@ mod tests {
         // NOTE(alex): Logout
         let request = test::TestRequest::delete()
             .uri("/users/logout")
-            .insert_header(("Authorization".to_string(), bearer_token))
-            .cookie(cookies)
+            .insert_header(("Authorization".to_string(), bearer_token.clone()))
+            .cookie(cookies.clone())
             .to_request();
         let response = test::call_service(&mut app, request).await;
 
         assert!(response.status().is_success());
+
+        // NOTE(alex): A copy of the cookie, and token, is no good after the session is over.
+        let request = test::TestRequest::delete()
+            .uri("/users/logout")
+            .insert_header(("Authorization".to_string(), bearer_token))
+            .cookie(cookies)
+            .to_request();
+        let error = app.call(request).await.unwrap_err();
+
+        assert_eq!(
+            error.as_response_error().status_code(),
+            StatusCode::UNAUTHORIZED
+        );
     }
 }
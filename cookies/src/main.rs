@@ -3,10 +3,12 @@ use actix_web::{get, middleware, App, HttpResponse, HttpServer, Responder};
 use errors::AppError;
 use routes::task_service;
 use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
+use validation::ValidationConfig;
 
 mod errors;
 mod models;
 mod routes;
+mod validation;
 
 const WELCOME_MSG: &'static str = include_str!("./../strings/welcome.txt");
 
@@ -52,10 +54,12 @@ async fn main() -> std::io::Result<()> {
     }
 
     let data = actix_web::web::Data::new(database_pool);
+    let validation_config = actix_web::web::Data::new(ValidationConfig::default());
 
     HttpServer::new(move || {
         App::new()
             .app_data(data.clone())
+            .app_data(validation_config.clone())
             .service(index)
             .configure(task_service)
             .wrap(CookieSession::signed(&[0; 32]).secure(false))
@@ -1,11 +1,11 @@
-use actix_web::{
-    body::BoxBody, dev::Payload, web::JsonBody, FromRequest, HttpRequest, HttpResponse, Responder,
-};
-use futures::{future::LocalBoxFuture, FutureExt};
+use actix_web::{body::BoxBody, HttpRequest, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, SqlitePool};
 
-use crate::errors::{AppError, TaskError};
+use crate::{
+    errors::{AppError, TaskError},
+    validation::{length, FieldErrors, Validate, ValidationConfig},
+};
 
 const FIND_BY_PATTERN: &'static str = include_str!("./../queries/find_by_pattern.sql");
 const FIND_ONGOING: &'static str = include_str!("./../queries/find_ongoing.sql");
@@ -61,14 +61,6 @@ impl InsertTask {
 
         Ok(task)
     }
-
-    fn validate(self) -> Result<Self, TaskError> {
-        if self.non_empty_title.trim().is_empty() {
-            Err(TaskError::EmptyTitle)
-        } else {
-            Ok(self)
-        }
-    }
 }
 
 impl UpdateTask {
@@ -83,13 +75,29 @@ impl UpdateTask {
 
         Ok(result.rows_affected())
     }
+}
+
+impl Validate for InsertTask {
+    fn check(&self, config: &ValidationConfig, errors: &mut FieldErrors) {
+        check_task(&self.non_empty_title, &self.details, config, errors);
+    }
+}
 
-    fn validate(self) -> Result<Self, TaskError> {
-        if self.new_title.trim().is_empty() {
-            Err(TaskError::EmptyTitle)
-        } else {
-            Ok(self)
-        }
+impl Validate for UpdateTask {
+    fn check(&self, config: &ValidationConfig, errors: &mut FieldErrors) {
+        check_task(&self.new_title, &self.details, config, errors);
+    }
+}
+
+fn check_task(title: &str, details: &str, config: &ValidationConfig, errors: &mut FieldErrors) {
+    if title.trim().is_empty() {
+        errors.push(TaskError::EmptyTitle);
+    } else if length(title) > config.max_title_length {
+        errors.push(TaskError::TitleLength(config.max_title_length));
+    }
+
+    if length(details) > config.max_details_length {
+        errors.push(TaskError::DetailsLength(config.max_details_length));
     }
 }
 
@@ -178,35 +186,3 @@ impl Responder for Task {
         response
     }
 }
-
-impl FromRequest for InsertTask {
-    type Error = AppError;
-
-    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
-
-    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-        JsonBody::new(req, payload, None, false)
-            .limit(4056)
-            .map(|res: Result<InsertTask, _>| match res {
-                Ok(insert_task) => insert_task.validate().map_err(|fail| AppError::from(fail)),
-                Err(fail) => Err(AppError::from(fail)),
-            })
-            .boxed_local()
-    }
-}
-
-impl FromRequest for UpdateTask {
-    type Error = AppError;
-
-    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
-
-    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-        JsonBody::new(req, payload, None, false)
-            .limit(4056)
-            .map(|res: Result<UpdateTask, _>| match res {
-                Ok(update_task) => update_task.validate().map_err(|fail| AppError::from(fail)),
-                Err(fail) => Err(AppError::from(fail)),
-            })
-            .boxed_local()
-    }
-}
@@ -5,12 +5,13 @@ use sqlx::SqlitePool;
 use crate::{
     errors::{AppError, TaskError},
     models::{InsertTask, QueryTask, Task, UpdateTask},
+    validation::Validated,
 };
 
 #[post("/tasks")]
 async fn insert(
     db_pool: web::Data<SqlitePool>,
-    input: InsertTask,
+    Validated(input): Validated<InsertTask>,
 ) -> Result<impl Responder, AppError> {
     let task = input.insert(db_pool.get_ref()).await?;
     Ok(HttpResponse::Created().json(task))
@@ -19,7 +20,7 @@ async fn insert(
 #[put("/tasks")]
 async fn update(
     db_pool: web::Data<SqlitePool>,
-    input: UpdateTask,
+    Validated(input): Validated<UpdateTask>,
 ) -> Result<impl Responder, AppError> {
     let num_modified = input.update(db_pool.get_ref()).await?;
 
@@ -1,32 +1,53 @@
 drop table if exists User;
 drop view if exists OngoingTask;
+drop view if exists TaskView;
 drop table if exists Task;
 drop table if exists Done;
 
+-- NOTE(alex): Timestamps are unix seconds, `priority` goes from 0 (low) to 3 (urgent), so sorting
+-- by it is just sorting the column.
 create table if not exists Task (
     id integer primary key,
     title text not null,
-    details text
+    details text,
+    owner_id int not null,
+    created_at int not null default 0,
+    updated_at int not null default 0,
+    due_at int,
+    priority int not null default 1 check (priority between 0 and 3),
+    foreign key (owner_id) references User(id) on delete cascade
 );
 
+create index if not exists Task_owner_priority on Task(owner_id, priority, id);
+create index if not exists Task_owner_due_at on Task(owner_id, due_at);
+
 create table if not exists Done (
     task_id int not null,
+    completed_at int not null default 0,
     foreign key (task_id) references Task(id) on delete cascade
 );
 
-create view if not exists OngoingTask as
-select Task.id,
-    Task.title,
-    Task.details
-from Task
-where
-    Task.id not in (
-        select task_id
+-- NOTE(alex): Every query that returns a `Task` reads from here, so `completed_at` comes along. A
+-- task is only done once, `done` refuses it again until it's undone.
+create view if not exists TaskView as
+select Task.*,
+    (
+        select max(Done.completed_at)
         from Done
-    );
+        where Done.task_id = Task.id
+    ) as completed_at
+from Task;
+
+create view if not exists OngoingTask as
+select *
+from TaskView
+where TaskView.completed_at is null;
 
 create table if not exists User (
     id integer primary key,
     username text not null,
-    password text not null
-);
\ No newline at end of file
+    password_hash text not null
+);
+
+-- NOTE(alex): `login` finds the user by `username` alone, so it must be unique.
+create unique index if not exists User_username on User(username);
\ No newline at end of file
@@ -1,7 +1,33 @@
-use actix_web::{error::JsonPayloadError, HttpResponse, ResponseError};
+use actix_web::{HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::{tasks::errors::TaskError, users::errors::UserError};
+use crate::{tasks::errors::TaskError, users::errors::UserError, validation::errors::BodyError};
+
+/// A field of the request that didn't pass validation.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl From<TaskError> for FieldError {
+    fn from(fail: TaskError) -> Self {
+        Self {
+            field: fail.field().unwrap_or_default().to_string(),
+            message: fail.to_string(),
+        }
+    }
+}
+
+impl From<UserError> for FieldError {
+    fn from(fail: UserError) -> Self {
+        Self {
+            field: fail.field().unwrap_or_default().to_string(),
+            message: fail.to_string(),
+        }
+    }
+}
 
 #[derive(Debug, Error)]
 pub enum AppError {
@@ -21,7 +47,14 @@ pub enum AppError {
     Actix(#[from] actix_web::Error),
 
     #[error("`{0}`")]
-    Payload(#[from] JsonPayloadError),
+    Body(#[from] BodyError),
+
+    #[error("`{0}`")]
+    PasswordHash(#[from] argon2::password_hash::Error),
+
+    /// Every field that failed, see `Validate`.
+    #[error("Request has {} invalid field(s)!", .0.len())]
+    Validation(Vec<FieldError>),
 }
 
 impl ResponseError for AppError {
@@ -29,37 +62,62 @@ impl ResponseError for AppError {
         match self {
             AppError::Task(task_error) => match task_error {
                 TaskError::EmptyTitle => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                TaskError::TitleLength(_) => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                TaskError::DetailsLength(_) => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
                 TaskError::NotFound(_) => actix_web::http::StatusCode::NOT_FOUND,
                 TaskError::NoneFavorite => actix_web::http::StatusCode::NOT_FOUND,
-                TaskError::Empty => actix_web::http::StatusCode::NOT_FOUND,
+                TaskError::AlreadyDone(_) => actix_web::http::StatusCode::CONFLICT,
+                TaskError::DueInPast => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                TaskError::DueDays => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
             },
             AppError::User(user_error) => match user_error {
                 UserError::EmptyUsername => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
-                UserError::UsernameLength => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
-                UserError::UsernameInvalidCharacter => {
+                UserError::UsernameLength { .. } => {
+                    actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+                }
+                UserError::UsernameInvalidCharacter(_) => {
                     actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
                 }
                 UserError::EmptyPassword => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
-                UserError::PasswordLength => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                UserError::PasswordLength { .. } => {
+                    actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+                }
                 UserError::PasswordInvalidCharacter => {
                     actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
                 }
+                UserError::WeakPassword(_) => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                UserError::UsernameTaken(_) => actix_web::http::StatusCode::CONFLICT,
                 UserError::NotFound(_) => actix_web::http::StatusCode::NOT_FOUND,
                 UserError::NotLoggedIn => actix_web::http::StatusCode::UNAUTHORIZED,
-                UserError::Empty => actix_web::http::StatusCode::NOT_FOUND,
                 UserError::LoginFailed => actix_web::http::StatusCode::NOT_FOUND,
                 UserError::InvalidToken => actix_web::http::StatusCode::UNAUTHORIZED,
             },
             AppError::Database(_) => actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
             AppError::Json(_) => actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
             AppError::Actix(fail) => fail.as_response_error().status_code(),
-            AppError::Payload(fail) => fail.error_response().status(),
+            AppError::Body(body_error) => match body_error {
+                BodyError::UnsupportedMediaType(_) => {
+                    actix_web::http::StatusCode::UNSUPPORTED_MEDIA_TYPE
+                }
+                BodyError::Overflow(_) => actix_web::http::StatusCode::PAYLOAD_TOO_LARGE,
+                BodyError::Payload(_)
+                | BodyError::Json(_)
+                | BodyError::Form(_)
+                | BodyError::MessagePack(_) => actix_web::http::StatusCode::BAD_REQUEST,
+            },
+            AppError::PasswordHash(_) => actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Validation(_) => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
         }
     }
 
+    /// NOTE(alex): A `Validation` error is the only one with a JSON body, the client needs to know
+    /// which fields failed, and why.
     fn error_response(&self) -> HttpResponse {
         let status_code = self.status_code();
-        let response = HttpResponse::build(status_code).body(self.to_string());
+        let response = match self {
+            AppError::Validation(errors) => HttpResponse::build(status_code).json(errors),
+            _ => HttpResponse::build(status_code).body(self.to_string()),
+        };
         response
     }
 }
@@ -1,8 +1,8 @@
 use actix_identity::{CookieIdentityPolicy, IdentityService, RequestIdentity};
 use actix_session::CookieSession;
 use actix_web::{
-    dev::ServiceRequest, error::ErrorUnauthorized, get, middleware, App, Error, HttpResponse,
-    HttpServer, Responder,
+    dev::ServiceRequest, error::ErrorUnauthorized, get, middleware, App, Error, HttpMessage,
+    HttpResponse, HttpServer, Responder,
 };
 use actix_web_httpauth::extractors::{basic::Config, bearer::BearerAuth};
 use errors::AppError;
@@ -10,12 +10,15 @@ use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
 use tasks::routes::task_service;
 use time::Duration;
 use users::{models::LoggedUser, routes::user_service};
+use validation::ValidationConfig;
 
 use crate::users::errors::UserError;
 
 pub mod errors;
 pub mod tasks;
+pub mod timestamps;
 pub mod users;
+pub mod validation;
 
 pub const WELCOME_MSG: &'static str = include_str!("./../strings/welcome.txt");
 
@@ -50,10 +53,14 @@ pub async fn validator(
         let logged_user: LoggedUser = serde_json::from_str(&identity)?;
 
         // NOTE(alex) Return `Ok(request)` if the token match our logged user's token, otherwise it
-        // returns an `Err`.
-        (credentials.token() == logged_user.token.to_string())
-            .then(|| req)
-            .ok_or(ErrorUnauthorized(UserError::InvalidToken))
+        // returns an `Err`. The `LoggedUser` is put into the request extensions, so routes may
+        // extract it to know who is making the request.
+        if credentials.token() == logged_user.token.to_string() {
+            req.extensions_mut().insert(logged_user);
+            Ok(req)
+        } else {
+            Err(ErrorUnauthorized(UserError::InvalidToken))
+        }
     } else {
         Err(ErrorUnauthorized(UserError::NotLoggedIn))
     }
@@ -75,10 +82,12 @@ pub async fn start_app() -> std::io::Result<()> {
     }
 
     let data = actix_web::web::Data::new(database_pool);
+    let validation_config = actix_web::web::Data::new(ValidationConfig::default());
 
     HttpServer::new(move || {
         App::new()
             .app_data(data.clone())
+            .app_data(validation_config.clone())
             .app_data(Config::default().realm("Restricted area, login first!"))
             .service(index)
             .configure(task_service)
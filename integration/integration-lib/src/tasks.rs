@@ -6,6 +6,9 @@ const FIND_BY_PATTERN: &'static str = include_str!("./tasks/queries/find_by_patt
 const FIND_ONGOING: &'static str = include_str!("./tasks/queries/find_ongoing.sql");
 const FIND_ALL: &'static str = include_str!("./tasks/queries/find_all.sql");
 const FIND_BY_ID: &'static str = include_str!("./tasks/queries/find_by_id.sql");
+const FIND_BY_PRIORITY: &'static str = include_str!("./tasks/queries/find_by_priority.sql");
+const FIND_OVERDUE: &'static str = include_str!("./tasks/queries/find_overdue.sql");
+const FIND_DUE_WITHIN: &'static str = include_str!("./tasks/queries/find_due_within.sql");
 const INSERT: &'static str = include_str!("./tasks/queries/insert.sql");
 const UPDATE: &'static str = include_str!("./tasks/queries/update.sql");
 const DELETE: &'static str = include_str!("./tasks/queries/delete.sql");
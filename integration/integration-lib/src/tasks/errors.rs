@@ -1,16 +1,43 @@
 use thiserror::Error;
 
+use super::models::MAX_DUE_DAYS;
+
 #[derive(Debug, Error)]
 pub enum TaskError {
     #[error("`title` field of `Task` cannot be empty!")]
     EmptyTitle,
 
+    #[error("`title` field of `Task` cannot be longer than {0} characters!")]
+    TitleLength(usize),
+
+    #[error("`details` field of `Task` cannot be longer than {0} characters!")]
+    DetailsLength(usize),
+
     #[error("Could not find any `Task` for id: `{0}`!")]
     NotFound(i64),
 
     #[error("You have not favorited any `Task` yet!")]
     NoneFavorite,
 
-    #[error("Could not find any `Task`!")]
-    Empty,
+    #[error("`Task` with id: `{0}` is already done, undo it first!")]
+    AlreadyDone(i64),
+
+    #[error("`due_at` field of `Task` cannot be in the past!")]
+    DueInPast,
+
+    #[error("`days` must be between 1 and {}!", MAX_DUE_DAYS)]
+    DueDays,
+}
+
+impl TaskError {
+    /// The field of the request at fault, for the validation errors.
+    pub fn field(&self) -> Option<&'static str> {
+        match self {
+            TaskError::EmptyTitle | TaskError::TitleLength(_) => Some("title"),
+            TaskError::DetailsLength(_) => Some("details"),
+            TaskError::DueInPast => Some("due_at"),
+            TaskError::DueDays => Some("days"),
+            _ => None,
+        }
+    }
 }
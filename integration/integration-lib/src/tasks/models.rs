@@ -1,26 +1,59 @@
 use actix_web::{
-    body::BoxBody,
-    dev::{JsonBody, Payload},
-    FromRequest, HttpRequest, HttpResponse, Responder,
+    body::BoxBody, dev::Payload, web::Query, FromRequest, HttpRequest, HttpResponse, Responder,
 };
-use futures::{future::LocalBoxFuture, FutureExt};
+use futures::future::{ready, Ready};
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, SqlitePool};
 
 use super::{errors::*, *};
-use crate::errors::AppError;
+use crate::{
+    errors::AppError,
+    timestamps::{self, rfc3339},
+    validation::{length, FieldErrors, Validate, ValidationConfig},
+};
+
+pub const MAX_DUE_DAYS: u32 = 365;
+
+/// NOTE(alex): Stored as an `int`, so sorting by priority is sorting by the column.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type,
+)]
+#[serde(rename_all = "lowercase")]
+#[repr(i64)]
+pub enum Priority {
+    Low = 0,
+    #[default]
+    Normal = 1,
+    High = 2,
+    Urgent = 3,
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize, FromRow)]
 pub struct Task {
     pub id: i64,
     pub title: String,
     pub details: String,
+    pub owner_id: i64,
+    pub priority: Priority,
+    #[serde(default, with = "rfc3339::option")]
+    pub due_at: Option<i64>,
+    #[serde(with = "rfc3339")]
+    pub created_at: i64,
+    #[serde(with = "rfc3339")]
+    pub updated_at: i64,
+    /// When it was last marked as done, `None` while it's ongoing.
+    #[serde(default, with = "rfc3339::option")]
+    pub completed_at: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct InsertTask {
     pub non_empty_title: String,
     pub details: String,
+    #[serde(default)]
+    pub priority: Priority,
+    #[serde(default, with = "rfc3339::option")]
+    pub due_at: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -28,6 +61,23 @@ pub struct UpdateTask {
     pub id: i64,
     pub new_title: String,
     pub details: String,
+    /// NOTE(alex): Left out, it keeps the priority the task has.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    /// Left out, it keeps the due date the task has, `null` clears it.
+    #[serde(
+        default,
+        with = "rfc3339::nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub due_at: Option<Option<i64>>,
+}
+
+/// Query string of `GET /tasks/due`, the tasks due in the next `days`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QueryDue {
+    pub days: u32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -37,11 +87,17 @@ pub struct QueryTask {
 }
 
 impl InsertTask {
-    pub async fn insert(self, db_pool: &SqlitePool) -> Result<Task, AppError> {
+    pub async fn insert(self, db_pool: &SqlitePool, owner_id: i64) -> Result<Task, AppError> {
+        let created_at = timestamps::now();
+
         let mut connection = db_pool.acquire().await?;
         let result = sqlx::query(INSERT)
             .bind(&self.non_empty_title)
             .bind(&self.details)
+            .bind(owner_id)
+            .bind(created_at)
+            .bind(self.due_at)
+            .bind(self.priority)
             .execute(&mut connection)
             .await?;
 
@@ -49,36 +105,92 @@ impl InsertTask {
             id: result.last_insert_rowid(),
             title: self.non_empty_title,
             details: self.details,
+            owner_id,
+            priority: self.priority,
+            due_at: self.due_at,
+            created_at,
+            updated_at: created_at,
+            completed_at: None,
         };
 
         Ok(task)
     }
-
-    fn validate(self) -> Result<Self, TaskError> {
-        if self.non_empty_title.trim().is_empty() {
-            Err(TaskError::EmptyTitle)
-        } else {
-            Ok(self)
-        }
-    }
 }
 
 impl UpdateTask {
-    pub async fn update(self, db_pool: &SqlitePool) -> Result<u64, AppError> {
+    pub async fn update(self, db_pool: &SqlitePool, owner_id: i64) -> Result<u64, AppError> {
+        let task = match Task::find_by_id(db_pool, self.id, owner_id).await? {
+            Some(task) => task,
+            None => return Ok(0),
+        };
+
+        let priority = self.priority.unwrap_or(task.priority);
+        let due_at = self.due_at.unwrap_or(task.due_at);
+
+        // NOTE(alex): Only a `due_at` that changes has to be in the future, a task that is already
+        // overdue can still be renamed.
+        if due_at != task.due_at
+            && due_at
+                .filter(|due_at| *due_at < timestamps::now())
+                .is_some()
+        {
+            return Err(TaskError::DueInPast.into());
+        }
+
         let mut connection = db_pool.acquire().await?;
         let result = sqlx::query(UPDATE)
             .bind(&self.new_title)
             .bind(&self.details)
+            .bind(due_at)
+            .bind(priority)
+            .bind(timestamps::now())
             .bind(&self.id)
+            .bind(owner_id)
             .execute(&mut connection)
             .await?;
 
         Ok(result.rows_affected())
     }
+}
+
+impl Validate for InsertTask {
+    fn check(&self, config: &ValidationConfig, errors: &mut FieldErrors) {
+        check_task(&self.non_empty_title, &self.details, config, errors);
+
+        if self
+            .due_at
+            .filter(|due_at| *due_at < timestamps::now())
+            .is_some()
+        {
+            errors.push(TaskError::DueInPast);
+        }
+    }
+}
+
+/// NOTE(alex): The `due_at` is checked by `update`, it needs the task as it is to tell if it
+/// changed.
+impl Validate for UpdateTask {
+    fn check(&self, config: &ValidationConfig, errors: &mut FieldErrors) {
+        check_task(&self.new_title, &self.details, config, errors);
+    }
+}
+
+fn check_task(title: &str, details: &str, config: &ValidationConfig, errors: &mut FieldErrors) {
+    if title.trim().is_empty() {
+        errors.push(TaskError::EmptyTitle);
+    } else if length(title) > config.max_title_length {
+        errors.push(TaskError::TitleLength(config.max_title_length));
+    }
 
+    if length(details) > config.max_details_length {
+        errors.push(TaskError::DetailsLength(config.max_details_length));
+    }
+}
+
+impl QueryDue {
     fn validate(self) -> Result<Self, TaskError> {
-        if self.new_title.trim().is_empty() {
-            Err(TaskError::EmptyTitle)
+        if self.days == 0 || self.days > MAX_DUE_DAYS {
+            Err(TaskError::DueDays)
         } else {
             Ok(self)
         }
@@ -86,51 +198,80 @@ impl UpdateTask {
 }
 
 impl Task {
-    pub async fn delete(db_pool: &SqlitePool, task_id: i64) -> Result<u64, AppError> {
+    pub async fn delete(
+        db_pool: &SqlitePool,
+        task_id: i64,
+        owner_id: i64,
+    ) -> Result<u64, AppError> {
         let mut connection = db_pool.acquire().await?;
         let result = sqlx::query(DELETE)
             .bind(task_id)
+            .bind(owner_id)
             .execute(&mut connection)
             .await?;
 
         Ok(result.rows_affected())
     }
 
-    pub async fn done(pool: &SqlitePool, task_id: i64) -> Result<i64, AppError> {
-        let mut connection = pool.acquire().await?;
+    /// Returns `false` if `owner_id` has no such task, and `TaskError::AlreadyDone` if it's done
+    /// (`undo` it first).
+    ///
+    /// NOTE(alex): The insert itself refuses a task that is done, only when nothing was inserted do
+    /// we look for the reason.
+    pub async fn done(pool: &SqlitePool, task_id: i64, owner_id: i64) -> Result<bool, AppError> {
         let result = sqlx::query(DONE)
             .bind(task_id)
-            .execute(&mut connection)
+            .bind(owner_id)
+            .bind(timestamps::now())
+            .execute(pool)
             .await?;
 
-        Ok(result.last_insert_rowid())
+        if result.rows_affected() > 0 {
+            return Ok(true);
+        }
+
+        match Self::find_by_id(pool, task_id, owner_id).await? {
+            Some(_) => Err(TaskError::AlreadyDone(task_id).into()),
+            None => Ok(false),
+        }
     }
 
-    pub async fn undo(db_pool: &SqlitePool, task_id: i64) -> Result<u64, AppError> {
+    pub async fn undo(db_pool: &SqlitePool, task_id: i64, owner_id: i64) -> Result<u64, AppError> {
         let mut connection = db_pool.acquire().await?;
         let result = sqlx::query(UNDO)
             .bind(task_id)
+            .bind(owner_id)
             .execute(&mut connection)
             .await;
 
         Ok(result?.rows_affected())
     }
 
-    pub async fn find_all(db_pool: &SqlitePool) -> Result<Vec<Self>, AppError> {
-        let result = sqlx::query_as(FIND_ALL).fetch_all(db_pool).await?;
+    pub async fn find_all(db_pool: &SqlitePool, owner_id: i64) -> Result<Vec<Self>, AppError> {
+        let result = sqlx::query_as(FIND_ALL)
+            .bind(owner_id)
+            .fetch_all(db_pool)
+            .await?;
+
         Ok(result)
     }
 
-    pub async fn find_ongoing(db_pool: &SqlitePool) -> Result<Vec<Self>, AppError> {
-        let result = sqlx::query_as(FIND_ONGOING).fetch_all(db_pool).await?;
+    pub async fn find_ongoing(db_pool: &SqlitePool, owner_id: i64) -> Result<Vec<Self>, AppError> {
+        let result = sqlx::query_as(FIND_ONGOING)
+            .bind(owner_id)
+            .fetch_all(db_pool)
+            .await?;
+
         Ok(result)
     }
 
     pub async fn find_by_pattern(
         db_pool: &SqlitePool,
         search_pattern: &str,
+        owner_id: i64,
     ) -> Result<Vec<Self>, AppError> {
         let result = sqlx::query_as(FIND_BY_PATTERN)
+            .bind(owner_id)
             .bind(search_pattern)
             .fetch_all(db_pool)
             .await?;
@@ -138,9 +279,56 @@ impl Task {
         Ok(result)
     }
 
-    pub async fn find_by_id(db_pool: &SqlitePool, task_id: i64) -> Result<Option<Self>, AppError> {
+    /// Most urgent first.
+    pub async fn find_by_priority(
+        db_pool: &SqlitePool,
+        owner_id: i64,
+    ) -> Result<Vec<Self>, AppError> {
+        let result = sqlx::query_as(FIND_BY_PRIORITY)
+            .bind(owner_id)
+            .fetch_all(db_pool)
+            .await?;
+
+        Ok(result)
+    }
+
+    /// Ongoing tasks that should've been done already, the most late first.
+    pub async fn find_overdue(db_pool: &SqlitePool, owner_id: i64) -> Result<Vec<Self>, AppError> {
+        let result = sqlx::query_as(FIND_OVERDUE)
+            .bind(owner_id)
+            .bind(timestamps::now())
+            .fetch_all(db_pool)
+            .await?;
+
+        Ok(result)
+    }
+
+    /// Ongoing tasks due from now until `days` from now, the closest first.
+    pub async fn find_due_within(
+        db_pool: &SqlitePool,
+        days: u32,
+        owner_id: i64,
+    ) -> Result<Vec<Self>, AppError> {
+        let now = timestamps::now();
+
+        let result = sqlx::query_as(FIND_DUE_WITHIN)
+            .bind(owner_id)
+            .bind(now)
+            .bind(now + i64::from(days) * 24 * 60 * 60)
+            .fetch_all(db_pool)
+            .await?;
+
+        Ok(result)
+    }
+
+    pub async fn find_by_id(
+        db_pool: &SqlitePool,
+        task_id: i64,
+        owner_id: i64,
+    ) -> Result<Option<Self>, AppError> {
         let result = sqlx::query_as(FIND_BY_ID)
             .bind(task_id)
+            .bind(owner_id)
             .fetch_optional(db_pool)
             .await?;
 
@@ -166,34 +354,20 @@ impl Responder for Task {
     }
 }
 
-impl FromRequest for InsertTask {
+impl FromRequest for QueryDue {
     type Error = AppError;
 
-    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
-
-    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-        JsonBody::new(req, payload, None, false)
-            .limit(4056)
-            .map(|res: Result<InsertTask, _>| match res {
-                Ok(insert_task) => insert_task.validate().map_err(|fail| AppError::from(fail)),
-                Err(fail) => Err(AppError::from(fail)),
-            })
-            .boxed_local()
-    }
-}
-
-impl FromRequest for UpdateTask {
-    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
 
-    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let query_due = match Query::<QueryDue>::from_query(req.query_string()) {
+            Ok(query_due) => query_due
+                .into_inner()
+                .validate()
+                .map_err(|fail| AppError::from(fail)),
+            Err(fail) => Err(AppError::from(actix_web::Error::from(fail))),
+        };
 
-    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-        JsonBody::new(req, payload, None, false)
-            .limit(4056)
-            .map(|res: Result<UpdateTask, _>| match res {
-                Ok(update_task) => update_task.validate().map_err(|fail| AppError::from(fail)),
-                Err(fail) => Err(AppError::from(fail)),
-            })
-            .boxed_local()
+        ready(query_due)
     }
 }
@@ -1,2 +1,8 @@
-insert into Done (task_id)
-values ($1)
\ No newline at end of file
+-- NOTE(alex): The `not exists` is what keeps a task from being done twice, two requests racing
+-- each other can't both get past it (see `Task::done`).
+insert into Done (task_id, completed_at)
+select Task.id, $3
+from Task
+where
+    Task.id = $1 and Task.owner_id = $2
+    and not exists (select 1 from Done where Done.task_id = $1)
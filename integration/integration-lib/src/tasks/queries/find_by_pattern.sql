@@ -1,4 +1,4 @@
 select *
-from Task
+from TaskView as Task
 where
-    Task.title like $1;
\ No newline at end of file
+    Task.owner_id = $1 and Task.title like $2;
\ No newline at end of file
@@ -0,0 +1,5 @@
+-- NOTE(alex): Most urgent first, ties go to the oldest task.
+select *
+from TaskView as Task
+where Task.owner_id = $1
+order by Task.priority desc, Task.id;
\ No newline at end of file
@@ -0,0 +1,8 @@
+select *
+from TaskView as Task
+where
+    Task.owner_id = $1
+    and Task.completed_at is null
+    and Task.due_at >= $2
+    and Task.due_at < $3
+order by Task.due_at, Task.id
\ No newline at end of file
@@ -0,0 +1,8 @@
+-- NOTE(alex): Oldest due date first, it is the most late.
+select *
+from TaskView as Task
+where
+    Task.owner_id = $1
+    and Task.completed_at is null
+    and Task.due_at < $2
+order by Task.due_at, Task.id
\ No newline at end of file
@@ -1,2 +1,2 @@
-insert into Task (title, details)
-values ($1, $2);
\ No newline at end of file
+insert into Task (title, details, owner_id, created_at, updated_at, due_at, priority)
+values ($1, $2, $3, $4, $4, $5, $6);
\ No newline at end of file
@@ -1,4 +1,8 @@
 update Task
 set title = $1,
-    details = $2
-where Task.id = $3
\ No newline at end of file
+    details = $2,
+    due_at = $3,
+    priority = $4,
+    updated_at = $5
+where
+    Task.id = $6 and Task.owner_id = $7
\ No newline at end of file
@@ -1,29 +1,36 @@
 use actix_session::Session;
-use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+use actix_web::{delete, get, http::header, post, put, web, HttpResponse, Responder};
 use actix_web_httpauth::middleware::HttpAuthentication;
 use sqlx::SqlitePool;
 
 use super::{errors::*, models::*};
-use crate::{errors::AppError, validator};
+use crate::{errors::AppError, users::models::LoggedUser, validation::Validated, validator};
 
 #[post("/tasks", wrap = "HttpAuthentication::bearer(validator)")]
 pub async fn insert(
     db_pool: web::Data<SqlitePool>,
-    input: InsertTask,
+    logged_user: LoggedUser,
+    Validated(input): Validated<InsertTask>,
 ) -> Result<impl Responder, AppError> {
-    let task = input.insert(db_pool.get_ref()).await?;
-    Ok(HttpResponse::Created().json(task))
+    let task = input.insert(db_pool.get_ref(), logged_user.id).await?;
+    Ok(HttpResponse::Created()
+        .insert_header((header::LOCATION, format!("/tasks/{}", task.id)))
+        .json(task))
 }
 
 #[put("/tasks", wrap = "HttpAuthentication::bearer(validator)")]
 pub async fn update(
     db_pool: web::Data<SqlitePool>,
-    input: UpdateTask,
+    logged_user: LoggedUser,
+    Validated(input): Validated<UpdateTask>,
 ) -> Result<impl Responder, AppError> {
-    let num_modified = input.update(db_pool.get_ref()).await?;
+    let task_id = input.id;
+    let num_modified = input.update(db_pool.get_ref(), logged_user.id).await?;
 
+    // NOTE(alex): A `Task` that belongs to someone else is reported as not found, we don't want to
+    // leak which ids exist.
     if num_modified == 0 {
-        Ok(HttpResponse::NotModified().body("No tasks were updated."))
+        Err(TaskError::NotFound(task_id).into())
     } else {
         Ok(HttpResponse::Ok().body(format!("Updated {} tasks.", num_modified)))
     }
@@ -32,79 +39,113 @@ pub async fn update(
 #[delete("/tasks/{id}", wrap = "HttpAuthentication::bearer(validator)")]
 pub async fn delete(
     db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
     id: web::Path<i64>,
 ) -> Result<impl Responder, AppError> {
-    let num_modified = Task::delete(db_pool.get_ref(), *id).await?;
+    let num_modified = Task::delete(db_pool.get_ref(), *id, logged_user.id).await?;
 
     if num_modified == 0 {
-        Ok(HttpResponse::NotModified().body("No tasks were deleted."))
+        Err(TaskError::NotFound(*id).into())
     } else {
-        Ok(HttpResponse::Ok().body(format!("Deleted {} tasks.", num_modified)))
+        Ok(HttpResponse::NoContent().finish())
     }
 }
 
 #[post("/tasks/{id}/done", wrap = "HttpAuthentication::bearer(validator)")]
 pub async fn done(
     db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
     id: web::Path<i64>,
 ) -> Result<impl Responder, AppError> {
-    let done_id = Task::done(db_pool.get_ref(), *id).await?;
-
-    if done_id == 0 {
-        Ok(HttpResponse::NotModified().body(format!("Task with id {} not done.", id)))
+    // NOTE(alex): Being done is a state of the task, not a resource of its own, so there is
+    // nothing to point a `Location` at.
+    if Task::done(db_pool.get_ref(), *id, logged_user.id).await? {
+        Ok(HttpResponse::NoContent().finish())
     } else {
-        Ok(HttpResponse::Created().body(done_id.to_string()))
+        Err(TaskError::NotFound(*id).into())
     }
 }
 
 #[delete("/tasks/{id}/undo", wrap = "HttpAuthentication::bearer(validator)")]
 pub async fn undo(
     db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
     id: web::Path<i64>,
 ) -> Result<impl Responder, AppError> {
-    let num_modified = Task::undo(db_pool.get_ref(), *id).await?;
-
-    if num_modified == 0 {
-        Ok(HttpResponse::NotModified().body(format!("Task with id {} not undone.", id)))
-    } else {
-        Ok(HttpResponse::Ok().body(format!("Undone {} tasks.", num_modified)))
+    if Task::find_by_id(db_pool.get_ref(), *id, logged_user.id)
+        .await?
+        .is_none()
+    {
+        return Err(TaskError::NotFound(*id).into());
     }
+
+    // NOTE(alex): Undoing a task that isn't done leaves it the way the client wants it, so it's
+    // the same `204` either way.
+    Task::undo(db_pool.get_ref(), *id, logged_user.id).await?;
+    Ok(HttpResponse::NoContent().finish())
 }
 
-#[get("/tasks")]
-pub async fn find_all(db_pool: web::Data<SqlitePool>) -> Result<impl Responder, AppError> {
-    let tasks = Task::find_all(db_pool.get_ref()).await?;
+#[get("/tasks", wrap = "HttpAuthentication::bearer(validator)")]
+pub async fn find_all(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+) -> Result<impl Responder, AppError> {
+    let tasks = Task::find_all(db_pool.get_ref(), logged_user.id).await?;
+    Ok(HttpResponse::Ok().json(&tasks))
+}
 
-    if tasks.is_empty() {
-        Err(TaskError::Empty.into())
-    } else {
-        Ok(HttpResponse::Found().json(&tasks))
-    }
+#[get("/tasks/ongoing", wrap = "HttpAuthentication::bearer(validator)")]
+pub async fn find_ongoing(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+) -> Result<impl Responder, AppError> {
+    let tasks = Task::find_ongoing(db_pool.get_ref(), logged_user.id).await?;
+    Ok(HttpResponse::Ok().json(&tasks))
+}
+
+#[get("/tasks/by-priority", wrap = "HttpAuthentication::bearer(validator)")]
+pub async fn find_by_priority(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+) -> Result<impl Responder, AppError> {
+    let tasks = Task::find_by_priority(db_pool.get_ref(), logged_user.id).await?;
+    Ok(HttpResponse::Ok().json(&tasks))
 }
 
-#[get("/tasks/ongoing")]
-pub async fn find_ongoing(db_pool: web::Data<SqlitePool>) -> Result<impl Responder, AppError> {
-    let tasks = Task::find_ongoing(db_pool.get_ref()).await?;
+#[get("/tasks/overdue", wrap = "HttpAuthentication::bearer(validator)")]
+pub async fn find_overdue(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+) -> Result<impl Responder, AppError> {
+    let tasks = Task::find_overdue(db_pool.get_ref(), logged_user.id).await?;
+    Ok(HttpResponse::Ok().json(&tasks))
+}
 
-    if tasks.is_empty() {
-        Err(TaskError::Empty.into())
-    } else {
-        Ok(HttpResponse::Found().json(&tasks))
-    }
+#[get("/tasks/due", wrap = "HttpAuthentication::bearer(validator)")]
+pub async fn find_due(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    query: QueryDue,
+) -> Result<impl Responder, AppError> {
+    let tasks = Task::find_due_within(db_pool.get_ref(), query.days, logged_user.id).await?;
+    Ok(HttpResponse::Ok().json(&tasks))
 }
 
-#[get("/tasks")]
+/// NOTE(alex): Not `GET /tasks`, that one is `find_all`, and the first route registered for a path
+/// is the only one ever reached.
+#[get("/tasks/search", wrap = "HttpAuthentication::bearer(validator)")]
 pub async fn find_by_pattern(
     db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
     pattern: web::Query<QueryTask>,
 ) -> Result<impl Responder, AppError> {
-    let tasks = Task::find_by_pattern(db_pool.get_ref(), &format!("%{}%", pattern.title)).await?;
-
-    if tasks.is_empty() {
-        Err(TaskError::Empty.into())
-    } else {
-        Ok(HttpResponse::Found().json(&tasks))
-    }
+    let tasks = Task::find_by_pattern(
+        db_pool.get_ref(),
+        &format!("%{}%", pattern.title),
+        logged_user.id,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(&tasks))
 }
 
 /// NOTE(alex): Regex to match only digits, otherwise it matches the "/tasks/favorite" find route.
@@ -116,24 +157,26 @@ pub async fn find_by_pattern(
 // is the equivalent of the `[^/]+` regex.
 ///
 /// There is a 3rd way of sorts, which boils down to: avoid possible route conflicting paths.
-#[get("/tasks/{id:\\d+}")]
+#[get("/tasks/{id:\\d+}", wrap = "HttpAuthentication::bearer(validator)")]
 pub async fn find_by_id(
     db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
     id: web::Path<i64>,
 ) -> Result<impl Responder, AppError> {
-    let task = Task::find_by_id(db_pool.get_ref(), *id).await?;
+    let task = Task::find_by_id(db_pool.get_ref(), *id, logged_user.id).await?;
 
     match task {
-        Some(task) => Ok(HttpResponse::Found().json(task)),
+        Some(task) => Ok(HttpResponse::Ok().json(task)),
         None => Err(TaskError::NotFound(*id).into()),
     }
 }
 
 const FAVORITE_TASK_STR: &'static str = "favorite_task";
 
-#[post("/tasks/favorite/{id}")]
+#[post("/tasks/favorite/{id}", wrap = "HttpAuthentication::bearer(validator)")]
 pub async fn favorite(
     db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
     session: Session,
     id: web::Path<i64>,
 ) -> Result<impl Responder, AppError> {
@@ -142,21 +185,21 @@ pub async fn favorite(
 
         if old_favorite.id == *id {
             // NOTE(alex): Just remove the task, this is basically "unfavorite".
-            Ok(HttpResponse::NoContent().body(format!("Task {} unfavorited", old_favorite.id)))
+            Ok(HttpResponse::NoContent().finish())
         } else {
-            match Task::find_by_id(&db_pool, *id).await? {
+            match Task::find_by_id(&db_pool, *id, logged_user.id).await? {
                 Some(task) => {
                     session.insert(FAVORITE_TASK_STR, task.clone())?;
-                    Ok(HttpResponse::Found().json(task))
+                    Ok(HttpResponse::Ok().json(task))
                 }
                 None => Err(TaskError::NotFound(*id).into()),
             }
         }
     } else {
-        match Task::find_by_id(&db_pool, *id).await? {
+        match Task::find_by_id(&db_pool, *id, logged_user.id).await? {
             Some(task) => {
                 session.insert(FAVORITE_TASK_STR, task.clone())?;
-                Ok(HttpResponse::Found().json(task))
+                Ok(HttpResponse::Ok().json(task))
             }
             None => Err(TaskError::NoneFavorite.into()),
         }
@@ -166,7 +209,7 @@ pub async fn favorite(
 #[get("/tasks/favorite")]
 pub async fn find_favorite(session: Session) -> Result<impl Responder, AppError> {
     if let Some(task) = session.get::<Task>(FAVORITE_TASK_STR)? {
-        Ok(HttpResponse::Found().json(task))
+        Ok(HttpResponse::Ok().json(task))
     } else {
         Err(TaskError::NoneFavorite.into())
     }
@@ -181,6 +224,9 @@ pub fn task_service(cfg: &mut web::ServiceConfig) {
     cfg.service(find_all);
     cfg.service(find_ongoing);
     cfg.service(find_by_pattern);
+    cfg.service(find_by_priority);
+    cfg.service(find_overdue);
+    cfg.service(find_due);
     cfg.service(find_by_id);
     cfg.service(favorite);
     cfg.service(find_favorite);
@@ -0,0 +1,87 @@
+// NOTE(alex): Timestamps are stored as unix seconds (`sqlx` 0.5 only knows about `time` 0.2), and
+// only become RFC 3339 strings when going through JSON. Use it as `#[serde(with = "rfc3339")]`,
+// or `#[serde(default, with = "rfc3339::option")]` for the optional ones (`rfc3339::nullable` when
+// a `null` has to be told apart from a missing field).
+use time::OffsetDateTime;
+
+pub fn now() -> i64 {
+    OffsetDateTime::now_utc().unix_timestamp()
+}
+
+pub mod rfc3339 {
+    use serde::{de, ser, Deserialize, Deserializer, Serializer};
+    use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+    pub fn serialize<S>(timestamp: &i64, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let formatted = OffsetDateTime::from_unix_timestamp(*timestamp)
+            .map_err(ser::Error::custom)?
+            .format(&Rfc3339)
+            .map_err(ser::Error::custom)?;
+
+        serializer.serialize_str(&formatted)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<i64, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let formatted = String::deserialize(deserializer)?;
+
+        OffsetDateTime::parse(&formatted, &Rfc3339)
+            .map(|date_time| date_time.unix_timestamp())
+            .map_err(de::Error::custom)
+    }
+
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S>(timestamp: &Option<i64>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            match timestamp {
+                Some(timestamp) => super::serialize(timestamp, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<i64>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            #[derive(Deserialize)]
+            struct Wrapper(#[serde(with = "super")] i64);
+
+            Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|Wrapper(timestamp)| timestamp))
+        }
+    }
+
+    pub mod nullable {
+        use serde::{Deserializer, Serializer};
+
+        pub fn serialize<S>(
+            timestamp: &Option<Option<i64>>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            match timestamp {
+                Some(timestamp) => super::option::serialize(timestamp, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        /// NOTE(alex): Only called when the field is there, `#[serde(default)]` takes care of the
+        /// missing one.
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Option<i64>>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            super::option::deserialize(deserializer).map(Some)
+        }
+    }
+}
@@ -1,5 +1,6 @@
 pub mod errors;
 pub mod models;
+pub mod password;
 pub mod routes;
 
 const FIND_ALL: &'static str = include_str!("./users/queries/find_all.sql");
@@ -8,3 +9,4 @@ const INSERT: &'static str = include_str!("./users/queries/insert.sql");
 const UPDATE: &'static str = include_str!("./users/queries/update.sql");
 const DELETE: &'static str = include_str!("./users/queries/delete.sql");
 const LOGIN: &'static str = include_str!("./users/queries/login.sql");
+const UPDATE_PASSWORD: &'static str = include_str!("./users/queries/update_password.sql");
@@ -1,33 +1,33 @@
 use thiserror::Error;
 
-use super::models::MIN_USERNAME_LENGTH;
+use crate::validation::{PasswordComplexity, UsernameCharset};
 
 #[derive(Debug, Error)]
 pub enum UserError {
     #[error("`username` field of `User` cannot be empty!")]
     EmptyUsername,
 
-    #[error(
-        "`username` field of `User` must be at least {} characters!",
-        MIN_USERNAME_LENGTH
-    )]
-    UsernameLength,
+    #[error("`username` field of `User` must be between {min} and {max} characters!")]
+    UsernameLength { min: usize, max: usize },
 
-    #[error("`username` field of `User` cannot contain whitespaces!")]
-    UsernameInvalidCharacter,
+    #[error("`username` field of `User` can only contain {0}!")]
+    UsernameInvalidCharacter(UsernameCharset),
 
     #[error("`password` field of `User` cannot be empty!")]
     EmptyPassword,
 
-    #[error(
-        "`password` field of `User` must be at least {} characters!",
-        MIN_USERNAME_LENGTH
-    )]
-    PasswordLength,
+    #[error("`password` field of `User` must be between {min} and {max} characters!")]
+    PasswordLength { min: usize, max: usize },
 
     #[error("`password` field of `User` cannot contain whitespaces!")]
     PasswordInvalidCharacter,
 
+    #[error("`password` field of `User` must contain {0}!")]
+    WeakPassword(PasswordComplexity),
+
+    #[error("`username` `{0}` is already taken!")]
+    UsernameTaken(String),
+
     #[error("Could not find any `User` for id: `{0}`!")]
     NotFound(i64),
 
@@ -39,7 +39,21 @@ pub enum UserError {
 
     #[error("Invalid authorization token!")]
     InvalidToken,
+}
 
-    #[error("Could not find any `User`!")]
-    Empty,
+impl UserError {
+    /// The field of the request at fault, for the validation errors.
+    pub fn field(&self) -> Option<&'static str> {
+        match self {
+            UserError::EmptyUsername
+            | UserError::UsernameLength { .. }
+            | UserError::UsernameInvalidCharacter(_)
+            | UserError::UsernameTaken(_) => Some("username"),
+            UserError::EmptyPassword
+            | UserError::PasswordLength { .. }
+            | UserError::PasswordInvalidCharacter
+            | UserError::WeakPassword(_) => Some("password"),
+            _ => None,
+        }
+    }
 }
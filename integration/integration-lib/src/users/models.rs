@@ -1,23 +1,28 @@
 use actix_web::{
-    body::BoxBody,
-    dev::{JsonBody, Payload},
-    FromRequest, HttpRequest, HttpResponse, Responder,
+    body::BoxBody, dev::Payload, FromRequest, HttpMessage, HttpRequest, HttpResponse, Responder,
 };
-use futures::{future::LocalBoxFuture, FutureExt};
+use futures::future::{ready, Ready};
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, SqlitePool};
 
-use super::{errors::*, *};
-use crate::errors::AppError;
+use super::{errors::*, password::*, *};
+use crate::{
+    errors::AppError,
+    validation::{length, FieldErrors, Validate, ValidationConfig},
+};
 
 pub const MIN_USERNAME_LENGTH: usize = 3;
+/// Extended result code of a `unique` constraint failing.
+const SQLITE_CONSTRAINT_UNIQUE: &str = "2067";
 pub const MIN_PASSWORD_LENGTH: usize = 4;
 
 #[derive(Hash, PartialEq, Eq, Clone, Debug, Serialize, Deserialize, FromRow)]
 pub struct User {
     pub id: i64,
     pub username: String,
-    pub password: String,
+    /// NOTE(alex): The Argon2id PHC string, never sent back in a response.
+    #[serde(skip)]
+    pub password_hash: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -42,76 +47,111 @@ pub struct LoginUser {
 pub struct LoggedUser {
     pub id: i64,
     pub username: String,
-    pub password: String,
     pub token: u64,
 }
 
 impl InsertUser {
     pub async fn insert(self, db_pool: &SqlitePool) -> Result<User, AppError> {
+        let password_hash = hash_password(&self.valid_password)?;
+
         let mut connection = db_pool.acquire().await?;
         let result = sqlx::query(INSERT)
             .bind(&self.valid_username)
-            .bind(&self.valid_password)
+            .bind(&password_hash)
             .execute(&mut connection)
-            .await?;
+            .await
+            .map_err(|fail| username_taken(fail, &self.valid_username))?;
 
         let user = User {
             id: result.last_insert_rowid(),
             username: self.valid_username,
-            password: self.valid_password,
+            password_hash,
         };
 
         Ok(user)
     }
-
-    fn validate(self) -> Result<Self, UserError> {
-        if self.valid_username.trim().is_empty() {
-            Err(UserError::EmptyUsername)
-        } else if self.valid_username.len() < MIN_USERNAME_LENGTH {
-            Err(UserError::UsernameLength)
-        } else if self.valid_username.contains(" ") {
-            Err(UserError::UsernameInvalidCharacter)
-        } else if self.valid_password.trim().is_empty() {
-            Err(UserError::EmptyPassword)
-        } else if self.valid_password.len() < MIN_PASSWORD_LENGTH {
-            Err(UserError::PasswordLength)
-        } else if self.valid_password.contains(" ") {
-            Err(UserError::PasswordInvalidCharacter)
-        } else {
-            Ok(self)
-        }
-    }
 }
 
 impl UpdateUser {
     pub async fn update(self, db_pool: &SqlitePool) -> Result<u64, AppError> {
+        let password_hash = hash_password(&self.valid_password)?;
+
         let mut connection = db_pool.acquire().await?;
         let result = sqlx::query(UPDATE)
             .bind(&self.valid_username)
-            .bind(&self.valid_password)
+            .bind(&password_hash)
             .bind(&self.id)
             .execute(&mut connection)
-            .await?;
+            .await
+            .map_err(|fail| username_taken(fail, &self.valid_username))?;
 
         Ok(result.rows_affected())
     }
+}
 
-    fn validate(self) -> Result<Self, UserError> {
-        if self.valid_username.trim().is_empty() {
-            Err(UserError::EmptyUsername)
-        } else if self.valid_username.len() < MIN_USERNAME_LENGTH {
-            Err(UserError::UsernameLength)
-        } else if self.valid_username.contains(" ") {
-            Err(UserError::UsernameInvalidCharacter)
-        } else if self.valid_password.trim().is_empty() {
-            Err(UserError::EmptyPassword)
-        } else if self.valid_password.len() < MIN_PASSWORD_LENGTH {
-            Err(UserError::PasswordLength)
-        } else if self.valid_password.contains(" ") {
-            Err(UserError::PasswordInvalidCharacter)
-        } else {
-            Ok(self)
+/// NOTE(alex): `User.username` is unique (`login` relies on it), so an insert, or update, with a
+/// username that some other user has fails on the index, and that is the client's fault.
+fn username_taken(fail: sqlx::Error, username: &str) -> AppError {
+    match &fail {
+        sqlx::Error::Database(database_error)
+            if database_error.code().as_deref() == Some(SQLITE_CONSTRAINT_UNIQUE)
+                && database_error.message().contains("User.username") =>
+        {
+            UserError::UsernameTaken(username.to_string()).into()
         }
+        _ => fail.into(),
+    }
+}
+
+impl Validate for InsertUser {
+    fn check(&self, config: &ValidationConfig, errors: &mut FieldErrors) {
+        check_username(&self.valid_username, config, errors);
+        check_password(&self.valid_password, config, errors);
+    }
+}
+
+impl Validate for UpdateUser {
+    fn check(&self, config: &ValidationConfig, errors: &mut FieldErrors) {
+        check_username(&self.valid_username, config, errors);
+        check_password(&self.valid_password, config, errors);
+    }
+}
+
+fn check_username(username: &str, config: &ValidationConfig, errors: &mut FieldErrors) {
+    let username_length = length(username);
+
+    if username.trim().is_empty() {
+        errors.push(UserError::EmptyUsername);
+    } else if username_length < MIN_USERNAME_LENGTH || username_length > config.max_username_length
+    {
+        errors.push(UserError::UsernameLength {
+            min: MIN_USERNAME_LENGTH,
+            max: config.max_username_length,
+        });
+    } else if !username
+        .chars()
+        .all(|character| config.username_charset.allows(character))
+    {
+        errors.push(UserError::UsernameInvalidCharacter(config.username_charset));
+    }
+}
+
+fn check_password(password: &str, config: &ValidationConfig, errors: &mut FieldErrors) {
+    let password_length = length(password);
+
+    if password.trim().is_empty() {
+        errors.push(UserError::EmptyPassword);
+    } else if password_length < config.min_password_length
+        || password_length > config.max_password_length
+    {
+        errors.push(UserError::PasswordLength {
+            min: config.min_password_length,
+            max: config.max_password_length,
+        });
+    } else if password.chars().any(char::is_whitespace) {
+        errors.push(UserError::PasswordInvalidCharacter);
+    } else if !config.password_complexity.allows(password) {
+        errors.push(UserError::WeakPassword(config.password_complexity));
     }
 }
 
@@ -144,7 +184,6 @@ impl User {
         LoggedUser {
             id: self.id,
             username: self.username,
-            password: self.password,
             token,
         }
     }
@@ -152,13 +191,32 @@ impl User {
 
 impl LoginUser {
     pub async fn login(self, db_pool: &SqlitePool) -> Result<Option<User>, AppError> {
-        let result = sqlx::query_as(LOGIN)
-            .bind(self.username)
-            .bind(self.password)
+        let user: Option<User> = sqlx::query_as(LOGIN)
+            .bind(&self.username)
             .fetch_optional(db_pool)
             .await?;
 
-        Ok(result)
+        match user {
+            Some(mut user) if verify_password(&self.password, &user.password_hash)? => {
+                // NOTE(alex): The password is correct, so this is our chance to upgrade a hash
+                // that was created with outdated parameters.
+                if needs_rehash(&user.password_hash)? {
+                    let password_hash = hash_password(&self.password)?;
+
+                    let mut connection = db_pool.acquire().await?;
+                    sqlx::query(UPDATE_PASSWORD)
+                        .bind(&password_hash)
+                        .bind(user.id)
+                        .execute(&mut connection)
+                        .await?;
+
+                    user.password_hash = password_hash;
+                }
+
+                Ok(Some(user))
+            }
+            _ => Ok(None),
+        }
     }
 }
 
@@ -180,34 +238,20 @@ impl Responder for User {
     }
 }
 
-impl FromRequest for InsertUser {
+/// NOTE(alex): Only available on routes wrapped by `validator`, which is responsible for putting the
+/// `LoggedUser` in the request extensions.
+impl FromRequest for LoggedUser {
     type Error = AppError;
 
-    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
-
-    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-        JsonBody::new(req, payload, None, false)
-            .limit(4056)
-            .map(|res: Result<InsertUser, _>| match res {
-                Ok(insert_user) => insert_user.validate().map_err(|fail| AppError::from(fail)),
-                Err(fail) => Err(AppError::from(fail)),
-            })
-            .boxed_local()
-    }
-}
-
-impl FromRequest for UpdateUser {
-    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
 
-    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let logged_user = req
+            .extensions()
+            .get::<LoggedUser>()
+            .cloned()
+            .ok_or_else(|| UserError::NotLoggedIn.into());
 
-    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-        JsonBody::new(req, payload, None, false)
-            .limit(4056)
-            .map(|res: Result<UpdateUser, _>| match res {
-                Ok(update_user) => update_user.validate().map_err(|fail| AppError::from(fail)),
-                Err(fail) => Err(AppError::from(fail)),
-            })
-            .boxed_local()
+        ready(logged_user)
     }
 }
@@ -0,0 +1,54 @@
+use std::convert::TryFrom;
+
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+
+use crate::errors::AppError;
+
+/// NOTE(alex): The hasher used for every new password. Changing the parameters here is enough to
+/// have old hashes upgraded, as `needs_rehash` will flag them on the user's next login.
+fn hasher() -> Argon2<'static> {
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::default())
+}
+
+/// Hashes `password` with a fresh random salt, returning the PHC string that goes into the `User`
+/// table (`$argon2id$v=19$m=...,t=...,p=...$<salt>$<hash>`).
+pub fn hash_password(password: &str) -> Result<String, AppError> {
+    let salt = SaltString::generate(&mut OsRng);
+    let password_hash = hasher().hash_password(password.as_bytes(), &salt)?;
+
+    Ok(password_hash.to_string())
+}
+
+/// Checks `password` against the stored PHC string. The parameters used for verification come
+/// from the PHC string itself, so hashes created with older parameters still verify.
+pub fn verify_password(password: &str, password_hash: &str) -> Result<bool, AppError> {
+    let parsed_hash = PasswordHash::new(password_hash)?;
+
+    match hasher().verify_password(password.as_bytes(), &parsed_hash) {
+        Ok(()) => Ok(true),
+        Err(argon2::password_hash::Error::Password) => Ok(false),
+        Err(fail) => Err(fail.into()),
+    }
+}
+
+/// Returns `true` when the stored hash was created with a different algorithm, version or set of
+/// parameters than the ones `hash_password` currently uses.
+pub fn needs_rehash(password_hash: &str) -> Result<bool, AppError> {
+    let parsed_hash = PasswordHash::new(password_hash)?;
+    let current = hasher();
+
+    let same_algorithm = parsed_hash.algorithm == Algorithm::Argon2id.ident();
+    let same_version = parsed_hash.version == Some(Version::V0x13.into());
+    let same_params = Params::try_from(&parsed_hash)
+        .map(|params| {
+            params.m_cost() == current.params().m_cost()
+                && params.t_cost() == current.params().t_cost()
+                && params.p_cost() == current.params().p_cost()
+        })
+        .unwrap_or(false);
+
+    Ok(!(same_algorithm && same_version && same_params))
+}
@@ -1,2 +1,2 @@
-insert into User (username, password)
+insert into User (username, password_hash)
 values ($1, $2);
\ No newline at end of file
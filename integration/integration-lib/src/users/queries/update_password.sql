@@ -0,0 +1,3 @@
+update User
+set password_hash = $1
+where User.id = $2
\ No newline at end of file
@@ -1,5 +1,5 @@
 use actix_identity::Identity;
-use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+use actix_web::{delete, get, http::header, post, put, web, HttpResponse, Responder};
 use actix_web_httpauth::middleware::HttpAuthentication;
 use sqlx::SqlitePool;
 
@@ -7,26 +7,29 @@ use super::{
     errors::UserError,
     models::{InsertUser, LoginUser, UpdateUser, User},
 };
-use crate::{errors::AppError, validator};
+use crate::{errors::AppError, validation::Validated, validator};
 
 #[post("/users/register")]
 pub async fn insert(
     db_pool: web::Data<SqlitePool>,
-    input: InsertUser,
+    Validated(input): Validated<InsertUser>,
 ) -> Result<impl Responder, AppError> {
     let user = input.insert(db_pool.get_ref()).await?;
-    Ok(HttpResponse::Created().json(user))
+    Ok(HttpResponse::Created()
+        .insert_header((header::LOCATION, format!("/users/{}", user.id)))
+        .json(user))
 }
 
 #[put("/users", wrap = "HttpAuthentication::bearer(validator)")]
 pub async fn update(
     db_pool: web::Data<SqlitePool>,
-    input: UpdateUser,
+    Validated(input): Validated<UpdateUser>,
 ) -> Result<impl Responder, AppError> {
+    let user_id = input.id;
     let num_modified = input.update(db_pool.get_ref()).await?;
 
     if num_modified == 0 {
-        Ok(HttpResponse::NotModified().body("No users were updated."))
+        Err(UserError::NotFound(user_id).into())
     } else {
         Ok(HttpResponse::Ok().body(format!("Updated {} users.", num_modified)))
     }
@@ -40,21 +43,16 @@ pub async fn delete(
     let num_modified = User::delete(db_pool.get_ref(), *id).await?;
 
     if num_modified == 0 {
-        Ok(HttpResponse::NotModified().body("No users were deleted."))
+        Err(UserError::NotFound(*id).into())
     } else {
-        Ok(HttpResponse::Ok().body(format!("Deleted {} users.", num_modified)))
+        Ok(HttpResponse::NoContent().finish())
     }
 }
 
 #[get("/users")]
 pub async fn find_all(db_pool: web::Data<SqlitePool>) -> Result<impl Responder, AppError> {
     let users = User::find_all(db_pool.get_ref()).await?;
-
-    if users.is_empty() {
-        Err(UserError::Empty.into())
-    } else {
-        Ok(HttpResponse::Found().json(&users))
-    }
+    Ok(HttpResponse::Ok().json(&users))
 }
 
 #[get("/users/{id:\\d+}")]
@@ -65,7 +63,7 @@ pub async fn find_by_id(
     let user = User::find_by_id(db_pool.get_ref(), *id).await?;
 
     match user {
-        Some(user) => Ok(HttpResponse::Found().json(user)),
+        Some(user) => Ok(HttpResponse::Ok().json(user)),
         None => Err(UserError::NotFound(*id).into()),
     }
 }
@@ -0,0 +1,300 @@
+use std::{fmt, ops::Deref};
+
+use actix_web::{
+    dev::Payload,
+    http::header,
+    web::{self, Bytes, BytesMut},
+    FromRequest, HttpMessage, HttpRequest,
+};
+use futures::{
+    future::{ready, LocalBoxFuture, Ready},
+    FutureExt, StreamExt,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use self::errors::BodyError;
+use crate::{
+    errors::{AppError, FieldError},
+    users::models::MIN_PASSWORD_LENGTH,
+};
+
+pub mod errors;
+
+/// Which characters a username may have.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsernameCharset {
+    /// Anything but whitespace.
+    #[default]
+    Any,
+    /// ASCII letters, digits, `_`, `-` and `.`.
+    Ascii,
+}
+
+impl UsernameCharset {
+    pub fn allows(&self, character: char) -> bool {
+        match self {
+            UsernameCharset::Any => !character.is_whitespace(),
+            UsernameCharset::Ascii => {
+                character.is_ascii_alphanumeric() || matches!(character, '_' | '-' | '.')
+            }
+        }
+    }
+}
+
+impl fmt::Display for UsernameCharset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UsernameCharset::Any => write!(f, "anything but whitespaces"),
+            UsernameCharset::Ascii => write!(f, "ASCII letters, digits, `_`, `-` and `.`"),
+        }
+    }
+}
+
+/// What a password must have, on top of its length.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PasswordComplexity {
+    #[default]
+    Any,
+    /// At least one letter, and one digit.
+    Mixed,
+    /// At least one lowercase letter, one uppercase letter, one digit, and one symbol.
+    Strong,
+}
+
+impl PasswordComplexity {
+    pub fn allows(&self, password: &str) -> bool {
+        let has = |predicate: fn(char) -> bool| password.chars().any(predicate);
+
+        match self {
+            PasswordComplexity::Any => true,
+            PasswordComplexity::Mixed => has(char::is_alphabetic) && has(char::is_numeric),
+            PasswordComplexity::Strong => {
+                has(char::is_lowercase)
+                    && has(char::is_uppercase)
+                    && has(char::is_numeric)
+                    && has(|c| !c.is_alphanumeric())
+            }
+        }
+    }
+}
+
+impl fmt::Display for PasswordComplexity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PasswordComplexity::Any => write!(f, "any characters"),
+            PasswordComplexity::Mixed => write!(f, "letters, and digits"),
+            PasswordComplexity::Strong => {
+                write!(f, "lowercase, and uppercase letters, digits, and symbols")
+            }
+        }
+    }
+}
+
+/// The configurable rules of the request bodies.
+///
+/// NOTE(alex): Registered with `app_data` by `start_app`, the extractors fall back to the defaults
+/// when it's not there (tests that don't care about it).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationConfig {
+    pub username_charset: UsernameCharset,
+    pub max_username_length: usize,
+    pub min_password_length: usize,
+    pub max_password_length: usize,
+    pub password_complexity: PasswordComplexity,
+    pub max_title_length: usize,
+    pub max_details_length: usize,
+    pub max_body_bytes: usize,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        Self {
+            username_charset: UsernameCharset::default(),
+            max_username_length: 32,
+            min_password_length: MIN_PASSWORD_LENGTH,
+            max_password_length: 128,
+            password_complexity: PasswordComplexity::default(),
+            max_title_length: 200,
+            max_details_length: 2000,
+            // NOTE(alex): Room for `max_title_length`, and `max_details_length`, of 4 byte
+            // characters, and then some.
+            max_body_bytes: 16_384,
+        }
+    }
+}
+
+impl ValidationConfig {
+    pub fn from_req(req: &HttpRequest) -> Self {
+        req.app_data::<web::Data<ValidationConfig>>()
+            .map(|config| config.get_ref().clone())
+            .unwrap_or_default()
+    }
+}
+
+impl FromRequest for ValidationConfig {
+    type Error = AppError;
+
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(Self::from_req(req)))
+    }
+}
+
+/// Collects the errors of every field, instead of stopping at the first one.
+#[derive(Debug, Default)]
+pub struct FieldErrors(Vec<FieldError>);
+
+impl FieldErrors {
+    pub fn push(&mut self, fail: impl Into<FieldError>) {
+        self.0.push(fail.into());
+    }
+
+    pub fn into_result(self) -> Result<(), AppError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::Validation(self.0))
+        }
+    }
+}
+
+/// Request bodies that are checked before reaching the routes.
+pub trait Validate: Sized {
+    /// Adds the first rule that fails, of each field, to `errors`.
+    fn check(&self, config: &ValidationConfig, errors: &mut FieldErrors);
+
+    /// How big the body may be, before it's even deserialized, see `Validated`.
+    fn body_limit(config: &ValidationConfig) -> usize {
+        config.max_body_bytes
+    }
+
+    /// Every field error goes into a single `AppError::Validation`.
+    fn validate(self, config: &ValidationConfig) -> Result<Self, AppError> {
+        let mut errors = FieldErrors::default();
+        self.check(config, &mut errors);
+        errors.into_result()?;
+
+        Ok(self)
+    }
+}
+
+/// NOTE(alex): Lengths are in characters, not bytes, `"ação"` is 4 long.
+pub fn length(value: &str) -> usize {
+    value.chars().count()
+}
+
+/// The formats a `Validated` body may come in, picked by its `Content-Type`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BodyFormat {
+    /// `application/json`, or any `+json` type.
+    Json,
+    /// `application/x-www-form-urlencoded`.
+    Form,
+    /// `application/msgpack`, or any of its unofficial names.
+    MessagePack,
+}
+
+impl BodyFormat {
+    /// NOTE(alex): A body without a `Content-Type` is taken as JSON, as it always was.
+    pub fn from_req(req: &HttpRequest) -> Result<Self, BodyError> {
+        let content_type = || req.content_type().to_string();
+        let mime = match req.mime_type() {
+            Ok(Some(mime)) => mime,
+            Ok(None) => return Ok(BodyFormat::Json),
+            Err(_) => return Err(BodyError::UnsupportedMediaType(content_type())),
+        };
+
+        match (mime.type_().as_str(), mime.subtype().as_str()) {
+            ("application", "json") => Ok(BodyFormat::Json),
+            (_, _) if mime.suffix().map(|suffix| suffix.as_str()) == Some("json") => {
+                Ok(BodyFormat::Json)
+            }
+            ("application", "x-www-form-urlencoded") => Ok(BodyFormat::Form),
+            ("application", "msgpack" | "x-msgpack" | "vnd.msgpack") => Ok(BodyFormat::MessagePack),
+            _ => Err(BodyError::UnsupportedMediaType(content_type())),
+        }
+    }
+
+    pub fn deserialize<T: DeserializeOwned>(&self, body: &[u8]) -> Result<T, BodyError> {
+        match self {
+            BodyFormat::Json => Ok(serde_json::from_slice(body)?),
+            BodyFormat::Form => Ok(serde_urlencoded::from_bytes(body)?),
+            BodyFormat::MessagePack => Ok(rmp_serde::from_slice(body)?),
+        }
+    }
+}
+
+/// A request body, in any of the `BodyFormat`s, that passed `Validate`.
+///
+/// NOTE(alex): The limit comes from `Validate::body_limit`, bodies that say (`Content-Length`)
+/// they're over it are refused before anything is read.
+#[derive(Debug)]
+pub struct Validated<T>(pub T);
+
+impl<T> Validated<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Validated<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> FromRequest for Validated<T>
+where
+    T: Validate + DeserializeOwned + 'static,
+{
+    type Error = AppError;
+
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let config = ValidationConfig::from_req(req);
+        let limit = T::body_limit(&config);
+        let content_length = req
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|length| length.to_str().ok())
+            .and_then(|length| length.parse::<usize>().ok());
+        let format = BodyFormat::from_req(req);
+        let payload = payload.take();
+
+        async move {
+            let format = format?;
+
+            if content_length.is_some_and(|length| length > limit) {
+                return Err(BodyError::Overflow(limit).into());
+            }
+
+            let body = read_body(payload, limit).await?;
+            let value: T = format.deserialize(&body)?;
+
+            value.validate(&config).map(Validated)
+        }
+        .boxed_local()
+    }
+}
+
+async fn read_body(mut payload: Payload, limit: usize) -> Result<Bytes, BodyError> {
+    let mut body = BytesMut::new();
+
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+
+        if body.len() + chunk.len() > limit {
+            return Err(BodyError::Overflow(limit));
+        }
+
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(body.freeze())
+}
@@ -0,0 +1,24 @@
+use actix_web::error::PayloadError;
+use thiserror::Error;
+
+/// Failures of `Validated` before the body is deserialized, and validated.
+#[derive(Debug, Error)]
+pub enum BodyError {
+    #[error("Request body of type `{0}` is not supported, send JSON, a form, or MessagePack!")]
+    UnsupportedMediaType(String),
+
+    #[error("Request body is larger than the limit of {0} bytes!")]
+    Overflow(usize),
+
+    #[error("Could not read the request body, failed with `{0}`!")]
+    Payload(#[from] PayloadError),
+
+    #[error("Invalid JSON request body, failed with `{0}`!")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Invalid form request body, failed with `{0}`!")]
+    Form(#[from] serde_urlencoded::de::Error),
+
+    #[error("Invalid MessagePack request body, failed with `{0}`!")]
+    MessagePack(#[from] rmp_serde::decode::Error),
+}
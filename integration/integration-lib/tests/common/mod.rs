@@ -25,7 +25,11 @@ pub async fn setup_data() -> web::Data<Pool<Sqlite>> {
 #[macro_export]
 macro_rules! setup_app {
     ($configure: expr) => {{
-        let data = setup_data().await;
+        setup_app!($configure, setup_data().await)
+    }};
+    // NOTE(alex): For the tests that need to reach into the database themselves.
+    ($configure: expr, $data: expr) => {{
+        let data = $data;
         let app = App::new()
             .app_data(data.clone())
             .configure($configure)
@@ -46,45 +50,52 @@ macro_rules! setup_app {
             );
         let mut app = test::init_service(app).await;
 
-        let (cookies, bearer_token) = {
-            let new_user = InsertUser {
-                valid_username: "spike".to_string(),
-                valid_password: "vicious".to_string(),
-            };
-            let register_user_request = test::TestRequest::post()
-                .uri("/users/register")
-                .set_json(&new_user)
-                .to_request();
-            let register_user_service_response =
-                test::call_service(&mut app, register_user_request).await;
-            assert!(register_user_service_response.status().is_success());
+        let (cookies, bearer_token) = register_and_login!(app, "spike", "vicious");
+
+        (app, bearer_token, cookies)
+    }};
+}
 
-            let user: User = test::read_body_json(register_user_service_response).await;
+// NOTE(alex): Registers a new `User`, and logs it in, returning the identity cookie and the bearer
+// token that protected routes require.
+#[macro_export]
+macro_rules! register_and_login {
+    ($app: expr, $username: expr, $password: expr) => {{
+        let new_user = InsertUser {
+            valid_username: $username.to_string(),
+            valid_password: $password.to_string(),
+        };
+        let register_user_request = test::TestRequest::post()
+            .uri("/users/register")
+            .set_json(&new_user)
+            .to_request();
+        let register_user_service_response =
+            test::call_service(&mut $app, register_user_request).await;
+        assert!(register_user_service_response.status().is_success());
 
-            let login_user = LoginUser {
-                username: user.username,
-                password: user.password,
-            };
-            let login_request = test::TestRequest::post()
-                .uri("/users/login")
-                .set_json(&login_user)
-                .to_request();
-            let login_service_response = test::call_service(&mut app, login_request).await;
-            assert!(login_service_response.status().is_success());
+        let user: User = test::read_body_json(register_user_service_response).await;
 
-            let cookies = login_service_response.response().cookies();
-            let cookies_str = cookies
-                .flat_map(|cookie| cookie.to_string().chars().collect::<Vec<_>>())
-                .collect::<String>();
+        let login_user = LoginUser {
+            username: user.username,
+            password: new_user.valid_password,
+        };
+        let login_request = test::TestRequest::post()
+            .uri("/users/login")
+            .set_json(&login_user)
+            .to_request();
+        let login_service_response = test::call_service(&mut $app, login_request).await;
+        assert!(login_service_response.status().is_success());
 
-            let logged_user: LoggedUser = test::read_body_json(login_service_response).await;
+        let cookies = login_service_response.response().cookies();
+        let cookies_str = cookies
+            .flat_map(|cookie| cookie.to_string().chars().collect::<Vec<_>>())
+            .collect::<String>();
 
-            let bearer_token = format!("Bearer {}", logged_user.token);
-            let cookies = Cookie::parse_encoded(cookies_str).unwrap();
+        let logged_user: LoggedUser = test::read_body_json(login_service_response).await;
 
-            (cookies, bearer_token)
-        };
+        let bearer_token = format!("Bearer {}", logged_user.token);
+        let cookies = Cookie::parse_encoded(cookies_str).unwrap();
 
-        (app, bearer_token, cookies)
+        (cookies, bearer_token)
     }};
 }
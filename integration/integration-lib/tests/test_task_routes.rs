@@ -2,15 +2,22 @@ mod common;
 
 use actix_identity::{CookieIdentityPolicy, IdentityService};
 use actix_session::CookieSession;
-use actix_web::{cookie::Cookie, http::StatusCode, test, web::ServiceConfig, App};
+use actix_web::{
+    cookie::Cookie,
+    http::{header, StatusCode},
+    test,
+    web::ServiceConfig,
+    App,
+};
 use common::setup_data;
 use integration_lib::{
     tasks::{
-        models::{InsertTask, Task, UpdateTask},
+        models::{InsertTask, Priority, Task, UpdateTask},
         routes::{
             delete as task_delete, done as task_done, favorite, find_all as task_find_all,
-            find_by_id as task_find_by_id, find_by_pattern as task_find_by_pattern, find_favorite,
-            find_ongoing, insert as task_insert, undo as task_undo, update as task_update,
+            find_by_id as task_find_by_id, find_by_pattern as task_find_by_pattern,
+            find_by_priority, find_due, find_favorite, find_ongoing, insert as task_insert,
+            undo as task_undo, update as task_update,
         },
     },
     users::{
@@ -18,13 +25,15 @@ use integration_lib::{
         routes::{find_by_id as user_find_by_id, insert as user_insert, login},
     },
 };
-use time::Duration;
+use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime};
 
 macro_rules! pre_insert_task {
     ($bearer_token: expr, $cookies: expr, $app: expr) => {{
         let insert_task = InsertTask {
             non_empty_title: "Re-watch Cowboy Bebop".to_string(),
             details: "Good show.".to_string(),
+            priority: Priority::Normal,
+            due_at: None,
         };
 
         let insert_task_request = test::TestRequest::post()
@@ -79,7 +88,7 @@ pub async fn test_task_insert_valid_task() {
 
         let login_user = LoginUser {
             username: user.username,
-            password: user.password,
+            password: new_user.valid_password,
         };
         let login_request = test::TestRequest::post()
             .uri("/users/login")
@@ -104,6 +113,8 @@ pub async fn test_task_insert_valid_task() {
     let valid_insert_task = InsertTask {
         non_empty_title: "Re-watch Cowboy Bebop".to_string(),
         details: "Good show.".to_string(),
+        priority: Priority::Normal,
+        due_at: None,
     };
 
     let request = test::TestRequest::post()
@@ -114,7 +125,11 @@ pub async fn test_task_insert_valid_task() {
         .to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert!(response.status().is_success());
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let location = response.headers().get(header::LOCATION).unwrap().clone();
+
+    let task: Task = test::read_body_json(response).await;
+    assert_eq!(location, format!("/tasks/{}", task.id).as_str());
 }
 
 #[actix_rt::test]
@@ -128,6 +143,8 @@ pub async fn test_task_insert_invalid_task_title() {
     let invalid_insert_task = InsertTask {
         non_empty_title: " \n\t".to_string(),
         details: "Good show.".to_string(),
+        priority: Priority::Normal,
+        due_at: None,
     };
 
     let request = test::TestRequest::post()
@@ -141,6 +158,64 @@ pub async fn test_task_insert_invalid_task_title() {
     assert!(response.status().is_client_error());
 }
 
+#[actix_rt::test]
+pub async fn test_task_insert_form_body() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    let request = test::TestRequest::post()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .insert_header((header::CONTENT_TYPE, "application/x-www-form-urlencoded"))
+        .cookie(cookies)
+        .set_payload("non_empty_title=Re-watch+Cowboy+Bebop&details=Good+show.&priority=high")
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let task: Task = test::read_body_json(response).await;
+    assert_eq!(task.title, "Re-watch Cowboy Bebop");
+    assert_eq!(task.priority, Priority::High);
+}
+
+#[actix_rt::test]
+pub async fn test_task_insert_refused_bodies() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    // NOTE(alex): Over the default `max_body_bytes`.
+    let insert_task = InsertTask {
+        non_empty_title: "Re-watch Cowboy Bebop".to_string(),
+        details: "Good show.".repeat(2000),
+        priority: Priority::Normal,
+        due_at: None,
+    };
+    let request = test::TestRequest::post()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .set_json(&insert_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let request = test::TestRequest::post()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .insert_header((header::CONTENT_TYPE, "text/plain"))
+        .cookie(cookies)
+        .set_payload("Re-watch Cowboy Bebop")
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
 #[actix_rt::test]
 pub async fn test_task_update_valid_task() {
     let configure = |cfg: &mut ServiceConfig| {
@@ -155,6 +230,8 @@ pub async fn test_task_update_valid_task() {
         id: task.id,
         new_title: format!("{}, and Yu Yu Hakusho", task.title),
         details: format!("{} Classic.", task.details),
+        priority: Some(Priority::Normal),
+        due_at: None,
     };
 
     // NOTE(alex): Update
@@ -183,6 +260,8 @@ pub async fn test_task_update_with_invalid_task_title() {
         id: task.id,
         new_title: " \n\t".to_string(),
         details: format!("{} Classic.", task.details),
+        priority: Some(Priority::Normal),
+        due_at: None,
     };
 
     // NOTE(alex): Update
@@ -197,6 +276,94 @@ pub async fn test_task_update_with_invalid_task_title() {
     assert!(response.status().is_client_error());
 }
 
+#[actix_rt::test]
+pub async fn test_task_update_overdue() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_update);
+        cfg.service(task_find_by_id);
+    };
+
+    let data = setup_data().await;
+    let (mut app, bearer_token, cookies) = setup_app!(configure, data.clone());
+    let task = pre_insert_task!(bearer_token, cookies, app);
+    let yesterday = (OffsetDateTime::now_utc() - Duration::days(1)).unix_timestamp();
+
+    sqlx::query("update Task set due_at = $1, priority = 2 where id = $2")
+        .bind(yesterday)
+        .bind(task.id)
+        .execute(data.get_ref())
+        .await
+        .unwrap();
+
+    // NOTE(alex): The fields that are left out keep their values, even the `due_at` in the past.
+    let update_task = serde_json::json!({
+        "id": task.id,
+        "new_title": "Re-watch Trigun",
+        "details": task.details,
+    });
+    let request = test::TestRequest::put()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .set_json(&update_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert!(response.status().is_success());
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/tasks/{}", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    let updated: Task = test::read_body_json(response).await;
+    assert_eq!(updated.title, "Re-watch Trigun");
+    assert_eq!(updated.priority, Priority::High);
+    assert_eq!(updated.due_at, Some(yesterday));
+
+    // NOTE(alex): Moving it somewhere else in the past is still refused, clearing it is fine.
+    let update_task = serde_json::json!({
+        "id": task.id,
+        "new_title": "Re-watch Trigun",
+        "details": task.details,
+        "due_at": "2001-09-11T00:00:00Z",
+    });
+    let request = test::TestRequest::put()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .set_json(&update_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let update_task = serde_json::json!({
+        "id": task.id,
+        "new_title": "Re-watch Trigun",
+        "details": task.details,
+        "due_at": null,
+    });
+    let request = test::TestRequest::put()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .set_json(&update_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert!(response.status().is_success());
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/tasks/{}", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    let updated: Task = test::read_body_json(response).await;
+    assert_eq!(updated.priority, Priority::High);
+    assert_eq!(updated.due_at, None);
+}
+
 #[actix_rt::test]
 pub async fn test_task_delete_existing_task() {
     let configure = |cfg: &mut ServiceConfig| {
@@ -215,7 +382,7 @@ pub async fn test_task_delete_existing_task() {
         .to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert!(response.status().is_success());
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
 }
 
 #[actix_rt::test]
@@ -234,7 +401,7 @@ pub async fn test_task_delete_non_existent_task() {
         .to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
 }
 
 #[actix_rt::test]
@@ -255,7 +422,37 @@ pub async fn test_task_mark_as_done() {
         .to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert!(response.status().is_success());
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+}
+
+#[actix_rt::test]
+pub async fn test_task_mark_as_done_twice() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_done);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = pre_insert_task!(bearer_token, cookies, app);
+
+    // NOTE(alex): Done
+    let request = test::TestRequest::post()
+        .uri(&format!("/tasks/{}/done", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    // NOTE(alex): Done again
+    let request = test::TestRequest::post()
+        .uri(&format!("/tasks/{}/done", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::CONFLICT);
 }
 
 #[actix_rt::test]
@@ -286,7 +483,7 @@ pub async fn test_task_undo() {
         .to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert!(response.status().is_success());
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
 }
 
 #[actix_rt::test]
@@ -300,10 +497,36 @@ pub async fn test_task_find_all() {
     let _ = pre_insert_task!(bearer_token, cookies, app);
 
     // NOTE(alex): Find all
-    let request = test::TestRequest::get().uri("/tasks").to_request();
+    let request = test::TestRequest::get()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[actix_rt::test]
+pub async fn test_task_find_all_empty() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_find_all);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    // NOTE(alex): Find all, with no tasks
+    let request = test::TestRequest::get()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let tasks: Vec<Task> = test::read_body_json(response).await;
+    assert!(tasks.is_empty());
 }
 
 #[actix_rt::test]
@@ -328,30 +551,150 @@ pub async fn test_task_ongoing_tasks() {
     assert!(task_done_response.status().is_success());
 
     // NOTE(alex): Find ongoing tasks only
-    let request = test::TestRequest::get().uri("/tasks/ongoing").to_request();
+    let request = test::TestRequest::get()
+        .uri("/tasks/ongoing")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(response.status(), StatusCode::OK);
 }
 
 #[actix_rt::test]
 pub async fn test_task_find_by_pattern() {
+    // NOTE(alex): Registered along with `find_all`, as `task_service` does, so the search must not
+    // be shadowed by it.
     let configure = |cfg: &mut ServiceConfig| {
         cfg.service(task_insert);
+        cfg.service(task_find_all);
         cfg.service(task_find_by_pattern);
     };
 
     let (mut app, bearer_token, cookies) = setup_app!(configure);
-    let _ = pre_insert_task!(bearer_token, cookies, app);
+    let task = pre_insert_task!(bearer_token, cookies, app);
+
+    let insert_task = InsertTask {
+        non_empty_title: "Buy groceries".to_string(),
+        details: "Bell peppers, and beef.".to_string(),
+        priority: Priority::Normal,
+        due_at: None,
+    };
+    let request = test::TestRequest::post()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .set_json(&insert_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert!(response.status().is_success());
 
     let title_pattern = "?title=Watch&details=.";
     // NOTE(alex): Find tasks with title pattern
     let request = test::TestRequest::get()
-        .uri(&format!("/tasks{}", title_pattern))
+        .uri(&format!("/tasks/search{}", title_pattern))
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
         .to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(response.status(), StatusCode::OK);
+    let tasks: Vec<Task> = test::read_body_json(response).await;
+    let ids: Vec<_> = tasks.iter().map(|found| found.id).collect();
+    assert_eq!(ids, vec![task.id]);
+}
+
+#[actix_rt::test]
+pub async fn test_task_schedule() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(find_by_priority);
+        cfg.service(find_due);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let normal = pre_insert_task!(bearer_token, cookies, app);
+
+    // NOTE(alex): Raw JSON, to check that dates go in (and come out) as RFC 3339.
+    let due_at = (OffsetDateTime::now_utc() + Duration::days(2))
+        .replace_nanosecond(0)
+        .unwrap();
+    let request = test::TestRequest::post()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .set_json(&serde_json::json!({
+            "non_empty_title": "Trigun",
+            "details": "Love and peace.",
+            "priority": "urgent",
+            "due_at": due_at.format(&Rfc3339).unwrap(),
+        }))
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert!(response.status().is_success());
+
+    let urgent: serde_json::Value = test::read_body_json(response).await;
+    assert_eq!(urgent["priority"], "urgent");
+    assert_eq!(urgent["due_at"], due_at.format(&Rfc3339).unwrap());
+    assert_eq!(urgent["created_at"], urgent["updated_at"]);
+    assert!(urgent["completed_at"].is_null());
+
+    let request = test::TestRequest::get()
+        .uri("/tasks/by-priority")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let tasks: Vec<Task> = test::read_body_json(response).await;
+    assert_eq!(tasks[0].priority, Priority::Urgent);
+    assert_eq!(tasks[1].id, normal.id);
+
+    let request = test::TestRequest::get()
+        .uri("/tasks/due?days=3")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let tasks: Vec<Task> = test::read_body_json(response).await;
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].due_at, Some(due_at.unix_timestamp()));
+
+    let request = test::TestRequest::get()
+        .uri("/tasks/due?days=0")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[actix_rt::test]
+pub async fn test_task_due_in_past() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    let insert_task = InsertTask {
+        non_empty_title: "Re-watch Cowboy Bebop".to_string(),
+        details: "Good show.".to_string(),
+        priority: Priority::High,
+        due_at: Some((OffsetDateTime::now_utc() - Duration::days(1)).unix_timestamp()),
+    };
+    let request = test::TestRequest::post()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .set_json(&insert_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
 }
 
 #[actix_rt::test]
@@ -367,10 +710,113 @@ pub async fn test_task_find_by_id() {
     // NOTE(alex): Find with id
     let request = test::TestRequest::get()
         .uri(&format!("/tasks/{}", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[actix_rt::test]
+pub async fn test_task_find_by_id_of_another_user() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_find_by_id);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = pre_insert_task!(bearer_token, cookies, app);
+
+    let (other_cookies, other_bearer_token) = register_and_login!(app, "jet", "black");
+
+    // NOTE(alex): Find with id, as a user that doesn't own the task
+    let request = test::TestRequest::get()
+        .uri(&format!("/tasks/{}", task.id))
+        .insert_header(("Authorization".to_string(), other_bearer_token))
+        .cookie(other_cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+pub async fn test_task_update_of_another_user() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_update);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = pre_insert_task!(bearer_token, cookies, app);
+
+    let (other_cookies, other_bearer_token) = register_and_login!(app, "jet", "black");
+
+    let update_task = UpdateTask {
+        id: task.id,
+        new_title: "Feed Ein".to_string(),
+        details: "Not a good show.".to_string(),
+        priority: Some(Priority::Normal),
+        due_at: None,
+    };
+
+    // NOTE(alex): Update, as a user that doesn't own the task
+    let request = test::TestRequest::put()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), other_bearer_token))
+        .cookie(other_cookies)
+        .set_json(&update_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+pub async fn test_task_delete_of_another_user() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_delete);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = pre_insert_task!(bearer_token, cookies, app);
+
+    let (other_cookies, other_bearer_token) = register_and_login!(app, "jet", "black");
+
+    // NOTE(alex): Delete, as a user that doesn't own the task
+    let request = test::TestRequest::delete()
+        .uri(&format!("/tasks/{}", task.id))
+        .insert_header(("Authorization".to_string(), other_bearer_token))
+        .cookie(other_cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+pub async fn test_task_mark_as_done_of_another_user() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_done);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = pre_insert_task!(bearer_token, cookies, app);
+
+    let (other_cookies, other_bearer_token) = register_and_login!(app, "jet", "black");
+
+    // NOTE(alex): Done, as a user that doesn't own the task
+    let request = test::TestRequest::post()
+        .uri(&format!("/tasks/{}/done", task.id))
+        .insert_header(("Authorization".to_string(), other_bearer_token))
+        .cookie(other_cookies)
         .to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
 }
 
 #[actix_rt::test]
@@ -391,7 +837,7 @@ pub async fn test_task_favorite() {
         .to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(response.status(), StatusCode::OK);
 }
 
 #[actix_rt::test]
@@ -412,7 +858,7 @@ pub async fn test_task_find_favorite() {
         .cookie(cookies.clone())
         .to_request();
     let task_favorite_response = test::call_service(&mut app, task_favorite_request).await;
-    assert_eq!(task_favorite_response.status(), StatusCode::FOUND);
+    assert_eq!(task_favorite_response.status(), StatusCode::OK);
 
     // NOTE(alex): Retrieve the session cookies to insert them into the find favorite request.
     let session_cookies = task_favorite_response.response().cookies();
@@ -428,5 +874,5 @@ pub async fn test_task_find_favorite() {
         .to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(response.status(), StatusCode::OK);
 }
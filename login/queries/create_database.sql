@@ -6,7 +6,9 @@ drop table if exists Done;
 create table if not exists Task (
     id integer primary key,
     title text not null,
-    details text
+    details text,
+    owner_id int not null,
+    foreign key (owner_id) references User(id) on delete cascade
 );
 
 create table if not exists Done (
@@ -17,7 +19,8 @@ create table if not exists Done (
 create view if not exists OngoingTask as
 select Task.id,
     Task.title,
-    Task.details
+    Task.details,
+    Task.owner_id
 from Task
 where
     Task.id not in (
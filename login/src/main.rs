@@ -6,10 +6,12 @@ use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
 use tasks::routes::task_service;
 use time::Duration;
 use users::routes::user_service;
+use validation::ValidationConfig;
 
 mod errors;
 mod tasks;
 mod users;
+mod validation;
 
 const WELCOME_MSG: &'static str = include_str!("./../strings/welcome.txt");
 
@@ -55,10 +57,12 @@ pub async fn main() -> std::io::Result<()> {
     }
 
     let data = actix_web::web::Data::new(database_pool);
+    let validation_config = actix_web::web::Data::new(ValidationConfig::default());
 
     HttpServer::new(move || {
         App::new()
             .app_data(data.clone())
+            .app_data(validation_config.clone())
             .service(index)
             .configure(task_service)
             .configure(user_service)
@@ -1,20 +1,19 @@
-use actix_web::{
-    body::BoxBody,
-    dev::{JsonBody, Payload},
-    FromRequest, HttpRequest, HttpResponse, Responder,
-};
-use futures::{future::LocalBoxFuture, FutureExt};
+use actix_web::{body::BoxBody, HttpRequest, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, SqlitePool};
 
 use super::{errors::*, *};
-use crate::errors::AppError;
+use crate::{
+    errors::AppError,
+    validation::{length, FieldErrors, Validate, ValidationConfig},
+};
 
 #[derive(Clone, Debug, Serialize, Deserialize, FromRow)]
 pub(crate) struct Task {
     pub(crate) id: i64,
     pub(crate) title: String,
     pub(crate) details: String,
+    pub(crate) owner_id: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -37,11 +36,16 @@ pub(crate) struct QueryTask {
 }
 
 impl InsertTask {
-    pub(crate) async fn insert(self, db_pool: &SqlitePool) -> Result<Task, AppError> {
+    pub(crate) async fn insert(
+        self,
+        db_pool: &SqlitePool,
+        owner_id: i64,
+    ) -> Result<Task, AppError> {
         let mut connection = db_pool.acquire().await?;
         let result = sqlx::query(INSERT)
             .bind(&self.non_empty_title)
             .bind(&self.details)
+            .bind(owner_id)
             .execute(&mut connection)
             .await?;
 
@@ -49,88 +53,129 @@ impl InsertTask {
             id: result.last_insert_rowid(),
             title: self.non_empty_title,
             details: self.details,
+            owner_id,
         };
 
         Ok(task)
     }
-
-    fn validate(self) -> Result<Self, TaskError> {
-        if self.non_empty_title.trim().is_empty() {
-            Err(TaskError::EmptyTitle)
-        } else {
-            Ok(self)
-        }
-    }
 }
 
 impl UpdateTask {
-    pub(crate) async fn update(self, db_pool: &SqlitePool) -> Result<u64, AppError> {
+    pub(crate) async fn update(self, db_pool: &SqlitePool, owner_id: i64) -> Result<u64, AppError> {
         let mut connection = db_pool.acquire().await?;
         let result = sqlx::query(UPDATE)
             .bind(&self.new_title)
             .bind(&self.details)
             .bind(&self.id)
+            .bind(owner_id)
             .execute(&mut connection)
             .await?;
 
         Ok(result.rows_affected())
     }
+}
+
+impl Validate for InsertTask {
+    fn check(&self, config: &ValidationConfig, errors: &mut FieldErrors) {
+        check_task(&self.non_empty_title, &self.details, config, errors);
+    }
+}
+
+impl Validate for UpdateTask {
+    fn check(&self, config: &ValidationConfig, errors: &mut FieldErrors) {
+        check_task(&self.new_title, &self.details, config, errors);
+    }
+}
 
-    fn validate(self) -> Result<Self, TaskError> {
-        if self.new_title.trim().is_empty() {
-            Err(TaskError::EmptyTitle)
-        } else {
-            Ok(self)
-        }
+fn check_task(title: &str, details: &str, config: &ValidationConfig, errors: &mut FieldErrors) {
+    if title.trim().is_empty() {
+        errors.push(TaskError::EmptyTitle);
+    } else if length(title) > config.max_title_length {
+        errors.push(TaskError::TitleLength(config.max_title_length));
+    }
+
+    if length(details) > config.max_details_length {
+        errors.push(TaskError::DetailsLength(config.max_details_length));
     }
 }
 
 impl Task {
-    pub(crate) async fn delete(db_pool: &SqlitePool, task_id: i64) -> Result<u64, AppError> {
+    pub(crate) async fn delete(
+        db_pool: &SqlitePool,
+        task_id: i64,
+        owner_id: i64,
+    ) -> Result<u64, AppError> {
         let mut connection = db_pool.acquire().await?;
         let result = sqlx::query(DELETE)
             .bind(task_id)
+            .bind(owner_id)
             .execute(&mut connection)
             .await?;
 
         Ok(result.rows_affected())
     }
 
-    pub(crate) async fn done(pool: &SqlitePool, task_id: i64) -> Result<u64, AppError> {
+    pub(crate) async fn done(
+        pool: &SqlitePool,
+        task_id: i64,
+        owner_id: i64,
+    ) -> Result<u64, AppError> {
         let mut connection = pool.acquire().await?;
         let result = sqlx::query(DONE)
             .bind(task_id)
+            .bind(owner_id)
             .execute(&mut connection)
             .await?;
 
         Ok(result.rows_affected())
     }
 
-    pub(crate) async fn undo(db_pool: &SqlitePool, task_id: i64) -> Result<u64, AppError> {
+    pub(crate) async fn undo(
+        db_pool: &SqlitePool,
+        task_id: i64,
+        owner_id: i64,
+    ) -> Result<u64, AppError> {
         let mut connection = db_pool.acquire().await?;
         let result = sqlx::query(UNDO)
             .bind(task_id)
+            .bind(owner_id)
             .execute(&mut connection)
             .await?;
 
         Ok(result.rows_affected())
     }
 
-    pub(crate) async fn find_all(db_pool: &SqlitePool) -> Result<Vec<Self>, AppError> {
-        let result = sqlx::query_as(FIND_ALL).fetch_all(db_pool).await?;
+    pub(crate) async fn find_all(
+        db_pool: &SqlitePool,
+        owner_id: i64,
+    ) -> Result<Vec<Self>, AppError> {
+        let result = sqlx::query_as(FIND_ALL)
+            .bind(owner_id)
+            .fetch_all(db_pool)
+            .await?;
+
         Ok(result)
     }
 
-    pub(crate) async fn find_ongoing(db_pool: &SqlitePool) -> Result<Vec<Self>, AppError> {
-        let result = sqlx::query_as(FIND_ONGOING).fetch_all(db_pool).await?;
+    pub(crate) async fn find_ongoing(
+        db_pool: &SqlitePool,
+        owner_id: i64,
+    ) -> Result<Vec<Self>, AppError> {
+        let result = sqlx::query_as(FIND_ONGOING)
+            .bind(owner_id)
+            .fetch_all(db_pool)
+            .await?;
+
         Ok(result)
     }
 
     pub(crate) async fn find_by_pattern(
         db_pool: &SqlitePool,
         search_pattern: &str,
+        owner_id: i64,
     ) -> Result<Vec<Self>, AppError> {
         let result = sqlx::query_as(FIND_BY_PATTERN)
+            .bind(owner_id)
             .bind(search_pattern)
             .fetch_all(db_pool)
             .await?;
@@ -141,9 +186,11 @@ impl Task {
     pub(crate) async fn find_by_id(
         db_pool: &SqlitePool,
         task_id: i64,
+        owner_id: i64,
     ) -> Result<Option<Self>, AppError> {
         let result = sqlx::query_as(FIND_BY_ID)
             .bind(task_id)
+            .bind(owner_id)
             .fetch_optional(db_pool)
             .await?;
 
@@ -168,35 +215,3 @@ impl Responder for Task {
         response
     }
 }
-
-impl FromRequest for InsertTask {
-    type Error = AppError;
-
-    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
-
-    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-        JsonBody::new(req, payload, None, false)
-            .limit(4056)
-            .map(|res: Result<InsertTask, _>| match res {
-                Ok(insert_task) => insert_task.validate().map_err(|fail| AppError::from(fail)),
-                Err(fail) => Err(AppError::from(fail)),
-            })
-            .boxed_local()
-    }
-}
-
-impl FromRequest for UpdateTask {
-    type Error = AppError;
-
-    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
-
-    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-        JsonBody::new(req, payload, None, false)
-            .limit(4056)
-            .map(|res: Result<UpdateTask, _>| match res {
-                Ok(update_task) => update_task.validate().map_err(|fail| AppError::from(fail)),
-                Err(fail) => Err(AppError::from(fail)),
-            })
-            .boxed_local()
-    }
-}
@@ -1,2 +1,5 @@
 insert into Done (task_id)
-values ($1)
\ No newline at end of file
+select Task.id
+from Task
+where
+    Task.id = $1 and Task.owner_id = $2
\ No newline at end of file
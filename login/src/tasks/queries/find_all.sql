@@ -1 +1,3 @@
-select * from Task;
\ No newline at end of file
+select *
+from Task
+where Task.owner_id = $1;
\ No newline at end of file
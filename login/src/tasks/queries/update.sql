@@ -1,4 +1,5 @@
 update Task
 set title = $1,
     details = $2
-where Task.id = $3
\ No newline at end of file
+where
+    Task.id = $3 and Task.owner_id = $4
\ No newline at end of file
@@ -3,26 +3,31 @@ use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
 use sqlx::SqlitePool;
 
 use super::{errors::*, models::*};
-use crate::errors::AppError;
+use crate::{errors::AppError, users::models::User, validation::Validated};
 
 #[post("/tasks")]
 async fn insert(
     db_pool: web::Data<SqlitePool>,
-    input: InsertTask,
+    logged_user: User,
+    Validated(input): Validated<InsertTask>,
 ) -> Result<impl Responder, AppError> {
-    let task = input.insert(db_pool.get_ref()).await?;
+    let task = input.insert(db_pool.get_ref(), logged_user.id).await?;
     Ok(HttpResponse::Created().json(task))
 }
 
 #[put("/tasks")]
 async fn update(
     db_pool: web::Data<SqlitePool>,
-    input: UpdateTask,
+    logged_user: User,
+    Validated(input): Validated<UpdateTask>,
 ) -> Result<impl Responder, AppError> {
-    let num_modified = input.update(db_pool.get_ref()).await?;
+    let task_id = input.id;
+    let num_modified = input.update(db_pool.get_ref(), logged_user.id).await?;
 
+    // NOTE(alex): A `Task` that belongs to someone else is reported as not found, we don't want to
+    // leak which ids exist.
     if num_modified == 0 {
-        Ok(HttpResponse::NotModified().body("No tasks were updated."))
+        Err(TaskError::NotFound(task_id).into())
     } else {
         Ok(HttpResponse::Ok().body(format!("Updated {} tasks.", num_modified)))
     }
@@ -31,12 +36,13 @@ async fn update(
 #[delete("/tasks/{id}")]
 async fn delete(
     db_pool: web::Data<SqlitePool>,
+    logged_user: User,
     id: web::Path<i64>,
 ) -> Result<impl Responder, AppError> {
-    let num_modified = Task::delete(db_pool.get_ref(), *id).await?;
+    let num_modified = Task::delete(db_pool.get_ref(), *id, logged_user.id).await?;
 
     if num_modified == 0 {
-        Ok(HttpResponse::NotModified().body("No tasks were deleted."))
+        Err(TaskError::NotFound(*id).into())
     } else {
         Ok(HttpResponse::Ok().body(format!("Deleted {} tasks.", num_modified)))
     }
@@ -45,23 +51,32 @@ async fn delete(
 #[post("/tasks/{id}/done")]
 async fn done(
     db_pool: web::Data<SqlitePool>,
+    logged_user: User,
     id: web::Path<i64>,
 ) -> Result<impl Responder, AppError> {
-    let created_id = Task::done(db_pool.get_ref(), *id).await?;
+    let num_modified = Task::done(db_pool.get_ref(), *id, logged_user.id).await?;
 
-    if created_id == 0 {
-        Ok(HttpResponse::NotModified().body(format!("Task with id {} not done.", id)))
+    if num_modified == 0 {
+        Err(TaskError::NotFound(*id).into())
     } else {
-        Ok(HttpResponse::Created().body(created_id.to_string()))
+        Ok(HttpResponse::Created().body(num_modified.to_string()))
     }
 }
 
 #[delete("/tasks/{id}/undo")]
 async fn undo(
     db_pool: web::Data<SqlitePool>,
+    logged_user: User,
     id: web::Path<i64>,
 ) -> Result<impl Responder, AppError> {
-    let num_modified = Task::undo(db_pool.get_ref(), *id).await?;
+    if Task::find_by_id(db_pool.get_ref(), *id, logged_user.id)
+        .await?
+        .is_none()
+    {
+        return Err(TaskError::NotFound(*id).into());
+    }
+
+    let num_modified = Task::undo(db_pool.get_ref(), *id, logged_user.id).await?;
 
     if num_modified == 0 {
         Ok(HttpResponse::NotModified().body(format!("Task with id {} not undone.", id)))
@@ -71,23 +86,35 @@ async fn undo(
 }
 
 #[get("/tasks")]
-async fn find_all(db_pool: web::Data<SqlitePool>) -> Result<impl Responder, AppError> {
-    let tasks = Task::find_all(db_pool.get_ref()).await?;
+async fn find_all(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: User,
+) -> Result<impl Responder, AppError> {
+    let tasks = Task::find_all(db_pool.get_ref(), logged_user.id).await?;
     Ok(HttpResponse::Found().json(&tasks))
 }
 
 #[get("/tasks/ongoing")]
-async fn find_ongoing(db_pool: web::Data<SqlitePool>) -> Result<impl Responder, AppError> {
-    let tasks = Task::find_ongoing(db_pool.get_ref()).await?;
+async fn find_ongoing(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: User,
+) -> Result<impl Responder, AppError> {
+    let tasks = Task::find_ongoing(db_pool.get_ref(), logged_user.id).await?;
     Ok(HttpResponse::Found().json(&tasks))
 }
 
 #[get("/tasks")]
 async fn find_by_pattern(
     db_pool: web::Data<SqlitePool>,
+    logged_user: User,
     pattern: web::Query<QueryTask>,
 ) -> Result<impl Responder, AppError> {
-    let tasks = Task::find_by_pattern(db_pool.get_ref(), &format!("%{}%", pattern.title)).await?;
+    let tasks = Task::find_by_pattern(
+        db_pool.get_ref(),
+        &format!("%{}%", pattern.title),
+        logged_user.id,
+    )
+    .await?;
     Ok(HttpResponse::Found().json(&tasks))
 }
 
@@ -103,10 +130,15 @@ async fn find_by_pattern(
 #[get("/tasks/{id:\\d+}")]
 async fn find_by_id(
     db_pool: web::Data<SqlitePool>,
+    logged_user: User,
     id: web::Path<i64>,
 ) -> Result<impl Responder, AppError> {
-    let task = Task::find_by_id(db_pool.get_ref(), *id).await?;
-    Ok(task)
+    let task = Task::find_by_id(db_pool.get_ref(), *id, logged_user.id).await?;
+
+    match task {
+        Some(task) => Ok(task),
+        None => Err(TaskError::NotFound(*id).into()),
+    }
 }
 
 const FAVORITE_TASK_STR: &'static str = "favorite_task";
@@ -114,6 +146,7 @@ const FAVORITE_TASK_STR: &'static str = "favorite_task";
 #[post("/tasks/favorite/{id}")]
 async fn favorite(
     db_pool: web::Data<SqlitePool>,
+    logged_user: User,
     session: Session,
     id: web::Path<i64>,
 ) -> Result<impl Responder, AppError> {
@@ -124,7 +157,7 @@ async fn favorite(
             // NOTE(alex): Just remove the task, this is basically "unfavorite".
             Ok(HttpResponse::NoContent().body(format!("Task {} unfavorited", old_favorite.id)))
         } else {
-            match Task::find_by_id(&db_pool, *id).await? {
+            match Task::find_by_id(&db_pool, *id, logged_user.id).await? {
                 Some(task) => {
                     session.insert(FAVORITE_TASK_STR, task.clone())?;
                     Ok(HttpResponse::Found().json(task))
@@ -133,7 +166,7 @@ async fn favorite(
             }
         }
     } else {
-        match Task::find_by_id(&db_pool, *id).await? {
+        match Task::find_by_id(&db_pool, *id, logged_user.id).await? {
             Some(task) => {
                 session.insert(FAVORITE_TASK_STR, task.clone())?;
                 Ok(HttpResponse::Found().json(task))
@@ -175,6 +208,7 @@ pub(crate) fn task_service(cfg: &mut web::ServiceConfig) {
 
 #[cfg(test)]
 mod tests {
+    use actix_identity::{CookieIdentityPolicy, IdentityService};
     use actix_session::CookieSession;
     use actix_web::{
         cookie::Cookie,
@@ -184,9 +218,16 @@ mod tests {
         App,
     };
     use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
+    use time::Duration;
 
     use super::*;
-    use crate::create_database;
+    use crate::{
+        create_database,
+        users::{
+            models::{InsertUser, LoginUser},
+            routes::{insert as user_insert, login},
+        },
+    };
 
     macro_rules! setup_app {
         ($configure: expr) => {{
@@ -194,20 +235,66 @@ mod tests {
             let app = App::new()
                 .app_data(data.clone())
                 .configure($configure)
+                .service(user_insert)
+                .service(login)
+                .wrap(IdentityService::new(
+                    CookieIdentityPolicy::new(&[0; 32])
+                        .name("auth-cookie")
+                        .login_deadline(Duration::minutes(5))
+                        .secure(false),
+                ))
                 .wrap(
                     CookieSession::signed(&[0; 32])
                         .name("session-cookie")
                         .secure(false),
                 );
 
-            let app = test::init_service(app).await;
+            let mut app = test::init_service(app).await;
+
+            let cookies = register_and_login!(app, "spike", "vicious");
+
+            (app, cookies)
+        }};
+    }
+
+    // NOTE(alex): Registers a new `User`, and logs it in, returning the identity cookie that the
+    // task routes require.
+    macro_rules! register_and_login {
+        ($app: expr, $username: expr, $password: expr) => {{
+            let new_user = InsertUser {
+                valid_username: $username.to_string(),
+                valid_password: $password.to_string(),
+            };
+            let register_user_request = test::TestRequest::post()
+                .uri("/users/register")
+                .set_json(&new_user)
+                .to_request();
+            let register_user_service_response =
+                test::call_service(&mut $app, register_user_request).await;
+            assert!(register_user_service_response.status().is_success());
+
+            let login_user = LoginUser {
+                username: new_user.valid_username,
+                password: new_user.valid_password,
+            };
+            let login_request = test::TestRequest::post()
+                .uri("/users/login")
+                .set_json(&login_user)
+                .to_request();
+            let login_service_response = test::call_service(&mut $app, login_request).await;
+            assert!(login_service_response.status().is_success());
+
+            let cookies = login_service_response.response().cookies();
+            let cookies_str = cookies
+                .flat_map(|cookie| cookie.to_string().chars().collect::<Vec<_>>())
+                .collect::<String>();
 
-            app
+            Cookie::parse_encoded(cookies_str).unwrap()
         }};
     }
 
     macro_rules! pre_insert_task {
-        ($app: expr) => {{
+        ($cookies: expr, $app: expr) => {{
             let insert_task = InsertTask {
                 non_empty_title: "Re-watch Cowboy Bebop".to_string(),
                 details: "Good show.".to_string(),
@@ -215,6 +302,7 @@ mod tests {
 
             let insert_task_request = test::TestRequest::post()
                 .uri("/tasks")
+                .cookie($cookies.clone())
                 .set_json(&insert_task)
                 .to_request();
             let insert_task_response = test::call_service(&mut $app, insert_task_request).await;
@@ -243,8 +331,11 @@ mod tests {
 
     #[actix_rt::test]
     pub async fn test_task_insert_valid_task() {
-        let data = setup_data().await;
-        let mut app = test::init_service(App::new().app_data(data).service(insert)).await;
+        let configure = |cfg: &mut ServiceConfig| {
+            cfg.service(insert);
+        };
+
+        let (mut app, cookies) = setup_app!(configure);
 
         let valid_insert_task = InsertTask {
             non_empty_title: "Re-watch Cowboy Bebop".to_string(),
@@ -253,6 +344,7 @@ mod tests {
 
         let request = test::TestRequest::post()
             .uri("/tasks")
+            .cookie(cookies.clone())
             .set_json(&valid_insert_task)
             .to_request();
         let response = test::call_service(&mut app, request).await;
@@ -266,7 +358,7 @@ mod tests {
             cfg.service(insert);
         };
 
-        let mut app = setup_app!(configure);
+        let (mut app, cookies) = setup_app!(configure);
 
         let invalid_insert_task = InsertTask {
             non_empty_title: " \n\t".to_string(),
@@ -275,6 +367,7 @@ mod tests {
 
         let request = test::TestRequest::post()
             .uri("/tasks")
+            .cookie(cookies.clone())
             .set_json(&invalid_insert_task)
             .to_request();
         let response = test::call_service(&mut app, request).await;
@@ -289,8 +382,8 @@ mod tests {
             cfg.service(update);
         };
 
-        let mut app = setup_app!(configure);
-        let task = pre_insert_task!(app);
+        let (mut app, cookies) = setup_app!(configure);
+        let task = pre_insert_task!(cookies, app);
 
         let update_task = UpdateTask {
             id: task.id,
@@ -301,6 +394,7 @@ mod tests {
         // NOTE(alex): Update
         let request = test::TestRequest::put()
             .uri("/tasks")
+            .cookie(cookies.clone())
             .set_json(&update_task)
             .to_request();
         let response = test::call_service(&mut app, request).await;
@@ -315,8 +409,8 @@ mod tests {
             cfg.service(update);
         };
 
-        let mut app = setup_app!(configure);
-        let task = pre_insert_task!(app);
+        let (mut app, cookies) = setup_app!(configure);
+        let task = pre_insert_task!(cookies, app);
 
         let update_task = UpdateTask {
             id: task.id,
@@ -327,6 +421,7 @@ mod tests {
         // NOTE(alex): Update
         let request = test::TestRequest::put()
             .uri("/tasks")
+            .cookie(cookies.clone())
             .set_json(&update_task)
             .to_request();
         let response = test::call_service(&mut app, request).await;
@@ -341,12 +436,13 @@ mod tests {
             cfg.service(delete);
         };
 
-        let mut app = setup_app!(configure);
-        let task = pre_insert_task!(app);
+        let (mut app, cookies) = setup_app!(configure);
+        let task = pre_insert_task!(cookies, app);
 
         // NOTE(alex): Delete
         let request = test::TestRequest::delete()
             .uri(&format!("/tasks/{}", task.id))
+            .cookie(cookies.clone())
             .to_request();
         let response = test::call_service(&mut app, request).await;
 
@@ -355,16 +451,20 @@ mod tests {
 
     #[actix_rt::test]
     pub async fn test_task_delete_non_existent_task() {
-        let database_pool = setup_data().await;
-        let mut app = test::init_service(App::new().app_data(database_pool).service(delete)).await;
+        let configure = |cfg: &mut ServiceConfig| {
+            cfg.service(delete);
+        };
+
+        let (mut app, cookies) = setup_app!(configure);
 
         // NOTE(alex): Delete
         let request = test::TestRequest::delete()
             .uri(&format!("/tasks/{}", 1000))
+            .cookie(cookies.clone())
             .to_request();
         let response = test::call_service(&mut app, request).await;
 
-        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
 
     #[actix_rt::test]
@@ -374,12 +474,13 @@ mod tests {
             cfg.service(done);
         };
 
-        let mut app = setup_app!(configure);
-        let task = pre_insert_task!(app);
+        let (mut app, cookies) = setup_app!(configure);
+        let task = pre_insert_task!(cookies, app);
 
         // NOTE(alex): Done
         let request = test::TestRequest::post()
             .uri(&format!("/tasks/{}/done", task.id))
+            .cookie(cookies.clone())
             .to_request();
         let response = test::call_service(&mut app, request).await;
 
@@ -394,12 +495,13 @@ mod tests {
             cfg.service(undo);
         };
 
-        let mut app = setup_app!(configure);
-        let task = pre_insert_task!(app);
+        let (mut app, cookies) = setup_app!(configure);
+        let task = pre_insert_task!(cookies, app);
 
         // NOTE(alex): Done
         let task_done_request = test::TestRequest::post()
             .uri(&format!("/tasks/{}/done", task.id))
+            .cookie(cookies.clone())
             .to_request();
         let task_done_response = test::call_service(&mut app, task_done_request).await;
         assert!(task_done_response.status().is_success());
@@ -407,6 +509,7 @@ mod tests {
         // NOTE(alex): Undo
         let request = test::TestRequest::delete()
             .uri(&format!("/tasks/{}/undo", task.id))
+            .cookie(cookies.clone())
             .to_request();
         let response = test::call_service(&mut app, request).await;
 
@@ -420,11 +523,14 @@ mod tests {
             cfg.service(find_all);
         };
 
-        let mut app = setup_app!(configure);
-        let _ = pre_insert_task!(app);
+        let (mut app, cookies) = setup_app!(configure);
+        let _ = pre_insert_task!(cookies, app);
 
         // NOTE(alex): Find all
-        let request = test::TestRequest::get().uri("/tasks").to_request();
+        let request = test::TestRequest::get()
+            .uri("/tasks")
+            .cookie(cookies.clone())
+            .to_request();
         let response = test::call_service(&mut app, request).await;
 
         assert_eq!(response.status(), StatusCode::FOUND);
@@ -438,19 +544,23 @@ mod tests {
             cfg.service(find_ongoing);
         };
 
-        let mut app = setup_app!(configure);
-        let _ = pre_insert_task!(app);
-        let task = pre_insert_task!(app);
+        let (mut app, cookies) = setup_app!(configure);
+        let _ = pre_insert_task!(cookies, app);
+        let task = pre_insert_task!(cookies, app);
 
         // NOTE(alex): Done
         let task_done_request = test::TestRequest::post()
             .uri(&format!("/tasks/{}/done", task.id))
+            .cookie(cookies.clone())
             .to_request();
         let task_done_response = test::call_service(&mut app, task_done_request).await;
         assert!(task_done_response.status().is_success());
 
         // NOTE(alex): Find ongoing tasks only
-        let request = test::TestRequest::get().uri("/tasks/ongoing").to_request();
+        let request = test::TestRequest::get()
+            .uri("/tasks/ongoing")
+            .cookie(cookies.clone())
+            .to_request();
         let response = test::call_service(&mut app, request).await;
 
         assert_eq!(response.status(), StatusCode::FOUND);
@@ -463,13 +573,14 @@ mod tests {
             cfg.service(find_by_pattern);
         };
 
-        let mut app = setup_app!(configure);
-        let _ = pre_insert_task!(app);
+        let (mut app, cookies) = setup_app!(configure);
+        let _ = pre_insert_task!(cookies, app);
 
         let title_pattern = "?title=Watch&details=.";
         // NOTE(alex): Find tasks with title pattern
         let request = test::TestRequest::get()
             .uri(&format!("/tasks{}", title_pattern))
+            .cookie(cookies.clone())
             .to_request();
         let response = test::call_service(&mut app, request).await;
 
@@ -483,18 +594,70 @@ mod tests {
             cfg.service(find_by_id);
         };
 
-        let mut app = setup_app!(configure);
-        let task = pre_insert_task!(app);
+        let (mut app, cookies) = setup_app!(configure);
+        let task = pre_insert_task!(cookies, app);
 
         // NOTE(alex): Find with id
         let request = test::TestRequest::get()
             .uri(&format!("/tasks/{}", task.id))
+            .cookie(cookies.clone())
             .to_request();
         let response = test::call_service(&mut app, request).await;
 
         assert!(response.status().is_success());
     }
 
+    #[actix_rt::test]
+    pub async fn test_task_find_by_id_of_another_user() {
+        let configure = |cfg: &mut ServiceConfig| {
+            cfg.service(insert);
+            cfg.service(find_by_id);
+        };
+
+        let (mut app, cookies) = setup_app!(configure);
+        let task = pre_insert_task!(cookies, app);
+
+        let other_cookies = register_and_login!(app, "jet", "black");
+
+        // NOTE(alex): Find with id, as a user that doesn't own the task
+        let request = test::TestRequest::get()
+            .uri(&format!("/tasks/{}", task.id))
+            .cookie(other_cookies)
+            .to_request();
+        let response = test::call_service(&mut app, request).await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    pub async fn test_task_update_of_another_user() {
+        let configure = |cfg: &mut ServiceConfig| {
+            cfg.service(insert);
+            cfg.service(update);
+        };
+
+        let (mut app, cookies) = setup_app!(configure);
+        let task = pre_insert_task!(cookies, app);
+
+        let other_cookies = register_and_login!(app, "jet", "black");
+
+        let update_task = UpdateTask {
+            id: task.id,
+            new_title: "Feed Ein".to_string(),
+            details: "Not a good show.".to_string(),
+        };
+
+        // NOTE(alex): Update, as a user that doesn't own the task
+        let request = test::TestRequest::put()
+            .uri("/tasks")
+            .cookie(other_cookies)
+            .set_json(&update_task)
+            .to_request();
+        let response = test::call_service(&mut app, request).await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
     #[actix_rt::test]
     pub async fn test_task_favorite() {
         let configure = |cfg: &mut ServiceConfig| {
@@ -502,12 +665,13 @@ mod tests {
             cfg.service(favorite);
         };
 
-        let mut app = setup_app!(configure);
-        let task = pre_insert_task!(app);
+        let (mut app, cookies) = setup_app!(configure);
+        let task = pre_insert_task!(cookies, app);
 
         // NOTE(alex): Favorite
         let request = test::TestRequest::post()
             .uri(&format!("/tasks/favorite/{}", task.id))
+            .cookie(cookies.clone())
             .to_request();
         let response = test::call_service(&mut app, request).await;
 
@@ -522,12 +686,13 @@ mod tests {
             cfg.service(find_favorite);
         };
 
-        let mut app = setup_app!(configure);
-        let task = pre_insert_task!(app);
+        let (mut app, cookies) = setup_app!(configure);
+        let task = pre_insert_task!(cookies, app);
 
         // NOTE(alex): Favorite
         let task_favorite_request = test::TestRequest::post()
             .uri(&format!("/tasks/favorite/{}", task.id))
+            .cookie(cookies.clone())
             .to_request();
         let task_favorite_response = test::call_service(&mut app, task_favorite_request).await;
         assert_eq!(task_favorite_response.status(), StatusCode::FOUND);
@@ -1,33 +1,49 @@
 use thiserror::Error;
 
-use super::models::MIN_USERNAME_LENGTH;
+use crate::validation::{PasswordComplexity, UsernameCharset};
 
 #[derive(Debug, Error)]
 pub(crate) enum UserError {
     #[error("`username` field of `User` cannot be empty!")]
     EmptyUsername,
 
-    #[error(
-        "`username` field of `User` must be at least {} characters!",
-        MIN_USERNAME_LENGTH
-    )]
-    UsernameLength,
+    #[error("`username` field of `User` must be between {min} and {max} characters!")]
+    UsernameLength { min: usize, max: usize },
 
-    #[error("`username` field of `User` cannot contain whitespaces!")]
-    UsernameInvalidCharacter,
+    #[error("`username` field of `User` can only contain {0}!")]
+    UsernameInvalidCharacter(UsernameCharset),
 
     #[error("`password` field of `User` cannot be empty!")]
     EmptyPassword,
 
-    #[error(
-        "`password` field of `User` must be at least {} characters!",
-        MIN_USERNAME_LENGTH
-    )]
-    PasswordLength,
+    #[error("`password` field of `User` must be between {min} and {max} characters!")]
+    PasswordLength { min: usize, max: usize },
 
     #[error("`password` field of `User` cannot contain whitespaces!")]
     PasswordInvalidCharacter,
 
+    #[error("`password` field of `User` must contain {0}!")]
+    WeakPassword(PasswordComplexity),
+
     #[error("Could not find any `User` for id: `{0}`!")]
     NotFound(i64),
+
+    #[error("User is not logged in!")]
+    NotLoggedIn,
+}
+
+impl UserError {
+    /// The field of the request at fault, for the validation errors.
+    pub(crate) fn field(&self) -> Option<&'static str> {
+        match self {
+            UserError::EmptyUsername
+            | UserError::UsernameLength { .. }
+            | UserError::UsernameInvalidCharacter(_) => Some("username"),
+            UserError::EmptyPassword
+            | UserError::PasswordLength { .. }
+            | UserError::PasswordInvalidCharacter
+            | UserError::WeakPassword(_) => Some("password"),
+            _ => None,
+        }
+    }
 }
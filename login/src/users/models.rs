@@ -1,12 +1,14 @@
-use actix_web::{
-    body::BoxBody, dev::Payload, web::JsonBody, FromRequest, HttpRequest, HttpResponse, Responder,
-};
-use futures::{future::LocalBoxFuture, FutureExt};
+use actix_identity::RequestIdentity;
+use actix_web::{body::BoxBody, dev::Payload, FromRequest, HttpRequest, HttpResponse, Responder};
+use futures::future::{ready, Ready};
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, SqlitePool};
 
 use super::{errors::*, *};
-use crate::errors::AppError;
+use crate::{
+    errors::AppError,
+    validation::{length, FieldErrors, Validate, ValidationConfig},
+};
 
 pub(crate) const MIN_USERNAME_LENGTH: usize = 3;
 pub(crate) const MIN_PASSWORD_LENGTH: usize = 4;
@@ -53,24 +55,6 @@ impl InsertUser {
 
         Ok(user)
     }
-
-    fn validate(self) -> Result<Self, UserError> {
-        if self.valid_username.trim().is_empty() {
-            Err(UserError::EmptyUsername)
-        } else if self.valid_username.len() < MIN_USERNAME_LENGTH {
-            Err(UserError::UsernameLength)
-        } else if self.valid_username.contains(" ") {
-            Err(UserError::UsernameInvalidCharacter)
-        } else if self.valid_password.trim().is_empty() {
-            Err(UserError::EmptyPassword)
-        } else if self.valid_password.len() < MIN_PASSWORD_LENGTH {
-            Err(UserError::PasswordLength)
-        } else if self.valid_password.contains(" ") {
-            Err(UserError::PasswordInvalidCharacter)
-        } else {
-            Ok(self)
-        }
-    }
 }
 
 impl UpdateUser {
@@ -85,23 +69,57 @@ impl UpdateUser {
 
         Ok(result.rows_affected())
     }
+}
+
+impl Validate for InsertUser {
+    fn check(&self, config: &ValidationConfig, errors: &mut FieldErrors) {
+        check_username(&self.valid_username, config, errors);
+        check_password(&self.valid_password, config, errors);
+    }
+}
+
+impl Validate for UpdateUser {
+    fn check(&self, config: &ValidationConfig, errors: &mut FieldErrors) {
+        check_username(&self.valid_username, config, errors);
+        check_password(&self.valid_password, config, errors);
+    }
+}
 
-    fn validate(self) -> Result<Self, UserError> {
-        if self.valid_username.trim().is_empty() {
-            Err(UserError::EmptyUsername)
-        } else if self.valid_username.len() < MIN_USERNAME_LENGTH {
-            Err(UserError::UsernameLength)
-        } else if self.valid_username.contains(" ") {
-            Err(UserError::UsernameInvalidCharacter)
-        } else if self.valid_password.trim().is_empty() {
-            Err(UserError::EmptyPassword)
-        } else if self.valid_password.len() < MIN_PASSWORD_LENGTH {
-            Err(UserError::PasswordLength)
-        } else if self.valid_password.contains(" ") {
-            Err(UserError::PasswordInvalidCharacter)
-        } else {
-            Ok(self)
-        }
+fn check_username(username: &str, config: &ValidationConfig, errors: &mut FieldErrors) {
+    let username_length = length(username);
+
+    if username.trim().is_empty() {
+        errors.push(UserError::EmptyUsername);
+    } else if username_length < MIN_USERNAME_LENGTH || username_length > config.max_username_length
+    {
+        errors.push(UserError::UsernameLength {
+            min: MIN_USERNAME_LENGTH,
+            max: config.max_username_length,
+        });
+    } else if !username
+        .chars()
+        .all(|character| config.username_charset.allows(character))
+    {
+        errors.push(UserError::UsernameInvalidCharacter(config.username_charset));
+    }
+}
+
+fn check_password(password: &str, config: &ValidationConfig, errors: &mut FieldErrors) {
+    let password_length = length(password);
+
+    if password.trim().is_empty() {
+        errors.push(UserError::EmptyPassword);
+    } else if password_length < config.min_password_length
+        || password_length > config.max_password_length
+    {
+        errors.push(UserError::PasswordLength {
+            min: config.min_password_length,
+            max: config.max_password_length,
+        });
+    } else if password.chars().any(char::is_whitespace) {
+        errors.push(UserError::PasswordInvalidCharacter);
+    } else if !config.password_complexity.allows(password) {
+        errors.push(UserError::WeakPassword(config.password_complexity));
     }
 }
 
@@ -164,34 +182,19 @@ impl Responder for User {
     }
 }
 
-impl FromRequest for InsertUser {
+/// NOTE(alex): The `login` route remembers the whole `User` as the identity, so we just have to
+/// deserialize it back.
+impl FromRequest for User {
     type Error = AppError;
 
-    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
-
-    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-        JsonBody::new(req, payload, None, false)
-            .limit(4056)
-            .map(|res: Result<InsertUser, _>| match res {
-                Ok(insert_user) => insert_user.validate().map_err(|fail| AppError::from(fail)),
-                Err(fail) => Err(AppError::from(fail)),
-            })
-            .boxed_local()
-    }
-}
+    type Future = Ready<Result<Self, Self::Error>>;
 
-impl FromRequest for UpdateUser {
-    type Error = AppError;
-
-    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let logged_user = match req.get_identity() {
+            Some(identity) => serde_json::from_str(&identity).map_err(AppError::from),
+            None => Err(UserError::NotLoggedIn.into()),
+        };
 
-    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-        JsonBody::new(req, payload, None, false)
-            .limit(4056)
-            .map(|res: Result<UpdateUser, _>| match res {
-                Ok(update_user) => update_user.validate().map_err(|fail| AppError::from(fail)),
-                Err(fail) => Err(AppError::from(fail)),
-            })
-            .boxed_local()
+        ready(logged_user)
     }
 }
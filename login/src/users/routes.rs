@@ -6,12 +6,12 @@ use super::{
     errors::UserError,
     models::{InsertUser, LoginUser, UpdateUser, User},
 };
-use crate::errors::AppError;
+use crate::{errors::AppError, validation::Validated};
 
 #[post("/users/register")]
 async fn insert(
     db_pool: web::Data<SqlitePool>,
-    input: InsertUser,
+    Validated(input): Validated<InsertUser>,
 ) -> Result<impl Responder, AppError> {
     let user = input.insert(db_pool.get_ref()).await?;
     Ok(HttpResponse::Created().json(user))
@@ -20,7 +20,7 @@ async fn insert(
 #[put("/users")]
 async fn update(
     db_pool: web::Data<SqlitePool>,
-    input: UpdateUser,
+    Validated(input): Validated<UpdateUser>,
 ) -> Result<impl Responder, AppError> {
     let num_modified = input.update(db_pool.get_ref()).await?;
 
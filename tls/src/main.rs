@@ -1,7 +1,32 @@
+use std::path::PathBuf;
+
+use tls_lib::settings::Settings;
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    std::env::set_var("RUST_LOG", "info");
-    env_logger::init();
+    // NOTE(alex): The configuration file comes from `--config <path>`, or `HELLO_ACTIX_CONFIG`,
+    // without either we run with the defaults (plus environment overrides).
+    let mut args = std::env::args();
+    let config_file = args
+        .position(|arg| arg == "--config")
+        .and_then(|_| args.next())
+        .or_else(|| std::env::var("HELLO_ACTIX_CONFIG").ok())
+        .map(PathBuf::from);
+
+    let settings = Settings::load(config_file.as_deref())
+        .map_err(|fail| std::io::Error::new(std::io::ErrorKind::InvalidInput, fail.to_string()))?;
+
+    env_logger::Builder::new()
+        .parse_filters(&settings.log_level)
+        .init();
+
+    // NOTE(alex): Brings the database schema up to date, without starting the server.
+    if std::env::args().any(|arg| arg == "--migrate-only") {
+        return tls_lib::setup_database(&settings)
+            .await
+            .map(|_| ())
+            .map_err(|fail| std::io::Error::new(std::io::ErrorKind::Other, fail.to_string()));
+    }
 
-    tls_lib::start_app().await
+    tls_lib::start_app(settings).await
 }
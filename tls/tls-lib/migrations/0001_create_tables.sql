@@ -0,0 +1,45 @@
+-- NOTE(alex): `if not exists` only keeps this from failing on tables that are already there. A
+-- database created before migrations existed is not adopted, its `Task`, and `User`, lack columns
+-- this schema has, `migrations::run` refuses it before getting here.
+
+create table if not exists Task (
+    id integer primary key,
+    title text not null,
+    details text,
+    owner_id int not null,
+    foreign key (owner_id) references User(id) on delete cascade
+);
+
+create table if not exists Done (
+    task_id int not null,
+    foreign key (task_id) references Task(id) on delete cascade
+);
+
+create view if not exists OngoingTask as
+select Task.id,
+    Task.title,
+    Task.details,
+    Task.owner_id
+from Task
+where
+    Task.id not in (
+        select task_id
+        from Done
+    );
+
+create table if not exists User (
+    id integer primary key,
+    username text not null,
+    password_hash text not null
+);
+
+create table if not exists Session (
+    id integer primary key,
+    user_id int not null,
+    token_id text not null unique,
+    created_at int not null,
+    last_seen_at int not null,
+    user_agent text,
+    ip text,
+    foreign key (user_id) references User(id) on delete cascade
+);
\ No newline at end of file
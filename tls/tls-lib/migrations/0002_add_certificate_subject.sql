@@ -0,0 +1,5 @@
+-- NOTE(alex): `unique` is not allowed in `alter table`, so it's enforced by the index instead. Many
+-- users without a certificate are fine, as `null`s are never equal.
+alter table User add column certificate_subject text;
+
+create unique index User_certificate_subject on User(certificate_subject);
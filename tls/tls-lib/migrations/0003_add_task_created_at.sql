@@ -0,0 +1,22 @@
+-- NOTE(alex): Tasks created before this migration get `0`, so they sort as the oldest ones.
+alter table Task add column created_at int not null default 0;
+
+-- NOTE(alex): `Task` gained a column, the view has to be recreated to show it.
+drop view OngoingTask;
+
+create view OngoingTask as
+select Task.id,
+    Task.title,
+    Task.details,
+    Task.owner_id,
+    Task.created_at
+from Task
+where
+    Task.id not in (
+        select task_id
+        from Done
+    );
+
+-- NOTE(alex): One per `sort`, the `id` is there because it's the tie breaker for the cursor.
+create index Task_owner_title on Task(owner_id, title, id);
+create index Task_owner_created_at on Task(owner_id, created_at, id);
@@ -0,0 +1,33 @@
+-- NOTE(alex): External content table, the text lives only in `Task`, `TaskSearch` keeps just the
+-- index. The triggers below are what keep both in sync, so every write to `Task` must go through
+-- them (no `insert into TaskSearch` anywhere else).
+create virtual table TaskSearch using fts5(
+    title,
+    details,
+    content = 'Task',
+    content_rowid = 'id'
+);
+
+create trigger Task_search_insert after insert on Task
+begin
+    insert into TaskSearch (rowid, title, details)
+    values (new.id, new.title, new.details);
+end;
+
+create trigger Task_search_delete after delete on Task
+begin
+    insert into TaskSearch (TaskSearch, rowid, title, details)
+    values ('delete', old.id, old.title, old.details);
+end;
+
+create trigger Task_search_update after update of title, details on Task
+begin
+    insert into TaskSearch (TaskSearch, rowid, title, details)
+    values ('delete', old.id, old.title, old.details);
+
+    insert into TaskSearch (rowid, title, details)
+    values (new.id, new.title, new.details);
+end;
+
+-- NOTE(alex): Indexes the tasks that existed before this migration.
+insert into TaskSearch (TaskSearch) values ('rebuild');
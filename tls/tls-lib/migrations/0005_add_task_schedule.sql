@@ -0,0 +1,32 @@
+-- NOTE(alex): `priority` goes from 0 (low) to 3 (urgent), so sorting by it is just sorting the
+-- column.
+alter table Task add column updated_at int not null default 0;
+alter table Task add column due_at int;
+alter table Task add column priority int not null default 1 check (priority between 0 and 3);
+
+update Task
+set updated_at = created_at;
+
+-- NOTE(alex): Tasks done before this migration get `0`.
+alter table Done add column completed_at int not null default 0;
+
+-- NOTE(alex): Every query that returns a `Task` reads from here, so `completed_at` comes along. A
+-- task may be done more than once (`done` doesn't check), the latest one wins.
+create view TaskView as
+select Task.*,
+    (
+        select max(Done.completed_at)
+        from Done
+        where Done.task_id = Task.id
+    ) as completed_at
+from Task;
+
+drop view OngoingTask;
+
+create view OngoingTask as
+select *
+from TaskView
+where TaskView.completed_at is null;
+
+create index Task_owner_priority on Task(owner_id, priority, id);
+create index Task_owner_due_at on Task(owner_id, due_at);
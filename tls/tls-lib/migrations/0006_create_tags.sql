@@ -0,0 +1,41 @@
+-- NOTE(alex): Tag names are unique per user, not globally.
+create table Tag (
+    id integer primary key,
+    name text not null,
+    owner_id int not null,
+    foreign key (owner_id) references User(id) on delete cascade
+);
+
+create unique index Tag_owner_name on Tag(owner_id, name);
+
+create table TaskTag (
+    task_id int not null,
+    tag_id int not null,
+    primary key (task_id, tag_id),
+    foreign key (task_id) references Task(id) on delete cascade,
+    foreign key (tag_id) references Tag(id) on delete cascade
+);
+
+create index TaskTag_tag on TaskTag(tag_id);
+
+-- NOTE(alex): `tags` is a JSON array of names (sorted), so they come inline with every `Task`.
+drop view TaskView;
+
+create view TaskView as
+select Task.*,
+    (
+        select max(Done.completed_at)
+        from Done
+        where Done.task_id = Task.id
+    ) as completed_at,
+    (
+        select json_group_array(TaskTagName.name)
+        from (
+            select Tag.name
+            from TaskTag
+                join Tag on Tag.id = TaskTag.tag_id
+            where TaskTag.task_id = Task.id
+            order by Tag.name
+        ) as TaskTagName
+    ) as tags
+from Task;
\ No newline at end of file
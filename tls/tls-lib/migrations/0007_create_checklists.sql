@@ -0,0 +1,42 @@
+-- NOTE(alex): `position` orders the items of a task, starting at `0`, and `checked_at` is `null`
+-- until the item is checked.
+create table ChecklistItem (
+    id integer primary key,
+    task_id int not null,
+    title text not null,
+    position int not null,
+    checked_at int,
+    foreign key (task_id) references Task(id) on delete cascade
+);
+
+create index ChecklistItem_task_position on ChecklistItem(task_id, position);
+
+-- NOTE(alex): `progress` is a JSON object `{"done": 3, "total": 5}` of its checklist.
+drop view TaskView;
+
+create view TaskView as
+select Task.*,
+    (
+        select max(Done.completed_at)
+        from Done
+        where Done.task_id = Task.id
+    ) as completed_at,
+    (
+        select json_group_array(TaskTagName.name)
+        from (
+            select Tag.name
+            from TaskTag
+                join Tag on Tag.id = TaskTag.tag_id
+            where TaskTag.task_id = Task.id
+            order by Tag.name
+        ) as TaskTagName
+    ) as tags,
+    (
+        select json_object(
+                'done', count(ChecklistItem.checked_at),
+                'total', count(*)
+            )
+        from ChecklistItem
+        where ChecklistItem.task_id = Task.id
+    ) as progress
+from Task;
\ No newline at end of file
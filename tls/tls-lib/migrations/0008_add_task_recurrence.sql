@@ -0,0 +1,2 @@
+-- NOTE(alex): An RRULE (see `Recurrence`), in its canonical form, `null` for tasks that don't repeat.
+alter table Task add column recurrence text;
\ No newline at end of file
@@ -0,0 +1,27 @@
+-- NOTE(alex): There's no foreign key to `Task` on purpose, the history of a task outlives it.
+-- `changes` is a JSON object of `{"field": {"from": old, "to": new}}`, only `updated` events have
+-- it.
+create table TaskEvent (
+    id integer primary key,
+    task_id int not null,
+    user_id int not null,
+    kind text not null,
+    changes text,
+    created_at int not null
+);
+
+create index TaskEvent_task on TaskEvent(task_id, id);
+
+create trigger TaskEvent_no_update before update on TaskEvent
+begin
+    select raise(abort, 'TaskEvent is append-only');
+end;
+
+create trigger TaskEvent_no_delete before delete on TaskEvent
+begin
+    select raise(abort, 'TaskEvent is append-only');
+end;
+
+-- NOTE(alex): Admins may read every `TaskEvent` (`GET /audit`), no route grants it, it's set
+-- straight in the database.
+alter table User add column is_admin int not null default 0;
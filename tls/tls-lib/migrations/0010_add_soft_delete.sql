@@ -0,0 +1,41 @@
+-- NOTE(alex): `deleted_at` is `null` until the row is moved to the trash, trashed rows are only
+-- really deleted by the purge (see `trash::purge`).
+alter table Task add column deleted_at int;
+
+alter table User add column deleted_at int;
+
+-- NOTE(alex): `AllTaskView` is the old `TaskView`, trashed tasks included, only the trash reads
+-- from it. `TaskView` (and so `OngoingTask`) leaves them out.
+drop view TaskView;
+
+create view AllTaskView as
+select Task.*,
+    (
+        select max(Done.completed_at)
+        from Done
+        where Done.task_id = Task.id
+    ) as completed_at,
+    (
+        select json_group_array(TaskTagName.name)
+        from (
+            select Tag.name
+            from TaskTag
+                join Tag on Tag.id = TaskTag.tag_id
+            where TaskTag.task_id = Task.id
+            order by Tag.name
+        ) as TaskTagName
+    ) as tags,
+    (
+        select json_object(
+                'done', count(ChecklistItem.checked_at),
+                'total', count(*)
+            )
+        from ChecklistItem
+        where ChecklistItem.task_id = Task.id
+    ) as progress
+from Task;
+
+create view TaskView as
+select *
+from AllTaskView
+where AllTaskView.deleted_at is null;
@@ -0,0 +1,46 @@
+-- NOTE(alex): `version` is the `ETag` of a task, it goes up whenever anything in its representation
+-- changes. `PUT`, `DELETE`, and restoring a task bump it in their own queries, the triggers take
+-- care of what lives in other tables (done, tags, checklist).
+alter table Task add column version int not null default 1;
+
+create trigger Done_insert_version after insert on Done
+begin
+    update Task set version = version + 1 where Task.id = new.task_id;
+end;
+
+create trigger Done_delete_version after delete on Done
+begin
+    update Task set version = version + 1 where Task.id = old.task_id;
+end;
+
+create trigger TaskTag_insert_version after insert on TaskTag
+begin
+    update Task set version = version + 1 where Task.id = new.task_id;
+end;
+
+create trigger TaskTag_delete_version after delete on TaskTag
+begin
+    update Task set version = version + 1 where Task.id = old.task_id;
+end;
+
+create trigger Tag_rename_version after update of name on Tag
+begin
+    update Task
+    set version = version + 1
+    where Task.id in (select TaskTag.task_id from TaskTag where TaskTag.tag_id = new.id);
+end;
+
+create trigger ChecklistItem_insert_version after insert on ChecklistItem
+begin
+    update Task set version = version + 1 where Task.id = new.task_id;
+end;
+
+create trigger ChecklistItem_update_version after update on ChecklistItem
+begin
+    update Task set version = version + 1 where Task.id = new.task_id;
+end;
+
+create trigger ChecklistItem_delete_version after delete on ChecklistItem
+begin
+    update Task set version = version + 1 where Task.id = old.task_id;
+end;
@@ -0,0 +1,6 @@
+-- NOTE(alex): `spawned_from` is the task whose completion created this one (see `Recurrence`),
+-- `null` for tasks that were inserted. A task spawns at most one next occurrence, completing it
+-- again (after an `undo`) finds the one it already has.
+alter table Task add column spawned_from int references Task (id) on delete set null;
+
+create unique index Task_spawned_from on Task (spawned_from);
@@ -0,0 +1,12 @@
+-- NOTE(alex): `login` finds the user by `username` alone, so it must be unique. Users that already
+-- share one keep it on the oldest account, the others get their id appended (`spike_7`), and have
+-- to login with that.
+update User
+set username = User.username || '_' || User.id
+where User.id not in (
+        select min(Duplicate.id)
+        from User as Duplicate
+        group by Duplicate.username
+    );
+
+create unique index User_username on User(username);
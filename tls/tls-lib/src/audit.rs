@@ -0,0 +1,7 @@
+pub mod models;
+pub mod routes;
+
+const INSERT: &'static str = include_str!("./audit/queries/insert.sql");
+const FIND_BY_TASK: &'static str = include_str!("./audit/queries/find_by_task.sql");
+const FIND_PAGE: &'static str = include_str!("./audit/queries/find_page.sql");
+const COUNT: &'static str = include_str!("./audit/queries/count.sql");
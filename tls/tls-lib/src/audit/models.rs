@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+
+use actix_web::{dev::Payload, web::Query, FromRequest, HttpRequest};
+use futures::future::{ready, Ready};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, Executor, FromRow, Sqlite, SqlitePool};
+
+use super::*;
+use crate::{
+    errors::AppError,
+    tasks::{
+        errors::TaskError,
+        models::{Page, DEFAULT_PAGE_LIMIT, MAX_PAGE_LIMIT},
+    },
+    timestamps::{self, rfc3339},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum TaskEventKind {
+    Created,
+    Updated,
+    Done,
+    Undone,
+    Deleted,
+    Restored,
+    Favorited,
+    Unfavorited,
+}
+
+/// The old, and new, value of a field.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    pub from: serde_json::Value,
+    pub to: serde_json::Value,
+}
+
+/// Only the fields that changed, keyed by their names in `Task`.
+pub type Changes = BTreeMap<String, Change>;
+
+/// NOTE(alex): So timestamps show up in `changes` the same way they do everywhere else.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Timestamp(#[serde(with = "rfc3339::option")] pub Option<i64>);
+
+/// Something that happened to a `Task`, these are never updated, or deleted.
+///
+/// NOTE(alex): Only owners mutate their tasks, so `user_id` is both who did it, and who the history
+/// belongs to.
+#[derive(Clone, Debug, Serialize, Deserialize, FromRow)]
+pub struct TaskEvent {
+    pub id: i64,
+    pub task_id: i64,
+    pub user_id: i64,
+    pub kind: TaskEventKind,
+    pub changes: Option<Json<Changes>>,
+    #[serde(with = "rfc3339")]
+    pub created_at: i64,
+}
+
+#[derive(Debug)]
+pub struct InsertTaskEvent {
+    pub task_id: i64,
+    pub user_id: i64,
+    pub kind: TaskEventKind,
+    pub changes: Option<Changes>,
+}
+
+/// Query string of `GET /audit`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuditQuery {
+    pub after_id: Option<i64>,
+    pub limit: Option<u32>,
+}
+
+impl InsertTaskEvent {
+    /// NOTE(alex): Takes any executor, so the event goes in the same transaction as the mutation it
+    /// records.
+    pub async fn insert<'c, E>(self, executor: E) -> Result<(), AppError>
+    where
+        E: Executor<'c, Database = Sqlite>,
+    {
+        sqlx::query(INSERT)
+            .bind(self.task_id)
+            .bind(self.user_id)
+            .bind(self.kind)
+            .bind(self.changes.map(Json))
+            .bind(timestamps::now())
+            .execute(executor)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Adds `field` to `changes`, but only when `from` and `to` differ.
+pub fn diff<T>(changes: &mut Changes, field: &str, from: &T, to: &T) -> Result<(), AppError>
+where
+    T: Serialize + PartialEq,
+{
+    if from != to {
+        changes.insert(
+            field.to_string(),
+            Change {
+                from: serde_json::to_value(from)?,
+                to: serde_json::to_value(to)?,
+            },
+        );
+    }
+
+    Ok(())
+}
+
+impl TaskEvent {
+    /// Oldest first, empty when `user_id` never did anything to this task.
+    pub async fn find_by_task(
+        db_pool: &SqlitePool,
+        task_id: i64,
+        user_id: i64,
+    ) -> Result<Vec<Self>, AppError> {
+        let result = sqlx::query_as(FIND_BY_TASK)
+            .bind(task_id)
+            .bind(user_id)
+            .fetch_all(db_pool)
+            .await?;
+
+        Ok(result)
+    }
+
+    /// Every event, of every user, oldest first.
+    pub async fn find_page(
+        db_pool: &SqlitePool,
+        query: &AuditQuery,
+    ) -> Result<Page<Self>, AppError> {
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+
+        // NOTE(alex): Same trick as `Task::find_page`, the extra event means there's a next page.
+        let mut events: Vec<Self> = sqlx::query_as(FIND_PAGE)
+            .bind(query.after_id)
+            .bind(limit + 1)
+            .fetch_all(db_pool)
+            .await?;
+
+        let next_cursor = if events.len() > limit as usize {
+            events.truncate(limit as usize);
+            events.last().map(|event| event.id)
+        } else {
+            None
+        };
+
+        let (total,): (i64,) = sqlx::query_as(COUNT).fetch_one(db_pool).await?;
+
+        Ok(Page {
+            items: events,
+            next_cursor,
+            total,
+        })
+    }
+}
+
+impl AuditQuery {
+    fn validate(self) -> Result<Self, TaskError> {
+        match self.limit {
+            Some(limit) if limit == 0 || limit > MAX_PAGE_LIMIT => Err(TaskError::PageLimit),
+            _ => Ok(self),
+        }
+    }
+}
+
+impl FromRequest for AuditQuery {
+    type Error = AppError;
+
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let audit_query = match Query::<AuditQuery>::from_query(req.query_string()) {
+            Ok(audit_query) => audit_query
+                .into_inner()
+                .validate()
+                .map_err(|fail| AppError::from(fail)),
+            Err(fail) => Err(AppError::from(fail)),
+        };
+
+        ready(audit_query)
+    }
+}
@@ -0,0 +1,2 @@
+select count(*)
+from TaskEvent
\ No newline at end of file
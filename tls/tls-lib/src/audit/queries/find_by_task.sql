@@ -0,0 +1,5 @@
+select *
+from TaskEvent
+where
+    TaskEvent.task_id = $1 and TaskEvent.user_id = $2
+order by TaskEvent.id asc
\ No newline at end of file
@@ -0,0 +1,7 @@
+-- NOTE(alex): `$1` is the cursor (`after_id`), ids only ever grow, so it is enough on its own.
+select *
+from TaskEvent
+where
+    $1 is null or TaskEvent.id > $1
+order by TaskEvent.id asc
+limit $2
\ No newline at end of file
@@ -0,0 +1,2 @@
+insert into TaskEvent (task_id, user_id, kind, changes, created_at)
+values ($1, $2, $3, $4, $5)
\ No newline at end of file
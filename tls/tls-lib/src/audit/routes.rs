@@ -0,0 +1,52 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use actix_web_httpauth::middleware::HttpAuthentication;
+use sqlx::SqlitePool;
+
+use super::models::{AuditQuery, TaskEvent};
+use crate::{
+    errors::AppError,
+    tasks::errors::TaskError,
+    users::{
+        errors::UserError,
+        models::{LoggedUser, User},
+    },
+    validator,
+};
+
+/// NOTE(alex): Works for deleted tasks too, that's the point of keeping a history.
+#[get(
+    "/tasks/{id:\\d+}/history",
+    wrap = "HttpAuthentication::with_fn(validator)"
+)]
+pub async fn find_history(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    id: web::Path<i64>,
+) -> Result<impl Responder, AppError> {
+    let events = TaskEvent::find_by_task(db_pool.get_ref(), *id, logged_user.id).await?;
+
+    if events.is_empty() {
+        Err(TaskError::NotFound(*id).into())
+    } else {
+        Ok(HttpResponse::Ok().json(&events))
+    }
+}
+
+#[get("/audit", wrap = "HttpAuthentication::with_fn(validator)")]
+pub async fn find_all(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    query: AuditQuery,
+) -> Result<impl Responder, AppError> {
+    if !User::is_admin(db_pool.get_ref(), logged_user.id).await? {
+        return Err(UserError::NotAdmin.into());
+    }
+
+    let page = TaskEvent::find_page(db_pool.get_ref(), &query).await?;
+    Ok(HttpResponse::Ok().json(&page))
+}
+
+pub fn audit_service(cfg: &mut web::ServiceConfig) {
+    cfg.service(find_history);
+    cfg.service(find_all);
+}
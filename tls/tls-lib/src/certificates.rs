@@ -0,0 +1,316 @@
+use std::{
+    any::Any,
+    convert::TryFrom,
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use actix_tls::accept::rustls::TlsStream;
+use actix_web::{
+    dev::{Extensions, Payload},
+    rt::net::TcpStream,
+    FromRequest, HttpRequest,
+};
+use futures::future::{ready, Ready};
+use rustls::{
+    server::{ClientHello, ResolvesServerCert},
+    sign::{self, CertifiedKey, SigningKey},
+    Certificate, PrivateKey, RootCertStore, SignatureScheme,
+};
+use rustls_pemfile::Item;
+
+use self::errors::TlsError;
+use crate::{errors::AppError, users::errors::UserError};
+
+pub mod errors;
+
+/// What gets signed to check that the private key belongs to the certificate, see `check_key`.
+const KEY_CHECK_MESSAGE: &[u8] = b"hello-actix key check";
+
+/// Schemes `check_key` signs with, one for each kind of key `sign::any_supported_type` takes, and
+/// how the certificate verifies it.
+static KEY_CHECK_SCHEMES: &[(SignatureScheme, &webpki::SignatureAlgorithm)] = &[
+    (
+        SignatureScheme::ECDSA_NISTP256_SHA256,
+        &webpki::ECDSA_P256_SHA256,
+    ),
+    (
+        SignatureScheme::ECDSA_NISTP384_SHA384,
+        &webpki::ECDSA_P384_SHA384,
+    ),
+    (SignatureScheme::ED25519, &webpki::ED25519),
+    (
+        SignatureScheme::RSA_PKCS1_SHA256,
+        &webpki::RSA_PKCS1_2048_8192_SHA256,
+    ),
+];
+
+/// Hands out the certificate chain loaded from `cert_file`, and `key_file`, to every new TLS
+/// handshake.
+///
+/// NOTE(alex): Reloading only swaps the `Arc` that new handshakes get, connections that are already
+/// established keep the certificate they started with, so nothing is dropped.
+pub struct CertificateResolver {
+    cert_file: PathBuf,
+    key_file: PathBuf,
+    certified_key: RwLock<Arc<CertifiedKey>>,
+    /// Modification times of `(cert_file, key_file)` from the last successful load.
+    loaded_at: Mutex<(Option<SystemTime>, Option<SystemTime>)>,
+}
+
+impl CertificateResolver {
+    pub fn new(
+        cert_file: impl Into<PathBuf>,
+        key_file: impl Into<PathBuf>,
+    ) -> Result<Self, TlsError> {
+        let cert_file = cert_file.into();
+        let key_file = key_file.into();
+
+        let loaded_at = modified_times(&cert_file, &key_file);
+        let certified_key = load_certified_key(&cert_file, &key_file)?;
+
+        Ok(Self {
+            cert_file,
+            key_file,
+            certified_key: RwLock::new(Arc::new(certified_key)),
+            loaded_at: Mutex::new(loaded_at),
+        })
+    }
+
+    /// The certificate new handshakes get.
+    pub fn certified_key(&self) -> Arc<CertifiedKey> {
+        self.certified_key.read().unwrap().clone()
+    }
+
+    /// Loads the files again, keeping the current certificate if anything goes wrong (a key that
+    /// doesn't belong to the certificate included).
+    pub fn reload(&self) -> Result<(), TlsError> {
+        let loaded_at = modified_times(&self.cert_file, &self.key_file);
+        let certified_key = load_certified_key(&self.cert_file, &self.key_file)?;
+
+        *self.certified_key.write().unwrap() = Arc::new(certified_key);
+        *self.loaded_at.lock().unwrap() = loaded_at;
+
+        Ok(())
+    }
+
+    /// Same as `reload`, but only when either file was modified since the last successful load.
+    /// Returns whether it reloaded.
+    pub fn reload_if_changed(&self) -> Result<bool, TlsError> {
+        let current = modified_times(&self.cert_file, &self.key_file);
+
+        if current == *self.loaded_at.lock().unwrap() {
+            Ok(false)
+        } else {
+            self.reload().map(|_| true)
+        }
+    }
+}
+
+impl ResolvesServerCert for CertificateResolver {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        Some(self.certified_key())
+    }
+}
+
+/// Reads a full chain `cert_file` (leaf first), and the private key in `key_file`, which may be
+/// PKCS#8, PKCS#1 (RSA), or SEC1 (EC).
+pub fn load_certified_key(cert_file: &Path, key_file: &Path) -> Result<CertifiedKey, TlsError> {
+    let certificates = read_pem(cert_file)?
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::X509Certificate(der) => Some(Certificate(der)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    if certificates.is_empty() {
+        return Err(TlsError::NoCertificates(cert_file.display().to_string()));
+    }
+
+    let private_key = read_pem(key_file)?
+        .into_iter()
+        .find_map(|item| match item {
+            Item::PKCS8Key(der) | Item::RSAKey(der) | Item::ECKey(der) => Some(PrivateKey(der)),
+            _ => None,
+        })
+        .ok_or_else(|| TlsError::NoPrivateKey(key_file.display().to_string()))?;
+
+    let signing_key = sign::any_supported_type(&private_key)
+        .map_err(|_| TlsError::UnsupportedKey(key_file.display().to_string()))?;
+
+    if !check_key(&certificates[0], signing_key.as_ref()) {
+        return Err(TlsError::KeyMismatch {
+            cert: cert_file.display().to_string(),
+            key: key_file.display().to_string(),
+        });
+    }
+
+    Ok(CertifiedKey::new(certificates, signing_key))
+}
+
+/// Whether `signing_key` is the private key of the `leaf` certificate, it signs a message that the
+/// public key of `leaf` must verify.
+///
+/// NOTE(alex): rustls takes any pair we give it, and only the handshakes would fail, so this is what
+/// keeps a reload from swapping in a new certificate with the old key (or the other way around).
+fn check_key(leaf: &Certificate, signing_key: &dyn SigningKey) -> bool {
+    let offered = KEY_CHECK_SCHEMES
+        .iter()
+        .map(|(scheme, _)| *scheme)
+        .collect::<Vec<_>>();
+
+    let verified = || -> Option<()> {
+        let signer = signing_key.choose_scheme(&offered)?;
+        let signature = signer.sign(KEY_CHECK_MESSAGE).ok()?;
+        let (_, algorithm) = KEY_CHECK_SCHEMES
+            .iter()
+            .find(|(scheme, _)| *scheme == signer.scheme())?;
+
+        webpki::EndEntityCert::try_from(leaf.0.as_slice())
+            .ok()?
+            .verify_signature(algorithm, KEY_CHECK_MESSAGE, &signature)
+            .ok()
+    };
+
+    verified().is_some()
+}
+
+/// Reads the CA bundle in `ca_file`, client certificates must be signed by one of these.
+pub fn load_client_roots(ca_file: &Path) -> Result<RootCertStore, TlsError> {
+    let mut roots = RootCertStore::empty();
+
+    for item in read_pem(ca_file)? {
+        if let Item::X509Certificate(der) = item {
+            roots
+                .add(&Certificate(der))
+                .map_err(|fail| TlsError::InvalidCa {
+                    path: ca_file.display().to_string(),
+                    reason: fail.to_string(),
+                })?;
+        }
+    }
+
+    if roots.is_empty() {
+        return Err(TlsError::NoCertificates(ca_file.display().to_string()));
+    }
+
+    Ok(roots)
+}
+
+/// The client certificate that rustls verified during the handshake, only the subject matters to
+/// us, as that's what gets mapped onto an `User`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerCertificate {
+    /// Such as `O=hello-actix, CN=task-runner`.
+    pub subject: String,
+}
+
+impl PeerCertificate {
+    pub fn from_der(der: &[u8]) -> Result<Self, TlsError> {
+        let (_, certificate) = x509_parser::parse_x509_certificate(der)
+            .map_err(|fail| TlsError::InvalidPeerCertificate(fail.to_string()))?;
+
+        Ok(Self {
+            subject: certificate.subject().to_string(),
+        })
+    }
+}
+
+/// Passed to `HttpServer::on_connect`, puts the `PeerCertificate` (if the client presented one) in
+/// the connection data, where it stays for every request made over this connection.
+pub fn peer_certificate_on_connect(connection: &dyn Any, data: &mut Extensions) {
+    let leaf = connection
+        .downcast_ref::<TlsStream<TcpStream>>()
+        .and_then(|tls_stream| tls_stream.get_ref().1.peer_certificates())
+        .and_then(|certificates| certificates.first());
+
+    if let Some(leaf) = leaf {
+        match PeerCertificate::from_der(&leaf.0) {
+            Ok(peer_certificate) => {
+                data.insert(peer_certificate);
+            }
+            Err(fail) => log::warn!("Ignoring client certificate, failed with `{}`!", fail),
+        }
+    }
+}
+
+/// NOTE(alex): Only available on connections where the client presented a certificate that passed
+/// verification, see `setup_tls`.
+impl FromRequest for PeerCertificate {
+    type Error = AppError;
+
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let peer_certificate = req
+            .conn_data::<PeerCertificate>()
+            .cloned()
+            .ok_or_else(|| UserError::NoCertificate.into());
+
+        ready(peer_certificate)
+    }
+}
+
+/// Reloads the certificate whenever its files change (checked every `interval`), or when the
+/// process receives `SIGHUP`. Runs until the server stops.
+///
+/// NOTE(alex): A failed reload is logged, and the old certificate is kept, a renewal tool may have
+/// only written one of the files so far (`load_certified_key` refuses a key that doesn't belong to
+/// the certificate), the next check will pick it up.
+pub async fn watch(resolver: Arc<CertificateResolver>, interval: Option<Duration>) {
+    #[cfg(unix)]
+    {
+        use actix_web::rt::signal::unix::{signal, SignalKind};
+
+        let resolver = resolver.clone();
+        match signal(SignalKind::hangup()) {
+            Ok(mut hangup) => {
+                actix_web::rt::spawn(async move {
+                    while hangup.recv().await.is_some() {
+                        match resolver.reload() {
+                            Ok(_) => log::info!("Reloaded TLS certificate on SIGHUP."),
+                            Err(fail) => {
+                                log::error!("Failed reloading TLS certificate with `{}`!", fail)
+                            }
+                        }
+                    }
+                });
+            }
+            Err(fail) => log::error!("Could not listen for SIGHUP, failed with `{}`!", fail),
+        }
+    }
+
+    if let Some(interval) = interval {
+        let mut ticks = actix_web::rt::time::interval(interval);
+
+        loop {
+            ticks.tick().await;
+
+            match resolver.reload_if_changed() {
+                Ok(true) => log::info!("Reloaded TLS certificate after its files changed."),
+                Ok(false) => (),
+                Err(fail) => log::error!("Failed reloading TLS certificate with `{}`!", fail),
+            }
+        }
+    }
+}
+
+fn read_pem(path: &Path) -> Result<Vec<Item>, TlsError> {
+    let read_error = |fail: std::io::Error| TlsError::Read {
+        path: path.display().to_string(),
+        reason: fail.to_string(),
+    };
+
+    let mut reader = BufReader::new(File::open(path).map_err(read_error)?);
+    rustls_pemfile::read_all(&mut reader).map_err(read_error)
+}
+
+fn modified_times(cert_file: &Path, key_file: &Path) -> (Option<SystemTime>, Option<SystemTime>) {
+    let modified = |path: &Path| fs::metadata(path).and_then(|meta| meta.modified()).ok();
+
+    (modified(cert_file), modified(key_file))
+}
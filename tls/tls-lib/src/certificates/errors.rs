@@ -0,0 +1,25 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TlsError {
+    #[error("Could not read `{path}`, failed with `{reason}`!")]
+    Read { path: String, reason: String },
+
+    #[error("No certificates found in `{0}`!")]
+    NoCertificates(String),
+
+    #[error("No PKCS#8, PKCS#1 or SEC1 private key found in `{0}`!")]
+    NoPrivateKey(String),
+
+    #[error("Private key in `{0}` is not a supported RSA, ECDSA or Ed25519 key!")]
+    UnsupportedKey(String),
+
+    #[error("Private key in `{key}` does not belong to the certificate in `{cert}`!")]
+    KeyMismatch { cert: String, key: String },
+
+    #[error("Client CA certificate in `{path}` is invalid, failed with `{reason}`!")]
+    InvalidCa { path: String, reason: String },
+
+    #[error("Could not parse client certificate, failed with `{0}`!")]
+    InvalidPeerCertificate(String),
+}
@@ -0,0 +1,11 @@
+pub mod models;
+pub mod routes;
+
+const INSERT: &'static str = include_str!("./checklists/queries/insert.sql");
+const DELETE: &'static str = include_str!("./checklists/queries/delete.sql");
+const FIND_BY_TASK: &'static str = include_str!("./checklists/queries/find_by_task.sql");
+const FIND_BY_ID: &'static str = include_str!("./checklists/queries/find_by_id.sql");
+const UPDATE_POSITION: &'static str = include_str!("./checklists/queries/update_position.sql");
+
+const CHECK: &'static str = include_str!("./checklists/queries/check.sql");
+const UNCHECK: &'static str = include_str!("./checklists/queries/uncheck.sql");
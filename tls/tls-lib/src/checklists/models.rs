@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+
+use super::*;
+use crate::{
+    errors::AppError,
+    tasks::errors::TaskError,
+    timestamps::{self, rfc3339},
+    validation::{FieldErrors, Validate, ValidationConfig},
+};
+
+/// One step of a `Task`, the task's `progress` counts how many of them are checked.
+#[derive(Clone, Debug, Serialize, Deserialize, FromRow)]
+pub struct ChecklistItem {
+    pub id: i64,
+    pub task_id: i64,
+    pub title: String,
+    pub position: i64,
+    #[serde(default, with = "rfc3339::option")]
+    pub checked_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InsertChecklistItem {
+    pub non_empty_title: String,
+}
+
+/// The new order of a checklist, it must have every item of it, once.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReorderChecklist {
+    pub item_ids: Vec<i64>,
+}
+
+impl InsertChecklistItem {
+    /// Returns `None` if `owner_id` has no such task.
+    pub async fn insert(
+        self,
+        db_pool: &SqlitePool,
+        task_id: i64,
+        owner_id: i64,
+    ) -> Result<Option<ChecklistItem>, AppError> {
+        // NOTE(alex): No connection of our own, the item is read back right after.
+        let result = sqlx::query(INSERT)
+            .bind(task_id)
+            .bind(&self.non_empty_title)
+            .bind(owner_id)
+            .execute(db_pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            Ok(None)
+        } else {
+            ChecklistItem::find_by_id(db_pool, result.last_insert_rowid(), task_id, owner_id).await
+        }
+    }
+}
+
+impl Validate for InsertChecklistItem {
+    fn check(&self, _: &ValidationConfig, errors: &mut FieldErrors) {
+        if self.non_empty_title.trim().is_empty() {
+            errors.push(TaskError::EmptyItemTitle);
+        }
+    }
+}
+
+/// NOTE(alex): Whether the ids are the checklist's is only known in `reorder`.
+impl Validate for ReorderChecklist {
+    fn check(&self, _: &ValidationConfig, _: &mut FieldErrors) {}
+}
+
+impl ReorderChecklist {
+    /// NOTE(alex): Positions are rewritten from `0` in a single transaction, so the checklist is
+    /// never seen half reordered.
+    pub async fn reorder(
+        self,
+        db_pool: &SqlitePool,
+        task_id: i64,
+        owner_id: i64,
+    ) -> Result<Vec<ChecklistItem>, AppError> {
+        let items = ChecklistItem::find_by_task(db_pool, task_id, owner_id).await?;
+
+        let mut current_ids = items.iter().map(|item| item.id).collect::<Vec<_>>();
+        let mut new_ids = self.item_ids.clone();
+        current_ids.sort_unstable();
+        new_ids.sort_unstable();
+
+        if current_ids != new_ids {
+            return Err(TaskError::ChecklistOrder.into());
+        }
+
+        let mut transaction = db_pool.begin().await?;
+        for (position, item_id) in self.item_ids.iter().enumerate() {
+            sqlx::query(UPDATE_POSITION)
+                .bind(position as i64)
+                .bind(item_id)
+                .bind(task_id)
+                .bind(owner_id)
+                .execute(&mut transaction)
+                .await?;
+        }
+        transaction.commit().await?;
+
+        ChecklistItem::find_by_task(db_pool, task_id, owner_id).await
+    }
+}
+
+impl ChecklistItem {
+    pub async fn delete(
+        db_pool: &SqlitePool,
+        item_id: i64,
+        task_id: i64,
+        owner_id: i64,
+    ) -> Result<u64, AppError> {
+        let mut connection = db_pool.acquire().await?;
+        let result = sqlx::query(DELETE)
+            .bind(item_id)
+            .bind(task_id)
+            .bind(owner_id)
+            .execute(&mut connection)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Returns `0` if it was already checked.
+    pub async fn check(
+        db_pool: &SqlitePool,
+        item_id: i64,
+        task_id: i64,
+        owner_id: i64,
+    ) -> Result<u64, AppError> {
+        let mut connection = db_pool.acquire().await?;
+        let result = sqlx::query(CHECK)
+            .bind(timestamps::now())
+            .bind(item_id)
+            .bind(task_id)
+            .bind(owner_id)
+            .execute(&mut connection)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Returns `0` if it wasn't checked.
+    pub async fn uncheck(
+        db_pool: &SqlitePool,
+        item_id: i64,
+        task_id: i64,
+        owner_id: i64,
+    ) -> Result<u64, AppError> {
+        let mut connection = db_pool.acquire().await?;
+        let result = sqlx::query(UNCHECK)
+            .bind(item_id)
+            .bind(task_id)
+            .bind(owner_id)
+            .execute(&mut connection)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn find_by_task(
+        db_pool: &SqlitePool,
+        task_id: i64,
+        owner_id: i64,
+    ) -> Result<Vec<Self>, AppError> {
+        let result = sqlx::query_as(FIND_BY_TASK)
+            .bind(task_id)
+            .bind(owner_id)
+            .fetch_all(db_pool)
+            .await?;
+
+        Ok(result)
+    }
+
+    pub async fn find_by_id(
+        db_pool: &SqlitePool,
+        item_id: i64,
+        task_id: i64,
+        owner_id: i64,
+    ) -> Result<Option<Self>, AppError> {
+        let result = sqlx::query_as(FIND_BY_ID)
+            .bind(item_id)
+            .bind(task_id)
+            .bind(owner_id)
+            .fetch_optional(db_pool)
+            .await?;
+
+        Ok(result)
+    }
+}
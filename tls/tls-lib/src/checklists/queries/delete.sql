@@ -0,0 +1,9 @@
+delete from ChecklistItem
+where
+    ChecklistItem.id = $1
+    and ChecklistItem.task_id in (
+        select Task.id
+        from Task
+        where
+            Task.id = $2 and Task.owner_id = $3 and Task.deleted_at is null
+    )
\ No newline at end of file
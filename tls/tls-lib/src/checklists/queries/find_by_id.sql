@@ -0,0 +1,5 @@
+select ChecklistItem.*
+from ChecklistItem
+    join Task on Task.id = ChecklistItem.task_id
+where
+    ChecklistItem.id = $1 and ChecklistItem.task_id = $2 and Task.owner_id = $3 and Task.deleted_at is null
\ No newline at end of file
@@ -0,0 +1,6 @@
+select ChecklistItem.*
+from ChecklistItem
+    join Task on Task.id = ChecklistItem.task_id
+where
+    ChecklistItem.task_id = $1 and Task.owner_id = $2 and Task.deleted_at is null
+order by ChecklistItem.position, ChecklistItem.id
\ No newline at end of file
@@ -0,0 +1,15 @@
+-- NOTE(alex): New items go to the end of the checklist.
+insert into ChecklistItem (task_id, title, position)
+select Task.id,
+    $2,
+    coalesce(
+        (
+            select max(ChecklistItem.position) + 1
+            from ChecklistItem
+            where ChecklistItem.task_id = Task.id
+        ),
+        0
+    )
+from Task
+where
+    Task.id = $1 and Task.owner_id = $3 and Task.deleted_at is null
\ No newline at end of file
@@ -0,0 +1,11 @@
+update ChecklistItem
+set checked_at = null
+where
+    ChecklistItem.id = $1
+    and ChecklistItem.checked_at is not null
+    and ChecklistItem.task_id in (
+        select Task.id
+        from Task
+        where
+            Task.id = $2 and Task.owner_id = $3 and Task.deleted_at is null
+    )
\ No newline at end of file
@@ -0,0 +1,10 @@
+update ChecklistItem
+set position = $1
+where
+    ChecklistItem.id = $2
+    and ChecklistItem.task_id in (
+        select Task.id
+        from Task
+        where
+            Task.id = $3 and Task.owner_id = $4 and Task.deleted_at is null
+    )
\ No newline at end of file
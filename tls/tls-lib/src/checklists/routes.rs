@@ -0,0 +1,150 @@
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+use actix_web_httpauth::middleware::HttpAuthentication;
+use sqlx::SqlitePool;
+
+use super::models::*;
+use crate::{
+    errors::AppError,
+    tasks::{errors::TaskError, models::Task},
+    users::models::LoggedUser,
+    validation::Validated,
+    validator,
+};
+
+/// NOTE(alex): `Ok` with `[]` for a task that exists, but has no checklist.
+#[get(
+    "/tasks/{id:\\d+}/checklist",
+    wrap = "HttpAuthentication::with_fn(validator)"
+)]
+pub async fn find_all(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    id: web::Path<i64>,
+) -> Result<impl Responder, AppError> {
+    if Task::find_by_id(db_pool.get_ref(), *id, logged_user.id)
+        .await?
+        .is_none()
+    {
+        return Err(TaskError::NotFound(*id).into());
+    }
+
+    let items = ChecklistItem::find_by_task(db_pool.get_ref(), *id, logged_user.id).await?;
+    Ok(HttpResponse::Ok().json(&items))
+}
+
+#[post(
+    "/tasks/{id:\\d+}/checklist",
+    wrap = "HttpAuthentication::with_fn(validator)"
+)]
+pub async fn insert(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    id: web::Path<i64>,
+    Validated(input): Validated<InsertChecklistItem>,
+) -> Result<impl Responder, AppError> {
+    match input.insert(db_pool.get_ref(), *id, logged_user.id).await? {
+        Some(item) => Ok(HttpResponse::Created().json(item)),
+        None => Err(TaskError::NotFound(*id).into()),
+    }
+}
+
+#[put(
+    "/tasks/{id:\\d+}/checklist",
+    wrap = "HttpAuthentication::with_fn(validator)"
+)]
+pub async fn reorder(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    id: web::Path<i64>,
+    Validated(input): Validated<ReorderChecklist>,
+) -> Result<impl Responder, AppError> {
+    if Task::find_by_id(db_pool.get_ref(), *id, logged_user.id)
+        .await?
+        .is_none()
+    {
+        return Err(TaskError::NotFound(*id).into());
+    }
+
+    let items = input
+        .reorder(db_pool.get_ref(), *id, logged_user.id)
+        .await?;
+    Ok(HttpResponse::Ok().json(&items))
+}
+
+#[delete(
+    "/tasks/{id:\\d+}/checklist/{item_id:\\d+}",
+    wrap = "HttpAuthentication::with_fn(validator)"
+)]
+pub async fn delete(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    path: web::Path<(i64, i64)>,
+) -> Result<impl Responder, AppError> {
+    let (id, item_id) = path.into_inner();
+    let num_modified =
+        ChecklistItem::delete(db_pool.get_ref(), item_id, id, logged_user.id).await?;
+
+    if num_modified == 0 {
+        Err(TaskError::ChecklistItemNotFound(item_id).into())
+    } else {
+        Ok(HttpResponse::NoContent().finish())
+    }
+}
+
+#[post(
+    "/tasks/{id:\\d+}/checklist/{item_id:\\d+}/check",
+    wrap = "HttpAuthentication::with_fn(validator)"
+)]
+pub async fn check(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    path: web::Path<(i64, i64)>,
+) -> Result<impl Responder, AppError> {
+    let (id, item_id) = path.into_inner();
+
+    if ChecklistItem::find_by_id(db_pool.get_ref(), item_id, id, logged_user.id)
+        .await?
+        .is_none()
+    {
+        return Err(TaskError::ChecklistItemNotFound(item_id).into());
+    }
+
+    let num_modified = ChecklistItem::check(db_pool.get_ref(), item_id, id, logged_user.id).await?;
+
+    if num_modified == 0 {
+        Ok(HttpResponse::Ok().body(format!("Item {} already checked.", item_id)))
+    } else {
+        Ok(HttpResponse::Ok().body(format!("Checked {} items.", num_modified)))
+    }
+}
+
+#[delete(
+    "/tasks/{id:\\d+}/checklist/{item_id:\\d+}/check",
+    wrap = "HttpAuthentication::with_fn(validator)"
+)]
+pub async fn uncheck(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    path: web::Path<(i64, i64)>,
+) -> Result<impl Responder, AppError> {
+    let (id, item_id) = path.into_inner();
+
+    if ChecklistItem::find_by_id(db_pool.get_ref(), item_id, id, logged_user.id)
+        .await?
+        .is_none()
+    {
+        return Err(TaskError::ChecklistItemNotFound(item_id).into());
+    }
+
+    ChecklistItem::uncheck(db_pool.get_ref(), item_id, id, logged_user.id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn checklist_service(cfg: &mut web::ServiceConfig) {
+    cfg.service(find_all);
+    cfg.service(insert);
+    cfg.service(reorder);
+    cfg.service(delete);
+    cfg.service(check);
+    cfg.service(uncheck);
+}
@@ -1,7 +1,74 @@
-use actix_web::{error::JsonPayloadError, HttpResponse, ResponseError};
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    error::{InternalError, QueryPayloadError},
+    Error, HttpResponse, ResponseError,
+};
+use futures::{
+    future::{ready, LocalBoxFuture, Ready},
+    FutureExt,
+};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::{tasks::errors::TaskError, users::errors::UserError};
+use crate::{
+    migrations::errors::MigrationError, tasks::errors::TaskError, users::errors::UserError,
+    validation::errors::BodyError,
+};
+
+/// `Content-Type` of every `AppError` response.
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// A field of the request that didn't pass validation.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    /// Same as the `Problem::code` the error would have on its own.
+    pub code: String,
+    pub message: String,
+}
+
+impl From<TaskError> for FieldError {
+    fn from(fail: TaskError) -> Self {
+        Self {
+            field: fail.field().unwrap_or_default().to_string(),
+            code: fail.code().to_string(),
+            message: fail.to_string(),
+        }
+    }
+}
+
+impl From<UserError> for FieldError {
+    fn from(fail: UserError) -> Self {
+        Self {
+            field: fail.field().unwrap_or_default().to_string(),
+            code: fail.code().to_string(),
+            message: fail.to_string(),
+        }
+    }
+}
+
+/// RFC 7807 problem details, the body of every `AppError` response.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Problem {
+    /// NOTE(alex): Always `about:blank`, `code` is what tells errors apart.
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    /// Stable, and machine readable, clients should match on this instead of `detail`.
+    pub code: String,
+    /// Path of the request, see `ProblemInstance`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+    /// Only server errors have it, it's what to look for in the logs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+}
 
 #[derive(Debug, Error)]
 pub enum AppError {
@@ -21,7 +88,26 @@ pub enum AppError {
     Actix(#[from] actix_web::Error),
 
     #[error("`{0}`")]
-    Payload(#[from] JsonPayloadError),
+    Query(#[from] QueryPayloadError),
+
+    #[error("`{0}`")]
+    PasswordHash(#[from] argon2::password_hash::Error),
+
+    #[error("`{0}`")]
+    Token(#[from] jsonwebtoken::errors::Error),
+
+    #[error("`{0}`")]
+    Migration(#[from] MigrationError),
+
+    #[error("`{0}`")]
+    Body(#[from] BodyError),
+
+    #[error("`None of `{0}` can be served, ask for JSON, NDJSON, CSV, or HTML!`")]
+    NotAcceptable(String),
+
+    /// NOTE(alex): Every field that failed, see `validation::Validate`.
+    #[error("`{} fields of the request are invalid!`", .0.len())]
+    Validation(Vec<FieldError>),
 }
 
 impl ResponseError for AppError {
@@ -29,37 +115,284 @@ impl ResponseError for AppError {
         match self {
             AppError::Task(task_error) => match task_error {
                 TaskError::EmptyTitle => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                TaskError::TitleLength(_) => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                TaskError::DetailsLength(_) => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
                 TaskError::NotFound(_) => actix_web::http::StatusCode::NOT_FOUND,
                 TaskError::NoneFavorite => actix_web::http::StatusCode::NOT_FOUND,
-                TaskError::Empty => actix_web::http::StatusCode::NOT_FOUND,
+                TaskError::PageLimit => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                TaskError::UnknownCursor(_) => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                TaskError::EmptySearch => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                TaskError::DueInPast => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                TaskError::DueDays => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                TaskError::InvalidTagName => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                TaskError::TagNotFound(_) => actix_web::http::StatusCode::NOT_FOUND,
+                TaskError::UnknownTag(_) => actix_web::http::StatusCode::NOT_FOUND,
+                TaskError::DuplicateTag(_) => actix_web::http::StatusCode::CONFLICT,
+                TaskError::EmptyItemTitle => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                TaskError::ChecklistItemNotFound(_) => actix_web::http::StatusCode::NOT_FOUND,
+                TaskError::ChecklistOrder => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                TaskError::UnfinishedChecklist { .. } => actix_web::http::StatusCode::CONFLICT,
+                TaskError::InvalidRecurrence(_) => {
+                    actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+                }
+                TaskError::RecurrenceWithoutDue => {
+                    actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+                }
+                TaskError::AlreadyDone(_) => actix_web::http::StatusCode::CONFLICT,
+                TaskError::NotRecurring(_) => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                TaskError::OccurrenceCount => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                TaskError::PreconditionFailed(_) => {
+                    actix_web::http::StatusCode::PRECONDITION_FAILED
+                }
+                TaskError::PreconditionRequired => {
+                    actix_web::http::StatusCode::PRECONDITION_REQUIRED
+                }
+                TaskError::BatchSize => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                TaskError::RolledBack(_) => actix_web::http::StatusCode::FAILED_DEPENDENCY,
             },
             AppError::User(user_error) => match user_error {
                 UserError::EmptyUsername => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
-                UserError::UsernameLength => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
-                UserError::UsernameInvalidCharacter => {
+                UserError::UsernameLength { .. } => {
+                    actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+                }
+                UserError::UsernameInvalidCharacter(_) => {
                     actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
                 }
                 UserError::EmptyPassword => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
-                UserError::PasswordLength => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                UserError::PasswordLength { .. } => {
+                    actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+                }
                 UserError::PasswordInvalidCharacter => {
                     actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
                 }
+                UserError::WeakPassword(_) => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                UserError::UsernameTaken(_) => actix_web::http::StatusCode::CONFLICT,
                 UserError::NotFound(_) => actix_web::http::StatusCode::NOT_FOUND,
                 UserError::NotLoggedIn => actix_web::http::StatusCode::UNAUTHORIZED,
-                UserError::Empty => actix_web::http::StatusCode::NOT_FOUND,
                 UserError::LoginFailed => actix_web::http::StatusCode::NOT_FOUND,
                 UserError::InvalidToken => actix_web::http::StatusCode::UNAUTHORIZED,
+                UserError::ExpiredToken => actix_web::http::StatusCode::UNAUTHORIZED,
+                UserError::RevokedToken => actix_web::http::StatusCode::UNAUTHORIZED,
+                UserError::NoCertificate => actix_web::http::StatusCode::UNAUTHORIZED,
+                UserError::UnknownCertificate(_) => actix_web::http::StatusCode::UNAUTHORIZED,
+                UserError::CertificateInUse(_) => actix_web::http::StatusCode::CONFLICT,
+                UserError::SessionNotFound(_) => actix_web::http::StatusCode::NOT_FOUND,
+                UserError::NoSessions => actix_web::http::StatusCode::NOT_FOUND,
+                UserError::NotAdmin => actix_web::http::StatusCode::FORBIDDEN,
             },
             AppError::Database(_) => actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
             AppError::Json(_) => actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
             AppError::Actix(fail) => fail.as_response_error().status_code(),
-            AppError::Payload(fail) => fail.error_response().status(),
+            AppError::Query(fail) => fail.error_response().status(),
+            AppError::PasswordHash(_) => actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Token(_) => actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Migration(_) => actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Body(fail) => match fail {
+                BodyError::UnsupportedMediaType(_) => {
+                    actix_web::http::StatusCode::UNSUPPORTED_MEDIA_TYPE
+                }
+                BodyError::Overflow(_) => actix_web::http::StatusCode::PAYLOAD_TOO_LARGE,
+                _ => actix_web::http::StatusCode::BAD_REQUEST,
+            },
+            AppError::NotAcceptable(_) => actix_web::http::StatusCode::NOT_ACCEPTABLE,
+            AppError::Validation(_) => actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
         }
     }
 
     fn error_response(&self) -> HttpResponse {
-        let status_code = self.status_code();
-        let response = HttpResponse::build(status_code).body(self.to_string());
+        let problem = self.problem();
+
+        // NOTE(alex): `json` keeps the `Content-Type` we set.
+        let mut response = HttpResponse::build(self.status_code())
+            .content_type(PROBLEM_JSON)
+            .json(&problem);
+        response.extensions_mut().insert(problem);
+
         response
     }
 }
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Task(task_error) => task_error.code(),
+            AppError::User(user_error) => user_error.code(),
+            AppError::Actix(_) => "bad_request",
+            AppError::Query(_) => "invalid_query",
+            AppError::Body(fail) => fail.code(),
+            AppError::NotAcceptable(_) => "not_acceptable",
+            AppError::Validation(_) => "validation_failed",
+            AppError::Database(_)
+            | AppError::Json(_)
+            | AppError::PasswordHash(_)
+            | AppError::Token(_)
+            | AppError::Migration(_) => "internal_error",
+        }
+    }
+
+    /// NOTE(alex): Server errors only get a `correlation_id`, what actually happened (an `sqlx`
+    /// message may have bits of our schema in it) goes to the logs, under that same id.
+    pub fn problem(&self) -> Problem {
+        let status_code = self.status_code();
+
+        let (detail, correlation_id) = if status_code.is_server_error() {
+            let correlation_id = format!("{:016x}", OsRng.next_u64());
+            log::error!("[{}] {}", correlation_id, self);
+
+            (
+                format!(
+                    "Something went wrong on our side, mention `{}` when reporting it!",
+                    correlation_id
+                ),
+                Some(correlation_id),
+            )
+        } else {
+            (self.detail(), None)
+        };
+
+        let field = match self {
+            AppError::Task(task_error) => task_error.field(),
+            AppError::User(user_error) => user_error.field(),
+            _ => None,
+        };
+        let errors = match self {
+            AppError::Validation(errors) => errors.clone(),
+            _ => field
+                .map(|field| {
+                    vec![FieldError {
+                        field: field.to_string(),
+                        code: self.code().to_string(),
+                        message: detail.clone(),
+                    }]
+                })
+                .unwrap_or_default(),
+        };
+
+        Problem {
+            problem_type: "about:blank".to_string(),
+            title: status_code
+                .canonical_reason()
+                .unwrap_or("Unknown")
+                .to_string(),
+            status: status_code.as_u16(),
+            detail,
+            code: self.code().to_string(),
+            instance: None,
+            errors,
+            correlation_id,
+        }
+    }
+
+    /// The message of the wrapped error, without our backticks around it.
+    fn detail(&self) -> String {
+        match self {
+            AppError::Task(fail) => fail.to_string(),
+            AppError::User(fail) => fail.to_string(),
+            AppError::Database(fail) => fail.to_string(),
+            AppError::Json(fail) => fail.to_string(),
+            AppError::Actix(fail) => fail.to_string(),
+            AppError::Query(fail) => fail.to_string(),
+            AppError::PasswordHash(fail) => fail.to_string(),
+            AppError::Token(fail) => fail.to_string(),
+            AppError::Migration(fail) => fail.to_string(),
+            AppError::Body(fail) => fail.to_string(),
+            AppError::NotAcceptable(accept) => format!(
+                "None of `{}` can be served, ask for JSON, NDJSON, CSV, or HTML!",
+                accept
+            ),
+            AppError::Validation(errors) => errors
+                .iter()
+                .map(|error| error.message.as_str())
+                .collect::<Vec<_>>()
+                .join(" "),
+        }
+    }
+}
+
+/// Fills in the `instance` of the `Problem` of error responses with the path of the request, which
+/// `ResponseError::error_response` has no way of knowing.
+///
+/// NOTE(alex): Errors that come out of the services it wraps (the ones from `validator`, for
+/// instance) are turned into responses here, so they get an `instance` too.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProblemInstance;
+
+impl<S, B> Transform<S, ServiceRequest> for ProblemInstance
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = ProblemInstanceMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ProblemInstanceMiddleware { service }))
+    }
+}
+
+pub struct ProblemInstanceMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for ProblemInstanceMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let path = req.path().to_string();
+        let response = self.service.call(req);
+
+        async move {
+            match response.await {
+                Ok(response) => {
+                    let (request, response) = response.into_parts();
+                    Ok(ServiceResponse::new(
+                        request,
+                        with_instance(response, &path),
+                    ))
+                }
+                // NOTE(alex): There's no request to build a `ServiceResponse` with here, so it stays
+                // an error, one that answers with the response we give it.
+                Err(fail) => {
+                    let cause = fail.to_string();
+                    let response = with_instance(HttpResponse::from_error(fail), &path);
+
+                    Err(InternalError::from_response(cause, response.map_into_boxed_body()).into())
+                }
+            }
+        }
+        .boxed_local()
+    }
+}
+
+/// Re-renders the `Problem` (if it's an `AppError` response) with `instance` set.
+fn with_instance<B>(response: HttpResponse<B>, instance: &str) -> HttpResponse<EitherBody<B>> {
+    let problem = response.extensions().get::<Problem>().cloned();
+    let body = problem.and_then(|problem| {
+        serde_json::to_string(&Problem {
+            instance: Some(instance.to_string()),
+            ..problem
+        })
+        .ok()
+    });
+
+    match body {
+        Some(body) => response
+            .set_body(body)
+            .map_into_boxed_body()
+            .map_into_right_body(),
+        None => response.map_into_left_body(),
+    }
+}
@@ -0,0 +1,150 @@
+use actix_web::{
+    cookie::Cookie,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{
+        header::{self, HeaderMap, HeaderValue},
+        uri::Authority,
+    },
+    web, Error, HttpRequest, HttpResponse,
+};
+use futures::{
+    future::{ready, LocalBoxFuture, Ready},
+    FutureExt,
+};
+
+/// Where the plaintext listener sends everyone, only the port is configured, the host comes from
+/// the request.
+#[derive(Debug, Clone, Copy)]
+pub struct RedirectConfig {
+    pub https_port: u16,
+}
+
+/// Default service of the plaintext listener, every request gets a `308` to the same path (and
+/// query) over HTTPS.
+///
+/// NOTE(alex): `308` instead of `301`, so clients keep the method and body when following it.
+pub async fn redirect(req: HttpRequest, config: web::Data<RedirectConfig>) -> HttpResponse {
+    let connection_info = req.connection_info();
+    let host = connection_info
+        .host()
+        .parse::<Authority>()
+        .map(|authority| authority.host().to_string())
+        .unwrap_or_else(|_| connection_info.host().to_string());
+
+    let path_and_query = req
+        .uri()
+        .path_and_query()
+        .map(|path_and_query| path_and_query.as_str())
+        .unwrap_or("/");
+
+    let location = match config.https_port {
+        443 => format!("https://{}{}", host, path_and_query),
+        port => format!("https://{}:{}{}", host, port, path_and_query),
+    };
+
+    HttpResponse::PermanentRedirect()
+        .insert_header((header::LOCATION, location))
+        .finish()
+}
+
+/// Adds `Strict-Transport-Security` (unless `max_age` is `0`), and marks every cookie as `Secure`,
+/// on responses to requests that came in over TLS.
+///
+/// WARNING(alex): Must wrap the cookie middlewares (`.wrap` it after them), otherwise their
+/// `Set-Cookie` headers don't exist yet when this runs.
+///
+/// NOTE(alex): "Over TLS" is `ConnectionInfo::scheme`, so a TLS terminating proxy that sends
+/// `X-Forwarded-Proto: https` counts too.
+#[derive(Debug, Clone, Copy)]
+pub struct StrictTransportSecurity {
+    pub max_age: u64,
+}
+
+impl<S, B> Transform<S, ServiceRequest> for StrictTransportSecurity
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = StrictTransportSecurityMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(StrictTransportSecurityMiddleware {
+            service,
+            max_age: self.max_age,
+        }))
+    }
+}
+
+pub struct StrictTransportSecurityMiddleware<S> {
+    service: S,
+    max_age: u64,
+}
+
+impl<S, B> Service<ServiceRequest> for StrictTransportSecurityMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let over_tls = req.connection_info().scheme() == "https";
+        let max_age = self.max_age;
+
+        let response = self.service.call(req);
+
+        async move {
+            let mut response = response.await?;
+
+            if over_tls {
+                let headers = response.headers_mut();
+
+                if max_age > 0 {
+                    headers.insert(
+                        header::STRICT_TRANSPORT_SECURITY,
+                        HeaderValue::from_str(&format!("max-age={}; includeSubDomains", max_age))
+                            .unwrap(),
+                    );
+                }
+
+                secure_cookies(headers);
+            }
+
+            Ok(response)
+        }
+        .boxed_local()
+    }
+}
+
+/// NOTE(alex): A `Set-Cookie` that doesn't parse is kept as is, it's not our job to fix it.
+fn secure_cookies(headers: &mut HeaderMap) {
+    let set_cookies = headers
+        .get_all(header::SET_COOKIE)
+        .map(|value| {
+            value
+                .to_str()
+                .ok()
+                .and_then(|value| Cookie::parse(value.to_string()).ok())
+                .and_then(|mut cookie| {
+                    cookie.set_secure(true);
+                    HeaderValue::from_str(&cookie.to_string()).ok()
+                })
+                .unwrap_or_else(|| value.clone())
+        })
+        .collect::<Vec<_>>();
+
+    headers.remove(header::SET_COOKIE);
+    for set_cookie in set_cookies {
+        headers.append(header::SET_COOKIE, set_cookie);
+    }
+}
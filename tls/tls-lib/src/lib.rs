@@ -1,29 +1,51 @@
-use std::io::BufReader;
+use std::{path::Path, sync::Arc};
 
-use actix_identity::{CookieIdentityPolicy, IdentityService, RequestIdentity};
+use actix_identity::{CookieIdentityPolicy, IdentityService};
 use actix_session::CookieSession;
 use actix_web::{
-    dev::ServiceRequest, error::ErrorUnauthorized, get, middleware, App, Error, HttpResponse,
-    HttpServer, Responder,
+    dev::ServiceRequest, error::ErrorInternalServerError, get, middleware, web, App, Error,
+    HttpMessage, HttpResponse, HttpServer, Responder,
 };
-use actix_web_httpauth::extractors::{basic::Config, bearer::BearerAuth};
-use errors::AppError;
+use actix_web_httpauth::{
+    extractors::{basic::Config, bearer::BearerAuth, AuthExtractor, AuthenticationError},
+    headers::www_authenticate::bearer::Bearer,
+};
+use audit::routes::audit_service;
+use certificates::{errors::TlsError, CertificateResolver, PeerCertificate};
+use checklists::routes::checklist_service;
+use errors::{AppError, ProblemInstance};
+use futures::{future::LocalBoxFuture, FutureExt};
+use https::{RedirectConfig, StrictTransportSecurity};
+use rand_core::{OsRng, RngCore};
+use rustls::server::{AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient};
+use sessions::{models::Session, routes::session_service};
+use settings::{Settings, MIN_COOKIE_KEY_LENGTH};
 use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
-use tasks::routes::task_service;
+use tags::routes::tag_service;
+use tasks::{models::PreconditionConfig, routes::task_service};
 use time::Duration;
-use users::{models::LoggedUser, routes::user_service};
-
-use crate::users::errors::UserError;
+use trash::routes::trash_service;
+use users::{errors::UserError, models::User, routes::user_service, tokens::TokenConfig};
+use validation::ValidationConfig;
 
+pub mod audit;
+pub mod certificates;
+pub mod checklists;
 pub mod errors;
+pub mod https;
+pub mod migrations;
+pub mod negotiation;
+pub mod sessions;
+pub mod settings;
+pub mod tags;
 pub mod tasks;
+pub mod timestamps;
+pub mod trash;
 pub mod users;
+pub mod validation;
 
 pub const WELCOME_MSG: &'static str = include_str!("./../strings/welcome.txt");
 
-/// WARNING(alex): This query drops every table before creating them.
-const CREATE_DATABASE: &'static str = include_str!("./../queries/create_database.sql");
-
 #[get("/")]
 pub async fn index() -> Result<impl Responder, AppError> {
     let response = HttpResponse::Ok()
@@ -32,100 +54,260 @@ pub async fn index() -> Result<impl Responder, AppError> {
     Ok(response)
 }
 
-/// NOTE(alex): This function should be part of some setup script, it's here for convenience. It
-/// could be moved to the `build.rs`, by adding `sqlx` and `tokio` as `dev-dependencies`:
-pub async fn create_database(db_pool: &SqlitePool) -> Result<String, AppError> {
-    let mut connection = db_pool.acquire().await?;
+/// Opens the database, creating the file if it doesn't exist yet, and brings its schema up to date.
+///
+/// NOTE(alex): This refuses to work with a database that was migrated by a newer binary.
+pub async fn setup_database(settings: &Settings) -> Result<SqlitePool, AppError> {
+    let db_options = sqlx::sqlite::SqliteConnectOptions::new()
+        .filename(&settings.database_file)
+        .create_if_missing(true);
 
-    let result = sqlx::query(CREATE_DATABASE)
-        .execute(&mut connection)
+    let database_pool = SqlitePoolOptions::new()
+        .max_connections(5)
+        .connect_with(db_options)
         .await?;
 
-    Ok(result.rows_affected().to_string())
+    let num_applied = migrations::run(&database_pool).await?;
+    log::info!(
+        "Database is at version {}, applied {} migrations.",
+        migrations::latest_version(),
+        num_applied
+    );
+
+    Ok(database_pool)
+}
+
+/// How a request proves who is making it, either with a bearer token (from `/users/login`), or with
+/// the client certificate used on the TLS connection.
+pub enum Credentials {
+    Bearer(BearerAuth),
+    Certificate(PeerCertificate),
 }
 
+/// NOTE(alex): An `Authorization` header wins over the certificate, so someone calling from a
+/// machine with a certificate may still log in as somebody else. Without either, the error is the
+/// usual bearer challenge.
+impl AuthExtractor for Credentials {
+    type Error = AuthenticationError<Bearer>;
+
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_service_request(req: &ServiceRequest) -> Self::Future {
+        let bearer = BearerAuth::from_service_request(req);
+        let peer_certificate = req.conn_data::<PeerCertificate>().cloned();
+
+        async move {
+            match (bearer.await, peer_certificate) {
+                (Ok(bearer), _) => Ok(Credentials::Bearer(bearer)),
+                (Err(_), Some(peer_certificate)) => Ok(Credentials::Certificate(peer_certificate)),
+                (Err(fail), None) => Err(fail),
+            }
+        }
+        .boxed_local()
+    }
+}
+
+/// NOTE(alex): The token is signed, and carries its own expiration, so we don't look at the
+/// identity cookie here. What we do look at is the `Session` table, as a token without a session
+/// has been revoked (logout). The verified `Claims`, and the `LoggedUser` they belong to, are
+/// inserted into the request extensions, so handlers may know who is making the request.
+///
+/// A client certificate was already verified by rustls during the handshake, so all that's left is
+/// finding the `User` bound to its subject. There are no `Claims` (or token) in this case.
 pub async fn validator(
     req: ServiceRequest,
-    credentials: BearerAuth,
+    credentials: Credentials,
 ) -> Result<ServiceRequest, Error> {
-    if let Some(identity) = req.get_identity() {
-        let logged_user: LoggedUser = serde_json::from_str(&identity)?;
-
-        // NOTE(alex) Return `Ok(request)` if the token match our logged user's token, otherwise it
-        // returns an `Err`.
-        (credentials.token() == logged_user.token.to_string())
-            .then(|| req)
-            .ok_or(ErrorUnauthorized(UserError::InvalidToken))
-    } else {
-        Err(ErrorUnauthorized(UserError::NotLoggedIn))
+    let db_pool = req
+        .app_data::<web::Data<SqlitePool>>()
+        .cloned()
+        .ok_or_else(|| ErrorInternalServerError("`SqlitePool` is not registered!"))?;
+
+    let logged_user = match credentials {
+        Credentials::Bearer(credentials) => {
+            let token_config = req
+                .app_data::<web::Data<TokenConfig>>()
+                .cloned()
+                .ok_or_else(|| ErrorInternalServerError("`TokenConfig` is not registered!"))?;
+
+            // NOTE(alex): As `AppError`s, so they're answered with a `Problem` like everything else.
+            let claims = token_config
+                .verify(credentials.token())
+                .map_err(AppError::from)?;
+
+            if !Session::touch(&db_pool, &claims).await? {
+                return Err(AppError::from(UserError::RevokedToken).into());
+            }
+
+            let logged_user = User::find_by_id(&db_pool, claims.sub)
+                .await?
+                .ok_or_else(|| AppError::from(UserError::InvalidToken))?
+                .to_logged(credentials.token().to_string());
+
+            req.extensions_mut().insert(claims);
+            logged_user
+        }
+        Credentials::Certificate(peer_certificate) => {
+            User::find_by_certificate_subject(&db_pool, &peer_certificate.subject)
+                .await?
+                .ok_or_else(|| {
+                    AppError::from(UserError::UnknownCertificate(peer_certificate.subject))
+                })?
+                .to_logged(String::new())
+        }
+    };
+
+    req.extensions_mut().insert(logged_user);
+
+    Ok(req)
+}
+
+/// NOTE(alex): Set `token_signing_key` to keep tokens valid across restarts, otherwise a random key
+/// is generated on startup.
+pub fn setup_tokens(settings: &Settings) -> TokenConfig {
+    let lifetime = Duration::minutes(settings.token_minutes);
+
+    match &settings.token_signing_key {
+        Some(signing_key) => TokenConfig::new(signing_key.as_bytes(), lifetime),
+        None => TokenConfig::with_random_key(lifetime),
     }
 }
 
-pub fn setup_tls() -> Result<rustls::ServerConfig, rustls::Error> {
-    let cert_file = &mut BufReader::new(&include_bytes!("../certificates/cert.pem")[..]);
-    let certificates = rustls_pemfile::certs(cert_file)
-        .expect("Could not read certificates!")
-        .iter()
-        .map(|bytes| rustls::Certificate(bytes.clone()))
-        .collect();
-
-    let key_file = &mut BufReader::new(&include_bytes!("../certificates/key.pem")[..]);
-    let keys = rustls_pemfile::pkcs8_private_keys(key_file)
-        .expect("Could not read keys!")
-        .iter()
-        .map(|bytes| rustls::PrivateKey(bytes.clone()))
-        .collect::<Vec<rustls::PrivateKey>>();
-
-    let server_config = rustls::ServerConfig::builder()
-        .with_safe_defaults()
-        .with_no_client_auth()
-        .with_single_cert(certificates, keys.first().expect("Must have key!").clone())?;
-
-    Ok(server_config)
+/// NOTE(alex): Same deal as `setup_tokens`, but for signing the cookies.
+pub fn setup_cookie_key(settings: &Settings) -> Vec<u8> {
+    match &settings.cookie_key {
+        Some(cookie_key) => cookie_key.as_bytes().to_vec(),
+        None => {
+            let mut cookie_key = vec![0; MIN_COOKIE_KEY_LENGTH];
+            OsRng.fill_bytes(&mut cookie_key);
+            cookie_key
+        }
+    }
 }
 
-pub async fn start_app() -> std::io::Result<()> {
-    let db_options = sqlx::sqlite::SqliteConnectOptions::new()
-        .filename(env!("DATABASE_FILE"))
-        .create_if_missing(true);
+/// Loads the certificate chain, and key, from the files in `settings`. Client certificates are only
+/// asked for when `client_ca_file` is set.
+///
+/// NOTE(alex): The `CertificateResolver` is returned as well, so it can be handed to
+/// `certificates::watch` for reloading.
+pub fn setup_tls(
+    settings: &Settings,
+) -> Result<(rustls::ServerConfig, Arc<CertificateResolver>), TlsError> {
+    let resolver = Arc::new(CertificateResolver::new(
+        &settings.cert_file,
+        &settings.key_file,
+    )?);
 
-    let database_pool = SqlitePoolOptions::new()
-        .max_connections(5)
-        .connect_with(db_options)
-        .await
-        .expect("Failed opening database!");
+    let server_config = rustls::ServerConfig::builder().with_safe_defaults();
+    let server_config = match &settings.client_ca_file {
+        Some(client_ca_file) => {
+            let roots = certificates::load_client_roots(Path::new(client_ca_file))?;
+
+            // NOTE(alex): When not required, clients without a certificate can still connect (and
+            // login with a password), but a client that does present one must have it signed by
+            // one of our CAs.
+            let client_verifier = if settings.require_client_cert {
+                AllowAnyAuthenticatedClient::new(roots)
+            } else {
+                AllowAnyAnonymousOrAuthenticatedClient::new(roots)
+            };
 
-    if option_env!("NEW_DATABASE").is_some() {
-        create_database(&database_pool).await.unwrap();
+            server_config.with_client_cert_verifier(client_verifier)
+        }
+        None => server_config.with_no_client_auth(),
     }
+    .with_cert_resolver(resolver.clone());
+
+    Ok((server_config, resolver))
+}
+
+pub async fn start_app(settings: Settings) -> std::io::Result<()> {
+    let database_pool = setup_database(&settings)
+        .await
+        .map_err(|fail| std::io::Error::new(std::io::ErrorKind::Other, fail.to_string()))?;
 
     let data = actix_web::web::Data::new(database_pool);
+    let token_config = actix_web::web::Data::new(setup_tokens(&settings));
+    let precondition_config = actix_web::web::Data::new(PreconditionConfig {
+        required: settings.require_if_match,
+    });
+    let validation_config = actix_web::web::Data::new(ValidationConfig::from(&settings));
+    // NOTE(alex): Generated once out here, every worker must sign cookies with the same key.
+    let cookie_key = setup_cookie_key(&settings);
 
-    let rustls_server_config = setup_tls().expect("Failed setting up TLS!");
+    let (rustls_server_config, cert_resolver) = setup_tls(&settings)
+        .map_err(|fail| std::io::Error::new(std::io::ErrorKind::Other, fail.to_string()))?;
+    let cert_reload_interval = match settings.cert_reload_seconds {
+        0 => None,
+        seconds => Some(std::time::Duration::from_secs(seconds)),
+    };
+    actix_web::rt::spawn(certificates::watch(cert_resolver, cert_reload_interval));
+    actix_web::rt::spawn(trash::watch(data.get_ref().clone(), settings.trash_days));
+    let address = settings.address.clone();
+    let redirect_address = settings.redirect_address.clone();
+    let hsts = StrictTransportSecurity {
+        max_age: settings.hsts_max_age_seconds,
+    };
 
-    HttpServer::new(move || {
+    let https_server = HttpServer::new(move || {
         App::new()
             .app_data(data.clone())
+            .app_data(token_config.clone())
+            .app_data(precondition_config.clone())
+            .app_data(validation_config.clone())
             .app_data(Config::default().realm("Restricted area, login first!"))
             .service(index)
             .configure(task_service)
+            .configure(tag_service)
+            .configure(checklist_service)
+            .configure(audit_service)
+            .configure(trash_service)
             .configure(user_service)
+            .configure(session_service)
+            .wrap(ProblemInstance)
             .wrap(IdentityService::new(
-                CookieIdentityPolicy::new(&[0; 32])
+                CookieIdentityPolicy::new(&cookie_key)
                     .name("auth-cookie")
-                    .login_deadline(Duration::minutes(5))
-                    .secure(false),
+                    .login_deadline(Duration::minutes(settings.login_minutes))
+                    .secure(settings.secure_cookies),
             ))
             .wrap(
-                CookieSession::signed(&[0; 32])
+                CookieSession::signed(&cookie_key)
                     .name("session-cookie")
-                    .secure(false)
+                    .secure(settings.secure_cookies)
                     // WARNING(alex): This uses the `time` crate, not `std::time`!
-                    .expires_in_time(Duration::minutes(5)),
+                    .expires_in_time(Duration::minutes(settings.session_minutes)),
             )
+            .wrap(hsts)
             .wrap(middleware::Logger::default())
     })
-    .bind_rustls(env!("ADDRESS"), rustls_server_config)?
-    .run()
-    .await
+    .on_connect(certificates::peer_certificate_on_connect)
+    .bind_rustls(&address, rustls_server_config)?
+    .run();
+
+    match redirect_address {
+        Some(redirect_address) => {
+            let redirect_config = web::Data::new(RedirectConfig {
+                // NOTE(alex): `validate` already made sure this is an `ip:port`.
+                https_port: address
+                    .parse::<std::net::SocketAddr>()
+                    .map(|address| address.port())
+                    .unwrap_or(443),
+            });
+
+            let http_server = HttpServer::new(move || {
+                App::new()
+                    .app_data(redirect_config.clone())
+                    .default_service(web::to(https::redirect))
+                    .wrap(middleware::Logger::default())
+            })
+            .bind(redirect_address)?
+            .run();
+
+            futures::future::try_join(https_server, http_server)
+                .await
+                .map(|_| ())
+        }
+        None => https_server.await,
+    }
 }
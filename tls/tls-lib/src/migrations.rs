@@ -0,0 +1,184 @@
+use sqlx::{Connection, SqliteConnection, SqlitePool};
+use time::OffsetDateTime;
+
+use self::errors::MigrationError;
+use crate::errors::AppError;
+
+pub mod errors;
+
+const CREATE_MIGRATIONS_TABLE: &'static str =
+    include_str!("./migrations/queries/create_migrations_table.sql");
+const FIND_APPLIED: &'static str = include_str!("./migrations/queries/find_applied.sql");
+const INSERT_APPLIED: &'static str = include_str!("./migrations/queries/insert_applied.sql");
+const FIND_COLUMNS: &'static str = include_str!("./migrations/queries/find_columns.sql");
+
+/// Columns that `0001_create_tables.sql` creates, and the tables of a database from before
+/// migrations existed don't have.
+const LEGACY_MISSING_COLUMNS: &[(&str, &str)] = &[("Task", "owner_id"), ("User", "password_hash")];
+
+/// A single schema change, identified by its `version`.
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+/// NOTE(alex): Every migration this binary knows about, in the order they must be applied. New
+/// migrations go at the end with the next version number, and an applied migration must never be
+/// edited, write a new one instead.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create_tables",
+        sql: include_str!("./../migrations/0001_create_tables.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "add_certificate_subject",
+        sql: include_str!("./../migrations/0002_add_certificate_subject.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "add_task_created_at",
+        sql: include_str!("./../migrations/0003_add_task_created_at.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "create_task_search",
+        sql: include_str!("./../migrations/0004_create_task_search.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "add_task_schedule",
+        sql: include_str!("./../migrations/0005_add_task_schedule.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "create_tags",
+        sql: include_str!("./../migrations/0006_create_tags.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "create_checklists",
+        sql: include_str!("./../migrations/0007_create_checklists.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "add_task_recurrence",
+        sql: include_str!("./../migrations/0008_add_task_recurrence.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "create_task_events",
+        sql: include_str!("./../migrations/0009_create_task_events.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "add_soft_delete",
+        sql: include_str!("./../migrations/0010_add_soft_delete.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "add_task_version",
+        sql: include_str!("./../migrations/0011_add_task_version.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "add_task_spawned_from",
+        sql: include_str!("./../migrations/0012_add_task_spawned_from.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "add_user_username_unique",
+        sql: include_str!("./../migrations/0013_add_user_username_unique.sql"),
+    },
+];
+
+/// The version the database will be at after `run` succeeds.
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+/// Applies every migration that is not in the `_migrations` table yet, returning how many were
+/// applied.
+///
+/// It's safe to call this on every startup, an up to date database is left untouched. A database
+/// that was migrated by a newer binary is refused, as we have no idea what its schema looks like,
+/// and so is one created before migrations existed, see `check_legacy_schema`.
+pub async fn run(db_pool: &SqlitePool) -> Result<usize, AppError> {
+    let mut connection = db_pool.acquire().await?;
+    sqlx::query(CREATE_MIGRATIONS_TABLE)
+        .execute(&mut connection)
+        .await?;
+
+    let applied: Vec<i64> = sqlx::query_scalar(FIND_APPLIED)
+        .fetch_all(&mut connection)
+        .await?;
+
+    let database_version = applied.iter().copied().max().unwrap_or(0);
+    if database_version > latest_version() {
+        return Err(MigrationError::SchemaTooNew {
+            database_version,
+            binary_version: latest_version(),
+        }
+        .into());
+    }
+
+    if applied.is_empty() {
+        check_legacy_schema(&mut connection).await?;
+    }
+
+    let mut num_applied = 0;
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|migration| !applied.contains(&migration.version))
+    {
+        log::info!(
+            "Applying migration {} `{}`.",
+            migration.version,
+            migration.name
+        );
+
+        // NOTE(alex): The migration, and its bookkeeping row, either both make it in, or neither
+        // does.
+        let mut transaction = connection.begin().await?;
+        sqlx::query(migration.sql)
+            .execute(&mut transaction)
+            .await
+            .map_err(|fail| MigrationError::Failed {
+                version: migration.version,
+                reason: fail.to_string(),
+            })?;
+        sqlx::query(INSERT_APPLIED)
+            .bind(migration.version)
+            .bind(migration.name)
+            .bind(OffsetDateTime::now_utc().unix_timestamp())
+            .execute(&mut transaction)
+            .await?;
+        transaction.commit().await?;
+
+        num_applied += 1;
+    }
+
+    Ok(num_applied)
+}
+
+/// NOTE(alex): `0001_create_tables.sql` only creates the tables that are missing, so the old tables
+/// of a database from before migrations would be kept, without the columns every query relies on.
+/// There's no telling who owns its tasks, or what the hash of a plain password is, so it's refused
+/// instead of migrated.
+async fn check_legacy_schema(connection: &mut SqliteConnection) -> Result<(), AppError> {
+    for &(table, column) in LEGACY_MISSING_COLUMNS {
+        let columns: Vec<String> = sqlx::query_scalar(FIND_COLUMNS)
+            .bind(table)
+            .fetch_all(&mut *connection)
+            .await?;
+
+        if !columns.is_empty() && !columns.iter().any(|name| name == column) {
+            return Err(MigrationError::LegacySchema { table, column }.into());
+        }
+    }
+
+    Ok(())
+}
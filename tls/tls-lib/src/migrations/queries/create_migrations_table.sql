@@ -0,0 +1,5 @@
+create table if not exists _migrations (
+    version integer primary key,
+    name text not null,
+    applied_at int not null
+);
\ No newline at end of file
@@ -0,0 +1,3 @@
+select version
+from _migrations
+order by version;
\ No newline at end of file
@@ -0,0 +1,335 @@
+use actix_web::{
+    body::BoxBody,
+    http::{
+        header::{self, Accept, Header, HeaderName, HeaderValue, Quality, TryIntoHeaderPair},
+        StatusCode,
+    },
+    HttpRequest, HttpResponse, Responder,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::errors::AppError;
+
+/// `total` of a page, when it's not rendered as JSON.
+pub const TOTAL_COUNT_HEADER: &str = "X-Total-Count";
+/// `next_cursor` of a page, when it's not rendered as JSON.
+pub const NEXT_CURSOR_HEADER: &str = "X-Next-Cursor";
+
+/// The formats `Negotiated` responses come in, picked from the `Accept` header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Representation {
+    Json,
+    /// One JSON object per line.
+    NdJson,
+    Csv,
+    /// A minimal page with a `<table>`, for browsers.
+    Html,
+}
+
+impl Representation {
+    pub const ALL: [Representation; 4] = [
+        Representation::Json,
+        Representation::NdJson,
+        Representation::Csv,
+        Representation::Html,
+    ];
+
+    /// The most preferred one we can serve, `AppError::NotAcceptable` when there's none.
+    ///
+    /// NOTE(alex): No `Accept` (or one that doesn't parse) means anything goes, so JSON, as it
+    /// always was.
+    pub fn from_req(req: &HttpRequest) -> Result<Self, AppError> {
+        let accept = match Accept::parse(req) {
+            Ok(accept) if !accept.is_empty() => accept,
+            _ => return Ok(Representation::Json),
+        };
+
+        // NOTE(alex): `ranked` keeps the `q=0` ones, which the client is explicitly refusing.
+        let acceptable = Accept(
+            accept
+                .iter()
+                .filter(|item| item.quality != Quality::ZERO)
+                .cloned()
+                .collect(),
+        );
+
+        acceptable
+            .ranked()
+            .iter()
+            .find_map(
+                |mime| match (mime.type_().as_str(), mime.subtype().as_str()) {
+                    ("*", "*") | ("application", "*") | ("application", "json") => {
+                        Some(Representation::Json)
+                    }
+                    ("application", "x-ndjson") | ("application", "ndjson") => {
+                        Some(Representation::NdJson)
+                    }
+                    ("text", "csv") => Some(Representation::Csv),
+                    ("text", "*") | ("text", "html") => Some(Representation::Html),
+                    _ => None,
+                },
+            )
+            .ok_or_else(|| {
+                let accept = req
+                    .headers()
+                    .get(header::ACCEPT)
+                    .and_then(|accept| accept.to_str().ok())
+                    .unwrap_or_default();
+
+                AppError::NotAcceptable(accept.to_string())
+            })
+    }
+
+    /// Tells the representations apart in an `ETag`, JSON has none, so its tags stay the same as
+    /// before there were others.
+    pub fn etag_suffix(&self) -> Option<&'static str> {
+        match self {
+            Representation::Json => None,
+            Representation::NdJson => Some("ndjson"),
+            Representation::Csv => Some("csv"),
+            Representation::Html => Some("html"),
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Representation::Json => "application/json",
+            Representation::NdJson => "application/x-ndjson",
+            Representation::Csv => "text/csv; charset=utf-8",
+            Representation::Html => "text/html; charset=utf-8",
+        }
+    }
+}
+
+/// What `Negotiated` needs to render something as rows, on top of its JSON.
+pub trait Tabular: Serialize {
+    /// Heading of the HTML page.
+    const NAME: &'static str;
+
+    /// Fields of the JSON object that become columns, in order, `task.title` for a field of a
+    /// nested object.
+    const COLUMNS: &'static [&'static str];
+}
+
+/// How the items are laid out in JSON.
+#[derive(Clone, Copy, Debug)]
+enum Shape {
+    One,
+    Many,
+    /// NOTE(alex): Only JSON has room for these, the other representations get them as the
+    /// `X-Total-Count`, and `X-Next-Cursor` headers.
+    Page {
+        total: i64,
+        next_cursor: Option<i64>,
+    },
+}
+
+/// Renders one `T` (an object), or many (an array), in the `Representation` the request asked for.
+///
+/// NOTE(alex): Cells are taken from the JSON of each item, so timestamps and enums look the same in
+/// every format. The status, and headers, are set here instead of with `Responder::customize`,
+/// which would put them on the `406` too.
+#[derive(Debug)]
+pub struct Negotiated<T> {
+    items: Vec<T>,
+    shape: Shape,
+    status: StatusCode,
+    headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+impl<T: Tabular> Negotiated<T> {
+    pub fn one(item: T) -> Self {
+        Self::new(vec![item], Shape::One)
+    }
+
+    pub fn many(items: Vec<T>) -> Self {
+        Self::new(items, Shape::Many)
+    }
+
+    /// JSON is `{ "items": [...], "total": 2, "next_cursor": null }`.
+    pub fn page(items: Vec<T>, total: i64, next_cursor: Option<i64>) -> Self {
+        Self::new(items, Shape::Page { total, next_cursor })
+    }
+
+    fn new(items: Vec<T>, shape: Shape) -> Self {
+        Self {
+            items,
+            shape,
+            status: StatusCode::OK,
+            headers: Vec::new(),
+        }
+    }
+
+    pub fn status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// NOTE(alex): A header that can't be converted is left out, like `HttpResponseBuilder` would.
+    pub fn header(mut self, header: impl TryIntoHeaderPair) -> Self {
+        if let Ok(pair) = header.try_into_pair() {
+            self.headers.push(pair);
+        }
+
+        self
+    }
+
+    fn render(&self, representation: Representation) -> Result<String, AppError> {
+        match (representation, self.shape) {
+            (Representation::Json, Shape::One) => Ok(serde_json::to_string(&self.items[0])?),
+            (Representation::Json, Shape::Many) => Ok(serde_json::to_string(&self.items)?),
+            (Representation::Json, Shape::Page { total, next_cursor }) => {
+                Ok(serde_json::to_string(&json!({
+                    "items": &self.items,
+                    "next_cursor": next_cursor,
+                    "total": total,
+                }))?)
+            }
+            (Representation::NdJson, _) => {
+                let mut body = String::new();
+                for item in self.items.iter() {
+                    body.push_str(&serde_json::to_string(item)?);
+                    body.push('\n');
+                }
+                Ok(body)
+            }
+            (Representation::Csv, _) => {
+                let mut body = csv_row(T::COLUMNS.iter().map(|column| column.to_string()));
+                for row in self.rows()? {
+                    body.push_str(&csv_row(row.into_iter()));
+                }
+                Ok(body)
+            }
+            (Representation::Html, _) => {
+                let headings = T::COLUMNS
+                    .iter()
+                    .map(|column| format!("<th>{}</th>", escape_html(column)))
+                    .collect::<String>();
+                let rows = self
+                    .rows()?
+                    .into_iter()
+                    .map(|row| {
+                        let cells = row
+                            .iter()
+                            .map(|cell| format!("<td>{}</td>", escape_html(cell)))
+                            .collect::<String>();
+                        format!("<tr>{}</tr>\n", cells)
+                    })
+                    .collect::<String>();
+
+                Ok(format!(
+                    "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{name}</title></head>\n\
+                     <body>\n<h1>{name}</h1>\n<table>\n<thead><tr>{headings}</tr></thead>\n\
+                     <tbody>\n{rows}</tbody>\n</table>\n</body>\n</html>\n",
+                    name = T::NAME,
+                    headings = headings,
+                    rows = rows,
+                ))
+            }
+        }
+    }
+
+    fn rows(&self) -> Result<Vec<Vec<String>>, AppError> {
+        self.items
+            .iter()
+            .map(|item| {
+                let value = serde_json::to_value(item)?;
+                Ok(T::COLUMNS
+                    .iter()
+                    .map(|column| cell(value.pointer(&format!("/{}", column.replace('.', "/")))))
+                    .collect())
+            })
+            .collect()
+    }
+}
+
+impl<T: Tabular> Responder for Negotiated<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        let response = Representation::from_req(req)
+            .and_then(|representation| Ok((representation, self.render(representation)?)));
+
+        match response {
+            Ok((representation, body)) => {
+                let mut response = HttpResponse::build(self.status);
+                response
+                    .content_type(representation.content_type())
+                    .insert_header((header::VARY, "Accept"));
+
+                match (representation, self.shape) {
+                    (Representation::Json, _) => (),
+                    (_, Shape::Page { total, next_cursor }) => {
+                        response.insert_header((TOTAL_COUNT_HEADER, total.to_string()));
+
+                        if let Some(next_cursor) = next_cursor {
+                            response.insert_header((NEXT_CURSOR_HEADER, next_cursor.to_string()));
+                        }
+                    }
+                    _ => (),
+                }
+
+                for header in self.headers {
+                    response.insert_header(header);
+                }
+
+                response.body(body)
+            }
+            Err(fail) => HttpResponse::from_error(fail),
+        }
+    }
+}
+
+/// NOTE(alex): Strings go in as they are, lists of strings (tags) are joined by spaces, anything
+/// else is its JSON.
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(value)) => value.clone(),
+        Some(Value::Array(values)) if values.iter().all(Value::is_string) => values
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" "),
+        Some(value) => value.to_string(),
+    }
+}
+
+/// RFC 4180, fields with a separator, quote, or line break are quoted.
+///
+/// NOTE(alex): A field that a spreadsheet would take for a formula (it starts with `=`, `+`, `-`,
+/// `@`, a tab, or a carriage return) gets a `'` in front, and is quoted, so it's shown as text
+/// instead of being evaluated.
+fn csv_row(fields: impl Iterator<Item = String>) -> String {
+    let fields = fields
+        .map(|field| {
+            if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+                format!("\"'{}\"", field.replace('"', "\"\""))
+            } else if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field
+            }
+        })
+        .collect::<Vec<_>>();
+
+    format!("{}\r\n", fields.join(","))
+}
+
+pub(crate) fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
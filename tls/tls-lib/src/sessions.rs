@@ -0,0 +1,9 @@
+pub mod models;
+pub mod routes;
+
+const INSERT: &'static str = include_str!("./sessions/queries/insert.sql");
+const FIND_BY_USER: &'static str = include_str!("./sessions/queries/find_by_user.sql");
+const TOUCH: &'static str = include_str!("./sessions/queries/touch.sql");
+const DELETE: &'static str = include_str!("./sessions/queries/delete.sql");
+const DELETE_ALL: &'static str = include_str!("./sessions/queries/delete_all.sql");
+const DELETE_BY_TOKEN: &'static str = include_str!("./sessions/queries/delete_by_token.sql");
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+use time::OffsetDateTime;
+
+use super::*;
+use crate::{errors::AppError, users::tokens::Claims};
+
+/// A token issued on login. Tokens without a matching `Session` are treated as revoked.
+#[derive(Clone, Debug, Serialize, Deserialize, FromRow)]
+pub struct Session {
+    pub id: i64,
+    pub user_id: i64,
+    /// NOTE(alex): The `jti` claim of the token, there is no reason to hand it out.
+    #[serde(skip)]
+    pub token_id: String,
+    pub created_at: i64,
+    pub last_seen_at: i64,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct InsertSession {
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+}
+
+impl InsertSession {
+    pub async fn insert(self, db_pool: &SqlitePool, claims: &Claims) -> Result<Session, AppError> {
+        let mut connection = db_pool.acquire().await?;
+        let result = sqlx::query(INSERT)
+            .bind(claims.sub)
+            .bind(&claims.jti)
+            .bind(claims.iat)
+            .bind(&self.user_agent)
+            .bind(&self.ip)
+            .execute(&mut connection)
+            .await?;
+
+        let session = Session {
+            id: result.last_insert_rowid(),
+            user_id: claims.sub,
+            token_id: claims.jti.clone(),
+            created_at: claims.iat,
+            last_seen_at: claims.iat,
+            user_agent: self.user_agent,
+            ip: self.ip,
+        };
+
+        Ok(session)
+    }
+}
+
+impl Session {
+    /// Marks the session that owns this token as seen right now, returning `false` when there is
+    /// no such session (it was revoked).
+    pub async fn touch(db_pool: &SqlitePool, claims: &Claims) -> Result<bool, AppError> {
+        let mut connection = db_pool.acquire().await?;
+        let result = sqlx::query(TOUCH)
+            .bind(OffsetDateTime::now_utc().unix_timestamp())
+            .bind(&claims.jti)
+            .bind(claims.sub)
+            .execute(&mut connection)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn find_by_user(db_pool: &SqlitePool, user_id: i64) -> Result<Vec<Self>, AppError> {
+        let result = sqlx::query_as(FIND_BY_USER)
+            .bind(user_id)
+            .fetch_all(db_pool)
+            .await?;
+
+        Ok(result)
+    }
+
+    pub async fn delete(
+        db_pool: &SqlitePool,
+        session_id: i64,
+        user_id: i64,
+    ) -> Result<u64, AppError> {
+        let mut connection = db_pool.acquire().await?;
+        let result = sqlx::query(DELETE)
+            .bind(session_id)
+            .bind(user_id)
+            .execute(&mut connection)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn delete_all(db_pool: &SqlitePool, user_id: i64) -> Result<u64, AppError> {
+        let mut connection = db_pool.acquire().await?;
+        let result = sqlx::query(DELETE_ALL)
+            .bind(user_id)
+            .execute(&mut connection)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn delete_by_token(db_pool: &SqlitePool, claims: &Claims) -> Result<u64, AppError> {
+        let mut connection = db_pool.acquire().await?;
+        let result = sqlx::query(DELETE_BY_TOKEN)
+            .bind(&claims.jti)
+            .execute(&mut connection)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,3 @@
+delete from Session
+where
+    Session.id = $1 and Session.user_id = $2
\ No newline at end of file
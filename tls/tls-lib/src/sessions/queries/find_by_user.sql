@@ -0,0 +1,4 @@
+select *
+from Session
+where Session.user_id = $1
+order by Session.last_seen_at desc;
\ No newline at end of file
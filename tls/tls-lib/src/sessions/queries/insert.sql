@@ -0,0 +1,2 @@
+insert into Session (user_id, token_id, created_at, last_seen_at, user_agent, ip)
+values ($1, $2, $3, $3, $4, $5);
\ No newline at end of file
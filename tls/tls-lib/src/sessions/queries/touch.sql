@@ -0,0 +1,4 @@
+update Session
+set last_seen_at = $1
+where
+    Session.token_id = $2 and Session.user_id = $3
\ No newline at end of file
@@ -0,0 +1,58 @@
+use actix_web::{delete, get, web, HttpResponse, Responder};
+use actix_web_httpauth::middleware::HttpAuthentication;
+use sqlx::SqlitePool;
+
+use super::models::Session;
+use crate::{
+    errors::AppError,
+    users::{errors::UserError, tokens::Claims},
+    validator,
+};
+
+#[get("/users/me/sessions", wrap = "HttpAuthentication::with_fn(validator)")]
+pub async fn find_all(
+    db_pool: web::Data<SqlitePool>,
+    claims: Claims,
+) -> Result<impl Responder, AppError> {
+    let sessions = Session::find_by_user(db_pool.get_ref(), claims.sub).await?;
+    Ok(HttpResponse::Ok().json(&sessions))
+}
+
+#[delete(
+    "/users/me/sessions/{id:\\d+}",
+    wrap = "HttpAuthentication::with_fn(validator)"
+)]
+pub async fn delete(
+    db_pool: web::Data<SqlitePool>,
+    claims: Claims,
+    id: web::Path<i64>,
+) -> Result<impl Responder, AppError> {
+    let num_modified = Session::delete(db_pool.get_ref(), *id, claims.sub).await?;
+
+    if num_modified == 0 {
+        Err(UserError::SessionNotFound(*id).into())
+    } else {
+        Ok(HttpResponse::NoContent().finish())
+    }
+}
+
+/// NOTE(alex): Logout everywhere, this includes the session making the request.
+#[delete("/users/me/sessions", wrap = "HttpAuthentication::with_fn(validator)")]
+pub async fn delete_all(
+    db_pool: web::Data<SqlitePool>,
+    claims: Claims,
+) -> Result<impl Responder, AppError> {
+    let num_modified = Session::delete_all(db_pool.get_ref(), claims.sub).await?;
+
+    if num_modified == 0 {
+        Err(UserError::NoSessions.into())
+    } else {
+        Ok(HttpResponse::NoContent().finish())
+    }
+}
+
+pub fn session_service(cfg: &mut web::ServiceConfig) {
+    cfg.service(find_all);
+    cfg.service(delete);
+    cfg.service(delete_all);
+}
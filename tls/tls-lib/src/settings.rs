@@ -0,0 +1,347 @@
+use std::{fs, net::SocketAddr, path::Path};
+
+use serde::Deserialize;
+
+use self::errors::SettingsError;
+use crate::{
+    users::models::{MIN_PASSWORD_LENGTH, MIN_USERNAME_LENGTH},
+    validation::{PasswordComplexity, UsernameCharset},
+};
+
+pub mod errors;
+
+/// Prefix of the environment variables that override the configuration file, such as
+/// `HELLO_ACTIX_ADDRESS`.
+pub const ENV_PREFIX: &str = "HELLO_ACTIX_";
+
+/// NOTE(alex): `actix-identity` and `actix-session` refuse to sign cookies with smaller keys.
+pub const MIN_COOKIE_KEY_LENGTH: usize = 32;
+
+const LOG_LEVELS: &[&str] = &["off", "error", "warn", "info", "debug", "trace"];
+
+/// NOTE(alex): The lengths are in characters, and a character may take up to 4 bytes in UTF-8, so a
+/// body that holds the longest title, and details, needs this many bytes for each.
+const MAX_UTF8_CHAR_BYTES: usize = 4;
+
+/// Everything that changes between running in dev, test, or staging.
+///
+/// Every field has a default, so a configuration file only has to mention what it wants to change.
+/// The values from `build.rs` are only used as these defaults.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub address: String,
+    pub database_file: String,
+    pub log_level: String,
+    /// Key used to sign the identity, and session cookies. A random one is generated on startup
+    /// when this is not set, logging everyone out on every restart.
+    pub cookie_key: Option<String>,
+    /// Mark cookies `Secure` on every response, those going out over TLS are always marked (see
+    /// `https::StrictTransportSecurity`).
+    pub secure_cookies: bool,
+    /// How long the session cookie (favorite task) lives.
+    pub session_minutes: i64,
+    /// How long the identity cookie lives.
+    pub login_minutes: i64,
+    /// How long the bearer tokens issued on login live.
+    pub token_minutes: i64,
+    /// Key used to sign the bearer tokens, see `cookie_key` for what happens when it's not set.
+    pub token_signing_key: Option<String>,
+    /// PEM file with the certificate chain, leaf first.
+    pub cert_file: String,
+    /// PEM file with the private key (PKCS#8, PKCS#1 or SEC1).
+    pub key_file: String,
+    /// How often to check if the certificate files changed, `0` only reloads on `SIGHUP`.
+    pub cert_reload_seconds: u64,
+    /// PEM bundle of the CAs that sign client certificates, mutual TLS is off when it's not set.
+    pub client_ca_file: Option<String>,
+    /// Refuse connections without a client certificate, instead of falling back to bearer tokens.
+    pub require_client_cert: bool,
+    /// Plaintext `ip:port` that redirects everything to `address` over HTTPS, off when not set.
+    pub redirect_address: Option<String>,
+    /// `max-age` of the `Strict-Transport-Security` header, `0` leaves the header out.
+    pub hsts_max_age_seconds: u64,
+    /// How long deleted tasks, and users, stay in the trash before they're purged.
+    pub trash_days: i64,
+    /// Refuse `PUT`, and `DELETE`, of tasks that don't come with an `If-Match`, so clients can't
+    /// overwrite changes they haven't seen.
+    pub require_if_match: bool,
+    /// Which characters usernames may have, `any` (but whitespace), or `ascii`.
+    pub username_charset: UsernameCharset,
+    pub max_username_length: usize,
+    pub min_password_length: usize,
+    pub max_password_length: usize,
+    /// What passwords must have, `any`, `mixed` (letters and digits), or `strong` (lowercase,
+    /// uppercase, digits and symbols).
+    pub password_complexity: PasswordComplexity,
+    pub max_title_length: usize,
+    pub max_details_length: usize,
+    /// Largest request body taken, in any format, `POST /tasks/batch` takes this many per operation.
+    pub max_body_bytes: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            address: env!("ADDRESS").to_string(),
+            database_file: env!("DATABASE_FILE").to_string(),
+            log_level: "info".to_string(),
+            cookie_key: None,
+            secure_cookies: false,
+            session_minutes: 5,
+            login_minutes: 5,
+            token_minutes: 5,
+            token_signing_key: None,
+            cert_file: concat!(env!("CARGO_MANIFEST_DIR"), "/certificates/cert.pem").to_string(),
+            key_file: concat!(env!("CARGO_MANIFEST_DIR"), "/certificates/key.pem").to_string(),
+            cert_reload_seconds: 60,
+            client_ca_file: None,
+            require_client_cert: false,
+            redirect_address: None,
+            // NOTE(alex): One year, what the HSTS preload list asks for.
+            hsts_max_age_seconds: 31_536_000,
+            trash_days: 30,
+            require_if_match: false,
+            username_charset: UsernameCharset::Any,
+            max_username_length: 32,
+            min_password_length: MIN_PASSWORD_LENGTH,
+            // NOTE(alex): Argon2 takes anything, this only keeps people from hashing novels.
+            max_password_length: 128,
+            password_complexity: PasswordComplexity::Any,
+            max_title_length: 200,
+            max_details_length: 2000,
+            // NOTE(alex): Room for `max_title_length`, and `max_details_length`, of 4 byte
+            // characters, plus the rest of the body.
+            max_body_bytes: 16_384,
+        }
+    }
+}
+
+impl Settings {
+    /// Reads `config_file` (if any), applies the `HELLO_ACTIX_*` environment overrides on top of it,
+    /// and validates the result.
+    pub fn load(config_file: Option<&Path>) -> Result<Self, SettingsError> {
+        let settings = match config_file {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+
+        settings.with_overrides(std::env::vars())?.validate()
+    }
+
+    /// The format is picked from the file extension, either `.toml` or `.json`.
+    pub fn from_file(path: &Path) -> Result<Self, SettingsError> {
+        let display_path = path.display().to_string();
+
+        let contents = fs::read_to_string(path).map_err(|fail| SettingsError::Read {
+            path: display_path.clone(),
+            reason: fail.to_string(),
+        })?;
+
+        let parse_error = |reason: String| SettingsError::Parse {
+            path: display_path.clone(),
+            reason,
+        };
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => toml::from_str(&contents).map_err(|fail| parse_error(fail.to_string())),
+            Some("json") => {
+                serde_json::from_str(&contents).map_err(|fail| parse_error(fail.to_string()))
+            }
+            _ => Err(SettingsError::UnknownFormat(display_path)),
+        }
+    }
+
+    /// Overrides fields with the `HELLO_ACTIX_*` variables found in `vars`. Variables without the
+    /// prefix are ignored, but an unknown `HELLO_ACTIX_*` one is an error, as it's most likely a typo.
+    ///
+    /// NOTE(alex): Takes the variables as an argument (instead of reading `std::env::vars`), so
+    /// tests don't have to mess with the process environment.
+    pub fn with_overrides<I>(mut self, vars: I) -> Result<Self, SettingsError>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        for (key, value) in vars {
+            let field = match key.strip_prefix(ENV_PREFIX) {
+                Some(field) => field,
+                None => continue,
+            };
+
+            match field {
+                "ADDRESS" => self.address = value,
+                "DATABASE_FILE" => self.database_file = value,
+                "LOG_LEVEL" => self.log_level = value,
+                "COOKIE_KEY" => self.cookie_key = Some(value),
+                "SECURE_COOKIES" => self.secure_cookies = parse_override(&key, &value)?,
+                "SESSION_MINUTES" => self.session_minutes = parse_override(&key, &value)?,
+                "LOGIN_MINUTES" => self.login_minutes = parse_override(&key, &value)?,
+                "TOKEN_MINUTES" => self.token_minutes = parse_override(&key, &value)?,
+                "TOKEN_SIGNING_KEY" => self.token_signing_key = Some(value),
+                "CERT_FILE" => self.cert_file = value,
+                "KEY_FILE" => self.key_file = value,
+                "CERT_RELOAD_SECONDS" => self.cert_reload_seconds = parse_override(&key, &value)?,
+                "CLIENT_CA_FILE" => self.client_ca_file = Some(value),
+                "REQUIRE_CLIENT_CERT" => self.require_client_cert = parse_override(&key, &value)?,
+                "REDIRECT_ADDRESS" => self.redirect_address = Some(value),
+                "HSTS_MAX_AGE_SECONDS" => self.hsts_max_age_seconds = parse_override(&key, &value)?,
+                "TRASH_DAYS" => self.trash_days = parse_override(&key, &value)?,
+                "REQUIRE_IF_MATCH" => self.require_if_match = parse_override(&key, &value)?,
+                "USERNAME_CHARSET" => self.username_charset = parse_enum_override(&key, &value)?,
+                "MAX_USERNAME_LENGTH" => self.max_username_length = parse_override(&key, &value)?,
+                "MIN_PASSWORD_LENGTH" => self.min_password_length = parse_override(&key, &value)?,
+                "MAX_PASSWORD_LENGTH" => self.max_password_length = parse_override(&key, &value)?,
+                "PASSWORD_COMPLEXITY" => {
+                    self.password_complexity = parse_enum_override(&key, &value)?
+                }
+                "MAX_TITLE_LENGTH" => self.max_title_length = parse_override(&key, &value)?,
+                "MAX_DETAILS_LENGTH" => self.max_details_length = parse_override(&key, &value)?,
+                "MAX_BODY_BYTES" => self.max_body_bytes = parse_override(&key, &value)?,
+                // NOTE(alex): Points to the configuration file itself, it's not a setting.
+                "CONFIG" => (),
+                _ => return Err(SettingsError::UnknownVariable(key)),
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// The smallest `max_body_bytes` that still takes a task with the longest title, and details.
+    fn min_body_bytes(&self) -> usize {
+        self.max_title_length
+            .saturating_add(self.max_details_length)
+            .saturating_mul(MAX_UTF8_CHAR_BYTES)
+    }
+
+    pub fn validate(self) -> Result<Self, SettingsError> {
+        if self.address.parse::<SocketAddr>().is_err() {
+            Err(invalid(
+                "address",
+                &self.address,
+                "must be an `ip:port` address",
+            ))
+        } else if let Some(redirect_address) = self
+            .redirect_address
+            .as_ref()
+            .filter(|redirect_address| redirect_address.parse::<SocketAddr>().is_err())
+        {
+            Err(invalid(
+                "redirect_address",
+                redirect_address,
+                "must be an `ip:port` address",
+            ))
+        } else if self.database_file.trim().is_empty() {
+            Err(invalid(
+                "database_file",
+                &self.database_file,
+                "cannot be empty",
+            ))
+        } else if self.cert_file.trim().is_empty() {
+            Err(invalid("cert_file", &self.cert_file, "cannot be empty"))
+        } else if self.key_file.trim().is_empty() {
+            Err(invalid("key_file", &self.key_file, "cannot be empty"))
+        } else if self.require_client_cert && self.client_ca_file.is_none() {
+            Err(invalid(
+                "require_client_cert",
+                "true",
+                "needs `client_ca_file` to verify the certificates with",
+            ))
+        } else if !LOG_LEVELS.contains(&self.log_level.as_str()) {
+            Err(invalid(
+                "log_level",
+                &self.log_level,
+                &format!("must be one of {:?}", LOG_LEVELS),
+            ))
+        } else if let Some(short_key) = self
+            .cookie_key
+            .as_ref()
+            .filter(|key| key.len() < MIN_COOKIE_KEY_LENGTH)
+        {
+            Err(invalid(
+                "cookie_key",
+                // NOTE(alex): Don't print the key itself.
+                &format!("{} bytes", short_key.len()),
+                &format!("must be at least {} bytes", MIN_COOKIE_KEY_LENGTH),
+            ))
+        } else if self.session_minutes <= 0 {
+            Err(invalid(
+                "session_minutes",
+                &self.session_minutes.to_string(),
+                "must be positive",
+            ))
+        } else if self.login_minutes <= 0 {
+            Err(invalid(
+                "login_minutes",
+                &self.login_minutes.to_string(),
+                "must be positive",
+            ))
+        } else if self.token_minutes <= 0 {
+            Err(invalid(
+                "token_minutes",
+                &self.token_minutes.to_string(),
+                "must be positive",
+            ))
+        } else if self.trash_days <= 0 {
+            Err(invalid(
+                "trash_days",
+                &self.trash_days.to_string(),
+                "must be positive",
+            ))
+        } else if self.max_username_length < MIN_USERNAME_LENGTH {
+            Err(invalid(
+                "max_username_length",
+                &self.max_username_length.to_string(),
+                &format!("must be at least {}", MIN_USERNAME_LENGTH),
+            ))
+        } else if self.min_password_length < MIN_PASSWORD_LENGTH {
+            Err(invalid(
+                "min_password_length",
+                &self.min_password_length.to_string(),
+                &format!("must be at least {}", MIN_PASSWORD_LENGTH),
+            ))
+        } else if self.max_password_length < self.min_password_length {
+            Err(invalid(
+                "max_password_length",
+                &self.max_password_length.to_string(),
+                "must be at least `min_password_length`",
+            ))
+        } else if self.max_title_length == 0 {
+            Err(invalid("max_title_length", "0", "must be positive"))
+        } else if self.max_details_length == 0 {
+            Err(invalid("max_details_length", "0", "must be positive"))
+        } else if self.max_body_bytes < self.min_body_bytes() {
+            Err(invalid(
+                "max_body_bytes",
+                &self.max_body_bytes.to_string(),
+                &format!(
+                    "must hold `max_title_length`, and `max_details_length`, in UTF-8, at least {}",
+                    self.min_body_bytes()
+                ),
+            ))
+        } else {
+            Ok(self)
+        }
+    }
+}
+
+fn parse_override<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, SettingsError> {
+    value
+        .parse()
+        .map_err(|_| invalid(key, value, "could not be parsed"))
+}
+
+/// NOTE(alex): The enums are spelled the same as in the configuration file, `ascii`, `strong`.
+fn parse_enum_override<T: serde::de::DeserializeOwned>(
+    key: &str,
+    value: &str,
+) -> Result<T, SettingsError> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| invalid(key, value, "is not one of the allowed values"))
+}
+
+fn invalid(key: &str, value: &str, reason: &str) -> SettingsError {
+    SettingsError::Invalid {
+        key: key.to_string(),
+        value: value.to_string(),
+        reason: reason.to_string(),
+    }
+}
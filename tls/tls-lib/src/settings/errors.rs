@@ -0,0 +1,23 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("Could not read settings file `{path}`, failed with `{reason}`!")]
+    Read { path: String, reason: String },
+
+    #[error("Could not parse settings file `{path}`, failed with `{reason}`!")]
+    Parse { path: String, reason: String },
+
+    #[error("Settings file `{0}` must be either `.toml` or `.json`!")]
+    UnknownFormat(String),
+
+    #[error("Unknown settings environment variable `{0}`!")]
+    UnknownVariable(String),
+
+    #[error("Setting `{key}` with value `{value}` {reason}!")]
+    Invalid {
+        key: String,
+        value: String,
+        reason: String,
+    },
+}
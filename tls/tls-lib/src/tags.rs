@@ -0,0 +1,8 @@
+pub mod models;
+pub mod routes;
+
+const INSERT: &'static str = include_str!("./tags/queries/insert.sql");
+const UPDATE: &'static str = include_str!("./tags/queries/update.sql");
+const DELETE: &'static str = include_str!("./tags/queries/delete.sql");
+const FIND_ALL: &'static str = include_str!("./tags/queries/find_all.sql");
+const FIND_BY_NAME: &'static str = include_str!("./tags/queries/find_by_name.sql");
@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+
+use super::*;
+use crate::{
+    errors::AppError,
+    tasks::errors::TaskError,
+    validation::{FieldErrors, Validate, ValidationConfig},
+};
+
+/// A label that groups tasks, see `Task::tag`. Each user has their own tags.
+#[derive(Clone, Debug, Serialize, Deserialize, FromRow)]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    pub owner_id: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InsertTag {
+    pub non_empty_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateTag {
+    pub id: i64,
+    pub new_name: String,
+}
+
+/// NOTE(alex): Names end up in paths (`/tasks/{id}/tags/{tag}`), so a `/` is not allowed, and the
+/// surrounding whitespace is dropped (see `normalize`).
+fn check_name(name: &str, errors: &mut FieldErrors) {
+    let name = name.trim();
+
+    if name.is_empty() || name.contains('/') {
+        errors.push(TaskError::InvalidTagName);
+    }
+}
+
+impl InsertTag {
+    pub async fn insert(self, db_pool: &SqlitePool, owner_id: i64) -> Result<Tag, AppError> {
+        if Tag::find_by_name(db_pool, &self.non_empty_name, owner_id)
+            .await?
+            .is_some()
+        {
+            return Err(TaskError::DuplicateTag(self.non_empty_name).into());
+        }
+
+        let mut connection = db_pool.acquire().await?;
+        let result = sqlx::query(INSERT)
+            .bind(&self.non_empty_name)
+            .bind(owner_id)
+            .execute(&mut connection)
+            .await?;
+
+        let tag = Tag {
+            id: result.last_insert_rowid(),
+            name: self.non_empty_name,
+            owner_id,
+        };
+
+        Ok(tag)
+    }
+}
+
+impl Validate for InsertTag {
+    fn check(&self, _: &ValidationConfig, errors: &mut FieldErrors) {
+        check_name(&self.non_empty_name, errors);
+    }
+
+    fn normalize(self) -> Self {
+        Self {
+            non_empty_name: self.non_empty_name.trim().to_string(),
+        }
+    }
+}
+
+impl UpdateTag {
+    pub async fn update(self, db_pool: &SqlitePool, owner_id: i64) -> Result<u64, AppError> {
+        match Tag::find_by_name(db_pool, &self.new_name, owner_id).await? {
+            Some(tag) if tag.id != self.id => Err(TaskError::DuplicateTag(self.new_name).into()),
+            _ => {
+                let mut connection = db_pool.acquire().await?;
+                let result = sqlx::query(UPDATE)
+                    .bind(&self.new_name)
+                    .bind(self.id)
+                    .bind(owner_id)
+                    .execute(&mut connection)
+                    .await?;
+
+                Ok(result.rows_affected())
+            }
+        }
+    }
+}
+
+impl Validate for UpdateTag {
+    fn check(&self, _: &ValidationConfig, errors: &mut FieldErrors) {
+        check_name(&self.new_name, errors);
+    }
+
+    fn normalize(self) -> Self {
+        Self {
+            new_name: self.new_name.trim().to_string(),
+            ..self
+        }
+    }
+}
+
+impl Tag {
+    /// NOTE(alex): Deleting a tag takes it off of every task it was on.
+    pub async fn delete(db_pool: &SqlitePool, tag_id: i64, owner_id: i64) -> Result<u64, AppError> {
+        let mut connection = db_pool.acquire().await?;
+        let result = sqlx::query(DELETE)
+            .bind(tag_id)
+            .bind(owner_id)
+            .execute(&mut connection)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn find_all(db_pool: &SqlitePool, owner_id: i64) -> Result<Vec<Self>, AppError> {
+        let result = sqlx::query_as(FIND_ALL)
+            .bind(owner_id)
+            .fetch_all(db_pool)
+            .await?;
+
+        Ok(result)
+    }
+
+    pub async fn find_by_name(
+        db_pool: &SqlitePool,
+        name: &str,
+        owner_id: i64,
+    ) -> Result<Option<Self>, AppError> {
+        let result = sqlx::query_as(FIND_BY_NAME)
+            .bind(name)
+            .bind(owner_id)
+            .fetch_optional(db_pool)
+            .await?;
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,3 @@
+delete from Tag
+where
+    Tag.id = $1 and Tag.owner_id = $2
\ No newline at end of file
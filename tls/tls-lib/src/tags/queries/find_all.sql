@@ -0,0 +1,4 @@
+select *
+from Tag
+where Tag.owner_id = $1
+order by Tag.name;
\ No newline at end of file
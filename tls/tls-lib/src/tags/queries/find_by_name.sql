@@ -0,0 +1,4 @@
+select *
+from Tag
+where
+    Tag.name = $1 and Tag.owner_id = $2
\ No newline at end of file
@@ -0,0 +1,2 @@
+insert into Tag (name, owner_id)
+values ($1, $2);
\ No newline at end of file
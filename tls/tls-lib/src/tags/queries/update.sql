@@ -0,0 +1,4 @@
+update Tag
+set name = $1
+where
+    Tag.id = $2 and Tag.owner_id = $3
\ No newline at end of file
@@ -0,0 +1,66 @@
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+use actix_web_httpauth::middleware::HttpAuthentication;
+use sqlx::SqlitePool;
+
+use super::models::*;
+use crate::{
+    errors::AppError, tasks::errors::TaskError, users::models::LoggedUser, validation::Validated,
+    validator,
+};
+
+#[post("/tags", wrap = "HttpAuthentication::with_fn(validator)")]
+pub async fn insert(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    Validated(input): Validated<InsertTag>,
+) -> Result<impl Responder, AppError> {
+    let tag = input.insert(db_pool.get_ref(), logged_user.id).await?;
+    Ok(HttpResponse::Created().json(tag))
+}
+
+#[put("/tags", wrap = "HttpAuthentication::with_fn(validator)")]
+pub async fn update(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    Validated(input): Validated<UpdateTag>,
+) -> Result<impl Responder, AppError> {
+    let tag_id = input.id;
+    let num_modified = input.update(db_pool.get_ref(), logged_user.id).await?;
+
+    if num_modified == 0 {
+        Err(TaskError::TagNotFound(tag_id).into())
+    } else {
+        Ok(HttpResponse::Ok().body(format!("Updated {} tags.", num_modified)))
+    }
+}
+
+#[delete("/tags/{id}", wrap = "HttpAuthentication::with_fn(validator)")]
+pub async fn delete(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    id: web::Path<i64>,
+) -> Result<impl Responder, AppError> {
+    let num_modified = Tag::delete(db_pool.get_ref(), *id, logged_user.id).await?;
+
+    if num_modified == 0 {
+        Err(TaskError::TagNotFound(*id).into())
+    } else {
+        Ok(HttpResponse::Ok().body(format!("Deleted {} tags.", num_modified)))
+    }
+}
+
+#[get("/tags", wrap = "HttpAuthentication::with_fn(validator)")]
+pub async fn find_all(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+) -> Result<impl Responder, AppError> {
+    let tags = Tag::find_all(db_pool.get_ref(), logged_user.id).await?;
+    Ok(HttpResponse::Ok().json(&tags))
+}
+
+pub fn tag_service(cfg: &mut web::ServiceConfig) {
+    cfg.service(insert);
+    cfg.service(update);
+    cfg.service(delete);
+    cfg.service(find_all);
+}
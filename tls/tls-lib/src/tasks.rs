@@ -1,14 +1,38 @@
+pub mod batch;
 pub mod errors;
 pub mod models;
+pub mod recurrence;
 pub mod routes;
 
-const FIND_BY_PATTERN: &'static str = include_str!("./tasks/queries/find_by_pattern.sql");
 const FIND_ONGOING: &'static str = include_str!("./tasks/queries/find_ongoing.sql");
-const FIND_ALL: &'static str = include_str!("./tasks/queries/find_all.sql");
+const FIND_PAGE_BY_TITLE: &'static str = include_str!("./tasks/queries/find_page_by_title.sql");
+const FIND_PAGE_BY_TITLE_DESC: &'static str =
+    include_str!("./tasks/queries/find_page_by_title_desc.sql");
+const FIND_PAGE_BY_CREATED_AT: &'static str =
+    include_str!("./tasks/queries/find_page_by_created_at.sql");
+const FIND_PAGE_BY_CREATED_AT_DESC: &'static str =
+    include_str!("./tasks/queries/find_page_by_created_at_desc.sql");
+const FIND_PAGE_BY_PRIORITY: &'static str =
+    include_str!("./tasks/queries/find_page_by_priority.sql");
+const FIND_PAGE_BY_PRIORITY_DESC: &'static str =
+    include_str!("./tasks/queries/find_page_by_priority_desc.sql");
+const FIND_OVERDUE: &'static str = include_str!("./tasks/queries/find_overdue.sql");
+const FIND_DUE_WITHIN: &'static str = include_str!("./tasks/queries/find_due_within.sql");
+const FIND_CURSOR: &'static str = include_str!("./tasks/queries/find_cursor.sql");
+const COUNT: &'static str = include_str!("./tasks/queries/count.sql");
+const SEARCH: &'static str = include_str!("./tasks/queries/search.sql");
 const FIND_BY_ID: &'static str = include_str!("./tasks/queries/find_by_id.sql");
 const INSERT: &'static str = include_str!("./tasks/queries/insert.sql");
 const UPDATE: &'static str = include_str!("./tasks/queries/update.sql");
 const DELETE: &'static str = include_str!("./tasks/queries/delete.sql");
+const RESTORE: &'static str = include_str!("./tasks/queries/restore.sql");
 
 const DONE: &'static str = include_str!("./tasks/queries/done.sql");
 const UNDO: &'static str = include_str!("./tasks/queries/undo.sql");
+const FIND_OCCURRENCE: &'static str = include_str!("./tasks/queries/find_occurrence.sql");
+const INSERT_OCCURRENCE: &'static str = include_str!("./tasks/queries/insert_occurrence.sql");
+const COPY_TAGS: &'static str = include_str!("./tasks/queries/copy_tags.sql");
+const COPY_CHECKLIST: &'static str = include_str!("./tasks/queries/copy_checklist.sql");
+
+const TAG: &'static str = include_str!("./tasks/queries/tag.sql");
+const UNTAG: &'static str = include_str!("./tasks/queries/untag.sql");
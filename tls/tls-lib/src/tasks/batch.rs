@@ -0,0 +1,229 @@
+use actix_web::{http::StatusCode, ResponseError};
+use serde::{Deserialize, Serialize};
+use sqlx::{Connection, Sqlite, SqlitePool, Transaction};
+
+use super::{errors::TaskError, models::*};
+use crate::{
+    errors::{AppError, Problem},
+    validation::{FieldErrors, Validate, ValidationConfig},
+};
+
+pub const MAX_BATCH_OPERATIONS: usize = 100;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchMode {
+    /// The first operation that fails rolls back the whole batch.
+    #[default]
+    Atomic,
+    /// Keeps every operation that went through, failed ones are rolled back on their own.
+    BestEffort,
+}
+
+/// The same operations there are routes for, `{ "op": "done", "id": 1 }`.
+///
+/// NOTE(alex): `update`, and `delete` take the `version` the task must be at, in place of the
+/// `If-Match` of their routes (see `Precondition::from_version`).
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Operation {
+    Insert(InsertTask),
+    Update {
+        #[serde(flatten)]
+        task: UpdateTask,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        version: Option<i64>,
+    },
+    Delete {
+        id: i64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        version: Option<i64>,
+    },
+    Done {
+        id: i64,
+        #[serde(default)]
+        force: bool,
+    },
+    Undo {
+        id: i64,
+    },
+}
+
+/// Body of `POST /tasks/batch`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Batch {
+    #[serde(default)]
+    pub mode: BatchMode,
+    pub operations: Vec<Operation>,
+}
+
+/// How one operation of a `Batch` went, `status` is what its own route would've answered.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OperationResult {
+    pub status: u16,
+    /// The task it was done to, for `insert` the new one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    /// Same body as the `AppError` response of its own route, without an `instance`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<Problem>,
+}
+
+impl OperationResult {
+    fn ok(status: StatusCode, id: i64) -> Self {
+        Self {
+            status: status.as_u16(),
+            id: Some(id),
+            error: None,
+        }
+    }
+
+    fn failed(fail: &AppError) -> Self {
+        Self {
+            status: fail.status_code().as_u16(),
+            id: None,
+            error: Some(fail.problem()),
+        }
+    }
+
+    /// NOTE(alex): Anything below `400` went through, a `204` just has nothing to say.
+    pub fn is_success(&self) -> bool {
+        self.status < 400
+    }
+}
+
+impl Operation {
+    fn validate(self, config: &ValidationConfig) -> Result<Self, AppError> {
+        match self {
+            Operation::Insert(insert_task) => insert_task.validate(config).map(Operation::Insert),
+            Operation::Update { task, version } => Ok(Operation::Update {
+                task: task.validate(config)?,
+                version,
+            }),
+            operation => Ok(operation),
+        }
+    }
+
+    /// NOTE(alex): Validated here, instead of in `Batch::from_request`, so an invalid operation is
+    /// just a failed result, like any other.
+    async fn run(
+        self,
+        transaction: &mut Transaction<'_, Sqlite>,
+        owner_id: i64,
+        config: &ValidationConfig,
+        precondition_config: &PreconditionConfig,
+    ) -> Result<OperationResult, AppError> {
+        match self.validate(config)? {
+            Operation::Insert(insert_task) => {
+                let task = insert_task.insert_in(transaction, owner_id).await?;
+                Ok(OperationResult::ok(StatusCode::CREATED, task.id))
+            }
+            Operation::Update { task, version } => {
+                let id = task.id;
+                let precondition = Precondition::from_version(version, precondition_config)?;
+
+                match task.update_in(transaction, owner_id, &precondition).await? {
+                    Some(_) => Ok(OperationResult::ok(StatusCode::OK, id)),
+                    None => Err(TaskError::NotFound(id).into()),
+                }
+            }
+            Operation::Delete { id, version } => {
+                let precondition = Precondition::from_version(version, precondition_config)?;
+
+                match Task::delete_in(transaction, id, owner_id, &precondition).await? {
+                    0 => Err(TaskError::NotFound(id).into()),
+                    _ => Ok(OperationResult::ok(StatusCode::NO_CONTENT, id)),
+                }
+            }
+            Operation::Done { id, force } => {
+                match Task::done_in(transaction, id, owner_id, force).await? {
+                    Some(Completed {
+                        next_task_id: Some(_),
+                        ..
+                    }) => Ok(OperationResult::ok(StatusCode::CREATED, id)),
+                    Some(_) => Ok(OperationResult::ok(StatusCode::NO_CONTENT, id)),
+                    None => Err(TaskError::NotFound(id).into()),
+                }
+            }
+            Operation::Undo { id } => {
+                if Task::find_by_id_in(transaction, id, owner_id)
+                    .await?
+                    .is_none()
+                {
+                    return Err(TaskError::NotFound(id).into());
+                }
+
+                Task::undo_in(transaction, id, owner_id).await?;
+                Ok(OperationResult::ok(StatusCode::NO_CONTENT, id))
+            }
+        }
+    }
+}
+
+impl Batch {
+    /// Runs every operation, in order, in one transaction, returning a result for each of them.
+    ///
+    /// NOTE(alex): Each operation gets its own savepoint, so a failed one never leaves half of its
+    /// writes behind. When `Atomic`, the first failure rolls everything back, and every other
+    /// operation is answered with `424`.
+    pub async fn run(
+        self,
+        db_pool: &SqlitePool,
+        owner_id: i64,
+        config: &ValidationConfig,
+        precondition_config: &PreconditionConfig,
+    ) -> Result<Vec<OperationResult>, AppError> {
+        let Batch { mode, operations } = self;
+        let num_operations = operations.len();
+        let mut results = Vec::with_capacity(num_operations);
+
+        let mut transaction = db_pool.begin().await?;
+
+        for (index, operation) in operations.into_iter().enumerate() {
+            let mut savepoint = transaction.begin().await?;
+
+            match operation
+                .run(&mut savepoint, owner_id, config, precondition_config)
+                .await
+            {
+                Ok(result) => {
+                    savepoint.commit().await?;
+                    results.push(result);
+                }
+                Err(fail) if mode == BatchMode::Atomic => {
+                    savepoint.rollback().await?;
+                    transaction.rollback().await?;
+
+                    let rolled_back = OperationResult::failed(&TaskError::RolledBack(index).into());
+                    let mut results = vec![rolled_back; num_operations];
+                    results[index] = OperationResult::failed(&fail);
+
+                    return Ok(results);
+                }
+                Err(fail) => {
+                    savepoint.rollback().await?;
+                    results.push(OperationResult::failed(&fail));
+                }
+            }
+        }
+
+        transaction.commit().await?;
+
+        Ok(results)
+    }
+}
+
+impl Validate for Batch {
+    fn check(&self, _: &ValidationConfig, errors: &mut FieldErrors) {
+        if self.operations.is_empty() || self.operations.len() > MAX_BATCH_OPERATIONS {
+            errors.push(TaskError::BatchSize);
+        }
+    }
+
+    /// NOTE(alex): Room for `MAX_BATCH_OPERATIONS` of the biggest `InsertTask` we'd take on its
+    /// own.
+    fn body_limit(config: &ValidationConfig) -> usize {
+        config.max_body_bytes.saturating_mul(MAX_BATCH_OPERATIONS)
+    }
+}
@@ -1,16 +1,150 @@
 use thiserror::Error;
 
+use super::{
+    batch::MAX_BATCH_OPERATIONS,
+    models::{MAX_DUE_DAYS, MAX_OCCURRENCES, MAX_PAGE_LIMIT},
+};
+
 #[derive(Debug, Error)]
 pub enum TaskError {
     #[error("`title` field of `Task` cannot be empty!")]
     EmptyTitle,
 
+    #[error("`title` field of `Task` cannot be longer than {0} characters!")]
+    TitleLength(usize),
+
+    #[error("`details` field of `Task` cannot be longer than {0} characters!")]
+    DetailsLength(usize),
+
     #[error("Could not find any `Task` for id: `{0}`!")]
     NotFound(i64),
 
     #[error("You have not favorited any `Task` yet!")]
     NoneFavorite,
 
-    #[error("Could not find any `Task`!")]
-    Empty,
+    #[error("`limit` must be between 1 and {}!", MAX_PAGE_LIMIT)]
+    PageLimit,
+
+    #[error("Could not find any `Task` for `after_id`: `{0}`, start again from the first page!")]
+    UnknownCursor(i64),
+
+    #[error("`q` must contain at least one word to search for!")]
+    EmptySearch,
+
+    #[error("`due_at` field of `Task` cannot be in the past!")]
+    DueInPast,
+
+    #[error("`days` must be between 1 and {}!", MAX_DUE_DAYS)]
+    DueDays,
+
+    #[error("`name` field of `Tag` cannot be empty, or contain a `/`!")]
+    InvalidTagName,
+
+    #[error("Could not find any `Tag` for id: `{0}`!")]
+    TagNotFound(i64),
+
+    #[error("Could not find any `Tag` named: `{0}`!")]
+    UnknownTag(String),
+
+    #[error("There is already a `Tag` named: `{0}`!")]
+    DuplicateTag(String),
+
+    #[error("`title` field of `ChecklistItem` cannot be empty!")]
+    EmptyItemTitle,
+
+    #[error("Could not find any `ChecklistItem` for id: `{0}`!")]
+    ChecklistItemNotFound(i64),
+
+    #[error("`item_ids` must have every item of the checklist, once!")]
+    ChecklistOrder,
+
+    #[error("Only {done}/{total} items of the checklist are checked, use `force=true` to mark the `Task` as done anyway!")]
+    UnfinishedChecklist { done: i64, total: i64 },
+
+    #[error("`recurrence` field of `Task` is not a valid rule, {0}!")]
+    InvalidRecurrence(String),
+
+    #[error("`recurrence` field of `Task` requires a `due_at`!")]
+    RecurrenceWithoutDue,
+
+    #[error("`Task` with id: `{0}` is already done, undo it first!")]
+    AlreadyDone(i64),
+
+    #[error("`Task` with id: `{0}` doesn't repeat!")]
+    NotRecurring(i64),
+
+    #[error("`count` must be between 1 and {}!", MAX_OCCURRENCES)]
+    OccurrenceCount,
+
+    #[error("`Task` with id: `{0}` has changed, get it again for its current `ETag`!")]
+    PreconditionFailed(i64),
+
+    #[error("`If-Match` header with the `ETag` of the `Task` is required!")]
+    PreconditionRequired,
+
+    #[error(
+        "`operations` of `Batch` must have between 1 and {} items!",
+        MAX_BATCH_OPERATIONS
+    )]
+    BatchSize,
+
+    #[error("Rolled back, operation {0} of the `Batch` failed!")]
+    RolledBack(usize),
+}
+
+impl TaskError {
+    /// Stable name of the error, it goes in `Problem::code`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            TaskError::EmptyTitle => "empty_title",
+            TaskError::TitleLength(_) => "title_length",
+            TaskError::DetailsLength(_) => "details_length",
+            TaskError::NotFound(_) => "task_not_found",
+            TaskError::NoneFavorite => "no_favorite_task",
+            TaskError::PageLimit => "invalid_page_limit",
+            TaskError::UnknownCursor(_) => "unknown_cursor",
+            TaskError::EmptySearch => "empty_search",
+            TaskError::DueInPast => "due_in_past",
+            TaskError::DueDays => "invalid_due_days",
+            TaskError::InvalidTagName => "invalid_tag_name",
+            TaskError::TagNotFound(_) => "tag_not_found",
+            TaskError::UnknownTag(_) => "unknown_tag",
+            TaskError::DuplicateTag(_) => "duplicate_tag",
+            TaskError::EmptyItemTitle => "empty_item_title",
+            TaskError::ChecklistItemNotFound(_) => "checklist_item_not_found",
+            TaskError::ChecklistOrder => "invalid_checklist_order",
+            TaskError::UnfinishedChecklist { .. } => "unfinished_checklist",
+            TaskError::InvalidRecurrence(_) => "invalid_recurrence",
+            TaskError::RecurrenceWithoutDue => "recurrence_without_due",
+            TaskError::AlreadyDone(_) => "task_already_done",
+            TaskError::NotRecurring(_) => "task_not_recurring",
+            TaskError::OccurrenceCount => "invalid_occurrence_count",
+            TaskError::PreconditionFailed(_) => "precondition_failed",
+            TaskError::PreconditionRequired => "precondition_required",
+            TaskError::BatchSize => "invalid_batch_size",
+            TaskError::RolledBack(_) => "rolled_back",
+        }
+    }
+
+    /// The field of the request at fault, for the validation errors.
+    pub fn field(&self) -> Option<&'static str> {
+        match self {
+            TaskError::EmptyTitle => Some("title"),
+            TaskError::TitleLength(_) => Some("title"),
+            TaskError::DetailsLength(_) => Some("details"),
+            TaskError::PageLimit => Some("limit"),
+            TaskError::UnknownCursor(_) => Some("after_id"),
+            TaskError::EmptySearch => Some("q"),
+            TaskError::DueInPast => Some("due_at"),
+            TaskError::DueDays => Some("days"),
+            TaskError::InvalidTagName => Some("name"),
+            TaskError::EmptyItemTitle => Some("title"),
+            TaskError::ChecklistOrder => Some("item_ids"),
+            TaskError::InvalidRecurrence(_) => Some("recurrence"),
+            TaskError::RecurrenceWithoutDue => Some("recurrence"),
+            TaskError::OccurrenceCount => Some("count"),
+            TaskError::BatchSize => Some("operations"),
+            _ => None,
+        }
+    }
 }
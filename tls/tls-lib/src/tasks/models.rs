@@ -1,197 +1,1354 @@
 use actix_web::{
-    body::BoxBody, dev::Payload, web::JsonBody, FromRequest, HttpRequest, HttpResponse, Responder,
+    body::BoxBody,
+    dev::Payload,
+    http::header::{self, EntityTag, Header, IfMatch},
+    web::{self, Query},
+    FromRequest, HttpRequest, HttpResponse, Responder,
 };
-use futures::{future::LocalBoxFuture, FutureExt};
-use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, SqlitePool};
+use futures::future::{ready, Ready};
+use serde::{Deserialize, Deserializer, Serialize};
+use sqlx::{sqlite::SqliteRow, types::Json, FromRow, Row, Sqlite, SqlitePool, Transaction};
+use time::OffsetDateTime;
 
-use super::{errors::*, *};
-use crate::errors::AppError;
+use super::{errors::*, recurrence::Recurrence, *};
+use crate::audit::models::{diff, Changes, InsertTaskEvent, TaskEventKind, Timestamp};
+use crate::{
+    errors::AppError,
+    negotiation::{escape_html, Negotiated, Representation, Tabular},
+    timestamps::{self, rfc3339},
+    validation::{length, FieldErrors, Validate, ValidationConfig},
+};
+
+/// NOTE(alex): Stored as an `int`, so sorting by priority is sorting by the column.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type,
+)]
+#[serde(rename_all = "lowercase")]
+#[repr(i64)]
+pub enum Priority {
+    Low = 0,
+    #[default]
+    Normal = 1,
+    High = 2,
+    Urgent = 3,
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize, FromRow)]
 pub struct Task {
     pub id: i64,
     pub title: String,
     pub details: String,
+    pub owner_id: i64,
+    pub priority: Priority,
+    #[serde(default, with = "rfc3339::option")]
+    pub due_at: Option<i64>,
+    #[serde(with = "rfc3339")]
+    pub created_at: i64,
+    #[serde(with = "rfc3339")]
+    pub updated_at: i64,
+    /// When it was last marked as done, `None` while it's ongoing.
+    #[serde(default, with = "rfc3339::option")]
+    pub completed_at: Option<i64>,
+    /// Names of its `Tag`s, sorted.
+    #[serde(default)]
+    pub tags: Json<Vec<String>>,
+    #[serde(default)]
+    pub progress: Json<Progress>,
+    /// See `Recurrence`, when it's done the next occurrence is created.
+    #[serde(default)]
+    pub recurrence: Option<String>,
+    /// When it was moved to the trash, only trashed tasks have it.
+    #[serde(default, with = "rfc3339::option")]
+    pub deleted_at: Option<i64>,
+    /// Goes up on every change, it's the `ETag` of the task (see `Task::etag`).
+    pub version: i64,
+}
+
+/// How many items of a task's checklist are checked, out of how many.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Progress {
+    pub done: i64,
+    pub total: i64,
+}
+
+/// What `Task::done` did.
+#[derive(Clone, Copy, Debug)]
+pub struct Completed {
+    /// Id of the new `Done` row.
+    pub done_id: i64,
+    /// The next occurrence, when it repeats, and this is the first time it's done (see
+    /// `insert_next_occurrence`).
+    pub next_task_id: Option<i64>,
+}
+
+/// Query string of `GET /tasks/{id}/occurrences`.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueryOccurrences {
+    pub count: Option<u32>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Occurrence {
+    #[serde(with = "rfc3339")]
+    pub due_at: i64,
+}
+
+/// Query string of `POST /tasks/{id}/done`.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DoneQuery {
+    /// Marks it as done even if its checklist isn't.
+    pub force: bool,
+}
+
+/// The `If-Match` of a `PUT`, or `DELETE`, of a task.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Precondition {
+    /// No `If-Match`, whatever the current version is.
+    Unconditional,
+    /// `If-Match: *`, the task only has to exist.
+    Any,
+    /// Only when one of these is the current `ETag`.
+    ETags(Vec<EntityTag>),
+}
+
+/// Registered with `app_data`, when it's not there `If-Match` is optional.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PreconditionConfig {
+    /// Missing `If-Match` is a `428`, see `Settings::require_if_match`.
+    pub required: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct InsertTask {
     pub non_empty_title: String,
     pub details: String,
+    #[serde(default)]
+    pub priority: Priority,
+    #[serde(default, with = "rfc3339::option")]
+    pub due_at: Option<i64>,
+    #[serde(default)]
+    pub recurrence: Option<String>,
 }
 
+/// NOTE(alex): The fields after `details` are left as they are when missing, `due_at` and
+/// `recurrence` are cleared with a `null`.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateTask {
     pub id: i64,
     pub new_title: String,
     pub details: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    #[serde(
+        default,
+        with = "rfc3339::nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub due_at: Option<Option<i64>>,
+    #[serde(
+        default,
+        deserialize_with = "nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub recurrence: Option<Option<String>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct QueryTask {
-    pub title: String,
-    pub details: String,
+pub const DEFAULT_PAGE_LIMIT: u32 = 20;
+pub const MAX_PAGE_LIMIT: u32 = 100;
+pub const MAX_DUE_DAYS: u32 = 365;
+pub const DEFAULT_OCCURRENCES: u32 = 5;
+pub const MAX_OCCURRENCES: u32 = 50;
+
+/// NOTE(alex): Shared by `InsertTask` and `UpdateTask`, the rule is parsed, then stored in its
+/// canonical form.
+fn validate_recurrence(
+    recurrence: Option<&str>,
+    due_at: Option<i64>,
+) -> Result<Option<String>, TaskError> {
+    match recurrence {
+        Some(_) if due_at.is_none() => Err(TaskError::RecurrenceWithoutDue),
+        Some(recurrence) => Ok(Some(recurrence.parse::<Recurrence>()?.to_string())),
+        None => Ok(None),
+    }
+}
+
+/// `Some(None)` for a `null` field, the missing one is `None` thanks to `#[serde(default)]`.
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// The rules shared by `InsertTask`, and `UpdateTask`.
+fn check_task(title: &str, details: &str, config: &ValidationConfig, errors: &mut FieldErrors) {
+    if title.trim().is_empty() {
+        errors.push(TaskError::EmptyTitle);
+    } else if length(title) > config.max_title_length {
+        errors.push(TaskError::TitleLength(config.max_title_length));
+    }
+
+    if length(details) > config.max_details_length {
+        errors.push(TaskError::DetailsLength(config.max_details_length));
+    }
+}
+
+/// NOTE(alex): The recurrence is stored in its canonical form, `check` already made sure it
+/// parses.
+fn normalize_recurrence(recurrence: Option<String>) -> Option<String> {
+    recurrence.map(|recurrence| {
+        recurrence
+            .parse::<Recurrence>()
+            .map(|parsed| parsed.to_string())
+            .unwrap_or(recurrence)
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TaskSort {
+    #[serde(rename = "title")]
+    Title,
+    #[serde(rename = "-title")]
+    TitleDesc,
+    #[serde(rename = "created_at")]
+    CreatedAt,
+    #[serde(rename = "-created_at")]
+    CreatedAtDesc,
+    #[serde(rename = "priority")]
+    Priority,
+    #[serde(rename = "-priority")]
+    PriorityDesc,
+}
+
+impl Default for TaskSort {
+    fn default() -> Self {
+        Self::CreatedAt
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Ongoing,
+    Done,
+}
+
+/// How the `tag`s of `TaskQuery` are matched.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMode {
+    /// The task has at least one of them.
+    #[default]
+    Any,
+    /// The task has every one of them.
+    All,
+}
+
+/// Query string of `GET /tasks`, such as `?limit=10&after_id=42&sort=-created_at&status=done&q=bebop`.
+///
+/// NOTE(alex): `after_id` is the `next_cursor` of the previous page, the cursor is just the id of the
+/// last task, so pages stay stable even when tasks are inserted (or deleted) in between requests.
+/// A cursor that isn't one of the user's tasks (anymore, after the purge) is refused, instead of
+/// coming back as an empty page that looks like the end of the list.
+///
+/// NOTE(alex): `tag` may be repeated (`?tag=anime&tag=classic&tag_mode=all`), which `Query` can't
+/// deal with, so those are taken out of the query string before it gets deserialized, see the
+/// `FromRequest` impl.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TaskQuery {
+    pub limit: Option<u32>,
+    pub after_id: Option<i64>,
+    pub sort: TaskSort,
+    pub status: Option<TaskStatus>,
+    /// Matches anywhere in the `title` or `details`.
+    pub q: Option<String>,
+    #[serde(skip)]
+    pub tags: Vec<String>,
+    pub tag_mode: TagMode,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Pass it as `after_id` to get the next page, `None` on the last one.
+    pub next_cursor: Option<i64>,
+    /// How many items match the filters, across every page.
+    pub total: i64,
+}
+
+/// Query string of `GET /tasks/due`, the tasks due in the next `days`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QueryDue {
+    pub days: u32,
+}
+
+/// Query string of `GET /tasks/search`, `q` uses a small subset of the FTS5 syntax, words are
+/// and-ed together, `"quoted words"` must appear as a phrase, and `word*` matches by prefix.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchTask {
+    pub q: String,
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TaskSearchResult {
+    pub task: Task,
+    /// HTML, the whole title (escaped), with the matches wrapped in `<mark></mark>`.
+    pub title_highlight: String,
+    /// HTML, a few words of `details` (escaped) around the matches, also wrapped in
+    /// `<mark></mark>`.
+    pub details_snippet: String,
+    /// `bm25` of the match, lower is better.
+    pub rank: f64,
 }
 
 impl InsertTask {
-    pub async fn insert(self, db_pool: &SqlitePool) -> Result<Task, AppError> {
-        let mut connection = db_pool.acquire().await?;
+    pub async fn insert(self, db_pool: &SqlitePool, owner_id: i64) -> Result<Task, AppError> {
+        let mut transaction = db_pool.begin().await?;
+        let task = self.insert_in(&mut transaction, owner_id).await?;
+        transaction.commit().await?;
+
+        Ok(task)
+    }
+
+    /// NOTE(alex): The `_in` versions leave committing to the caller, so `Batch` can run many of
+    /// them in one transaction.
+    pub(crate) async fn insert_in(
+        self,
+        transaction: &mut Transaction<'_, Sqlite>,
+        owner_id: i64,
+    ) -> Result<Task, AppError> {
+        let created_at = timestamps::now();
+
         let result = sqlx::query(INSERT)
             .bind(&self.non_empty_title)
             .bind(&self.details)
-            .execute(&mut connection)
+            .bind(owner_id)
+            .bind(created_at)
+            .bind(self.due_at)
+            .bind(self.priority)
+            .bind(&self.recurrence)
+            .execute(&mut *transaction)
             .await?;
+        let task_id = result.last_insert_rowid();
+
+        InsertTaskEvent {
+            task_id,
+            user_id: owner_id,
+            kind: TaskEventKind::Created,
+            changes: None,
+        }
+        .insert(&mut *transaction)
+        .await?;
 
         let task = Task {
-            id: result.last_insert_rowid(),
+            id: task_id,
             title: self.non_empty_title,
             details: self.details,
+            owner_id,
+            priority: self.priority,
+            due_at: self.due_at,
+            created_at,
+            updated_at: created_at,
+            completed_at: None,
+            tags: Json(Vec::new()),
+            progress: Json(Progress::default()),
+            recurrence: self.recurrence,
+            deleted_at: None,
+            version: 1,
         };
 
         Ok(task)
     }
+}
 
-    fn validate(self) -> Result<Self, TaskError> {
-        if self.non_empty_title.trim().is_empty() {
-            Err(TaskError::EmptyTitle)
-        } else {
-            Ok(self)
+impl Precondition {
+    /// For the operations of a `Batch`, which carry the `version` they expect, instead of an
+    /// `If-Match`.
+    pub fn from_version(
+        version: Option<i64>,
+        config: &PreconditionConfig,
+    ) -> Result<Self, TaskError> {
+        match version {
+            Some(version) => Ok(Precondition::ETags(vec![version_etag(version)])),
+            None if config.required => Err(TaskError::PreconditionRequired),
+            None => Ok(Precondition::Unconditional),
+        }
+    }
+
+    /// The version the query has to find, `None` when any version will do.
+    ///
+    /// NOTE(alex): `If-Match` uses the strong comparison, but our tags are never weak anyway. The
+    /// tag of any representation will do, they all stand for the same version.
+    fn expected_version(&self, task: &Task) -> Result<Option<i64>, TaskError> {
+        match self {
+            Self::Unconditional | Self::Any => Ok(None),
+            Self::ETags(etags)
+                if etags.iter().any(|etag| {
+                    Representation::ALL.iter().any(|representation| {
+                        etag.strong_eq(&task.representation_etag(*representation))
+                    })
+                }) =>
+            {
+                Ok(Some(task.version))
+            }
+            Self::ETags(_) => Err(TaskError::PreconditionFailed(task.id)),
         }
     }
 }
 
 impl UpdateTask {
-    pub async fn update(self, db_pool: &SqlitePool) -> Result<u64, AppError> {
-        let mut connection = db_pool.acquire().await?;
+    /// Returns the new version of the task, `None` if `owner_id` has no such task.
+    pub async fn update(
+        self,
+        db_pool: &SqlitePool,
+        owner_id: i64,
+        precondition: &Precondition,
+    ) -> Result<Option<i64>, AppError> {
+        let mut transaction = db_pool.begin().await?;
+        let version = self
+            .update_in(&mut transaction, owner_id, precondition)
+            .await?;
+        transaction.commit().await?;
+
+        Ok(version)
+    }
+
+    pub(crate) async fn update_in(
+        self,
+        transaction: &mut Transaction<'_, Sqlite>,
+        owner_id: i64,
+        precondition: &Precondition,
+    ) -> Result<Option<i64>, AppError> {
+        // NOTE(alex): The task as it was, for the `changes` of the `TaskEvent`.
+        let task = match Task::find_by_id_in(transaction, self.id, owner_id).await? {
+            Some(task) => task,
+            None => return Ok(None),
+        };
+        let expected_version = precondition.expected_version(&task)?;
+
+        let priority = self.priority.unwrap_or(task.priority);
+        let recurrence = self.recurrence.as_ref().unwrap_or(&task.recurrence);
+        let due_at = self.due_at.unwrap_or(task.due_at);
+
+        // NOTE(alex): Only a `due_at` that changes has to be in the future, a task that is already
+        // overdue can still be renamed.
+        if due_at != task.due_at
+            && due_at
+                .filter(|due_at| *due_at < timestamps::now())
+                .is_some()
+        {
+            return Err(TaskError::DueInPast.into());
+        }
+        validate_recurrence(recurrence.as_deref(), due_at)?;
+
         let result = sqlx::query(UPDATE)
             .bind(&self.new_title)
             .bind(&self.details)
+            .bind(due_at)
+            .bind(priority)
+            .bind(recurrence)
+            .bind(timestamps::now())
             .bind(&self.id)
-            .execute(&mut connection)
+            .bind(owner_id)
+            .bind(expected_version)
+            .execute(&mut *transaction)
             .await?;
 
-        Ok(result.rows_affected())
+        // NOTE(alex): Someone else got to it between our read, and the update.
+        if result.rows_affected() == 0 {
+            return Err(TaskError::PreconditionFailed(self.id).into());
+        }
+
+        InsertTaskEvent {
+            task_id: self.id,
+            user_id: owner_id,
+            kind: TaskEventKind::Updated,
+            changes: Some(self.changes_from(&task)?),
+        }
+        .insert(&mut *transaction)
+        .await?;
+
+        Ok(Some(task.version + 1))
     }
 
+    fn changes_from(&self, task: &Task) -> Result<Changes, AppError> {
+        let mut changes = Changes::new();
+
+        diff(&mut changes, "title", &task.title, &self.new_title)?;
+        diff(&mut changes, "details", &task.details, &self.details)?;
+        if let Some(priority) = &self.priority {
+            diff(&mut changes, "priority", &task.priority, priority)?;
+        }
+        if let Some(due_at) = self.due_at {
+            diff(
+                &mut changes,
+                "due_at",
+                &Timestamp(task.due_at),
+                &Timestamp(due_at),
+            )?;
+        }
+        if let Some(recurrence) = &self.recurrence {
+            diff(&mut changes, "recurrence", &task.recurrence, recurrence)?;
+        }
+
+        Ok(changes)
+    }
+}
+
+impl TaskQuery {
     fn validate(self) -> Result<Self, TaskError> {
-        if self.new_title.trim().is_empty() {
-            Err(TaskError::EmptyTitle)
+        match self.limit {
+            Some(limit) if limit == 0 || limit > MAX_PAGE_LIMIT => Err(TaskError::PageLimit),
+            _ => Ok(self),
+        }
+    }
+
+    /// The tags as a JSON array, and how many of them a task needs to match, `None` when there is
+    /// nothing to filter by.
+    fn tags_filter(&self) -> Option<(String, usize)> {
+        let mut tags = self.tags.clone();
+        tags.sort();
+        tags.dedup();
+
+        if tags.is_empty() {
+            None
+        } else {
+            let required = match self.tag_mode {
+                TagMode::Any => 1,
+                TagMode::All => tags.len(),
+            };
+
+            serde_json::to_string(&tags)
+                .ok()
+                .map(|tags| (tags, required))
+        }
+    }
+
+    /// NOTE(alex): `q` is matched with `like`, so its own `%` and `_` must not act as wildcards.
+    fn escaped_q(&self) -> Option<String> {
+        self.q.as_ref().map(|q| {
+            q.replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        })
+    }
+}
+
+impl QueryOccurrences {
+    fn validate(self) -> Result<Self, TaskError> {
+        match self.count {
+            Some(count) if count == 0 || count > MAX_OCCURRENCES => Err(TaskError::OccurrenceCount),
+            _ => Ok(self),
+        }
+    }
+}
+
+impl QueryDue {
+    fn validate(self) -> Result<Self, TaskError> {
+        if self.days == 0 || self.days > MAX_DUE_DAYS {
+            Err(TaskError::DueDays)
         } else {
             Ok(self)
         }
     }
 }
 
+impl SearchTask {
+    fn validate(self) -> Result<Self, TaskError> {
+        match self.limit {
+            Some(limit) if limit == 0 || limit > MAX_PAGE_LIMIT => Err(TaskError::PageLimit),
+            _ if self.fts_query().is_none() => Err(TaskError::EmptySearch),
+            _ => Ok(self),
+        }
+    }
+
+    /// Turns `q` into an FTS5 query where every word (or phrase) is quoted, so nothing the user
+    /// types can be taken as FTS5 syntax (`AND`, `NEAR`, `column:`, ...), which would either
+    /// surprise them, or fail the query. A `*` right after a word (or phrase) is kept as a prefix
+    /// match.
+    fn fts_query(&self) -> Option<String> {
+        let mut terms = Vec::new();
+
+        for (index, part) in self.q.split('"').enumerate() {
+            // NOTE(alex): Odd parts were inside quotes, so they're kept together as a phrase.
+            let words = if index % 2 == 1 {
+                vec![part.trim()]
+            } else {
+                part.split_whitespace().collect()
+            };
+
+            for word in words {
+                let (word, prefix) = match word.strip_suffix('*') {
+                    Some(word) => (word, "*"),
+                    None => (word, ""),
+                };
+                let word = word.trim_matches('*').trim();
+
+                // NOTE(alex): `word` can't have a `"` in it, we split on them.
+                if !word.is_empty() {
+                    terms.push(format!("\"{}\"{}", word, prefix));
+                }
+            }
+        }
+
+        (!terms.is_empty()).then(|| terms.join(" "))
+    }
+}
+
 impl Task {
-    pub async fn delete(db_pool: &SqlitePool, task_id: i64) -> Result<u64, AppError> {
-        let mut connection = db_pool.acquire().await?;
+    /// The strong `ETag` of this version of the task.
+    pub fn etag(&self) -> EntityTag {
+        version_etag(self.version)
+    }
+
+    /// NOTE(alex): Each representation is a different body, so it gets its own (strong) tag, a
+    /// cached CSV is not a match for a JSON request.
+    pub fn representation_etag(&self, representation: Representation) -> EntityTag {
+        match representation.etag_suffix() {
+            Some(suffix) => EntityTag::new_strong(format!("{}-{}", self.version, suffix)),
+            None => self.etag(),
+        }
+    }
+
+    /// Moves the task to the trash, it's only gone for good after the purge (see `trash::purge`).
+    pub async fn delete(
+        db_pool: &SqlitePool,
+        task_id: i64,
+        owner_id: i64,
+        precondition: &Precondition,
+    ) -> Result<u64, AppError> {
+        let mut transaction = db_pool.begin().await?;
+        let num_modified =
+            Self::delete_in(&mut transaction, task_id, owner_id, precondition).await?;
+        transaction.commit().await?;
+
+        Ok(num_modified)
+    }
+
+    pub(crate) async fn delete_in(
+        transaction: &mut Transaction<'_, Sqlite>,
+        task_id: i64,
+        owner_id: i64,
+        precondition: &Precondition,
+    ) -> Result<u64, AppError> {
+        let task = match Self::find_by_id_in(transaction, task_id, owner_id).await? {
+            Some(task) => task,
+            None => return Ok(0),
+        };
+        let expected_version = precondition.expected_version(&task)?;
+
         let result = sqlx::query(DELETE)
             .bind(task_id)
-            .execute(&mut connection)
+            .bind(owner_id)
+            .bind(timestamps::now())
+            .bind(expected_version)
+            .execute(&mut *transaction)
+            .await?;
+
+        // NOTE(alex): Same as in `UpdateTask::update`, it changed after we read it.
+        if result.rows_affected() == 0 {
+            return Err(TaskError::PreconditionFailed(task_id).into());
+        }
+
+        InsertTaskEvent {
+            task_id,
+            user_id: owner_id,
+            kind: TaskEventKind::Deleted,
+            changes: None,
+        }
+        .insert(&mut *transaction)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Takes the task back out of the trash, `0` if it wasn't there.
+    pub async fn restore(
+        db_pool: &SqlitePool,
+        task_id: i64,
+        owner_id: i64,
+    ) -> Result<u64, AppError> {
+        let mut transaction = db_pool.begin().await?;
+        let result = sqlx::query(RESTORE)
+            .bind(task_id)
+            .bind(owner_id)
+            .execute(&mut transaction)
             .await?;
 
+        if result.rows_affected() > 0 {
+            InsertTaskEvent {
+                task_id,
+                user_id: owner_id,
+                kind: TaskEventKind::Restored,
+                changes: None,
+            }
+            .insert(&mut transaction)
+            .await?;
+        }
+
+        transaction.commit().await?;
+
         Ok(result.rows_affected())
     }
 
-    pub async fn done(pool: &SqlitePool, task_id: i64) -> Result<i64, AppError> {
-        let mut connection = pool.acquire().await?;
+    /// Returns `None` if `owner_id` has no such task, and `TaskError::AlreadyDone` if it's done
+    /// (`undo` it first).
+    ///
+    /// NOTE(alex): A task with unchecked checklist items is only done when `force`d. Completing a
+    /// task that repeats creates its next occurrence, in the same transaction.
+    pub async fn done(
+        pool: &SqlitePool,
+        task_id: i64,
+        owner_id: i64,
+        force: bool,
+    ) -> Result<Option<Completed>, AppError> {
+        let mut transaction = pool.begin().await?;
+        let completed = Self::done_in(&mut transaction, task_id, owner_id, force).await?;
+        transaction.commit().await?;
+
+        Ok(completed)
+    }
+
+    pub(crate) async fn done_in(
+        transaction: &mut Transaction<'_, Sqlite>,
+        task_id: i64,
+        owner_id: i64,
+        force: bool,
+    ) -> Result<Option<Completed>, AppError> {
+        let task = match Self::find_by_id_in(transaction, task_id, owner_id).await? {
+            Some(task) => task,
+            None => return Ok(None),
+        };
+
+        if task.completed_at.is_some() {
+            return Err(TaskError::AlreadyDone(task_id).into());
+        }
+
+        if !force {
+            let Progress { done, total } = *task.progress;
+
+            if done < total {
+                return Err(TaskError::UnfinishedChecklist { done, total }.into());
+            }
+        }
+
         let result = sqlx::query(DONE)
             .bind(task_id)
+            .bind(owner_id)
+            .bind(timestamps::now())
+            .execute(&mut *transaction)
+            .await?;
+
+        // NOTE(alex): We found it ongoing, so someone else got to it between our read, and the
+        // insert.
+        if result.rows_affected() == 0 {
+            return Err(TaskError::AlreadyDone(task_id).into());
+        }
+        let done_id = result.last_insert_rowid();
+
+        InsertTaskEvent {
+            task_id,
+            user_id: owner_id,
+            kind: TaskEventKind::Done,
+            changes: None,
+        }
+        .insert(&mut *transaction)
+        .await?;
+
+        let next_task_id = task.insert_next_occurrence(transaction).await?;
+
+        Ok(Some(Completed {
+            done_id,
+            next_task_id,
+        }))
+    }
+
+    /// The due dates after this task's, `None` if it doesn't repeat.
+    pub fn next_occurrences(&self, count: usize) -> Result<Option<Vec<Occurrence>>, TaskError> {
+        match (&self.recurrence, self.due_at) {
+            (Some(recurrence), Some(due_at)) => {
+                let recurrence = recurrence.parse::<Recurrence>()?;
+                let occurrences = OffsetDateTime::from_unix_timestamp(due_at)
+                    .map(|due_at| recurrence.occurrences(due_at, count))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|due_at| Occurrence {
+                        due_at: due_at.unix_timestamp(),
+                    })
+                    .collect();
+
+                Ok(Some(occurrences))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// NOTE(alex): The next occurrence is the first one after now (that follows the rule), a chore
+    /// done three weeks late doesn't leave three overdue copies of itself behind. Its tags, and its
+    /// checklist (unchecked), come along.
+    ///
+    /// A task only ever spawns one occurrence, done, `undo`, done again leaves the one from the first
+    /// time as it is, and returns `None`.
+    async fn insert_next_occurrence(
+        &self,
+        transaction: &mut Transaction<'_, Sqlite>,
+    ) -> Result<Option<i64>, AppError> {
+        let (recurrence, due_at) = match (&self.recurrence, self.due_at) {
+            (Some(recurrence), Some(due_at)) => (recurrence.parse::<Recurrence>()?, due_at),
+            _ => return Ok(None),
+        };
+
+        let spawned: Option<(i64,)> = sqlx::query_as(FIND_OCCURRENCE)
+            .bind(self.id)
+            .fetch_optional(&mut *transaction)
+            .await?;
+        if spawned.is_some() {
+            return Ok(None);
+        }
+
+        let now = timestamps::now();
+        let mut next_due_at = OffsetDateTime::from_unix_timestamp(due_at)
+            .ok()
+            .and_then(|due_at| recurrence.next_after(due_at));
+        while let Some(due_at) = next_due_at.filter(|due_at| due_at.unix_timestamp() < now) {
+            next_due_at = recurrence.next_after(due_at);
+        }
+
+        let next_due_at = match next_due_at {
+            Some(next_due_at) => next_due_at.unix_timestamp(),
+            None => return Ok(None),
+        };
+
+        let result = sqlx::query(INSERT_OCCURRENCE)
+            .bind(&self.title)
+            .bind(&self.details)
+            .bind(self.owner_id)
+            .bind(now)
+            .bind(next_due_at)
+            .bind(self.priority)
+            .bind(&self.recurrence)
+            .bind(self.id)
+            .execute(&mut *transaction)
+            .await?;
+        let next_task_id = result.last_insert_rowid();
+
+        InsertTaskEvent {
+            task_id: next_task_id,
+            user_id: self.owner_id,
+            kind: TaskEventKind::Created,
+            changes: None,
+        }
+        .insert(&mut *transaction)
+        .await?;
+
+        sqlx::query(COPY_TAGS)
+            .bind(self.id)
+            .bind(next_task_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        sqlx::query(COPY_CHECKLIST)
+            .bind(self.id)
+            .bind(next_task_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        Ok(Some(next_task_id))
+    }
+
+    pub async fn undo(db_pool: &SqlitePool, task_id: i64, owner_id: i64) -> Result<u64, AppError> {
+        let mut transaction = db_pool.begin().await?;
+        let num_modified = Self::undo_in(&mut transaction, task_id, owner_id).await?;
+        transaction.commit().await?;
+
+        Ok(num_modified)
+    }
+
+    pub(crate) async fn undo_in(
+        transaction: &mut Transaction<'_, Sqlite>,
+        task_id: i64,
+        owner_id: i64,
+    ) -> Result<u64, AppError> {
+        let result = sqlx::query(UNDO)
+            .bind(task_id)
+            .bind(owner_id)
+            .execute(&mut *transaction)
+            .await?;
+
+        if result.rows_affected() > 0 {
+            InsertTaskEvent {
+                task_id,
+                user_id: owner_id,
+                kind: TaskEventKind::Undone,
+                changes: None,
+            }
+            .insert(&mut *transaction)
+            .await?;
+        }
+
+        Ok(result.rows_affected())
+    }
+
+    /// Returns how many tags were added, `0` if it already had it.
+    pub async fn tag(
+        db_pool: &SqlitePool,
+        task_id: i64,
+        tag_name: &str,
+        owner_id: i64,
+    ) -> Result<u64, AppError> {
+        let mut connection = db_pool.acquire().await?;
+        let result = sqlx::query(TAG)
+            .bind(task_id)
+            .bind(tag_name)
+            .bind(owner_id)
             .execute(&mut connection)
             .await?;
 
-        Ok(result.last_insert_rowid())
+        Ok(result.rows_affected())
     }
 
-    pub async fn undo(db_pool: &SqlitePool, task_id: i64) -> Result<u64, AppError> {
+    pub async fn untag(
+        db_pool: &SqlitePool,
+        task_id: i64,
+        tag_name: &str,
+        owner_id: i64,
+    ) -> Result<u64, AppError> {
         let mut connection = db_pool.acquire().await?;
-        let result = sqlx::query(UNDO)
+        let result = sqlx::query(UNTAG)
             .bind(task_id)
+            .bind(tag_name)
+            .bind(owner_id)
             .execute(&mut connection)
-            .await;
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn find_page(
+        db_pool: &SqlitePool,
+        query: &TaskQuery,
+        owner_id: i64,
+    ) -> Result<Page<Self>, AppError> {
+        let page_query = match query.sort {
+            TaskSort::Title => FIND_PAGE_BY_TITLE,
+            TaskSort::TitleDesc => FIND_PAGE_BY_TITLE_DESC,
+            TaskSort::CreatedAt => FIND_PAGE_BY_CREATED_AT,
+            TaskSort::CreatedAtDesc => FIND_PAGE_BY_CREATED_AT_DESC,
+            TaskSort::Priority => FIND_PAGE_BY_PRIORITY,
+            TaskSort::PriorityDesc => FIND_PAGE_BY_PRIORITY_DESC,
+        };
+        let status = query.status.map(|status| match status {
+            TaskStatus::Ongoing => "ongoing",
+            TaskStatus::Done => "done",
+        });
+        let q = query.escaped_q();
+        let (tags, required_tags) = query.tags_filter().unzip();
+        let required_tags = required_tags.map(|required_tags| required_tags as i64);
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+
+        if let Some(after_id) = query.after_id {
+            let (found,): (bool,) = sqlx::query_as(FIND_CURSOR)
+                .bind(after_id)
+                .bind(owner_id)
+                .fetch_one(db_pool)
+                .await?;
+
+            if !found {
+                return Err(TaskError::UnknownCursor(after_id).into());
+            }
+        }
 
-        Ok(result?.rows_affected())
+        // NOTE(alex): Asks for one extra task, if it comes back there is a next page.
+        let mut tasks: Vec<Self> = sqlx::query_as(page_query)
+            .bind(owner_id)
+            .bind(status)
+            .bind(&q)
+            .bind(&tags)
+            .bind(required_tags)
+            .bind(query.after_id)
+            .bind(limit + 1)
+            .fetch_all(db_pool)
+            .await?;
+
+        let next_cursor = if tasks.len() > limit as usize {
+            tasks.truncate(limit as usize);
+            tasks.last().map(|task| task.id)
+        } else {
+            None
+        };
+
+        let (total,): (i64,) = sqlx::query_as(COUNT)
+            .bind(owner_id)
+            .bind(status)
+            .bind(&q)
+            .bind(&tags)
+            .bind(required_tags)
+            .fetch_one(db_pool)
+            .await?;
+
+        Ok(Page {
+            items: tasks,
+            next_cursor,
+            total,
+        })
     }
 
-    pub async fn find_all(db_pool: &SqlitePool) -> Result<Vec<Self>, AppError> {
-        let result = sqlx::query_as(FIND_ALL).fetch_all(db_pool).await?;
+    pub async fn find_ongoing(db_pool: &SqlitePool, owner_id: i64) -> Result<Vec<Self>, AppError> {
+        let result = sqlx::query_as(FIND_ONGOING)
+            .bind(owner_id)
+            .fetch_all(db_pool)
+            .await?;
+
         Ok(result)
     }
 
-    pub async fn find_ongoing(db_pool: &SqlitePool) -> Result<Vec<Self>, AppError> {
-        let result = sqlx::query_as(FIND_ONGOING).fetch_all(db_pool).await?;
+    /// Ongoing tasks that should've been done already, the most late first.
+    pub async fn find_overdue(db_pool: &SqlitePool, owner_id: i64) -> Result<Vec<Self>, AppError> {
+        let result = sqlx::query_as(FIND_OVERDUE)
+            .bind(owner_id)
+            .bind(timestamps::now())
+            .fetch_all(db_pool)
+            .await?;
+
         Ok(result)
     }
 
-    pub async fn find_by_pattern(
+    /// Ongoing tasks due from now until `days` from now, the closest first.
+    pub async fn find_due_within(
         db_pool: &SqlitePool,
-        search_pattern: &str,
+        days: u32,
+        owner_id: i64,
     ) -> Result<Vec<Self>, AppError> {
-        let result = sqlx::query_as(FIND_BY_PATTERN)
-            .bind(search_pattern)
+        let now = timestamps::now();
+
+        let result = sqlx::query_as(FIND_DUE_WITHIN)
+            .bind(owner_id)
+            .bind(now)
+            .bind(now + i64::from(days) * 24 * 60 * 60)
             .fetch_all(db_pool)
             .await?;
 
         Ok(result)
     }
 
-    pub async fn find_by_id(db_pool: &SqlitePool, task_id: i64) -> Result<Option<Self>, AppError> {
+    /// Best matches first, see `SearchTask` for what `q` may contain.
+    pub async fn search(
+        db_pool: &SqlitePool,
+        search: &SearchTask,
+        owner_id: i64,
+    ) -> Result<Vec<TaskSearchResult>, AppError> {
+        let fts_query = search.fts_query().ok_or(TaskError::EmptySearch)?;
+
+        let result = sqlx::query_as(SEARCH)
+            .bind(owner_id)
+            .bind(fts_query)
+            .bind(search.limit.unwrap_or(DEFAULT_PAGE_LIMIT))
+            .fetch_all(db_pool)
+            .await?;
+
+        Ok(result)
+    }
+
+    pub async fn find_by_id(
+        db_pool: &SqlitePool,
+        task_id: i64,
+        owner_id: i64,
+    ) -> Result<Option<Self>, AppError> {
         let result = sqlx::query_as(FIND_BY_ID)
             .bind(task_id)
+            .bind(owner_id)
             .fetch_optional(db_pool)
             .await?;
 
         Ok(result)
     }
+
+    pub(crate) async fn find_by_id_in(
+        transaction: &mut Transaction<'_, Sqlite>,
+        task_id: i64,
+        owner_id: i64,
+    ) -> Result<Option<Self>, AppError> {
+        let result = sqlx::query_as(FIND_BY_ID)
+            .bind(task_id)
+            .bind(owner_id)
+            .fetch_optional(&mut *transaction)
+            .await?;
+
+        Ok(result)
+    }
+}
+
+/// NOTE(alex): Also used for the `ETag` of a `PUT` response, where we only know the new version.
+pub fn version_etag(version: i64) -> EntityTag {
+    EntityTag::new_strong(version.to_string())
+}
+
+impl Tabular for Task {
+    const NAME: &'static str = "Tasks";
+
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "title",
+        "details",
+        "priority",
+        "due_at",
+        "completed_at",
+        "recurrence",
+        "tags",
+        "progress",
+        "created_at",
+        "updated_at",
+        "version",
+    ];
+}
+
+impl Tabular for TaskSearchResult {
+    const NAME: &'static str = "Search";
+
+    const COLUMNS: &'static [&'static str] = &[
+        "task.id",
+        "task.title",
+        "task.details",
+        "task.priority",
+        "task.due_at",
+        "task.completed_at",
+        "task.tags",
+        "title_highlight",
+        "details_snippet",
+        "rank",
+    ];
 }
 
 impl Responder for Task {
     type Body = BoxBody;
 
-    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
-        let response = match serde_json::to_string(&self) {
-            Ok(body) => {
-                // Create response and set content type
-                HttpResponse::Ok()
-                    .content_type("application/json")
-                    .body(body)
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        Negotiated::one(self).respond_to(req)
+    }
+}
+
+impl<T: Tabular> From<Page<T>> for Negotiated<T> {
+    fn from(page: Page<T>) -> Self {
+        Negotiated::page(page.items, page.total, page.next_cursor)
+    }
+}
+
+impl<T: Tabular> Responder for Page<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        Negotiated::from(self).respond_to(req)
+    }
+}
+
+impl PreconditionConfig {
+    pub fn from_req(req: &HttpRequest) -> Self {
+        req.app_data::<web::Data<PreconditionConfig>>()
+            .map(|config| *config.get_ref())
+            .unwrap_or_default()
+    }
+}
+
+impl FromRequest for PreconditionConfig {
+    type Error = AppError;
+
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(Self::from_req(req)))
+    }
+}
+
+impl FromRequest for Precondition {
+    type Error = AppError;
+
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let required = PreconditionConfig::from_req(req).required;
+
+        let precondition = if !req.headers().contains_key(header::IF_MATCH) {
+            if required {
+                Err(TaskError::PreconditionRequired.into())
+            } else {
+                Ok(Precondition::Unconditional)
+            }
+        } else {
+            match IfMatch::parse(req) {
+                Ok(IfMatch::Any) => Ok(Precondition::Any),
+                Ok(IfMatch::Items(etags)) => Ok(Precondition::ETags(etags)),
+                Err(fail) => Err(AppError::from(actix_web::Error::from(fail))),
             }
-            Err(fail) => HttpResponse::from_error(AppError::from(fail)),
         };
 
-        response
+        ready(precondition)
+    }
+}
+
+impl Validate for InsertTask {
+    fn check(&self, config: &ValidationConfig, errors: &mut FieldErrors) {
+        check_task(&self.non_empty_title, &self.details, config, errors);
+
+        if self
+            .due_at
+            .filter(|due_at| *due_at < timestamps::now())
+            .is_some()
+        {
+            errors.push(TaskError::DueInPast);
+        }
+
+        if let Err(fail) = validate_recurrence(self.recurrence.as_deref(), self.due_at) {
+            errors.push(fail);
+        }
+    }
+
+    fn normalize(self) -> Self {
+        Self {
+            recurrence: normalize_recurrence(self.recurrence),
+            ..self
+        }
+    }
+}
+
+/// NOTE(alex): The schedule depends on what is stored (a `recurrence` needs a `due_at`, maybe the
+/// one the task already has), here it's only checked that the rule parses, `UpdateTask::update`
+/// does the rest.
+impl Validate for UpdateTask {
+    fn check(&self, config: &ValidationConfig, errors: &mut FieldErrors) {
+        check_task(&self.new_title, &self.details, config, errors);
+
+        if let Some(Some(recurrence)) = &self.recurrence {
+            if let Err(fail) = recurrence.parse::<Recurrence>() {
+                errors.push(fail);
+            }
+        }
+    }
+
+    fn normalize(self) -> Self {
+        Self {
+            recurrence: self.recurrence.map(normalize_recurrence),
+            ..self
+        }
+    }
+}
+
+/// NOTE(alex): The text is whatever the user wrote, `<script>` included, so it's escaped before the
+/// markers `search.sql` put around the matches become `<mark>` tags.
+fn marked_html(marked: String) -> String {
+    escape_html(&marked)
+        .replace('\u{2}', "<mark>")
+        .replace('\u{3}', "</mark>")
+}
+
+/// NOTE(alex): `sqlx` 0.5 can't `flatten` a `FromRow`, so the `Task` part is done by hand.
+impl<'r> FromRow<'r, SqliteRow> for TaskSearchResult {
+    fn from_row(row: &'r SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            task: Task::from_row(row)?,
+            title_highlight: marked_html(row.try_get("title_highlight")?),
+            details_snippet: marked_html(row.try_get("details_snippet")?),
+            rank: row.try_get("rank")?,
+        })
     }
 }
 
-impl FromRequest for InsertTask {
+impl FromRequest for TaskQuery {
     type Error = AppError;
 
-    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+    type Future = Ready<Result<Self, Self::Error>>;
 
-    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-        JsonBody::new(req, payload, None, false)
-            .limit(4056)
-            .map(|res: Result<InsertTask, _>| match res {
-                Ok(insert_task) => insert_task.validate().map_err(|fail| AppError::from(fail)),
-                Err(fail) => Err(AppError::from(fail)),
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let (tags, rest): (Vec<_>, Vec<_>) = req
+            .query_string()
+            .split('&')
+            .partition(|pair| pair.starts_with("tag="));
+
+        let task_query = Query::<Vec<(String, String)>>::from_query(&tags.join("&"))
+            .and_then(|tags| {
+                let mut task_query = Query::<TaskQuery>::from_query(&rest.join("&"))?.into_inner();
+                task_query.tags = tags.into_inner().into_iter().map(|(_, tag)| tag).collect();
+
+                Ok(task_query)
             })
-            .boxed_local()
+            .map_err(|fail| AppError::from(fail))
+            .and_then(|task_query| task_query.validate().map_err(|fail| AppError::from(fail)));
+
+        ready(task_query)
     }
 }
 
-impl FromRequest for UpdateTask {
+impl FromRequest for SearchTask {
     type Error = AppError;
 
-    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+    type Future = Ready<Result<Self, Self::Error>>;
 
-    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-        JsonBody::new(req, payload, None, false)
-            .limit(4056)
-            .map(|res: Result<UpdateTask, _>| match res {
-                Ok(update_task) => update_task.validate().map_err(|fail| AppError::from(fail)),
-                Err(fail) => Err(AppError::from(fail)),
-            })
-            .boxed_local()
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let search_task = match Query::<SearchTask>::from_query(req.query_string()) {
+            Ok(search_task) => search_task
+                .into_inner()
+                .validate()
+                .map_err(|fail| AppError::from(fail)),
+            Err(fail) => Err(AppError::from(fail)),
+        };
+
+        ready(search_task)
+    }
+}
+
+impl FromRequest for DoneQuery {
+    type Error = AppError;
+
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let done_query = Query::<DoneQuery>::from_query(req.query_string())
+            .map(|done_query| done_query.into_inner())
+            .map_err(|fail| AppError::from(fail));
+
+        ready(done_query)
+    }
+}
+
+impl FromRequest for QueryDue {
+    type Error = AppError;
+
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let query_due = match Query::<QueryDue>::from_query(req.query_string()) {
+            Ok(query_due) => query_due
+                .into_inner()
+                .validate()
+                .map_err(|fail| AppError::from(fail)),
+            Err(fail) => Err(AppError::from(fail)),
+        };
+
+        ready(query_due)
+    }
+}
+
+impl FromRequest for QueryOccurrences {
+    type Error = AppError;
+
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let query_occurrences = match Query::<QueryOccurrences>::from_query(req.query_string()) {
+            Ok(query_occurrences) => query_occurrences
+                .into_inner()
+                .validate()
+                .map_err(|fail| AppError::from(fail)),
+            Err(fail) => Err(AppError::from(fail)),
+        };
+
+        ready(query_occurrences)
     }
 }
@@ -0,0 +1,5 @@
+-- NOTE(alex): Copies come unchecked, it's a new occurrence.
+insert into ChecklistItem (task_id, title, position)
+select $2, ChecklistItem.title, ChecklistItem.position
+from ChecklistItem
+where ChecklistItem.task_id = $1
\ No newline at end of file
@@ -0,0 +1,4 @@
+insert into TaskTag (task_id, tag_id)
+select $2, TaskTag.tag_id
+from TaskTag
+where TaskTag.task_id = $1
\ No newline at end of file
@@ -1,2 +1,9 @@
-delete from Task
-where Task.id = $1
\ No newline at end of file
+-- NOTE(alex): Only moves it to the trash, see `restore.sql`. `$4` works like in `update.sql`.
+update Task
+set deleted_at = $3,
+    version = version + 1
+where
+    Task.id = $1
+    and Task.owner_id = $2
+    and Task.deleted_at is null
+    and ($4 is null or Task.version = $4)
\ No newline at end of file
@@ -1 +0,0 @@
-select * from Task;
\ No newline at end of file
@@ -1,4 +0,0 @@
-select *
-from Task
-where
-    Task.title like $1;
\ No newline at end of file
@@ -0,0 +1,6 @@
+-- NOTE(alex): Trashed tasks are still good cursors, the page queries read them from `Task` too.
+select exists (
+        select 1
+        from Task
+        where Task.id = $1 and Task.owner_id = $2
+    )
@@ -0,0 +1,3 @@
+select Task.id
+from Task
+where Task.spawned_from = $1
@@ -0,0 +1,39 @@
+-- NOTE(alex): `$6` is the cursor (`after_id`), the page starts right after that task.
+select *
+from TaskView as Task
+where
+    Task.owner_id = $1
+    -- NOTE(alex): `status` is either `null`, `'ongoing'`, or `'done'`.
+    and (
+        $2 is null
+        or ($2 = 'done') = (Task.id in (select task_id from Done))
+    )
+    -- NOTE(alex): `q` comes in with its `%` and `_` already escaped.
+    and (
+        $3 is null
+        or Task.title like '%' || $3 || '%' escape '\'
+        or Task.details like '%' || $3 || '%' escape '\'
+    )
+    -- NOTE(alex): `$4` is a JSON array of tag names (or `null`), and `$5` how many of them a task
+    -- must have, `1` for any of them.
+    and (
+        $4 is null
+        or (
+            select count(*)
+            from TaskTag
+                join Tag on Tag.id = TaskTag.tag_id
+            where
+                TaskTag.task_id = Task.id
+                and Tag.name in (select value from json_each($4))
+        ) >= $5
+    )
+    and (
+        $6 is null
+        or (Task.created_at, Task.id) > (
+            select Cursor.created_at, Cursor.id
+            from Task as Cursor
+            where Cursor.id = $6 and Cursor.owner_id = $1
+        )
+    )
+order by Task.created_at asc, Task.id asc
+limit $7
\ No newline at end of file
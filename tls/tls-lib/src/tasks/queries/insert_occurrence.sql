@@ -0,0 +1,12 @@
+insert into Task (
+        title,
+        details,
+        owner_id,
+        created_at,
+        updated_at,
+        due_at,
+        priority,
+        recurrence,
+        spawned_from
+    )
+values ($1, $2, $3, $4, $4, $5, $6, $7, $8);
@@ -0,0 +1,5 @@
+update Task
+set deleted_at = null,
+    version = version + 1
+where
+    Task.id = $1 and Task.owner_id = $2 and Task.deleted_at is not null
\ No newline at end of file
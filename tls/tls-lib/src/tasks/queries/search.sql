@@ -0,0 +1,14 @@
+-- NOTE(alex): `bm25` is lower for better matches, so ascending order puts the best ones first.
+--
+-- NOTE(alex): The matches are marked with `char(2)`, and `char(3)`, not `<mark>`, the text is
+-- HTML-escaped first (see `TaskSearchResult`), and only then are the markers turned into tags.
+select Task.*,
+    highlight(TaskSearch, 0, char(2), char(3)) as title_highlight,
+    snippet(TaskSearch, 1, char(2), char(3), '…', 16) as details_snippet,
+    bm25(TaskSearch) as rank
+from TaskSearch
+    join TaskView as Task on Task.id = TaskSearch.rowid
+where
+    TaskSearch match $2 and Task.owner_id = $1
+order by rank
+limit $3
@@ -0,0 +1,10 @@
+-- NOTE(alex): `or ignore`, tagging a task twice is not an error, it just doesn't do anything.
+insert or ignore into TaskTag (task_id, tag_id)
+select Task.id, Tag.id
+from Task, Tag
+where
+    Task.id = $1
+    and Task.owner_id = $3
+    and Task.deleted_at is null
+    and Tag.name = $2
+    and Tag.owner_id = $3
\ No newline at end of file
@@ -1,2 +1,8 @@
 delete from Done
-where task_id = $1
\ No newline at end of file
+where
+    task_id in (
+        select Task.id
+        from Task
+        where
+            Task.id = $1 and Task.owner_id = $2 and Task.deleted_at is null
+    )
\ No newline at end of file
@@ -0,0 +1,14 @@
+delete from TaskTag
+where
+    TaskTag.task_id in (
+        select Task.id
+        from Task
+        where
+            Task.id = $1 and Task.owner_id = $3 and Task.deleted_at is null
+    )
+    and TaskTag.tag_id in (
+        select Tag.id
+        from Tag
+        where
+            Tag.name = $2 and Tag.owner_id = $3
+    )
\ No newline at end of file
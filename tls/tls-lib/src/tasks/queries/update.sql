@@ -1,4 +1,15 @@
+-- NOTE(alex): `$9` is the `version` the request expects (`If-Match`), `null` for unconditional
+-- updates.
 update Task
 set title = $1,
-    details = $2
-where Task.id = $3
\ No newline at end of file
+    details = $2,
+    due_at = $3,
+    priority = $4,
+    recurrence = $5,
+    updated_at = $6,
+    version = version + 1
+where
+    Task.id = $7
+    and Task.owner_id = $8
+    and Task.deleted_at is null
+    and ($9 is null or Task.version = $9)
\ No newline at end of file
@@ -0,0 +1,216 @@
+use std::{fmt, str::FromStr};
+
+use time::{Date, OffsetDateTime, Weekday};
+
+use super::errors::TaskError;
+
+pub const MAX_INTERVAL: u32 = 99;
+
+/// NOTE(alex): Far enough to get past any `INTERVAL` of months (plus the months that don't have
+/// the right day in them), a rule that finds nothing in here never will.
+const MAX_SEARCH_DAYS: u32 = 40 * 366;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// The subset of RFC 5545 `RRULE`s we support: `FREQ` (`DAILY`, `WEEKLY`, or `MONTHLY`),
+/// `INTERVAL`, and `BYDAY` (plain weekdays, such as `MO,WE`, without the `1MO` / `-1FR` prefixes).
+/// For example `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,FR` is every other week, on mondays and fridays.
+///
+/// NOTE(alex): The rule is anchored on the task's `due_at` (the `DTSTART`), its time of day is kept,
+/// and everything is in UTC. Without `BYDAY`, the anchor decides the day: same weekday for
+/// `WEEKLY`, same day of the month for `MONTHLY` (months that don't have it, such as the 31st, are
+/// skipped, like RFC 5545 does). Weeks start on monday.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub by_day: Vec<Weekday>,
+}
+
+impl Recurrence {
+    /// The first occurrence after `anchor`, `None` if there is no such date.
+    pub fn next_after(&self, anchor: OffsetDateTime) -> Option<OffsetDateTime> {
+        let start = anchor.date();
+        let mut date = start;
+
+        for _ in 0..MAX_SEARCH_DAYS {
+            date = date.next_day()?;
+
+            if self.is_occurrence(start, date) {
+                return Some(anchor.replace_date(date));
+            }
+        }
+
+        None
+    }
+
+    /// Up to `count` occurrences after `anchor`, in order.
+    pub fn occurrences(&self, anchor: OffsetDateTime, count: usize) -> Vec<OffsetDateTime> {
+        let mut occurrences = Vec::with_capacity(count);
+        let mut current = anchor;
+
+        while occurrences.len() < count {
+            match self.next_after(current) {
+                Some(next) => {
+                    occurrences.push(next);
+                    current = next;
+                }
+                None => break,
+            }
+        }
+
+        occurrences
+    }
+
+    fn is_occurrence(&self, start: Date, date: Date) -> bool {
+        let interval = i64::from(self.interval);
+
+        let in_period = match self.frequency {
+            Frequency::Daily => {
+                (i64::from(date.to_julian_day()) - i64::from(start.to_julian_day())) % interval == 0
+            }
+            Frequency::Weekly => (week_start(date) - week_start(start)) / 7 % interval == 0,
+            Frequency::Monthly => (month_index(date) - month_index(start)) % interval == 0,
+        };
+
+        let on_day = if self.by_day.is_empty() {
+            match self.frequency {
+                Frequency::Daily => true,
+                Frequency::Weekly => date.weekday() == start.weekday(),
+                Frequency::Monthly => date.day() == start.day(),
+            }
+        } else {
+            self.by_day.contains(&date.weekday())
+        };
+
+        in_period && on_day
+    }
+}
+
+/// Julian day of the monday of `date`'s week.
+fn week_start(date: Date) -> i64 {
+    i64::from(date.to_julian_day()) - i64::from(date.weekday().number_days_from_monday())
+}
+
+fn month_index(date: Date) -> i64 {
+    i64::from(date.year()) * 12 + i64::from(u8::from(date.month()))
+}
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("MO", Weekday::Monday),
+    ("TU", Weekday::Tuesday),
+    ("WE", Weekday::Wednesday),
+    ("TH", Weekday::Thursday),
+    ("FR", Weekday::Friday),
+    ("SA", Weekday::Saturday),
+    ("SU", Weekday::Sunday),
+];
+
+fn invalid(reason: impl Into<String>) -> TaskError {
+    TaskError::InvalidRecurrence(reason.into())
+}
+
+impl FromStr for Recurrence {
+    type Err = TaskError;
+
+    /// Accepts the rule with, or without, the `RRULE:` prefix, in any case.
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let rule = rule.trim().to_uppercase();
+        let rule = rule.strip_prefix("RRULE:").unwrap_or(&rule);
+
+        let mut frequency = None;
+        let mut interval = None;
+        let mut by_day = None;
+
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| invalid(format!("`{}` is not a `KEY=VALUE` pair", part)))?;
+
+            match key {
+                "FREQ" if frequency.is_none() => {
+                    frequency = Some(match value {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        _ => return Err(invalid(format!("`FREQ={}` is not supported", value))),
+                    });
+                }
+                "INTERVAL" if interval.is_none() => {
+                    interval = Some(
+                        value
+                            .parse::<u32>()
+                            .ok()
+                            .filter(|interval| (1..=MAX_INTERVAL).contains(interval))
+                            .ok_or_else(|| {
+                                invalid(format!(
+                                    "`INTERVAL` must be between 1 and {}",
+                                    MAX_INTERVAL
+                                ))
+                            })?,
+                    );
+                }
+                "BYDAY" if by_day.is_none() => {
+                    let mut days = Vec::new();
+
+                    for day in value.split(',') {
+                        let (_, weekday) = WEEKDAYS
+                            .iter()
+                            .find(|(code, _)| *code == day)
+                            .ok_or_else(|| invalid(format!("`{}` is not a weekday", day)))?;
+
+                        if !days.contains(weekday) {
+                            days.push(*weekday);
+                        }
+                    }
+
+                    days.sort_by_key(|weekday| weekday.number_days_from_monday());
+                    by_day = Some(days);
+                }
+                "FREQ" | "INTERVAL" | "BYDAY" => {
+                    return Err(invalid(format!("`{}` appears more than once", key)))
+                }
+                _ => return Err(invalid(format!("`{}` is not supported", key))),
+            }
+        }
+
+        Ok(Self {
+            frequency: frequency.ok_or_else(|| invalid("`FREQ` is required"))?,
+            interval: interval.unwrap_or(1),
+            by_day: by_day.unwrap_or_default(),
+        })
+    }
+}
+
+/// The canonical form, it's what gets stored.
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+        };
+        write!(f, "FREQ={};INTERVAL={}", frequency, self.interval)?;
+
+        if !self.by_day.is_empty() {
+            let days = self
+                .by_day
+                .iter()
+                .filter_map(|weekday| {
+                    WEEKDAYS
+                        .iter()
+                        .find(|(_, day)| day == weekday)
+                        .map(|(code, _)| *code)
+                })
+                .collect::<Vec<_>>();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+
+        Ok(())
+    }
+}
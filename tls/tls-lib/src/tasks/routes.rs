@@ -1,114 +1,298 @@
 use actix_session::Session;
-use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+use actix_web::{
+    delete, get,
+    http::{
+        header::{self, Header, IfNoneMatch},
+        StatusCode,
+    },
+    post, put, web, HttpRequest, HttpResponse, Responder,
+};
 use actix_web_httpauth::middleware::HttpAuthentication;
 use sqlx::SqlitePool;
 
-use super::{errors::*, models::*};
-use crate::{errors::AppError, validator};
+use super::{
+    batch::{Batch, OperationResult},
+    errors::*,
+    models::*,
+};
+use crate::{
+    audit::models::{InsertTaskEvent, TaskEventKind},
+    errors::AppError,
+    negotiation::{Negotiated, Representation},
+    tags::models::Tag,
+    users::models::LoggedUser,
+    validation::{Validated, ValidationConfig},
+    validator,
+};
 
-#[post("/tasks", wrap = "HttpAuthentication::bearer(validator)")]
+#[post("/tasks", wrap = "HttpAuthentication::with_fn(validator)")]
 pub async fn insert(
     db_pool: web::Data<SqlitePool>,
-    input: InsertTask,
+    logged_user: LoggedUser,
+    Validated(input): Validated<InsertTask>,
 ) -> Result<impl Responder, AppError> {
-    let task = input.insert(db_pool.get_ref()).await?;
-    Ok(HttpResponse::Created().json(task))
+    let task = input.insert(db_pool.get_ref(), logged_user.id).await?;
+    let location = format!("/tasks/{}", task.id);
+
+    Ok(Negotiated::one(task)
+        .status(StatusCode::CREATED)
+        .header((header::LOCATION, location)))
 }
 
-#[put("/tasks", wrap = "HttpAuthentication::bearer(validator)")]
+/// NOTE(alex): `200` when every operation went through, `207` otherwise, either way the body has a
+/// result for each operation (see `Batch::run`).
+#[post("/tasks/batch", wrap = "HttpAuthentication::with_fn(validator)")]
+pub async fn batch(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    validation_config: ValidationConfig,
+    precondition_config: PreconditionConfig,
+    Validated(input): Validated<Batch>,
+) -> Result<impl Responder, AppError> {
+    let results = input
+        .run(
+            db_pool.get_ref(),
+            logged_user.id,
+            &validation_config,
+            &precondition_config,
+        )
+        .await?;
+
+    if results.iter().all(OperationResult::is_success) {
+        Ok(HttpResponse::Ok().json(&results))
+    } else {
+        Ok(HttpResponse::MultiStatus().json(&results))
+    }
+}
+
+#[put("/tasks", wrap = "HttpAuthentication::with_fn(validator)")]
 pub async fn update(
     db_pool: web::Data<SqlitePool>,
-    input: UpdateTask,
+    logged_user: LoggedUser,
+    precondition: Precondition,
+    Validated(input): Validated<UpdateTask>,
 ) -> Result<impl Responder, AppError> {
-    let num_modified = input.update(db_pool.get_ref()).await?;
+    let task_id = input.id;
+    let version = input
+        .update(db_pool.get_ref(), logged_user.id, &precondition)
+        .await?;
+
+    // NOTE(alex): A `Task` that belongs to someone else is reported as not found, we don't want to
+    // leak which ids exist.
+    match version {
+        Some(version) => Ok(HttpResponse::Ok()
+            .insert_header(header::ETag(version_etag(version)))
+            .body("Updated 1 tasks.")),
+        None => Err(TaskError::NotFound(task_id).into()),
+    }
+}
+
+#[delete("/tasks/{id}", wrap = "HttpAuthentication::with_fn(validator)")]
+pub async fn delete(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    precondition: Precondition,
+    id: web::Path<i64>,
+) -> Result<impl Responder, AppError> {
+    let num_modified = Task::delete(db_pool.get_ref(), *id, logged_user.id, &precondition).await?;
 
     if num_modified == 0 {
-        Ok(HttpResponse::NotModified().body("No tasks were updated."))
+        Err(TaskError::NotFound(*id).into())
     } else {
-        Ok(HttpResponse::Ok().body(format!("Updated {} tasks.", num_modified)))
+        Ok(HttpResponse::NoContent().finish())
     }
 }
 
-#[delete("/tasks/{id}", wrap = "HttpAuthentication::bearer(validator)")]
-pub async fn delete(
+#[post(
+    "/tasks/{id:\\d+}/restore",
+    wrap = "HttpAuthentication::with_fn(validator)"
+)]
+pub async fn restore(
     db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
     id: web::Path<i64>,
 ) -> Result<impl Responder, AppError> {
-    let num_modified = Task::delete(db_pool.get_ref(), *id).await?;
+    let num_modified = Task::restore(db_pool.get_ref(), *id, logged_user.id).await?;
 
     if num_modified == 0 {
-        Ok(HttpResponse::NotModified().body("No tasks were deleted."))
+        Err(TaskError::NotFound(*id).into())
     } else {
-        Ok(HttpResponse::Ok().body(format!("Deleted {} tasks.", num_modified)))
+        Ok(HttpResponse::Ok().body(format!("Restored {} tasks.", num_modified)))
     }
 }
 
-// TODO(alex): Cleanup error returned:
-// `error returned from database: FOREIGN KEY constraint failed`
-#[post("/tasks/{id}/done", wrap = "HttpAuthentication::bearer(validator)")]
+#[post("/tasks/{id}/done", wrap = "HttpAuthentication::with_fn(validator)")]
 pub async fn done(
     db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
     id: web::Path<i64>,
+    query: DoneQuery,
 ) -> Result<impl Responder, AppError> {
-    let done_id = Task::done(db_pool.get_ref(), *id).await?;
+    let completed = Task::done(db_pool.get_ref(), *id, logged_user.id, query.force).await?;
 
-    if done_id == 0 {
-        Ok(HttpResponse::NotModified().body(format!("Task with id {} not done.", id)))
-    } else {
-        Ok(HttpResponse::Created().body(done_id.to_string()))
+    // NOTE(alex): Only a task that repeats creates something, its next occurrence, which goes in
+    // the `Location` header. Otherwise the task is just done, there is nothing else to send.
+    match completed {
+        Some(Completed {
+            next_task_id: Some(next_task_id),
+            ..
+        }) => Ok(HttpResponse::Created()
+            .insert_header((header::LOCATION, format!("/tasks/{}", next_task_id)))
+            .finish()),
+        Some(Completed { .. }) => Ok(HttpResponse::NoContent().finish()),
+        None => Err(TaskError::NotFound(*id).into()),
+    }
+}
+
+#[get(
+    "/tasks/{id:\\d+}/occurrences",
+    wrap = "HttpAuthentication::with_fn(validator)"
+)]
+pub async fn find_occurrences(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    id: web::Path<i64>,
+    query: QueryOccurrences,
+) -> Result<impl Responder, AppError> {
+    let task = Task::find_by_id(db_pool.get_ref(), *id, logged_user.id)
+        .await?
+        .ok_or(TaskError::NotFound(*id))?;
+
+    let count = query.count.unwrap_or(DEFAULT_OCCURRENCES) as usize;
+    match task.next_occurrences(count)? {
+        Some(occurrences) => Ok(HttpResponse::Ok().json(&occurrences)),
+        None => Err(TaskError::NotRecurring(*id).into()),
     }
 }
 
 // TODO(alex): Cleanup error:
 // Error: Stream error in the HTTP/2 framing layer
-#[delete("/tasks/{id}/undo", wrap = "HttpAuthentication::bearer(validator)")]
+#[delete("/tasks/{id}/undo", wrap = "HttpAuthentication::with_fn(validator)")]
 pub async fn undo(
     db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
     id: web::Path<i64>,
 ) -> Result<impl Responder, AppError> {
-    let num_modified = Task::undo(db_pool.get_ref(), *id).await?;
-
-    if num_modified == 0 {
-        Ok(HttpResponse::NotModified().body(format!("Task with id {} not undone.", id)))
-    } else {
-        Ok(HttpResponse::Ok().body(format!("Undone {} tasks.", num_modified)))
+    if Task::find_by_id(db_pool.get_ref(), *id, logged_user.id)
+        .await?
+        .is_none()
+    {
+        return Err(TaskError::NotFound(*id).into());
     }
+
+    // NOTE(alex): Undoing a task that isn't done leaves it the way the client wants it, so it's
+    // the same `204` either way.
+    Task::undo(db_pool.get_ref(), *id, logged_user.id).await?;
+    Ok(HttpResponse::NoContent().finish())
 }
 
-#[get("/tasks")]
-pub async fn find_all(db_pool: web::Data<SqlitePool>) -> Result<impl Responder, AppError> {
-    let tasks = Task::find_all(db_pool.get_ref()).await?;
+#[post(
+    "/tasks/{id:\\d+}/tags/{tag}",
+    wrap = "HttpAuthentication::with_fn(validator)"
+)]
+pub async fn tag(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    path: web::Path<(i64, String)>,
+) -> Result<impl Responder, AppError> {
+    let (id, tag_name) = path.into_inner();
 
-    if tasks.is_empty() {
-        Err(TaskError::Empty.into())
-    } else {
-        Ok(HttpResponse::Found().json(&tasks))
+    if Task::find_by_id(db_pool.get_ref(), id, logged_user.id)
+        .await?
+        .is_none()
+    {
+        return Err(TaskError::NotFound(id).into());
     }
+
+    if Tag::find_by_name(db_pool.get_ref(), &tag_name, logged_user.id)
+        .await?
+        .is_none()
+    {
+        return Err(TaskError::UnknownTag(tag_name).into());
+    }
+
+    // NOTE(alex): A task that already has the tag is the way the client wants it, so it's the same
+    // `204` either way (like `untag`).
+    Task::tag(db_pool.get_ref(), id, &tag_name, logged_user.id).await?;
+    Ok(HttpResponse::NoContent().finish())
 }
 
-#[get("/tasks/ongoing")]
-pub async fn find_ongoing(db_pool: web::Data<SqlitePool>) -> Result<impl Responder, AppError> {
-    let tasks = Task::find_ongoing(db_pool.get_ref()).await?;
+#[delete(
+    "/tasks/{id:\\d+}/tags/{tag}",
+    wrap = "HttpAuthentication::with_fn(validator)"
+)]
+pub async fn untag(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    path: web::Path<(i64, String)>,
+) -> Result<impl Responder, AppError> {
+    let (id, tag_name) = path.into_inner();
 
-    if tasks.is_empty() {
-        Err(TaskError::Empty.into())
-    } else {
-        Ok(HttpResponse::Found().json(&tasks))
+    if Task::find_by_id(db_pool.get_ref(), id, logged_user.id)
+        .await?
+        .is_none()
+    {
+        return Err(TaskError::NotFound(id).into());
+    }
+
+    if Tag::find_by_name(db_pool.get_ref(), &tag_name, logged_user.id)
+        .await?
+        .is_none()
+    {
+        return Err(TaskError::UnknownTag(tag_name).into());
     }
+
+    Task::untag(db_pool.get_ref(), id, &tag_name, logged_user.id).await?;
+    Ok(HttpResponse::NoContent().finish())
 }
 
-#[get("/tasks")]
-pub async fn find_by_pattern(
+/// NOTE(alex): Lists, searches, and filters tasks, one page at a time (see `TaskQuery`).
+#[get("/tasks", wrap = "HttpAuthentication::with_fn(validator)")]
+pub async fn find_all(
     db_pool: web::Data<SqlitePool>,
-    pattern: web::Query<QueryTask>,
+    logged_user: LoggedUser,
+    query: TaskQuery,
 ) -> Result<impl Responder, AppError> {
-    let tasks = Task::find_by_pattern(db_pool.get_ref(), &format!("%{}%", pattern.title)).await?;
+    let page = Task::find_page(db_pool.get_ref(), &query, logged_user.id).await?;
+    Ok(Negotiated::from(page))
+}
 
-    if tasks.is_empty() {
-        Err(TaskError::Empty.into())
-    } else {
-        Ok(HttpResponse::Found().json(&tasks))
-    }
+#[get("/tasks/overdue", wrap = "HttpAuthentication::with_fn(validator)")]
+pub async fn find_overdue(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+) -> Result<impl Responder, AppError> {
+    let tasks = Task::find_overdue(db_pool.get_ref(), logged_user.id).await?;
+    Ok(Negotiated::many(tasks))
+}
+
+#[get("/tasks/due", wrap = "HttpAuthentication::with_fn(validator)")]
+pub async fn find_due(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    query: QueryDue,
+) -> Result<impl Responder, AppError> {
+    let tasks = Task::find_due_within(db_pool.get_ref(), query.days, logged_user.id).await?;
+    Ok(Negotiated::many(tasks))
+}
+
+#[get("/tasks/search", wrap = "HttpAuthentication::with_fn(validator)")]
+pub async fn search(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    search: SearchTask,
+) -> Result<impl Responder, AppError> {
+    let results = Task::search(db_pool.get_ref(), &search, logged_user.id).await?;
+    Ok(Negotiated::many(results))
+}
+
+#[get("/tasks/ongoing", wrap = "HttpAuthentication::with_fn(validator)")]
+pub async fn find_ongoing(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+) -> Result<impl Responder, AppError> {
+    let tasks = Task::find_ongoing(db_pool.get_ref(), logged_user.id).await?;
+    Ok(Negotiated::many(tasks))
 }
 
 /// NOTE(alex): Regex to match only digits, otherwise it matches the "/tasks/favorite" find route.
@@ -120,24 +304,52 @@ pub async fn find_by_pattern(
 // is the equivalent of the `[^/]+` regex.
 ///
 /// There is a 3rd way of sorts, which boils down to: avoid possible route conflicting paths.
-#[get("/tasks/{id:\\d+}")]
+#[get("/tasks/{id:\\d+}", wrap = "HttpAuthentication::with_fn(validator)")]
 pub async fn find_by_id(
+    req: HttpRequest,
     db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
     id: web::Path<i64>,
 ) -> Result<impl Responder, AppError> {
-    let task = Task::find_by_id(db_pool.get_ref(), *id).await?;
+    let task = Task::find_by_id(db_pool.get_ref(), *id, logged_user.id)
+        .await?
+        .ok_or(TaskError::NotFound(*id))?;
 
-    match task {
-        Some(task) => Ok(HttpResponse::Found().json(task)),
-        None => Err(TaskError::NotFound(*id).into()),
+    // NOTE(alex): Negotiated before `If-None-Match`, an `Accept` we can't serve is a `406`, not a
+    // `304` for some other representation.
+    let representation = Representation::from_req(&req)?;
+    let etag = task.representation_etag(representation);
+
+    // NOTE(alex): Unlike `If-Match`, `If-None-Match` uses the weak comparison. A header that
+    // doesn't parse is ignored, the client just gets the task.
+    let not_modified = match IfNoneMatch::parse(&req) {
+        Ok(IfNoneMatch::Any) => true,
+        Ok(IfNoneMatch::Items(etags)) => etags.iter().any(|other| other.weak_eq(&etag)),
+        Err(_) => false,
+    };
+
+    if not_modified {
+        Ok(HttpResponse::NotModified()
+            .insert_header(header::ETag(etag))
+            .insert_header((header::VARY, "Accept"))
+            .finish())
+    } else {
+        Ok(Negotiated::one(task)
+            .header(header::ETag(etag))
+            .respond_to(&req))
     }
 }
 
 const FAVORITE_TASK_STR: &'static str = "favorite_task";
 
-#[post("/tasks/favorite/{id}")]
+#[post(
+    "/tasks/favorite/{id}",
+    wrap = "HttpAuthentication::with_fn(validator)"
+)]
 pub async fn favorite(
+    req: HttpRequest,
     db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
     session: Session,
     id: web::Path<i64>,
 ) -> Result<impl Responder, AppError> {
@@ -146,31 +358,53 @@ pub async fn favorite(
 
         if old_favorite.id == *id {
             // NOTE(alex): Just remove the task, this is basically "unfavorite".
-            Ok(HttpResponse::NoContent().body(format!("Task {} unfavorited", old_favorite.id)))
+            record_favorite(&db_pool, *id, logged_user.id, TaskEventKind::Unfavorited).await?;
+            Ok(HttpResponse::NoContent().finish())
         } else {
-            match Task::find_by_id(&db_pool, *id).await? {
+            match Task::find_by_id(&db_pool, *id, logged_user.id).await? {
                 Some(task) => {
                     session.insert(FAVORITE_TASK_STR, task.clone())?;
-                    Ok(HttpResponse::Found().json(task))
+                    record_favorite(&db_pool, *id, logged_user.id, TaskEventKind::Favorited)
+                        .await?;
+                    Ok(Negotiated::one(task).respond_to(&req))
                 }
                 None => Err(TaskError::NotFound(*id).into()),
             }
         }
     } else {
-        match Task::find_by_id(&db_pool, *id).await? {
+        match Task::find_by_id(&db_pool, *id, logged_user.id).await? {
             Some(task) => {
                 session.insert(FAVORITE_TASK_STR, task.clone())?;
-                Ok(HttpResponse::Found().json(task))
+                record_favorite(&db_pool, *id, logged_user.id, TaskEventKind::Favorited).await?;
+                Ok(Negotiated::one(task).respond_to(&req))
             }
             None => Err(TaskError::NoneFavorite.into()),
         }
     }
 }
 
+/// NOTE(alex): The favorite lives in the session cookie, so the event is the only thing that goes
+/// into the database, there's no transaction to share.
+async fn record_favorite(
+    db_pool: &SqlitePool,
+    task_id: i64,
+    user_id: i64,
+    kind: TaskEventKind,
+) -> Result<(), AppError> {
+    InsertTaskEvent {
+        task_id,
+        user_id,
+        kind,
+        changes: None,
+    }
+    .insert(db_pool)
+    .await
+}
+
 #[get("/tasks/favorite")]
 pub async fn find_favorite(session: Session) -> Result<impl Responder, AppError> {
     if let Some(task) = session.get::<Task>(FAVORITE_TASK_STR)? {
-        Ok(HttpResponse::Found().json(task))
+        Ok(Negotiated::one(task))
     } else {
         Err(TaskError::NoneFavorite.into())
     }
@@ -178,13 +412,20 @@ pub async fn find_favorite(session: Session) -> Result<impl Responder, AppError>
 
 pub fn task_service(cfg: &mut web::ServiceConfig) {
     cfg.service(insert);
+    cfg.service(batch);
     cfg.service(update);
     cfg.service(delete);
+    cfg.service(restore);
     cfg.service(done);
     cfg.service(undo);
+    cfg.service(find_occurrences);
+    cfg.service(tag);
+    cfg.service(untag);
     cfg.service(find_all);
     cfg.service(find_ongoing);
-    cfg.service(find_by_pattern);
+    cfg.service(search);
+    cfg.service(find_overdue);
+    cfg.service(find_due);
     cfg.service(find_by_id);
     cfg.service(favorite);
     cfg.service(find_favorite);
@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use sqlx::SqlitePool;
+
+pub mod models;
+pub mod routes;
+
+const FIND_TASKS: &'static str = include_str!("./trash/queries/find_tasks.sql");
+const FIND_USERS: &'static str = include_str!("./trash/queries/find_users.sql");
+const PURGE_TASKS: &'static str = include_str!("./trash/queries/purge_tasks.sql");
+const PURGE_USERS: &'static str = include_str!("./trash/queries/purge_users.sql");
+
+/// How often `watch` looks for rows to purge.
+pub const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Purges whatever has been in the trash for more than `trash_days`, every `PURGE_INTERVAL`. Runs
+/// until the server stops.
+///
+/// NOTE(alex): A failed purge is only logged, the next one picks up where it left off.
+pub async fn watch(db_pool: SqlitePool, trash_days: i64) {
+    let mut ticks = actix_web::rt::time::interval(PURGE_INTERVAL);
+
+    loop {
+        ticks.tick().await;
+
+        match models::purge(&db_pool, trash_days).await {
+            Ok((0, 0)) => (),
+            Ok((num_tasks, num_users)) => log::info!(
+                "Purged {} tasks, and {} users, from the trash.",
+                num_tasks,
+                num_users
+            ),
+            Err(fail) => log::error!("Failed purging the trash with `{}`!", fail),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use time::Duration;
+
+use super::*;
+use crate::{errors::AppError, tasks::models::Task, timestamps, users::models::User};
+
+/// What was deleted, but not purged yet, newest first.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Trash {
+    pub tasks: Vec<Task>,
+    /// NOTE(alex): Always empty for users that aren't admins.
+    pub users: Vec<User>,
+}
+
+impl Trash {
+    pub async fn find(
+        db_pool: &SqlitePool,
+        owner_id: i64,
+        with_users: bool,
+    ) -> Result<Self, AppError> {
+        let tasks = sqlx::query_as(FIND_TASKS)
+            .bind(owner_id)
+            .fetch_all(db_pool)
+            .await?;
+
+        let users = if with_users {
+            sqlx::query_as(FIND_USERS).fetch_all(db_pool).await?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self { tasks, users })
+    }
+}
+
+/// Hard-deletes the tasks, and users, trashed more than `trash_days` ago, returning how many of
+/// each went away.
+pub async fn purge(db_pool: &SqlitePool, trash_days: i64) -> Result<(u64, u64), AppError> {
+    let purge_before = timestamps::now() - Duration::days(trash_days).whole_seconds();
+
+    let mut transaction = db_pool.begin().await?;
+    let tasks = sqlx::query(PURGE_TASKS)
+        .bind(purge_before)
+        .execute(&mut transaction)
+        .await?;
+    let users = sqlx::query(PURGE_USERS)
+        .bind(purge_before)
+        .execute(&mut transaction)
+        .await?;
+    transaction.commit().await?;
+
+    Ok((tasks.rows_affected(), users.rows_affected()))
+}
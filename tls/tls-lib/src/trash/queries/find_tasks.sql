@@ -0,0 +1,5 @@
+select *
+from AllTaskView as Task
+where
+    Task.owner_id = $1 and Task.deleted_at is not null
+order by Task.deleted_at desc, Task.id desc
\ No newline at end of file
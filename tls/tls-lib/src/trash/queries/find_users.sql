@@ -0,0 +1,4 @@
+select *
+from User
+where User.deleted_at is not null
+order by User.deleted_at desc, User.id desc
\ No newline at end of file
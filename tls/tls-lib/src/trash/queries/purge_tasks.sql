@@ -0,0 +1,3 @@
+-- NOTE(alex): Its `Done`, `TaskTag`, and `ChecklistItem` rows go with it (`on delete cascade`).
+delete from Task
+where Task.deleted_at <= $1
\ No newline at end of file
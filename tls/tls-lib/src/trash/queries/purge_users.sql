@@ -0,0 +1,3 @@
+-- NOTE(alex): Takes every task, and session, of the user with it (`on delete cascade`).
+delete from User
+where User.deleted_at <= $1
\ No newline at end of file
@@ -0,0 +1,26 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use actix_web_httpauth::middleware::HttpAuthentication;
+use sqlx::SqlitePool;
+
+use super::models::Trash;
+use crate::{
+    errors::AppError,
+    users::models::{LoggedUser, User},
+    validator,
+};
+
+/// NOTE(alex): Our own tasks, and every user for admins (see `POST /users/{id}/restore`).
+#[get("/trash", wrap = "HttpAuthentication::with_fn(validator)")]
+pub async fn find_all(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+) -> Result<impl Responder, AppError> {
+    let is_admin = User::is_admin(db_pool.get_ref(), logged_user.id).await?;
+    let trash = Trash::find(db_pool.get_ref(), logged_user.id, is_admin).await?;
+
+    Ok(HttpResponse::Ok().json(&trash))
+}
+
+pub fn trash_service(cfg: &mut web::ServiceConfig) {
+    cfg.service(find_all);
+}
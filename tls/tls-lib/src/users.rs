@@ -1,10 +1,19 @@
 pub mod errors;
 pub mod models;
+pub mod password;
 pub mod routes;
+pub mod tokens;
 
 const FIND_ALL: &'static str = include_str!("./users/queries/find_all.sql");
 const FIND_BY_ID: &'static str = include_str!("./users/queries/find_by_id.sql");
 const INSERT: &'static str = include_str!("./users/queries/insert.sql");
 const UPDATE: &'static str = include_str!("./users/queries/update.sql");
 const DELETE: &'static str = include_str!("./users/queries/delete.sql");
+const RESTORE: &'static str = include_str!("./users/queries/restore.sql");
 const LOGIN: &'static str = include_str!("./users/queries/login.sql");
+const UPDATE_PASSWORD: &'static str = include_str!("./users/queries/update_password.sql");
+const FIND_BY_CERTIFICATE_SUBJECT: &'static str =
+    include_str!("./users/queries/find_by_certificate_subject.sql");
+const IS_ADMIN: &'static str = include_str!("./users/queries/is_admin.sql");
+const UPDATE_CERTIFICATE_SUBJECT: &'static str =
+    include_str!("./users/queries/update_certificate_subject.sql");
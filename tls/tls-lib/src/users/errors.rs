@@ -1,33 +1,33 @@
 use thiserror::Error;
 
-use super::models::MIN_USERNAME_LENGTH;
+use crate::validation::{PasswordComplexity, UsernameCharset};
 
 #[derive(Debug, Error)]
 pub enum UserError {
     #[error("`username` field of `User` cannot be empty!")]
     EmptyUsername,
 
-    #[error(
-        "`username` field of `User` must be at least {} characters!",
-        MIN_USERNAME_LENGTH
-    )]
-    UsernameLength,
+    #[error("`username` field of `User` must be between {min} and {max} characters!")]
+    UsernameLength { min: usize, max: usize },
 
-    #[error("`username` field of `User` cannot contain whitespaces!")]
-    UsernameInvalidCharacter,
+    #[error("`username` field of `User` can only contain {0}!")]
+    UsernameInvalidCharacter(UsernameCharset),
 
     #[error("`password` field of `User` cannot be empty!")]
     EmptyPassword,
 
-    #[error(
-        "`password` field of `User` must be at least {} characters!",
-        MIN_USERNAME_LENGTH
-    )]
-    PasswordLength,
+    #[error("`password` field of `User` must be between {min} and {max} characters!")]
+    PasswordLength { min: usize, max: usize },
 
     #[error("`password` field of `User` cannot contain whitespaces!")]
     PasswordInvalidCharacter,
 
+    #[error("`password` field of `User` must contain {0}!")]
+    WeakPassword(PasswordComplexity),
+
+    #[error("`username` `{0}` is already taken!")]
+    UsernameTaken(String),
+
     #[error("Could not find any `User` for id: `{0}`!")]
     NotFound(i64),
 
@@ -40,6 +40,70 @@ pub enum UserError {
     #[error("Invalid authorization token!")]
     InvalidToken,
 
-    #[error("Could not find any `User`!")]
-    Empty,
+    #[error("Authorization token has expired, login again!")]
+    ExpiredToken,
+
+    #[error("Authorization token was revoked, login again!")]
+    RevokedToken,
+
+    #[error("No verified client certificate was presented!")]
+    NoCertificate,
+
+    #[error("No `User` is bound to the client certificate `{0}`!")]
+    UnknownCertificate(String),
+
+    #[error("Client certificate `{0}` is already bound to another `User`!")]
+    CertificateInUse(String),
+
+    #[error("Could not find any `Session` for id: `{0}`!")]
+    SessionNotFound(i64),
+
+    #[error("Could not find any `Session` of this `User`!")]
+    NoSessions,
+
+    #[error("Only admins may do this!")]
+    NotAdmin,
+}
+
+impl UserError {
+    /// Stable name of the error, it goes in `Problem::code`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            UserError::EmptyUsername => "empty_username",
+            UserError::UsernameLength { .. } => "username_length",
+            UserError::UsernameInvalidCharacter(_) => "invalid_username_character",
+            UserError::EmptyPassword => "empty_password",
+            UserError::PasswordLength { .. } => "password_length",
+            UserError::PasswordInvalidCharacter => "invalid_password_character",
+            UserError::WeakPassword(_) => "weak_password",
+            UserError::UsernameTaken(_) => "username_taken",
+            UserError::NotFound(_) => "user_not_found",
+            UserError::LoginFailed => "login_failed",
+            UserError::NotLoggedIn => "not_logged_in",
+            UserError::InvalidToken => "invalid_token",
+            UserError::ExpiredToken => "expired_token",
+            UserError::RevokedToken => "revoked_token",
+            UserError::NoCertificate => "no_certificate",
+            UserError::UnknownCertificate(_) => "unknown_certificate",
+            UserError::CertificateInUse(_) => "certificate_in_use",
+            UserError::SessionNotFound(_) => "session_not_found",
+            UserError::NoSessions => "no_sessions",
+            UserError::NotAdmin => "not_admin",
+        }
+    }
+
+    /// The field of the request at fault, for the validation errors.
+    pub fn field(&self) -> Option<&'static str> {
+        match self {
+            UserError::EmptyUsername
+            | UserError::UsernameLength { .. }
+            | UserError::UsernameInvalidCharacter(_)
+            | UserError::UsernameTaken(_) => Some("username"),
+            UserError::EmptyPassword
+            | UserError::PasswordLength { .. }
+            | UserError::PasswordInvalidCharacter
+            | UserError::WeakPassword(_) => Some("password"),
+            _ => None,
+        }
+    }
 }
@@ -1,2 +1,4 @@
-delete from User
-where User.id = $1
\ No newline at end of file
+-- NOTE(alex): Only moves it to the trash, see `restore.sql`.
+update User
+set deleted_at = $2
+where User.id = $1 and User.deleted_at is null
\ No newline at end of file
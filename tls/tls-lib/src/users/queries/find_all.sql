@@ -1 +1,3 @@
-select * from User;
\ No newline at end of file
+select *
+from User
+where User.deleted_at is null;
\ No newline at end of file
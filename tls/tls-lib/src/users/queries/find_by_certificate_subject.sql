@@ -0,0 +1,3 @@
+select *
+from User
+where User.certificate_subject = $1 and User.deleted_at is null
\ No newline at end of file
@@ -1,3 +1,3 @@
 select *
 from User
-where User.id = $1
\ No newline at end of file
+where User.id = $1 and User.deleted_at is null
\ No newline at end of file
@@ -0,0 +1,3 @@
+select User.is_admin
+from User
+where User.id = $1 and User.deleted_at is null
\ No newline at end of file
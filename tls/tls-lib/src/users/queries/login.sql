@@ -1,4 +1,4 @@
 select *
 from User
 where
-    User.username = $1 and User.password = $2;
\ No newline at end of file
+    User.username = $1 and User.deleted_at is null;
\ No newline at end of file
@@ -0,0 +1,3 @@
+update User
+set deleted_at = null
+where User.id = $1 and User.deleted_at is not null
\ No newline at end of file
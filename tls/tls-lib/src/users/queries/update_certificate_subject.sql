@@ -0,0 +1,3 @@
+update User
+set certificate_subject = $1
+where User.id = $2 and User.deleted_at is null
\ No newline at end of file
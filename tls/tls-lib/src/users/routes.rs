@@ -1,38 +1,55 @@
 use actix_identity::Identity;
-use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+use actix_web::{
+    delete, get,
+    http::{header, StatusCode},
+    post, put, web, HttpRequest, HttpResponse, Responder,
+};
 use actix_web_httpauth::middleware::HttpAuthentication;
 use sqlx::SqlitePool;
 
 use super::{
     errors::UserError,
-    models::{InsertUser, LoginUser, UpdateUser, User},
+    models::{InsertUser, LoggedUser, LoginUser, UpdateUser, User},
+    tokens::{Claims, TokenConfig},
+};
+use crate::{
+    certificates::PeerCertificate,
+    errors::AppError,
+    negotiation::Negotiated,
+    sessions::models::{InsertSession, Session},
+    validation::Validated,
+    validator,
 };
-use crate::{errors::AppError, validator};
 
 #[post("/users/register")]
 pub async fn insert(
     db_pool: web::Data<SqlitePool>,
-    input: InsertUser,
+    Validated(input): Validated<InsertUser>,
 ) -> Result<impl Responder, AppError> {
     let user = input.insert(db_pool.get_ref()).await?;
-    Ok(HttpResponse::Created().json(user))
+    let location = format!("/users/{}", user.id);
+
+    Ok(Negotiated::one(user)
+        .status(StatusCode::CREATED)
+        .header((header::LOCATION, location)))
 }
 
-#[put("/users", wrap = "HttpAuthentication::bearer(validator)")]
+#[put("/users", wrap = "HttpAuthentication::with_fn(validator)")]
 pub async fn update(
     db_pool: web::Data<SqlitePool>,
-    input: UpdateUser,
+    Validated(input): Validated<UpdateUser>,
 ) -> Result<impl Responder, AppError> {
+    let user_id = input.id;
     let num_modified = input.update(db_pool.get_ref()).await?;
 
     if num_modified == 0 {
-        Ok(HttpResponse::NotModified().body("No users were updated."))
+        Err(UserError::NotFound(user_id).into())
     } else {
         Ok(HttpResponse::Ok().body(format!("Updated {} users.", num_modified)))
     }
 }
 
-#[delete("/users/{id:\\d+}", wrap = "HttpAuthentication::bearer(validator)")]
+#[delete("/users/{id:\\d+}", wrap = "HttpAuthentication::with_fn(validator)")]
 pub async fn delete(
     db_pool: web::Data<SqlitePool>,
     id: web::Path<i64>,
@@ -40,23 +57,44 @@ pub async fn delete(
     let num_modified = User::delete(db_pool.get_ref(), *id).await?;
 
     if num_modified == 0 {
-        Ok(HttpResponse::NotModified().body("No users were deleted."))
+        Err(UserError::NotFound(*id).into())
     } else {
-        Ok(HttpResponse::Ok().body(format!("Deleted {} users.", num_modified)))
+        // NOTE(alex): Its tokens are useless already (see `validator`), but they shouldn't come
+        // back if the user is restored.
+        Session::delete_all(db_pool.get_ref(), *id).await?;
+        Ok(HttpResponse::NoContent().finish())
     }
 }
 
-#[get("/users")]
-pub async fn find_all(db_pool: web::Data<SqlitePool>) -> Result<impl Responder, AppError> {
-    let users = User::find_all(db_pool.get_ref()).await?;
+/// NOTE(alex): Admins only, a trashed user can't login to restore itself.
+#[post(
+    "/users/{id:\\d+}/restore",
+    wrap = "HttpAuthentication::with_fn(validator)"
+)]
+pub async fn restore(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    id: web::Path<i64>,
+) -> Result<impl Responder, AppError> {
+    if !User::is_admin(db_pool.get_ref(), logged_user.id).await? {
+        return Err(UserError::NotAdmin.into());
+    }
 
-    if users.is_empty() {
-        Err(UserError::Empty.into())
+    let num_modified = User::restore(db_pool.get_ref(), *id).await?;
+
+    if num_modified == 0 {
+        Err(UserError::NotFound(*id).into())
     } else {
-        Ok(HttpResponse::Found().json(&users))
+        Ok(HttpResponse::Ok().body(format!("Restored {} users.", num_modified)))
     }
 }
 
+#[get("/users")]
+pub async fn find_all(db_pool: web::Data<SqlitePool>) -> Result<impl Responder, AppError> {
+    let users = User::find_all(db_pool.get_ref()).await?;
+    Ok(Negotiated::many(users))
+}
+
 #[get("/users/{id:\\d+}")]
 pub async fn find_by_id(
     db_pool: web::Data<SqlitePool>,
@@ -65,41 +103,42 @@ pub async fn find_by_id(
     let user = User::find_by_id(db_pool.get_ref(), *id).await?;
 
     match user {
-        Some(user) => Ok(HttpResponse::Found().json(user)),
+        Some(user) => Ok(Negotiated::one(user)),
         None => Err(UserError::NotFound(*id).into()),
     }
 }
 
-pub fn create_auth_token(user: &User) -> u64 {
-    use std::{
-        collections::hash_map::DefaultHasher,
-        hash::{Hash, Hasher},
-    };
-
-    let mut hasher = DefaultHasher::new();
-    user.hash(&mut hasher);
-    hasher.finish()
-}
-
 #[post("/users/login")]
 pub async fn login(
+    req: HttpRequest,
     db_pool: web::Data<SqlitePool>,
+    token_config: web::Data<TokenConfig>,
     identity: Identity,
-    input: web::Json<LoginUser>,
+    Validated(login_user): Validated<LoginUser>,
 ) -> Result<impl Responder, AppError> {
-    let login_user = input.into_inner();
     let user = login_user.login(&db_pool).await?;
     match user {
         Some(user) => {
-            let auth_token = create_auth_token(&user);
-            let logged_user = user.to_logged(auth_token);
+            let (auth_token, claims) = token_config.issue(&user)?;
+
+            let insert_session = InsertSession {
+                user_agent: req
+                    .headers()
+                    .get(header::USER_AGENT)
+                    .and_then(|user_agent| user_agent.to_str().ok())
+                    .map(String::from),
+                ip: req.peer_addr().map(|address| address.ip().to_string()),
+            };
+            insert_session.insert(&db_pool, &claims).await?;
+
+            let logged_user = user.to_logged(auth_token.clone());
 
             // NOTE(alex): We'll use this identity cookie to check if the user is logged in for
             // routes that require it.
             identity.remember(serde_json::to_string_pretty(&logged_user)?);
 
             let response: HttpResponse = HttpResponse::Ok()
-                .append_header(("X-Auth-Token", auth_token.to_string()))
+                .append_header(("X-Auth-Token", auth_token))
                 .json(logged_user);
             Ok(response)
         }
@@ -107,18 +146,47 @@ pub async fn login(
     }
 }
 
-#[delete("/users/logout", wrap = "HttpAuthentication::bearer(validator)")]
-pub async fn logout(identity: Identity) -> impl Responder {
+#[delete("/users/logout", wrap = "HttpAuthentication::with_fn(validator)")]
+pub async fn logout(
+    db_pool: web::Data<SqlitePool>,
+    identity: Identity,
+    claims: Claims,
+) -> Result<impl Responder, AppError> {
+    // NOTE(alex): Forgetting the identity only gets rid of the cookie, deleting the session is what
+    // stops the token from being accepted again.
+    Session::delete_by_token(&db_pool, &claims).await?;
     identity.forget();
-    HttpResponse::Ok().body("Logged out.")
+
+    Ok(HttpResponse::Ok().body("Logged out."))
+}
+
+/// Binds the client certificate of this connection to the logged user, from then on the certificate
+/// alone is enough to be let in by `validator`.
+///
+/// NOTE(alex): Taking the subject from the verified certificate (instead of the request body) means
+/// nobody can claim a certificate they don't have.
+#[put("/users/certificate", wrap = "HttpAuthentication::with_fn(validator)")]
+pub async fn bind_certificate(
+    db_pool: web::Data<SqlitePool>,
+    logged_user: LoggedUser,
+    peer_certificate: PeerCertificate,
+) -> Result<impl Responder, AppError> {
+    User::bind_certificate(&db_pool, logged_user.id, &peer_certificate.subject).await?;
+
+    Ok(HttpResponse::Ok().body(format!(
+        "Bound certificate `{}` to user `{}`.",
+        peer_certificate.subject, logged_user.username
+    )))
 }
 
 pub fn user_service(cfg: &mut web::ServiceConfig) {
     cfg.service(insert);
     cfg.service(update);
     cfg.service(delete);
+    cfg.service(restore);
     cfg.service(find_all);
     cfg.service(find_by_id);
     cfg.service(login);
     cfg.service(logout);
+    cfg.service(bind_certificate);
 }
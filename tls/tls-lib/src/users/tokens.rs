@@ -0,0 +1,115 @@
+use actix_web::{dev::Payload, FromRequest, HttpMessage, HttpRequest};
+use futures::future::{ready, Ready};
+use jsonwebtoken::{
+    decode, encode, errors::ErrorKind, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+
+use super::{errors::UserError, models::User};
+use crate::errors::AppError;
+
+/// The payload of our bearer tokens.
+///
+/// NOTE(alex): These are the registered JWT claim names, so any JWT tooling is able to inspect
+/// them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Claims {
+    /// Id of the `User` this token was issued to.
+    pub sub: i64,
+    /// Issued at, as a unix timestamp.
+    pub iat: i64,
+    /// Expires at, as a unix timestamp.
+    pub exp: i64,
+    /// Unique token id.
+    pub jti: String,
+}
+
+/// Signing key and lifetime for the tokens issued on login.
+///
+/// This is registered as `web::Data<TokenConfig>`, and is required by both the `login` route, and
+/// the `validator` that protects our routes.
+#[derive(Clone)]
+pub struct TokenConfig {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    lifetime: Duration,
+}
+
+impl TokenConfig {
+    pub fn new(signing_key: &[u8], lifetime: Duration) -> Self {
+        Self {
+            encoding_key: EncodingKey::from_secret(signing_key),
+            decoding_key: DecodingKey::from_secret(signing_key),
+            lifetime,
+        }
+    }
+
+    /// Uses a random signing key, which means that every token is invalidated when the server
+    /// restarts.
+    pub fn with_random_key(lifetime: Duration) -> Self {
+        let mut signing_key = [0; 32];
+        OsRng.fill_bytes(&mut signing_key);
+
+        Self::new(&signing_key, lifetime)
+    }
+
+    pub fn lifetime(&self) -> Duration {
+        self.lifetime
+    }
+
+    /// Creates a signed token for `user`, returning it alongside the claims it carries.
+    pub fn issue(&self, user: &User) -> Result<(String, Claims), AppError> {
+        let issued_at = OffsetDateTime::now_utc();
+        let claims = Claims {
+            sub: user.id,
+            iat: issued_at.unix_timestamp(),
+            exp: (issued_at + self.lifetime).unix_timestamp(),
+            jti: new_token_id(),
+        };
+
+        let token = encode(&Header::new(Algorithm::HS256), &claims, &self.encoding_key)?;
+
+        Ok((token, claims))
+    }
+
+    /// Checks the token signature and expiration, returning the claims it carries.
+    pub fn verify(&self, token: &str) -> Result<Claims, UserError> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.leeway = 0;
+
+        match decode::<Claims>(token, &self.decoding_key, &validation) {
+            Ok(token_data) => Ok(token_data.claims),
+            Err(fail) => match fail.kind() {
+                ErrorKind::ExpiredSignature => Err(UserError::ExpiredToken),
+                _ => Err(UserError::InvalidToken),
+            },
+        }
+    }
+}
+
+fn new_token_id() -> String {
+    let mut bytes = [0; 16];
+    OsRng.fill_bytes(&mut bytes);
+
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// NOTE(alex): Only available on routes wrapped by `validator`, which is responsible for putting
+/// the verified `Claims` in the request extensions.
+impl FromRequest for Claims {
+    type Error = AppError;
+
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let claims = req
+            .extensions()
+            .get::<Claims>()
+            .cloned()
+            .ok_or_else(|| UserError::NotLoggedIn.into());
+
+        ready(claims)
+    }
+}
@@ -0,0 +1,38 @@
+use actix_web::error::PayloadError;
+use thiserror::Error;
+
+/// Failures of `Validated` before the body is deserialized, and validated.
+#[derive(Debug, Error)]
+pub enum BodyError {
+    #[error("Request body of type `{0}` is not supported, send JSON, a form, or MessagePack!")]
+    UnsupportedMediaType(String),
+
+    #[error("Request body is larger than the limit of {0} bytes!")]
+    Overflow(usize),
+
+    #[error("Could not read the request body, failed with `{0}`!")]
+    Payload(#[from] PayloadError),
+
+    #[error("Invalid JSON request body, failed with `{0}`!")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Invalid form request body, failed with `{0}`!")]
+    Form(#[from] serde_urlencoded::de::Error),
+
+    #[error("Invalid MessagePack request body, failed with `{0}`!")]
+    MessagePack(#[from] rmp_serde::decode::Error),
+}
+
+impl BodyError {
+    /// Stable name of the error, it goes in `Problem::code`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            BodyError::UnsupportedMediaType(_) => "unsupported_media_type",
+            BodyError::Overflow(_) => "body_too_large",
+            BodyError::Payload(_)
+            | BodyError::Json(_)
+            | BodyError::Form(_)
+            | BodyError::MessagePack(_) => "invalid_body",
+        }
+    }
+}
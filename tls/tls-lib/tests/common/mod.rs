@@ -1,11 +1,17 @@
+use std::str::FromStr;
+
 use actix_web::web;
-use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
-use tls_lib::create_database;
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    Pool, Sqlite,
+};
+use time::Duration;
+use tls_lib::{migrations, users::tokens::TokenConfig};
 
+// NOTE(alex): Migrations never drop anything, so every test gets its own in-memory database. It only
+// lives as long as its single connection does.
 pub async fn setup_data() -> web::Data<Pool<Sqlite>> {
-    let db_options = sqlx::sqlite::SqliteConnectOptions::new()
-        .filename(env!("DATABASE_FILE"))
-        .create_if_missing(true);
+    let db_options = SqliteConnectOptions::from_str("sqlite::memory:").unwrap();
 
     let database_pool = SqlitePoolOptions::new()
         .max_connections(1)
@@ -13,11 +19,20 @@ pub async fn setup_data() -> web::Data<Pool<Sqlite>> {
         .await
         .unwrap();
 
-    create_database(&database_pool).await.unwrap();
+    migrations::run(&database_pool).await.unwrap();
 
     web::Data::new(database_pool)
 }
 
+// NOTE(alex): Not every test file deals with tokens.
+#[allow(dead_code)]
+pub fn setup_token_config() -> web::Data<TokenConfig> {
+    web::Data::new(TokenConfig::new(
+        b"hello-actix-test-key",
+        Duration::minutes(10),
+    ))
+}
+
 // WARNING(alex): This macro doesn't check if there is an user register already, or if some user is
 // logged in, so the tests must be run with:
 // cargo test -- --test-threads=1
@@ -25,9 +40,14 @@ pub async fn setup_data() -> web::Data<Pool<Sqlite>> {
 #[macro_export]
 macro_rules! setup_app {
     ($configure: expr) => {{
-        let data = setup_data().await;
+        setup_app!($configure, setup_data().await)
+    }};
+    // NOTE(alex): For the tests that need to reach into the database themselves.
+    ($configure: expr, $data: expr) => {{
+        let data = $data;
         let app = App::new()
             .app_data(data.clone())
+            .app_data(setup_token_config())
             .configure($configure)
             .service(user_insert)
             .service(login)
@@ -46,45 +66,98 @@ macro_rules! setup_app {
             );
         let mut app = test::init_service(app).await;
 
-        let (cookies, bearer_token) = {
-            let new_user = InsertUser {
-                valid_username: "spike".to_string(),
-                valid_password: "vicious".to_string(),
-            };
-            let register_user_request = test::TestRequest::post()
-                .uri("/users/register")
-                .set_json(&new_user)
-                .to_request();
-            let register_user_service_response =
-                test::call_service(&mut app, register_user_request).await;
-            assert!(register_user_service_response.status().is_success());
-
-            let user: User = test::read_body_json(register_user_service_response).await;
-
-            let login_user = LoginUser {
-                username: user.username,
-                password: user.password,
-            };
-            let login_request = test::TestRequest::post()
-                .uri("/users/login")
-                .set_json(&login_user)
-                .to_request();
-            let login_service_response = test::call_service(&mut app, login_request).await;
-            assert!(login_service_response.status().is_success());
-
-            let cookies = login_service_response.response().cookies();
-            let cookies_str = cookies
-                .flat_map(|cookie| cookie.to_string().chars().collect::<Vec<_>>())
-                .collect::<String>();
-
-            let logged_user: LoggedUser = test::read_body_json(login_service_response).await;
-
-            let bearer_token = format!("Bearer {}", logged_user.token);
-            let cookies = Cookie::parse_encoded(cookies_str).unwrap();
-
-            (cookies, bearer_token)
-        };
+        let (cookies, bearer_token) = register_and_login!(app, "spike", "vicious");
 
         (app, bearer_token, cookies)
     }};
 }
+
+// NOTE(alex): Registers a new `User`, and logs it in, returning the identity cookie and the bearer
+// token that protected routes require.
+#[macro_export]
+macro_rules! register_and_login {
+    ($app: expr, $username: expr, $password: expr) => {{
+        let new_user = InsertUser {
+            valid_username: $username.to_string(),
+            valid_password: $password.to_string(),
+        };
+        let register_user_request = test::TestRequest::post()
+            .uri("/users/register")
+            .set_json(&new_user)
+            .to_request();
+        let register_user_service_response =
+            test::call_service(&mut $app, register_user_request).await;
+        assert!(register_user_service_response.status().is_success());
+
+        let user: User = test::read_body_json(register_user_service_response).await;
+
+        let login_user = LoginUser {
+            username: user.username,
+            password: new_user.valid_password,
+        };
+        let login_request = test::TestRequest::post()
+            .uri("/users/login")
+            .set_json(&login_user)
+            .to_request();
+        let login_service_response = test::call_service(&mut $app, login_request).await;
+        assert!(login_service_response.status().is_success());
+
+        let cookies = login_service_response.response().cookies();
+        let cookies_str = cookies
+            .flat_map(|cookie| cookie.to_string().chars().collect::<Vec<_>>())
+            .collect::<String>();
+
+        let logged_user: LoggedUser = test::read_body_json(login_service_response).await;
+
+        let bearer_token = format!("Bearer {}", logged_user.token);
+        let cookies = Cookie::parse_encoded(cookies_str).unwrap();
+
+        (cookies, bearer_token)
+    }};
+}
+
+// NOTE(alex): Inserts a `Task` through its route, which the test has to register. The title, and
+// details, are only given by the tests that care about them.
+#[macro_export]
+macro_rules! insert_task {
+    ($bearer_token: expr, $cookies: expr, $app: expr) => {{
+        insert_task!("Re-watch Cowboy Bebop", $bearer_token, $cookies, $app)
+    }};
+    ($title: expr, $bearer_token: expr, $cookies: expr, $app: expr) => {{
+        insert_task!($title, "Good show.", $bearer_token, $cookies, $app)
+    }};
+    ($title: expr, $details: expr, $bearer_token: expr, $cookies: expr, $app: expr) => {{
+        let insert_task = InsertTask {
+            non_empty_title: $title.to_string(),
+            details: $details.to_string(),
+            priority: Default::default(),
+            due_at: None,
+            recurrence: None,
+        };
+
+        let insert_task_request = test::TestRequest::post()
+            .uri("/tasks")
+            .insert_header(("Authorization".to_string(), $bearer_token.clone()))
+            .cookie($cookies.clone())
+            .set_json(&insert_task)
+            .to_request();
+        let insert_task_response = test::call_service(&mut $app, insert_task_request).await;
+        assert_eq!(insert_task_response.status(), StatusCode::CREATED);
+
+        let task: Task = test::read_body_json(insert_task_response).await;
+        task
+    }};
+}
+
+// NOTE(alex): A request without a body, from the logged in user, `call!(get, "/tasks", ...)`.
+#[macro_export]
+macro_rules! call {
+    ($method: ident, $uri: expr, $bearer_token: expr, $cookies: expr, $app: expr) => {{
+        let request = test::TestRequest::$method()
+            .uri(&$uri)
+            .insert_header(("Authorization".to_string(), $bearer_token.clone()))
+            .cookie($cookies.clone())
+            .to_request();
+        test::call_service(&mut $app, request).await
+    }};
+}
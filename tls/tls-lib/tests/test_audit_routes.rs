@@ -0,0 +1,193 @@
+mod common;
+
+use actix_identity::{CookieIdentityPolicy, IdentityService};
+use actix_session::CookieSession;
+use actix_web::{cookie::Cookie, http::StatusCode, test, web::ServiceConfig, App};
+use common::{setup_data, setup_token_config};
+use time::Duration;
+use tls_lib::{
+    audit::{
+        models::{TaskEvent, TaskEventKind},
+        routes::{find_all as audit_find_all, find_history},
+    },
+    tasks::{
+        models::{InsertTask, Page, Task, UpdateTask},
+        routes::{
+            delete as task_delete, done as task_done, insert as task_insert, undo as task_undo,
+            update as task_update,
+        },
+    },
+    users::{
+        models::{InsertUser, LoggedUser, LoginUser, User},
+        routes::{insert as user_insert, login},
+    },
+};
+
+#[actix_rt::test]
+pub async fn test_audit_task_history() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_update);
+        cfg.service(task_done);
+        cfg.service(task_undo);
+        cfg.service(task_delete);
+        cfg.service(find_history);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = insert_task!(bearer_token, cookies, app);
+
+    let update_task = UpdateTask {
+        id: task.id,
+        new_title: "Re-watch Trigun".to_string(),
+        details: task.details.clone(),
+        priority: Some(task.priority),
+        due_at: None,
+        recurrence: None,
+    };
+    let request = test::TestRequest::put()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .set_json(&update_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = call!(
+        post,
+        format!("/tasks/{}/done", task.id),
+        bearer_token,
+        cookies,
+        app
+    );
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = call!(
+        delete,
+        format!("/tasks/{}/undo", task.id),
+        bearer_token,
+        cookies,
+        app
+    );
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = call!(
+        delete,
+        format!("/tasks/{}", task.id),
+        bearer_token,
+        cookies,
+        app
+    );
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    // NOTE(alex): The task is gone, its history isn't.
+    let response = call!(
+        get,
+        format!("/tasks/{}/history", task.id),
+        bearer_token,
+        cookies,
+        app
+    );
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let events: Vec<TaskEvent> = test::read_body_json(response).await;
+    let kinds = events.iter().map(|event| event.kind).collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![
+            TaskEventKind::Created,
+            TaskEventKind::Updated,
+            TaskEventKind::Done,
+            TaskEventKind::Undone,
+            TaskEventKind::Deleted,
+        ]
+    );
+
+    let changes = events[1].changes.as_ref().unwrap();
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes["title"].from, "Re-watch Cowboy Bebop");
+    assert_eq!(changes["title"].to, "Re-watch Trigun");
+}
+
+#[actix_rt::test]
+pub async fn test_audit_history_of_another_user() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(find_history);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = insert_task!(bearer_token, cookies, app);
+
+    let (other_cookies, other_bearer_token) = register_and_login!(app, "jet", "black");
+
+    let response = call!(
+        get,
+        format!("/tasks/{}/history", task.id),
+        other_bearer_token,
+        other_cookies,
+        app
+    );
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+pub async fn test_audit_requires_admin() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(audit_find_all);
+    };
+
+    let data = setup_data().await;
+    let (mut app, bearer_token, cookies) = setup_app!(configure, data.clone());
+    let _ = insert_task!(bearer_token, cookies, app);
+    let _ = insert_task!(bearer_token, cookies, app);
+
+    let response = call!(get, "/audit", bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    sqlx::query("update User set is_admin = 1 where username = 'spike'")
+        .execute(data.get_ref())
+        .await
+        .unwrap();
+
+    let response = call!(get, "/audit?limit=1", bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let page: Page<TaskEvent> = test::read_body_json(response).await;
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.total, 2);
+
+    let response = call!(
+        get,
+        format!("/audit?after_id={}", page.next_cursor.unwrap()),
+        bearer_token,
+        cookies,
+        app
+    );
+    let page: Page<TaskEvent> = test::read_body_json(response).await;
+    assert_eq!(page.items.len(), 1);
+    assert!(page.next_cursor.is_none());
+}
+
+#[actix_rt::test]
+pub async fn test_audit_events_are_append_only() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+    };
+
+    let data = setup_data().await;
+    let (mut app, bearer_token, cookies) = setup_app!(configure, data.clone());
+    let _ = insert_task!(bearer_token, cookies, app);
+
+    let updated = sqlx::query("update TaskEvent set kind = 'deleted'")
+        .execute(data.get_ref())
+        .await;
+    assert!(updated.is_err());
+
+    let deleted = sqlx::query("delete from TaskEvent")
+        .execute(data.get_ref())
+        .await;
+    assert!(deleted.is_err());
+}
@@ -0,0 +1,230 @@
+mod common;
+
+use actix_identity::{CookieIdentityPolicy, IdentityService};
+use actix_session::CookieSession;
+use actix_web::{
+    cookie::Cookie,
+    http::StatusCode,
+    test,
+    web::{self, ServiceConfig},
+    App,
+};
+use common::{setup_data, setup_token_config};
+use serde_json::json;
+use time::Duration;
+use tls_lib::{
+    tasks::{
+        batch::OperationResult,
+        models::{Page, PreconditionConfig, Task},
+        routes::{batch, find_all as task_find_all, find_by_id as task_find_by_id},
+    },
+    users::{
+        models::{InsertUser, LoggedUser, LoginUser, User},
+        routes::{insert as user_insert, login},
+    },
+};
+
+macro_rules! post_batch {
+    ($body: expr, $bearer_token: expr, $cookies: expr, $app: expr) => {{
+        let request = test::TestRequest::post()
+            .uri("/tasks/batch")
+            .insert_header(("Authorization".to_string(), $bearer_token.clone()))
+            .cookie($cookies.clone())
+            .set_json(&$body)
+            .to_request();
+        test::call_service(&mut $app, request).await
+    }};
+}
+
+macro_rules! find_all {
+    ($bearer_token: expr, $cookies: expr, $app: expr) => {{
+        let request = test::TestRequest::get()
+            .uri("/tasks")
+            .insert_header(("Authorization".to_string(), $bearer_token.clone()))
+            .cookie($cookies.clone())
+            .to_request();
+        let response = test::call_service(&mut $app, request).await;
+        let page: Page<Task> = test::read_body_json(response).await;
+        page
+    }};
+}
+
+#[actix_rt::test]
+pub async fn test_batch_all_operations() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(batch);
+        cfg.service(task_find_all);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    let body = json!({
+        "operations": [
+            { "op": "insert", "non_empty_title": "Re-watch Cowboy Bebop", "details": "Good show." },
+            { "op": "insert", "non_empty_title": "Re-watch Trigun", "details": "Also good." },
+        ]
+    });
+    let response = post_batch!(body, bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let results: Vec<OperationResult> = test::read_body_json(response).await;
+    assert!(results.iter().all(|result| result.status == 201));
+    let bebop_id = results[0].id.unwrap();
+    let trigun_id = results[1].id.unwrap();
+
+    let body = json!({
+        "operations": [
+            { "op": "update", "id": bebop_id, "new_title": "Re-watch Bebop", "details": "Good." },
+            { "op": "done", "id": bebop_id },
+            { "op": "undo", "id": bebop_id },
+            { "op": "undo", "id": bebop_id },
+            { "op": "delete", "id": trigun_id },
+        ]
+    });
+    let response = post_batch!(body, bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let results: Vec<OperationResult> = test::read_body_json(response).await;
+    let statuses = results
+        .iter()
+        .map(|result| result.status)
+        .collect::<Vec<_>>();
+    assert_eq!(statuses, vec![200, 204, 204, 204, 204]);
+
+    let page = find_all!(bearer_token, cookies, app);
+    assert_eq!(page.total, 1);
+    assert_eq!(page.items[0].title, "Re-watch Bebop");
+}
+
+#[actix_rt::test]
+pub async fn test_batch_atomic_rolls_back() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(batch);
+        cfg.service(task_find_all);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    let body = json!({
+        "operations": [
+            { "op": "insert", "non_empty_title": "Re-watch Cowboy Bebop", "details": "Good show." },
+            { "op": "done", "id": 1000 },
+            { "op": "insert", "non_empty_title": "Re-watch Trigun", "details": "Also good." },
+        ]
+    });
+    let response = post_batch!(body, bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::MULTI_STATUS);
+
+    let results: Vec<OperationResult> = test::read_body_json(response).await;
+    let statuses = results
+        .iter()
+        .map(|result| result.status)
+        .collect::<Vec<_>>();
+    assert_eq!(statuses, vec![424, 404, 424]);
+    let error = results[1].error.as_ref().unwrap();
+    assert_eq!(error.code, "task_not_found");
+    assert_eq!(error.detail, "Could not find any `Task` for id: `1000`!");
+    assert_eq!(results[0].error.as_ref().unwrap().code, "rolled_back");
+
+    // NOTE(alex): Not even the insert that went through before the failure is kept.
+    let page = find_all!(bearer_token, cookies, app);
+    assert_eq!(page.total, 0);
+}
+
+#[actix_rt::test]
+pub async fn test_batch_best_effort_keeps_what_worked() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(batch);
+        cfg.service(task_find_all);
+        cfg.service(task_find_by_id);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    let body = json!({
+        "mode": "best_effort",
+        "operations": [
+            { "op": "insert", "non_empty_title": "Re-watch Cowboy Bebop", "details": "Good show." },
+            { "op": "insert", "non_empty_title": " \n\t", "details": "No title." },
+            { "op": "delete", "id": 1000 },
+            { "op": "insert", "non_empty_title": "Re-watch Trigun", "details": "Also good." },
+        ]
+    });
+    let response = post_batch!(body, bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::MULTI_STATUS);
+
+    let results: Vec<OperationResult> = test::read_body_json(response).await;
+    let statuses = results
+        .iter()
+        .map(|result| result.status)
+        .collect::<Vec<_>>();
+    assert_eq!(statuses, vec![201, 422, 404, 201]);
+    assert!(results[1].id.is_none());
+    assert_eq!(results[1].error.as_ref().unwrap().errors[0].field, "title");
+
+    let page = find_all!(bearer_token, cookies, app);
+    assert_eq!(page.total, 2);
+}
+
+#[actix_rt::test]
+pub async fn test_batch_invalid_size() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(batch);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    let response = post_batch!(json!({ "operations": [] }), bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let operations = vec![json!({ "op": "undo", "id": 1 }); 101];
+    let response = post_batch!(
+        json!({ "operations": operations }),
+        bearer_token,
+        cookies,
+        app
+    );
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[actix_rt::test]
+pub async fn test_batch_version_required() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.app_data(web::Data::new(PreconditionConfig { required: true }));
+        cfg.service(batch);
+        cfg.service(task_find_all);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    let body = json!({
+        "operations": [
+            { "op": "insert", "non_empty_title": "Re-watch Cowboy Bebop", "details": "Good show." },
+        ]
+    });
+    let response = post_batch!(body, bearer_token, cookies, app);
+    let results: Vec<OperationResult> = test::read_body_json(response).await;
+    let id = results[0].id.unwrap();
+    let version = find_all!(bearer_token, cookies, app).items[0].version;
+
+    // NOTE(alex): Same as `If-Match` on the routes, missing is a `428`, stale is a `412`.
+    let body = json!({
+        "mode": "best_effort",
+        "operations": [
+            { "op": "update", "id": id, "new_title": "Re-watch Bebop", "details": "Good." },
+            { "op": "delete", "id": id },
+            { "op": "delete", "id": id, "version": version + 1 },
+            { "op": "update", "id": id, "new_title": "Re-watch Bebop", "details": "Good.", "version": version },
+            { "op": "delete", "id": id, "version": version },
+        ]
+    });
+    let response = post_batch!(body, bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::MULTI_STATUS);
+
+    let results: Vec<OperationResult> = test::read_body_json(response).await;
+    let statuses = results
+        .iter()
+        .map(|result| result.status)
+        .collect::<Vec<_>>();
+    assert_eq!(statuses, vec![428, 428, 412, 200, 412]);
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use actix_web::{
+    http::{header, StatusCode},
+    test,
+    web::{self, ServiceConfig},
+    App,
+};
+use common::setup_data;
+use tls_lib::{
+    errors::Problem,
+    users::{
+        models::{InsertUser, User},
+        routes::insert as user_insert,
+    },
+    validation::ValidationConfig,
+};
+
+#[actix_rt::test]
+pub async fn test_bodies_form_and_message_pack() {
+    let app = App::new()
+        .app_data(setup_data().await)
+        .configure(|cfg: &mut ServiceConfig| {
+            cfg.service(user_insert);
+        });
+    let mut app = test::init_service(app).await;
+
+    let request = test::TestRequest::post()
+        .uri("/users/register")
+        .set_form(&InsertUser {
+            valid_username: "yusuke".to_string(),
+            valid_password: "toguro".to_string(),
+        })
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let user: User = test::read_body_json(response).await;
+    assert_eq!(user.username, "yusuke");
+
+    let body = rmp_serde::to_vec_named(&InsertUser {
+        valid_username: "kuwabara".to_string(),
+        valid_password: "shizuru".to_string(),
+    })
+    .unwrap();
+    let request = test::TestRequest::post()
+        .uri("/users/register")
+        .insert_header((header::CONTENT_TYPE, "application/msgpack"))
+        .set_payload(body)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let user: User = test::read_body_json(response).await;
+    assert_eq!(user.username, "kuwabara");
+
+    // NOTE(alex): Validated the same, whatever the format.
+    let request = test::TestRequest::post()
+        .uri("/users/register")
+        .set_form(&InsertUser {
+            valid_username: "yu".to_string(),
+            valid_password: "toguro".to_string(),
+        })
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let problem: Problem = test::read_body_json(response).await;
+    assert_eq!(problem.errors[0].code, "username_length");
+}
+
+#[actix_rt::test]
+pub async fn test_bodies_unsupported_media_type() {
+    let app = App::new()
+        .app_data(setup_data().await)
+        .configure(|cfg: &mut ServiceConfig| {
+            cfg.service(user_insert);
+        });
+    let mut app = test::init_service(app).await;
+
+    let request = test::TestRequest::post()
+        .uri("/users/register")
+        .insert_header((header::CONTENT_TYPE, "text/plain"))
+        .set_payload("yusuke toguro")
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    let problem: Problem = test::read_body_json(response).await;
+    assert_eq!(problem.code, "unsupported_media_type");
+
+    let request = test::TestRequest::post()
+        .uri("/users/register")
+        .insert_header((header::CONTENT_TYPE, "application/json"))
+        .set_payload("{ \"valid_username\": ")
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let problem: Problem = test::read_body_json(response).await;
+    assert_eq!(problem.code, "invalid_body");
+}
+
+#[actix_rt::test]
+pub async fn test_bodies_limit_from_config() {
+    let validation_config = ValidationConfig {
+        max_body_bytes: 64,
+        ..Default::default()
+    };
+    let app = App::new()
+        .app_data(setup_data().await)
+        .app_data(web::Data::new(validation_config))
+        .configure(|cfg: &mut ServiceConfig| {
+            cfg.service(user_insert);
+        });
+    let mut app = test::init_service(app).await;
+
+    let request = test::TestRequest::post()
+        .uri("/users/register")
+        .set_json(&InsertUser {
+            valid_username: "yusuke".to_string(),
+            valid_password: "toguro".repeat(10),
+        })
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    let problem: Problem = test::read_body_json(response).await;
+    assert_eq!(problem.code, "body_too_large");
+
+    let request = test::TestRequest::post()
+        .uri("/users/register")
+        .set_json(&InsertUser {
+            valid_username: "yusuke".to_string(),
+            valid_password: "toguro".to_string(),
+        })
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
@@ -0,0 +1,184 @@
+mod common;
+
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::Path,
+    sync::Arc,
+};
+
+use common::setup_data;
+use tls_lib::{
+    certificates::{
+        errors::TlsError, load_certified_key, load_client_roots, CertificateResolver,
+        PeerCertificate,
+    },
+    errors::AppError,
+    users::{
+        errors::UserError,
+        models::{InsertUser, User},
+    },
+};
+
+fn certificate(file: &str) -> String {
+    format!("{}/certificates/{}", env!("CARGO_MANIFEST_DIR"), file)
+}
+
+fn fixture(file: &str) -> String {
+    format!("{}/tests/certificates/{}", env!("CARGO_MANIFEST_DIR"), file)
+}
+
+#[test]
+pub fn test_certificates_load_pkcs8() {
+    let certified_key = load_certified_key(
+        Path::new(&certificate("cert.pem")),
+        Path::new(&certificate("key.pem")),
+    )
+    .unwrap();
+
+    assert_eq!(certified_key.cert.len(), 1);
+}
+
+#[test]
+pub fn test_certificates_load_sec1() {
+    let certified_key = load_certified_key(
+        Path::new(&fixture("ec_cert.pem")),
+        Path::new(&fixture("ec_key.pem")),
+    )
+    .unwrap();
+
+    assert_eq!(certified_key.cert.len(), 1);
+}
+
+#[test]
+pub fn test_certificates_load_pkcs1_full_chain() {
+    let certified_key = load_certified_key(
+        Path::new(&fixture("rsa_chain.pem")),
+        Path::new(&fixture("rsa_key.pem")),
+    )
+    .unwrap();
+
+    assert_eq!(certified_key.cert.len(), 2);
+}
+
+#[test]
+pub fn test_certificates_missing_file() {
+    let result = load_certified_key(
+        Path::new(&fixture("missing.pem")),
+        Path::new(&certificate("key.pem")),
+    );
+
+    assert!(matches!(result, Err(TlsError::Read { .. })));
+}
+
+#[test]
+pub fn test_certificates_no_private_key() {
+    let result = load_certified_key(
+        Path::new(&certificate("cert.pem")),
+        Path::new(&certificate("cert.pem")),
+    );
+
+    assert!(matches!(result, Err(TlsError::NoPrivateKey(_))));
+}
+
+#[test]
+pub fn test_certificates_reload_only_when_changed() {
+    let resolver =
+        CertificateResolver::new(certificate("cert.pem"), certificate("key.pem")).unwrap();
+
+    assert!(!resolver.reload_if_changed().unwrap());
+    assert!(resolver.reload().is_ok());
+}
+
+#[test]
+pub fn test_certificates_key_mismatch() {
+    let result = load_certified_key(
+        Path::new(&fixture("ec_cert.pem")),
+        Path::new(&certificate("key.pem")),
+    );
+    assert!(matches!(result, Err(TlsError::KeyMismatch { .. })));
+
+    let result = load_certified_key(
+        Path::new(&fixture("rsa_chain.pem")),
+        Path::new(&certificate("key.pem")),
+    );
+    assert!(matches!(result, Err(TlsError::KeyMismatch { .. })));
+}
+
+#[test]
+pub fn test_certificates_reload_keeps_old_on_key_mismatch() {
+    let directory = std::env::temp_dir().join(format!("tls-lib-reload-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let cert_file = directory.join("cert.pem");
+    let key_file = directory.join("key.pem");
+
+    fs::copy(certificate("cert.pem"), &cert_file).unwrap();
+    fs::copy(certificate("key.pem"), &key_file).unwrap();
+    let resolver = CertificateResolver::new(&cert_file, &key_file).unwrap();
+    let loaded = resolver.certified_key();
+
+    // NOTE(alex): The renewal wrote the new certificate, but not its key yet.
+    fs::copy(fixture("rsa_chain.pem"), &cert_file).unwrap();
+    let result = resolver.reload();
+
+    fs::remove_dir_all(&directory).unwrap();
+    assert!(matches!(result, Err(TlsError::KeyMismatch { .. })));
+    assert!(Arc::ptr_eq(&loaded, &resolver.certified_key()));
+}
+
+#[test]
+pub fn test_certificates_load_client_roots() {
+    let roots = load_client_roots(Path::new(&fixture("client_ca.pem"))).unwrap();
+    assert_eq!(roots.len(), 1);
+
+    let result = load_client_roots(Path::new(&certificate("key.pem")));
+    assert!(matches!(result, Err(TlsError::NoCertificates(_))));
+}
+
+#[test]
+pub fn test_certificates_peer_subject() {
+    let mut reader = BufReader::new(File::open(fixture("client_cert.pem")).unwrap());
+    let der = rustls_pemfile::certs(&mut reader).unwrap().remove(0);
+
+    let peer_certificate = PeerCertificate::from_der(&der).unwrap();
+
+    assert_eq!(peer_certificate.subject, "O=hello-actix, CN=task-runner");
+}
+
+#[actix_rt::test]
+pub async fn test_certificates_bind_to_user() {
+    let data = setup_data().await;
+    let subject = "O=hello-actix, CN=task-runner";
+
+    let mut users = Vec::new();
+    for (username, password) in [("spike", "spiegel"), ("jet", "black")] {
+        let insert_user = InsertUser {
+            valid_username: username.to_string(),
+            valid_password: password.to_string(),
+        };
+        users.push(insert_user.insert(&data).await.unwrap());
+    }
+
+    assert!(User::find_by_certificate_subject(&data, subject)
+        .await
+        .unwrap()
+        .is_none());
+
+    let num_modified = User::bind_certificate(&data, users[0].id, subject)
+        .await
+        .unwrap();
+    assert_eq!(num_modified, 1);
+
+    let bound_user = User::find_by_certificate_subject(&data, subject)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(bound_user.id, users[0].id);
+
+    // NOTE(alex): A certificate may only log in as a single user.
+    let result = User::bind_certificate(&data, users[1].id, subject).await;
+    assert!(matches!(
+        result,
+        Err(AppError::User(UserError::CertificateInUse(_)))
+    ));
+}
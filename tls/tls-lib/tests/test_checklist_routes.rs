@@ -0,0 +1,297 @@
+mod common;
+
+use actix_identity::{CookieIdentityPolicy, IdentityService};
+use actix_session::CookieSession;
+use actix_web::{cookie::Cookie, http::StatusCode, test, web::ServiceConfig, App};
+use common::{setup_data, setup_token_config};
+use time::Duration;
+use tls_lib::{
+    checklists::{
+        models::{ChecklistItem, InsertChecklistItem, ReorderChecklist},
+        routes::{
+            check, delete as item_delete, find_all as item_find_all, insert as item_insert,
+            reorder, uncheck,
+        },
+    },
+    tasks::{
+        models::{InsertTask, Progress, Task},
+        routes::{done as task_done, find_by_id as task_find_by_id, insert as task_insert},
+    },
+    users::{
+        models::{InsertUser, LoggedUser, LoginUser, User},
+        routes::{insert as user_insert, login},
+    },
+};
+
+macro_rules! pre_insert_item {
+    ($task_id: expr, $title: expr, $bearer_token: expr, $cookies: expr, $app: expr) => {{
+        let insert_item = InsertChecklistItem {
+            non_empty_title: $title.to_string(),
+        };
+
+        let insert_item_request = test::TestRequest::post()
+            .uri(&format!("/tasks/{}/checklist", $task_id))
+            .insert_header(("Authorization".to_string(), $bearer_token.clone()))
+            .cookie($cookies.clone())
+            .set_json(&insert_item)
+            .to_request();
+        let insert_item_response = test::call_service(&mut $app, insert_item_request).await;
+        assert_eq!(insert_item_response.status(), StatusCode::CREATED);
+
+        let item: ChecklistItem = test::read_body_json(insert_item_response).await;
+        item
+    }};
+}
+
+#[actix_rt::test]
+pub async fn test_checklist_insert_and_progress() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_find_by_id);
+        cfg.service(item_insert);
+        cfg.service(item_find_all);
+        cfg.service(check);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = insert_task!(bearer_token, cookies, app);
+    assert_eq!(*task.progress, Progress { done: 0, total: 0 });
+
+    let first = pre_insert_item!(task.id, "Session 1", bearer_token, cookies, app);
+    let second = pre_insert_item!(task.id, "Session 2", bearer_token, cookies, app);
+    let _ = pre_insert_item!(task.id, "Session 3", bearer_token, cookies, app);
+    assert_eq!((first.position, second.position), (0, 1));
+
+    let response = call!(
+        post,
+        format!("/tasks/{}/checklist/{}/check", task.id, first.id),
+        bearer_token,
+        cookies,
+        app
+    );
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = call!(
+        get,
+        format!("/tasks/{}", task.id),
+        bearer_token,
+        cookies,
+        app
+    );
+    let found: Task = test::read_body_json(response).await;
+    assert_eq!(*found.progress, Progress { done: 1, total: 3 });
+
+    let response = call!(
+        get,
+        format!("/tasks/{}/checklist", task.id),
+        bearer_token,
+        cookies,
+        app
+    );
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let items: Vec<ChecklistItem> = test::read_body_json(response).await;
+    assert_eq!(items.len(), 3);
+    assert!(items[0].checked_at.is_some());
+    assert!(items[1].checked_at.is_none());
+
+    // NOTE(alex): Not a task of ours.
+    let insert_item_request = test::TestRequest::post()
+        .uri(&format!("/tasks/{}/checklist", task.id + 1000))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .set_json(&InsertChecklistItem {
+            non_empty_title: "Session 4".to_string(),
+        })
+        .to_request();
+    let response = test::call_service(&mut app, insert_item_request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let insert_item_request = test::TestRequest::post()
+        .uri(&format!("/tasks/{}/checklist", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .set_json(&InsertChecklistItem {
+            non_empty_title: " \n\t".to_string(),
+        })
+        .to_request();
+    let response = test::call_service(&mut app, insert_item_request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[actix_rt::test]
+pub async fn test_checklist_check_and_uncheck() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(item_insert);
+        cfg.service(check);
+        cfg.service(uncheck);
+        cfg.service(item_delete);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = insert_task!(bearer_token, cookies, app);
+    let item = pre_insert_item!(task.id, "Session 1", bearer_token, cookies, app);
+    let check_uri = format!("/tasks/{}/checklist/{}/check", task.id, item.id);
+
+    let response = call!(post, check_uri, bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = call!(post, check_uri, bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = call!(delete, check_uri, bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = call!(delete, check_uri, bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    // NOTE(alex): The item exists, but under another task.
+    let response = call!(
+        post,
+        format!("/tasks/{}/checklist/{}/check", task.id + 1000, item.id),
+        bearer_token,
+        cookies,
+        app
+    );
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = call!(
+        delete,
+        format!("/tasks/{}/checklist/{}", task.id, item.id),
+        bearer_token,
+        cookies,
+        app
+    );
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = call!(post, check_uri, bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+pub async fn test_checklist_reorder() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(item_insert);
+        cfg.service(reorder);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = insert_task!(bearer_token, cookies, app);
+    let first = pre_insert_item!(task.id, "Session 1", bearer_token, cookies, app);
+    let second = pre_insert_item!(task.id, "Session 2", bearer_token, cookies, app);
+    let third = pre_insert_item!(task.id, "Session 3", bearer_token, cookies, app);
+
+    let reorder_checklist = ReorderChecklist {
+        item_ids: vec![third.id, first.id, second.id],
+    };
+    let request = test::TestRequest::put()
+        .uri(&format!("/tasks/{}/checklist", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .set_json(&reorder_checklist)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let items: Vec<ChecklistItem> = test::read_body_json(response).await;
+    let ids = items.iter().map(|item| item.id).collect::<Vec<_>>();
+    assert_eq!(ids, vec![third.id, first.id, second.id]);
+
+    // NOTE(alex): Missing one, and one twice.
+    for item_ids in [vec![third.id, first.id], vec![third.id, first.id, first.id]].iter() {
+        let request = test::TestRequest::put()
+            .uri(&format!("/tasks/{}/checklist", task.id))
+            .insert_header(("Authorization".to_string(), bearer_token.clone()))
+            .cookie(cookies.clone())
+            .set_json(&ReorderChecklist {
+                item_ids: item_ids.clone(),
+            })
+            .to_request();
+        let response = test::call_service(&mut app, request).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+}
+
+#[actix_rt::test]
+pub async fn test_checklist_done_requires_every_item() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_done);
+        cfg.service(item_insert);
+        cfg.service(check);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = insert_task!(bearer_token, cookies, app);
+    let first = pre_insert_item!(task.id, "Session 1", bearer_token, cookies, app);
+    let second = pre_insert_item!(task.id, "Session 2", bearer_token, cookies, app);
+
+    let response = call!(
+        post,
+        format!("/tasks/{}/checklist/{}/check", task.id, first.id),
+        bearer_token,
+        cookies,
+        app
+    );
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = call!(
+        post,
+        format!("/tasks/{}/done", task.id),
+        bearer_token,
+        cookies,
+        app
+    );
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = call!(
+        post,
+        format!("/tasks/{}/checklist/{}/check", task.id, second.id),
+        bearer_token,
+        cookies,
+        app
+    );
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = call!(
+        post,
+        format!("/tasks/{}/done", task.id),
+        bearer_token,
+        cookies,
+        app
+    );
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+}
+
+#[actix_rt::test]
+pub async fn test_checklist_done_with_force() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_done);
+        cfg.service(item_insert);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = insert_task!(bearer_token, cookies, app);
+    let _ = pre_insert_item!(task.id, "Session 1", bearer_token, cookies, app);
+
+    let response = call!(
+        post,
+        format!("/tasks/{}/done?force=false", task.id),
+        bearer_token,
+        cookies,
+        app
+    );
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = call!(
+        post,
+        format!("/tasks/{}/done?force=true", task.id),
+        bearer_token,
+        cookies,
+        app
+    );
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+}
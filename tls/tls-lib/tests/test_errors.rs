@@ -0,0 +1,187 @@
+mod common;
+
+use actix_identity::{CookieIdentityPolicy, IdentityService};
+use actix_session::CookieSession;
+use actix_web::{
+    body,
+    cookie::Cookie,
+    dev::Service,
+    http::{header, StatusCode},
+    test,
+    web::ServiceConfig,
+    App,
+};
+use common::{setup_data, setup_token_config};
+use time::Duration;
+use tls_lib::{
+    errors::{Problem, ProblemInstance, PROBLEM_JSON},
+    tasks::{
+        models::InsertTask,
+        routes::{done as task_done, find_by_id as task_find_by_id, insert as task_insert},
+    },
+    users::{
+        models::{InsertUser, LoggedUser, LoginUser, User},
+        routes::{insert as user_insert, login},
+    },
+};
+
+#[actix_rt::test]
+pub async fn test_errors_problem_json() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_find_by_id);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    let insert_task = InsertTask {
+        non_empty_title: " \n\t".to_string(),
+        details: "Good show.".to_string(),
+        priority: Default::default(),
+        due_at: None,
+        recurrence: None,
+    };
+    let request = test::TestRequest::post()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .set_json(&insert_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        response.headers().get(header::CONTENT_TYPE).unwrap(),
+        PROBLEM_JSON
+    );
+
+    let problem: Problem = test::read_body_json(response).await;
+    assert_eq!(problem.status, 422);
+    assert_eq!(problem.title, "Unprocessable Entity");
+    assert_eq!(problem.code, "validation_failed");
+    assert_eq!(problem.errors.len(), 1);
+    assert_eq!(problem.errors[0].field, "title");
+    assert_eq!(problem.errors[0].code, "empty_title");
+    assert!(problem.correlation_id.is_none());
+
+    let request = test::TestRequest::get()
+        .uri("/tasks/1000")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let problem: Problem = test::read_body_json(response).await;
+    assert_eq!(problem.code, "task_not_found");
+    assert_eq!(problem.detail, "Could not find any `Task` for id: `1000`!");
+    assert!(problem.errors.is_empty());
+}
+
+#[actix_rt::test]
+pub async fn test_errors_malformed_requests() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_done);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    // NOTE(alex): Neither reaches the route, they still get a problem, not actix's plain text.
+    let request = test::TestRequest::post()
+        .uri("/tasks/1/done?force=maybe")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.headers().get(header::CONTENT_TYPE).unwrap(),
+        PROBLEM_JSON
+    );
+
+    let problem: Problem = test::read_body_json(response).await;
+    assert_eq!(problem.code, "invalid_query");
+
+    let request = test::TestRequest::post()
+        .uri("/users/login")
+        .insert_header((header::CONTENT_TYPE, "application/json"))
+        .set_payload(r#"{ "username": "spike" "#)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        response.headers().get(header::CONTENT_TYPE).unwrap(),
+        PROBLEM_JSON
+    );
+
+    let problem: Problem = test::read_body_json(response).await;
+    assert_eq!(problem.code, "invalid_body");
+}
+
+#[actix_rt::test]
+pub async fn test_errors_instance_is_the_path() {
+    let app = App::new()
+        .app_data(setup_data().await)
+        .app_data(setup_token_config())
+        .service(task_find_by_id)
+        .service(user_insert)
+        .wrap(ProblemInstance);
+    let mut app = test::init_service(app).await;
+
+    // NOTE(alex): Fails in `validator`, before the route ever runs.
+    let request = test::TestRequest::get()
+        .uri("/tasks/1")
+        .insert_header(("Authorization".to_string(), "Bearer not-a-token"))
+        .to_request();
+    let error = app.call(request).await.unwrap_err();
+    let response = error.error_response();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let body = body::to_bytes(response.into_body()).await.unwrap();
+    let problem: Problem = serde_json::from_slice(&body).unwrap();
+    assert_eq!(problem.code, "invalid_token");
+    assert_eq!(problem.instance.as_deref(), Some("/tasks/1"));
+
+    let request = test::TestRequest::post()
+        .uri("/users/register")
+        .set_json(&InsertUser {
+            valid_username: "".to_string(),
+            valid_password: "vicious".to_string(),
+        })
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let problem: Problem = test::read_body_json(response).await;
+    assert_eq!(problem.code, "validation_failed");
+    assert_eq!(problem.errors[0].code, "empty_username");
+    assert_eq!(problem.instance.as_deref(), Some("/users/register"));
+}
+
+#[actix_rt::test]
+pub async fn test_errors_database_details_are_hidden() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_find_by_id);
+    };
+
+    let data = setup_data().await;
+    let (mut app, bearer_token, cookies) = setup_app!(configure, data.clone());
+
+    sqlx::query("drop view TaskView")
+        .execute(data.get_ref())
+        .await
+        .unwrap();
+
+    let request = test::TestRequest::get()
+        .uri("/tasks/1")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    let problem: Problem = test::read_body_json(response).await;
+    assert_eq!(problem.code, "internal_error");
+    let correlation_id = problem.correlation_id.unwrap();
+    assert!(problem.detail.contains(&correlation_id));
+    assert!(!problem.detail.contains("TaskView"));
+}
@@ -0,0 +1,105 @@
+use actix_web::{
+    cookie::Cookie,
+    http::{header, StatusCode},
+    test, web, App, HttpResponse,
+};
+use tls_lib::https::{redirect, RedirectConfig, StrictTransportSecurity};
+
+async fn set_cookie() -> HttpResponse {
+    HttpResponse::Ok()
+        .cookie(Cookie::new("session-cookie", "favorite"))
+        .finish()
+}
+
+#[actix_rt::test]
+pub async fn test_https_redirect_keeps_path_and_query() {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(RedirectConfig { https_port: 8443 }))
+            .default_service(web::to(redirect)),
+    )
+    .await;
+
+    let request = test::TestRequest::post()
+        .uri("/tasks?title=bebop")
+        .insert_header((header::HOST, "localhost:8080"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+
+    assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(
+        response.headers().get(header::LOCATION).unwrap(),
+        "https://localhost:8443/tasks?title=bebop"
+    );
+}
+
+#[actix_rt::test]
+pub async fn test_https_redirect_default_port() {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(RedirectConfig { https_port: 443 }))
+            .default_service(web::to(redirect)),
+    )
+    .await;
+
+    let request = test::TestRequest::get()
+        .uri("/")
+        .insert_header((header::HOST, "example.com"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+
+    assert_eq!(
+        response.headers().get(header::LOCATION).unwrap(),
+        "https://example.com/"
+    );
+}
+
+#[actix_rt::test]
+pub async fn test_https_hsts_and_secure_cookies_over_tls() {
+    let app = test::init_service(
+        App::new()
+            .route("/", web::get().to(set_cookie))
+            .wrap(StrictTransportSecurity { max_age: 60 }),
+    )
+    .await;
+
+    // NOTE(alex): Test requests are never over TLS, so we pretend to be behind a proxy.
+    let request = test::TestRequest::get()
+        .uri("/")
+        .insert_header(("X-Forwarded-Proto", "https"))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+
+    assert_eq!(
+        response
+            .headers()
+            .get(header::STRICT_TRANSPORT_SECURITY)
+            .unwrap(),
+        "max-age=60; includeSubDomains"
+    );
+
+    let cookie = response.response().cookies().next().unwrap();
+    assert_eq!(cookie.name(), "session-cookie");
+    assert_eq!(cookie.secure(), Some(true));
+}
+
+#[actix_rt::test]
+pub async fn test_https_nothing_over_plain_http() {
+    let app = test::init_service(
+        App::new()
+            .route("/", web::get().to(set_cookie))
+            .wrap(StrictTransportSecurity { max_age: 60 }),
+    )
+    .await;
+
+    let request = test::TestRequest::get().uri("/").to_request();
+    let response = test::call_service(&app, request).await;
+
+    assert!(response
+        .headers()
+        .get(header::STRICT_TRANSPORT_SECURITY)
+        .is_none());
+
+    let cookie = response.response().cookies().next().unwrap();
+    assert_ne!(cookie.secure(), Some(true));
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use std::str::FromStr;
+
+use common::setup_data;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use tls_lib::{
+    errors::AppError,
+    migrations::{self, errors::MigrationError, MIGRATIONS},
+};
+
+#[actix_rt::test]
+pub async fn test_migrations_run_twice() {
+    // NOTE(alex): `setup_data` already brought the database up to date.
+    let data = setup_data().await;
+
+    let num_applied = migrations::run(&data).await.unwrap();
+
+    assert_eq!(num_applied, 0);
+}
+
+#[actix_rt::test]
+pub async fn test_migrations_refuse_newer_schema() {
+    let data = setup_data().await;
+
+    // NOTE(alex): Pretend some newer binary migrated this database.
+    sqlx::query(
+        "insert into _migrations (version, name, applied_at) values ($1, 'from_the_future', 0)",
+    )
+    .bind(migrations::latest_version() + 1)
+    .execute(data.get_ref())
+    .await
+    .unwrap();
+
+    let result = migrations::run(&data).await;
+
+    assert!(matches!(
+        result,
+        Err(AppError::Migration(MigrationError::SchemaTooNew { .. }))
+    ));
+}
+
+#[actix_rt::test]
+pub async fn test_migrations_rename_duplicate_usernames() {
+    let data = setup_data().await;
+
+    // NOTE(alex): Back to how things were before usernames were unique.
+    sqlx::query("drop index User_username")
+        .execute(data.get_ref())
+        .await
+        .unwrap();
+    for _ in 0..2 {
+        sqlx::query("insert into User (username, password_hash) values ('spike', 'hash')")
+            .execute(data.get_ref())
+            .await
+            .unwrap();
+    }
+
+    let migration = MIGRATIONS
+        .iter()
+        .find(|migration| migration.name == "add_user_username_unique")
+        .unwrap();
+    sqlx::query(migration.sql)
+        .execute(data.get_ref())
+        .await
+        .unwrap();
+
+    let usernames: Vec<String> = sqlx::query_scalar("select username from User order by id")
+        .fetch_all(data.get_ref())
+        .await
+        .unwrap();
+    assert_eq!(usernames, vec!["spike".to_string(), "spike_2".to_string()]);
+}
+
+#[actix_rt::test]
+pub async fn test_migrations_refuse_legacy_schema() {
+    let db_options = SqliteConnectOptions::from_str("sqlite::memory:").unwrap();
+    let database_pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(db_options)
+        .await
+        .unwrap();
+
+    // NOTE(alex): The tables as `create_database.sql` made them, before migrations existed.
+    sqlx::query(
+        "create table Task (id integer primary key, title text not null, details text);
+        create table User (id integer primary key, username text not null, password text not null);",
+    )
+    .execute(&database_pool)
+    .await
+    .unwrap();
+
+    let result = migrations::run(&database_pool).await;
+
+    assert!(matches!(
+        result,
+        Err(AppError::Migration(MigrationError::LegacySchema {
+            table: "Task",
+            column: "owner_id",
+        }))
+    ));
+}
@@ -0,0 +1,284 @@
+mod common;
+
+use actix_identity::{CookieIdentityPolicy, IdentityService};
+use actix_session::CookieSession;
+use actix_web::{
+    cookie::Cookie,
+    http::{header, StatusCode},
+    test,
+    web::ServiceConfig,
+    App,
+};
+use common::{setup_data, setup_token_config};
+use time::Duration;
+use tls_lib::{
+    errors::Problem,
+    negotiation::TOTAL_COUNT_HEADER,
+    tasks::{
+        models::{InsertTask, Task},
+        routes::{
+            find_all as task_find_all, find_by_id as task_find_by_id, insert as task_insert,
+            search as task_search,
+        },
+    },
+    users::{
+        models::{InsertUser, LoggedUser, LoginUser, User},
+        routes::{find_all as user_find_all, insert as user_insert, login},
+    },
+};
+
+macro_rules! get_with_accept {
+    ($uri: expr, $accept: expr, $bearer_token: expr, $cookies: expr, $app: expr) => {{
+        let request = test::TestRequest::get()
+            .uri($uri)
+            .insert_header(("Authorization".to_string(), $bearer_token.clone()))
+            .insert_header((header::ACCEPT, $accept))
+            .cookie($cookies.clone())
+            .to_request();
+        test::call_service(&mut $app, request).await
+    }};
+}
+
+#[actix_rt::test]
+pub async fn test_negotiation_tasks_as_csv() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_find_all);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    insert_task!(
+        "Re-watch Cowboy Bebop, again",
+        "Good \"show\".",
+        bearer_token,
+        cookies,
+        app
+    );
+    insert_task!("Re-watch Trigun", "Also good.", bearer_token, cookies, app);
+
+    let response = get_with_accept!("/tasks", "text/csv", bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers().get(header::CONTENT_TYPE).unwrap(),
+        "text/csv; charset=utf-8"
+    );
+    assert_eq!(response.headers().get(header::VARY).unwrap(), "Accept");
+    assert_eq!(response.headers().get(TOTAL_COUNT_HEADER).unwrap(), "2");
+
+    let body = test::read_body(response).await;
+    let body = std::str::from_utf8(&body).unwrap();
+    let lines = body.split_terminator("\r\n").collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[0].starts_with("id,title,details,priority,"));
+    assert!(body.contains("\"Re-watch Cowboy Bebop, again\",\"Good \"\"show\"\".\",normal,"));
+}
+
+#[actix_rt::test]
+pub async fn test_negotiation_task_as_html_and_ndjson() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_find_all);
+        cfg.service(task_find_by_id);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    let task = insert_task!(
+        "Re-watch Cowboy Bebop",
+        "<b>Good</b> show.",
+        bearer_token,
+        cookies,
+        app
+    );
+
+    let uri = format!("/tasks/{}", task.id);
+    let response = get_with_accept!(
+        &uri,
+        "text/html,application/xhtml+xml,*/*;q=0.8",
+        bearer_token,
+        cookies,
+        app
+    );
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get(header::ETAG).is_some());
+
+    let body = test::read_body(response).await;
+    let body = std::str::from_utf8(&body).unwrap();
+    assert!(body.contains("<th>title</th>"));
+    assert!(body.contains("<td>&lt;b&gt;Good&lt;/b&gt; show.</td>"));
+
+    // NOTE(alex): `q=0` refuses JSON, even though it's listed first.
+    let response = get_with_accept!(
+        "/tasks",
+        "application/json;q=0, application/x-ndjson",
+        bearer_token,
+        cookies,
+        app
+    );
+    assert_eq!(
+        response.headers().get(header::CONTENT_TYPE).unwrap(),
+        "application/x-ndjson"
+    );
+
+    let body = test::read_body(response).await;
+    let tasks = std::str::from_utf8(&body)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<Task>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].id, task.id);
+}
+
+#[actix_rt::test]
+pub async fn test_negotiation_not_acceptable() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_find_by_id);
+        cfg.service(user_find_all);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    let response = get_with_accept!("/users", "image/png", bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::NOT_ACCEPTABLE);
+
+    let problem: Problem = test::read_body_json(response).await;
+    assert_eq!(problem.code, "not_acceptable");
+
+    // NOTE(alex): Anything else is still JSON.
+    let response = get_with_accept!("/users", "*/*", bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let users: Vec<User> = test::read_body_json(response).await;
+    assert_eq!(users[0].username, "spike");
+}
+
+#[actix_rt::test]
+pub async fn test_negotiation_csv_formulas() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_find_all);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    insert_task!(
+        "=HYPERLINK(\"http://example.com\", \"Bebop\")",
+        "@SUM(A1:A2)",
+        bearer_token,
+        cookies,
+        app
+    );
+
+    // NOTE(alex): A spreadsheet shows these as text, instead of running them.
+    let response = get_with_accept!("/tasks", "text/csv", bearer_token, cookies, app);
+    let body = test::read_body(response).await;
+    let body = std::str::from_utf8(&body).unwrap();
+    assert!(body
+        .contains(",\"'=HYPERLINK(\"\"http://example.com\"\", \"\"Bebop\"\")\",\"'@SUM(A1:A2)\","));
+}
+
+#[actix_rt::test]
+pub async fn test_negotiation_etag_per_representation() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_find_by_id);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    let task = insert_task!("Re-watch Cowboy Bebop", "", bearer_token, cookies, app);
+    let uri = format!("/tasks/{}", task.id);
+
+    let response = get_with_accept!(&uri, "application/json", bearer_token, cookies, app);
+    let json_etag = response.headers().get(header::ETAG).unwrap().clone();
+
+    let response = get_with_accept!(&uri, "text/csv", bearer_token, cookies, app);
+    let csv_etag = response.headers().get(header::ETAG).unwrap().clone();
+    assert_eq!(csv_etag, format!("\"{}-csv\"", task.version).as_str());
+    assert_ne!(csv_etag, json_etag);
+
+    // NOTE(alex): The client has the JSON cached, the CSV is still news to it.
+    let request = test::TestRequest::get()
+        .uri(&uri)
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .insert_header((header::ACCEPT, "text/csv"))
+        .insert_header((header::IF_NONE_MATCH, json_etag))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get()
+        .uri(&uri)
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .insert_header((header::ACCEPT, "text/csv"))
+        .insert_header((header::IF_NONE_MATCH, csv_etag))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+    // NOTE(alex): Nothing we could send would match, so it's not a `304`.
+    let request = test::TestRequest::get()
+        .uri(&uri)
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .insert_header((header::ACCEPT, "image/png"))
+        .insert_header((header::IF_NONE_MATCH, "*"))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_ACCEPTABLE);
+}
+
+#[actix_rt::test]
+pub async fn test_negotiation_insert_and_search() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_search);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    // NOTE(alex): Still a `201`, with its `Location`, in whatever representation was asked for.
+    let request = test::TestRequest::post()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .insert_header((header::ACCEPT, "text/csv"))
+        .cookie(cookies.clone())
+        .set_json(&InsertTask {
+            non_empty_title: "Re-watch Cowboy Bebop".to_string(),
+            details: "Good show.".to_string(),
+            priority: Default::default(),
+            due_at: None,
+            recurrence: None,
+        })
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(
+        response.headers().get(header::LOCATION).unwrap(),
+        "/tasks/1"
+    );
+    assert_eq!(
+        response.headers().get(header::CONTENT_TYPE).unwrap(),
+        "text/csv; charset=utf-8"
+    );
+
+    let response = get_with_accept!(
+        "/tasks/search?q=bebop",
+        "text/csv",
+        bearer_token,
+        cookies,
+        app
+    );
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = test::read_body(response).await;
+    let body = std::str::from_utf8(&body).unwrap();
+    let lines = body.split_terminator("\r\n").collect::<Vec<_>>();
+    assert!(lines[0].starts_with("task.id,task.title,"));
+    assert!(lines[1].starts_with("1,Re-watch Cowboy Bebop,Good show.,"));
+    assert!(lines[1].contains(",Re-watch Cowboy <mark>Bebop</mark>,"));
+}
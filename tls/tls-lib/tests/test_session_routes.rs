@@ -0,0 +1,182 @@
+mod common;
+
+use actix_identity::{CookieIdentityPolicy, IdentityService};
+use actix_session::CookieSession;
+use actix_web::{cookie::Cookie, dev::Service, http::StatusCode, test, web::ServiceConfig, App};
+use common::{setup_data, setup_token_config};
+use time::Duration;
+use tls_lib::{
+    sessions::{
+        models::Session,
+        routes::{delete as session_delete, delete_all as session_delete_all, find_all},
+    },
+    users::{
+        models::{InsertUser, LoggedUser, LoginUser, User},
+        routes::{insert as user_insert, login, logout},
+    },
+};
+
+#[actix_rt::test]
+pub async fn test_session_find_all() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(find_all);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    // NOTE(alex): Find all sessions
+    let request = test::TestRequest::get()
+        .uri("/users/me/sessions")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let sessions: Vec<Session> = test::read_body_json(response).await;
+    assert_eq!(sessions.len(), 1);
+}
+
+#[actix_rt::test]
+pub async fn test_session_delete_revokes_token() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(find_all);
+        cfg.service(session_delete);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    let find_all_request = test::TestRequest::get()
+        .uri("/users/me/sessions")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let find_all_response = test::call_service(&mut app, find_all_request).await;
+    assert_eq!(find_all_response.status(), StatusCode::OK);
+
+    let sessions: Vec<Session> = test::read_body_json(find_all_response).await;
+
+    // NOTE(alex): Delete
+    let request = test::TestRequest::delete()
+        .uri(&format!("/users/me/sessions/{}", sessions[0].id))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(test::read_body(response).await.is_empty());
+
+    // NOTE(alex): The token is still signed and not expired, but its session is gone.
+    let request = test::TestRequest::get()
+        .uri("/users/me/sessions")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let error = app.call(request).await.unwrap_err();
+
+    assert_eq!(
+        error.as_response_error().status_code(),
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[actix_rt::test]
+pub async fn test_session_delete_non_existent_session() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(session_delete);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    // NOTE(alex): Delete
+    let request = test::TestRequest::delete()
+        .uri(&format!("/users/me/sessions/{}", 1000))
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+pub async fn test_session_delete_all() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(find_all);
+        cfg.service(session_delete_all);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    // NOTE(alex): Login a second time, so we have another session to revoke.
+    let login_user = LoginUser {
+        username: "spike".to_string(),
+        password: "vicious".to_string(),
+    };
+    let login_request = test::TestRequest::post()
+        .uri("/users/login")
+        .set_json(&login_user)
+        .to_request();
+    let login_response = test::call_service(&mut app, login_request).await;
+    assert!(login_response.status().is_success());
+
+    let other_logged_user: LoggedUser = test::read_body_json(login_response).await;
+    let other_bearer_token = format!("Bearer {}", other_logged_user.token);
+
+    // NOTE(alex): Delete all
+    let request = test::TestRequest::delete()
+        .uri("/users/me/sessions")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(test::read_body(response).await.is_empty());
+
+    let request = test::TestRequest::get()
+        .uri("/users/me/sessions")
+        .insert_header(("Authorization".to_string(), other_bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let error = app.call(request).await.unwrap_err();
+
+    assert_eq!(
+        error.as_response_error().status_code(),
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[actix_rt::test]
+pub async fn test_session_revoked_on_logout() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(find_all);
+        cfg.service(logout);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    // NOTE(alex): Logout
+    let request = test::TestRequest::delete()
+        .uri("/users/logout")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert!(response.status().is_success());
+
+    let request = test::TestRequest::get()
+        .uri("/users/me/sessions")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let error = app.call(request).await.unwrap_err();
+
+    assert_eq!(
+        error.as_response_error().status_code(),
+        StatusCode::UNAUTHORIZED
+    );
+}
@@ -0,0 +1,103 @@
+use std::path::Path;
+
+use tls_lib::{
+    settings::{errors::SettingsError, Settings},
+    validation::PasswordComplexity,
+};
+
+fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+pub fn test_settings_default_is_valid() {
+    assert!(Settings::default().validate().is_ok());
+}
+
+#[test]
+pub fn test_settings_from_example_file() {
+    let path = Path::new(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/settings.example.toml"
+    ));
+
+    let settings = Settings::from_file(path).unwrap().validate().unwrap();
+
+    assert_eq!(settings.database_file, "tls-lib.db");
+    assert!(settings.secure_cookies);
+    // NOTE(alex): Not in the file, so it keeps the default.
+    assert!(settings.token_signing_key.is_none());
+}
+
+#[test]
+pub fn test_settings_environment_overrides() {
+    let settings = Settings::default()
+        .with_overrides(vars(&[
+            ("HELLO_ACTIX_ADDRESS", "0.0.0.0:8443"),
+            ("HELLO_ACTIX_SESSION_MINUTES", "30"),
+            ("HELLO_ACTIX_PASSWORD_COMPLEXITY", "strong"),
+            ("PATH", "/usr/bin"),
+        ]))
+        .unwrap()
+        .validate()
+        .unwrap();
+
+    assert_eq!(settings.address, "0.0.0.0:8443");
+    assert_eq!(settings.session_minutes, 30);
+    assert_eq!(settings.password_complexity, PasswordComplexity::Strong);
+}
+
+#[test]
+pub fn test_settings_unknown_environment_variable() {
+    let result =
+        Settings::default().with_overrides(vars(&[("HELLO_ACTIX_ADRESS", "0.0.0.0:8443")]));
+
+    assert!(matches!(result, Err(SettingsError::UnknownVariable(_))));
+}
+
+#[test]
+pub fn test_settings_invalid_values() {
+    let result = Settings::default().with_overrides(vars(&[("HELLO_ACTIX_LOGIN_MINUTES", "soon")]));
+    assert!(matches!(result, Err(SettingsError::Invalid { .. })));
+
+    let result = Settings::default()
+        .with_overrides(vars(&[("HELLO_ACTIX_COOKIE_KEY", "too short")]))
+        .unwrap()
+        .validate();
+    assert!(matches!(result, Err(SettingsError::Invalid { .. })));
+
+    let result = Settings::default()
+        .with_overrides(vars(&[("HELLO_ACTIX_ADDRESS", "localhost")]))
+        .unwrap()
+        .validate();
+    assert!(matches!(result, Err(SettingsError::Invalid { .. })));
+
+    let result = Settings::default()
+        .with_overrides(vars(&[("HELLO_ACTIX_REQUIRE_CLIENT_CERT", "true")]))
+        .unwrap()
+        .validate();
+    assert!(matches!(result, Err(SettingsError::Invalid { .. })));
+
+    let result =
+        Settings::default().with_overrides(vars(&[("HELLO_ACTIX_USERNAME_CHARSET", "emoji")]));
+    assert!(matches!(result, Err(SettingsError::Invalid { .. })));
+
+    let result = Settings::default()
+        .with_overrides(vars(&[("HELLO_ACTIX_MIN_PASSWORD_LENGTH", "2")]))
+        .unwrap()
+        .validate();
+    assert!(matches!(result, Err(SettingsError::Invalid { .. })));
+
+    // NOTE(alex): 2000 characters of details may take 8000 bytes.
+    let result = Settings::default()
+        .with_overrides(vars(&[("HELLO_ACTIX_MAX_BODY_BYTES", "4056")]))
+        .unwrap()
+        .validate();
+    assert!(matches!(
+        result,
+        Err(SettingsError::Invalid { key, .. }) if key == "max_body_bytes"
+    ));
+}
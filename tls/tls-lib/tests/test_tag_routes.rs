@@ -0,0 +1,306 @@
+mod common;
+
+use actix_identity::{CookieIdentityPolicy, IdentityService};
+use actix_session::CookieSession;
+use actix_web::{cookie::Cookie, http::StatusCode, test, web::ServiceConfig, App};
+use common::{setup_data, setup_token_config};
+use time::Duration;
+use tls_lib::{
+    tags::{
+        models::{InsertTag, Tag, UpdateTag},
+        routes::{
+            delete as tag_delete, find_all as tag_find_all, insert as tag_insert,
+            update as tag_update,
+        },
+    },
+    tasks::{
+        models::{InsertTask, Page, Task},
+        routes::{
+            find_all as task_find_all, find_by_id as task_find_by_id, insert as task_insert,
+            tag as task_tag, untag as task_untag,
+        },
+    },
+    users::{
+        models::{InsertUser, LoggedUser, LoginUser, User},
+        routes::{insert as user_insert, login},
+    },
+};
+
+macro_rules! pre_insert_tag {
+    ($name: expr, $bearer_token: expr, $cookies: expr, $app: expr) => {{
+        let insert_tag = InsertTag {
+            non_empty_name: $name.to_string(),
+        };
+
+        let insert_tag_request = test::TestRequest::post()
+            .uri("/tags")
+            .insert_header(("Authorization".to_string(), $bearer_token.clone()))
+            .cookie($cookies.clone())
+            .set_json(&insert_tag)
+            .to_request();
+        let insert_tag_response = test::call_service(&mut $app, insert_tag_request).await;
+        assert_eq!(insert_tag_response.status(), StatusCode::CREATED);
+
+        let tag: Tag = test::read_body_json(insert_tag_response).await;
+        tag
+    }};
+}
+
+macro_rules! tag_task {
+    ($task_id: expr, $tag: expr, $bearer_token: expr, $cookies: expr, $app: expr) => {{
+        let request = test::TestRequest::post()
+            .uri(&format!("/tasks/{}/tags/{}", $task_id, $tag))
+            .insert_header(("Authorization".to_string(), $bearer_token.clone()))
+            .cookie($cookies.clone())
+            .to_request();
+        test::call_service(&mut $app, request).await
+    }};
+}
+
+#[actix_rt::test]
+pub async fn test_tag_insert_and_find_all() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(tag_insert);
+        cfg.service(tag_find_all);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let _ = pre_insert_tag!("classic", bearer_token, cookies, app);
+    let _ = pre_insert_tag!(" anime ", bearer_token, cookies, app);
+
+    let request = test::TestRequest::get()
+        .uri("/tags")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let tags: Vec<Tag> = test::read_body_json(response).await;
+    let names = tags.iter().map(|tag| tag.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["anime", "classic"]);
+}
+
+#[actix_rt::test]
+pub async fn test_tag_insert_invalid_and_duplicate() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(tag_insert);
+        cfg.service(tag_update);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let _ = pre_insert_tag!("classic", bearer_token, cookies, app);
+    let anime = pre_insert_tag!("anime", bearer_token, cookies, app);
+
+    for (name, status) in [
+        (" \n\t", StatusCode::UNPROCESSABLE_ENTITY),
+        ("anime/classic", StatusCode::UNPROCESSABLE_ENTITY),
+        ("classic", StatusCode::CONFLICT),
+    ]
+    .iter()
+    {
+        let request = test::TestRequest::post()
+            .uri("/tags")
+            .insert_header(("Authorization".to_string(), bearer_token.clone()))
+            .cookie(cookies.clone())
+            .set_json(&InsertTag {
+                non_empty_name: name.to_string(),
+            })
+            .to_request();
+        let response = test::call_service(&mut app, request).await;
+
+        assert_eq!(response.status(), *status);
+    }
+
+    // NOTE(alex): Renaming into a name that is taken fails the same way.
+    let update_tag = UpdateTag {
+        id: anime.id,
+        new_name: "classic".to_string(),
+    };
+    let request = test::TestRequest::put()
+        .uri("/tags")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .set_json(&update_tag)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
+#[actix_rt::test]
+pub async fn test_tag_task_and_untag() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(tag_insert);
+        cfg.service(task_insert);
+        cfg.service(task_find_by_id);
+        cfg.service(task_tag);
+        cfg.service(task_untag);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = insert_task!("Cowboy Bebop", bearer_token, cookies, app);
+    let _ = pre_insert_tag!("classic", bearer_token, cookies, app);
+    let _ = pre_insert_tag!("anime", bearer_token, cookies, app);
+
+    let response = tag_task!(task.id, "classic", bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = tag_task!(task.id, "anime", bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    // NOTE(alex): Again, nothing changes.
+    let response = tag_task!(task.id, "anime", bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = tag_task!(task.id, "western", bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = tag_task!(task.id + 1000, "anime", bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // NOTE(alex): Untag
+    let request = test::TestRequest::delete()
+        .uri(&format!("/tasks/{}/tags/classic", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/tasks/{}", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    let task: Task = test::read_body_json(response).await;
+    assert_eq!(task.tags.0, vec!["anime".to_string()]);
+}
+
+#[actix_rt::test]
+pub async fn test_tag_update_and_delete() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(tag_insert);
+        cfg.service(tag_update);
+        cfg.service(tag_delete);
+        cfg.service(task_insert);
+        cfg.service(task_find_by_id);
+        cfg.service(task_tag);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = insert_task!("Cowboy Bebop", bearer_token, cookies, app);
+    let classic = pre_insert_tag!("classic", bearer_token, cookies, app);
+    let response = tag_task!(task.id, "classic", bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let update_tag = UpdateTag {
+        id: classic.id,
+        new_name: "timeless".to_string(),
+    };
+    let request = test::TestRequest::put()
+        .uri("/tags")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .set_json(&update_tag)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/tasks/{}", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    let found: Task = test::read_body_json(response).await;
+    assert_eq!(found.tags.0, vec!["timeless".to_string()]);
+
+    // NOTE(alex): Delete takes the tag off of the task too.
+    let request = test::TestRequest::delete()
+        .uri(&format!("/tags/{}", classic.id))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/tasks/{}", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    let found: Task = test::read_body_json(response).await;
+    assert!(found.tags.is_empty());
+
+    let request = test::TestRequest::delete()
+        .uri(&format!("/tags/{}", classic.id))
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+pub async fn test_tag_find_tasks_by_tags() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(tag_insert);
+        cfg.service(task_insert);
+        cfg.service(task_find_all);
+        cfg.service(task_tag);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let bebop = insert_task!("Cowboy Bebop", bearer_token, cookies, app);
+    let trigun = insert_task!("Trigun", bearer_token, cookies, app);
+    let _ = insert_task!("Outlaw Star", bearer_token, cookies, app);
+    let _ = pre_insert_tag!("classic", bearer_token, cookies, app);
+    let _ = pre_insert_tag!("western", bearer_token, cookies, app);
+
+    for (task_id, tag_name) in [
+        (bebop.id, "classic"),
+        (bebop.id, "western"),
+        (trigun.id, "western"),
+    ]
+    .iter()
+    {
+        let response = tag_task!(task_id, tag_name, bearer_token, cookies, app);
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+
+    for (query, expected) in [
+        ("tag=western", vec![bebop.id, trigun.id]),
+        ("tag=classic&tag=western", vec![bebop.id, trigun.id]),
+        ("tag=classic&tag=western&tag_mode=all", vec![bebop.id]),
+        (
+            "tag_mode=all&tag=western&sort=-created_at",
+            vec![trigun.id, bebop.id],
+        ),
+    ]
+    .iter()
+    {
+        let request = test::TestRequest::get()
+            .uri(&format!("/tasks?{}", query))
+            .insert_header(("Authorization".to_string(), bearer_token.clone()))
+            .cookie(cookies.clone())
+            .to_request();
+        let response = test::call_service(&mut app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let page: Page<Task> = test::read_body_json(response).await;
+        let ids = page.items.iter().map(|task| task.id).collect::<Vec<_>>();
+        assert_eq!(&ids, expected, "{}", query);
+        assert_eq!(page.total, expected.len() as i64);
+    }
+
+    let request = test::TestRequest::get()
+        .uri("/tasks?tag=classic&tag_mode=some")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
@@ -3,30 +3,48 @@ mod common;
 use actix_identity::{CookieIdentityPolicy, IdentityService};
 use actix_session::CookieSession;
 use actix_web::{
-    cookie::Cookie, http::StatusCode, test, web::ServiceConfig, App,
+    cookie::Cookie,
+    dev::Service,
+    http::{header, StatusCode},
+    test,
+    web::{self, ServiceConfig},
+    App,
 };
-use common::setup_data;
-use time::Duration;
+use common::{setup_data, setup_token_config};
+use serde_json::json;
+use time::{format_description::well_known::Rfc3339, Date, Duration, Month, OffsetDateTime};
 use tls_lib::{
+    errors::Problem,
     tasks::{
-        models::{InsertTask, Task, UpdateTask},
+        models::{
+            InsertTask, Occurrence, Page, PreconditionConfig, Priority, Task, TaskSearchResult,
+            UpdateTask,
+        },
+        recurrence::Recurrence,
         routes::{
             delete as task_delete, done as task_done, favorite, find_all as task_find_all,
-            find_by_id as task_find_by_id, find_by_pattern as task_find_by_pattern, find_favorite,
-            find_ongoing, insert as task_insert, undo as task_undo, update as task_update,
+            find_by_id as task_find_by_id, find_due, find_favorite, find_occurrences, find_ongoing,
+            insert as task_insert, search as task_search, undo as task_undo, update as task_update,
         },
     },
     users::{
         models::{InsertUser, LoggedUser, LoginUser, User},
         routes::{find_by_id as user_find_by_id, insert as user_insert, login},
+        tokens::TokenConfig,
     },
 };
 
 macro_rules! pre_insert_task {
     ($bearer_token: expr, $cookies: expr, $app: expr) => {{
+        pre_insert_task!("Re-watch Cowboy Bebop", $bearer_token, $cookies, $app)
+    }};
+    ($title: expr, $bearer_token: expr, $cookies: expr, $app: expr) => {{
         let insert_task = InsertTask {
-            non_empty_title: "Re-watch Cowboy Bebop".to_string(),
+            non_empty_title: $title.to_string(),
             details: "Good show.".to_string(),
+            priority: Priority::Normal,
+            due_at: None,
+            recurrence: None,
         };
 
         let insert_task_request = test::TestRequest::post()
@@ -50,6 +68,7 @@ pub async fn test_task_insert_valid_task() {
     let data = setup_data().await;
     let app = App::new()
         .app_data(data.clone())
+        .app_data(setup_token_config())
         .service(user_insert)
         .service(user_find_by_id)
         .configure(|cfg| {
@@ -81,7 +100,7 @@ pub async fn test_task_insert_valid_task() {
 
         let login_user = LoginUser {
             username: user.username,
-            password: user.password,
+            password: new_user.valid_password,
         };
         let login_request = test::TestRequest::post()
             .uri("/users/login")
@@ -106,6 +125,9 @@ pub async fn test_task_insert_valid_task() {
     let valid_insert_task = InsertTask {
         non_empty_title: "Re-watch Cowboy Bebop".to_string(),
         details: "Good show.".to_string(),
+        priority: Priority::Normal,
+        due_at: None,
+        recurrence: None,
     };
 
     let request = test::TestRequest::post()
@@ -116,7 +138,11 @@ pub async fn test_task_insert_valid_task() {
         .to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert!(response.status().is_success());
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let location = response.headers().get(header::LOCATION).unwrap().clone();
+
+    let task: Task = test::read_body_json(response).await;
+    assert_eq!(location, format!("/tasks/{}", task.id).as_str());
 }
 
 #[actix_rt::test]
@@ -130,6 +156,9 @@ pub async fn test_task_insert_invalid_task_title() {
     let invalid_insert_task = InsertTask {
         non_empty_title: " \n\t".to_string(),
         details: "Good show.".to_string(),
+        priority: Priority::Normal,
+        due_at: None,
+        recurrence: None,
     };
 
     let request = test::TestRequest::post()
@@ -143,6 +172,134 @@ pub async fn test_task_insert_invalid_task_title() {
     assert!(response.status().is_client_error());
 }
 
+#[actix_rt::test]
+pub async fn test_task_insert_reports_every_invalid_field() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+
+    let invalid_insert_task = InsertTask {
+        non_empty_title: "Re-watch Cowboy Bebop ".repeat(10),
+        details: "Good show.".to_string(),
+        priority: Priority::Normal,
+        due_at: Some((OffsetDateTime::now_utc() - Duration::days(1)).unix_timestamp()),
+        recurrence: Some("fortnightly".to_string()),
+    };
+
+    let request = test::TestRequest::post()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .set_json(&invalid_insert_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let problem: Problem = test::read_body_json(response).await;
+    let fields = problem
+        .errors
+        .iter()
+        .map(|error| error.field.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(fields, vec!["title", "due_at", "recurrence"]);
+    assert_eq!(problem.errors[0].code, "title_length");
+}
+
+#[actix_rt::test]
+pub async fn test_task_insert_with_expired_token() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+    };
+
+    let (app, _, cookies) = setup_app!(configure);
+
+    let user = User {
+        id: 1,
+        username: "spike".to_string(),
+        password_hash: String::new(),
+        certificate_subject: None,
+        deleted_at: None,
+    };
+    let expired_config = TokenConfig::new(b"hello-actix-test-key", Duration::minutes(-10));
+    let (expired_token, _) = expired_config.issue(&user).unwrap();
+
+    let valid_insert_task = InsertTask {
+        non_empty_title: "Re-watch Cowboy Bebop".to_string(),
+        details: "Good show.".to_string(),
+        priority: Priority::Normal,
+        due_at: None,
+        recurrence: None,
+    };
+
+    let request = test::TestRequest::post()
+        .uri("/tasks")
+        .insert_header((
+            "Authorization".to_string(),
+            format!("Bearer {}", expired_token),
+        ))
+        .cookie(cookies)
+        .set_json(&valid_insert_task)
+        .to_request();
+
+    // NOTE(alex): The authentication middleware fails the request before it reaches our route, so
+    // we get an `Err` instead of an error response here.
+    let error = app.call(request).await.unwrap_err();
+
+    assert_eq!(
+        error.as_response_error().status_code(),
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[actix_rt::test]
+pub async fn test_task_insert_with_forged_token() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+    };
+
+    let (app, _, cookies) = setup_app!(configure);
+
+    // NOTE(alex): Same claims we would issue, but signed with a key the server doesn't know.
+    let user = User {
+        id: 1,
+        username: "spike".to_string(),
+        password_hash: String::new(),
+        certificate_subject: None,
+        deleted_at: None,
+    };
+    let forged_config = TokenConfig::new(b"not-the-server-key", Duration::minutes(10));
+    let (forged_token, _) = forged_config.issue(&user).unwrap();
+
+    let valid_insert_task = InsertTask {
+        non_empty_title: "Re-watch Cowboy Bebop".to_string(),
+        details: "Good show.".to_string(),
+        priority: Priority::Normal,
+        due_at: None,
+        recurrence: None,
+    };
+
+    let request = test::TestRequest::post()
+        .uri("/tasks")
+        .insert_header((
+            "Authorization".to_string(),
+            format!("Bearer {}", forged_token),
+        ))
+        .cookie(cookies)
+        .set_json(&valid_insert_task)
+        .to_request();
+
+    // NOTE(alex): The authentication middleware fails the request before it reaches our route, so
+    // we get an `Err` instead of an error response here.
+    let error = app.call(request).await.unwrap_err();
+
+    assert_eq!(
+        error.as_response_error().status_code(),
+        StatusCode::UNAUTHORIZED
+    );
+}
+
 #[actix_rt::test]
 pub async fn test_task_update_valid_task() {
     let configure = |cfg: &mut ServiceConfig| {
@@ -157,6 +314,9 @@ pub async fn test_task_update_valid_task() {
         id: task.id,
         new_title: format!("{}, and Yu Yu Hakusho", task.title),
         details: format!("{} Classic.", task.details),
+        priority: Some(Priority::Normal),
+        due_at: None,
+        recurrence: None,
     };
 
     // NOTE(alex): Update
@@ -185,6 +345,9 @@ pub async fn test_task_update_with_invalid_task_title() {
         id: task.id,
         new_title: " \n\t".to_string(),
         details: format!("{} Classic.", task.details),
+        priority: Some(Priority::Normal),
+        due_at: None,
+        recurrence: None,
     };
 
     // NOTE(alex): Update
@@ -236,109 +399,246 @@ pub async fn test_task_delete_non_existent_task() {
         .to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+macro_rules! etag_of {
+    ($task_id: expr, $bearer_token: expr, $cookies: expr, $app: expr) => {{
+        let request = test::TestRequest::get()
+            .uri(&format!("/tasks/{}", $task_id))
+            .insert_header(("Authorization".to_string(), $bearer_token.clone()))
+            .cookie($cookies.clone())
+            .to_request();
+        let response = test::call_service(&mut $app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        response
+            .headers()
+            .get(header::ETAG)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string()
+    }};
 }
 
 #[actix_rt::test]
-pub async fn test_task_mark_as_done() {
+pub async fn test_task_find_by_id_etag() {
     let configure = |cfg: &mut ServiceConfig| {
         cfg.service(task_insert);
+        cfg.service(task_find_by_id);
         cfg.service(task_done);
     };
 
     let (mut app, bearer_token, cookies) = setup_app!(configure);
     let task = pre_insert_task!(bearer_token, cookies, app);
 
-    // NOTE(alex): Done
+    let etag = etag_of!(task.id, bearer_token, cookies, app);
+    assert_eq!(etag, format!("\"{}\"", task.version));
+
+    for if_none_match in [etag.as_str(), "*", "\"0\", W/\"1\""].iter() {
+        let request = test::TestRequest::get()
+            .uri(&format!("/tasks/{}", task.id))
+            .insert_header(("Authorization".to_string(), bearer_token.clone()))
+            .insert_header((header::IF_NONE_MATCH, *if_none_match))
+            .cookie(cookies.clone())
+            .to_request();
+        let response = test::call_service(&mut app, request).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers().get(header::ETAG).unwrap(), etag.as_str());
+    }
+
+    // NOTE(alex): Marking it as done changes the task, even if it happens in another table.
     let request = test::TestRequest::post()
         .uri(&format!("/tasks/{}/done", task.id))
-        .insert_header(("Authorization".to_string(), bearer_token))
-        .cookie(cookies)
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
         .to_request();
     let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
 
-    assert!(response.status().is_success());
+    let request = test::TestRequest::get()
+        .uri(&format!("/tasks/{}", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .insert_header((header::IF_NONE_MATCH, etag.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_ne!(response.headers().get(header::ETAG).unwrap(), etag.as_str());
 }
 
 #[actix_rt::test]
-pub async fn test_task_undo() {
+pub async fn test_task_update_if_match() {
     let configure = |cfg: &mut ServiceConfig| {
         cfg.service(task_insert);
-        cfg.service(task_done);
-        cfg.service(task_undo);
+        cfg.service(task_update);
+        cfg.service(task_find_by_id);
     };
 
     let (mut app, bearer_token, cookies) = setup_app!(configure);
     let task = pre_insert_task!(bearer_token, cookies, app);
+    let etag = etag_of!(task.id, bearer_token, cookies, app);
 
-    // NOTE(alex): Done
-    let task_done_request = test::TestRequest::post()
-        .uri(&format!("/tasks/{}/done", task.id))
+    let update_task = UpdateTask {
+        id: task.id,
+        new_title: "Re-watch Trigun".to_string(),
+        details: task.details.clone(),
+        priority: Some(task.priority),
+        due_at: None,
+        recurrence: None,
+    };
+
+    let request = test::TestRequest::put()
+        .uri("/tasks")
         .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .insert_header((header::IF_MATCH, etag.clone()))
         .cookie(cookies.clone())
+        .set_json(&update_task)
         .to_request();
-    let task_done_response = test::call_service(&mut app, task_done_request).await;
-    assert!(task_done_response.status().is_success());
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let new_etag = response.headers().get(header::ETAG).unwrap().clone();
+    assert_ne!(new_etag, etag.as_str());
+    assert_eq!(
+        new_etag,
+        etag_of!(task.id, bearer_token, cookies, app).as_str()
+    );
+
+    // NOTE(alex): The old `ETag` is stale now.
+    let request = test::TestRequest::put()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .insert_header((header::IF_MATCH, etag.clone()))
+        .cookie(cookies.clone())
+        .set_json(&update_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+
+    let request = test::TestRequest::put()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .insert_header((header::IF_MATCH, "*"))
+        .cookie(cookies.clone())
+        .set_json(&update_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[actix_rt::test]
+pub async fn test_task_delete_if_match() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_delete);
+        cfg.service(task_find_by_id);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = pre_insert_task!(bearer_token, cookies, app);
+    let etag = etag_of!(task.id, bearer_token, cookies, app);
 
-    // NOTE(alex): Undo
     let request = test::TestRequest::delete()
-        .uri(&format!("/tasks/{}/undo", task.id))
-        .insert_header(("Authorization".to_string(), bearer_token))
-        .cookie(cookies)
+        .uri(&format!("/tasks/{}", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .insert_header((header::IF_MATCH, "\"1000\""))
+        .cookie(cookies.clone())
         .to_request();
     let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
 
-    assert!(response.status().is_success());
+    let request = test::TestRequest::delete()
+        .uri(&format!("/tasks/{}", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .insert_header((header::IF_MATCH, etag))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
 }
 
 #[actix_rt::test]
-pub async fn test_task_undo_when_no_tasks_are_done() {
+pub async fn test_task_if_match_required() {
     let configure = |cfg: &mut ServiceConfig| {
+        cfg.app_data(web::Data::new(PreconditionConfig { required: true }));
         cfg.service(task_insert);
-        cfg.service(task_undo);
+        cfg.service(task_update);
+        cfg.service(task_delete);
+        cfg.service(task_find_by_id);
     };
 
     let (mut app, bearer_token, cookies) = setup_app!(configure);
     let task = pre_insert_task!(bearer_token, cookies, app);
 
-    // NOTE(alex): Undo
+    let update_task = UpdateTask {
+        id: task.id,
+        new_title: "Re-watch Trigun".to_string(),
+        details: task.details.clone(),
+        priority: Some(task.priority),
+        due_at: None,
+        recurrence: None,
+    };
+
+    let request = test::TestRequest::put()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .set_json(&update_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_REQUIRED);
+
     let request = test::TestRequest::delete()
-        .uri(&format!("/tasks/{}/undo", task.id))
-        .insert_header(("Authorization".to_string(), bearer_token))
-        .cookie(cookies)
+        .uri(&format!("/tasks/{}", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
         .to_request();
     let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_REQUIRED);
 
-    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    let etag = etag_of!(task.id, bearer_token, cookies, app);
+    let request = test::TestRequest::delete()
+        .uri(&format!("/tasks/{}", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .insert_header((header::IF_MATCH, etag))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
 }
 
 #[actix_rt::test]
-pub async fn test_task_find_all() {
+pub async fn test_task_mark_as_done() {
     let configure = |cfg: &mut ServiceConfig| {
         cfg.service(task_insert);
-        cfg.service(task_find_all);
+        cfg.service(task_done);
     };
 
     let (mut app, bearer_token, cookies) = setup_app!(configure);
-    let _ = pre_insert_task!(bearer_token, cookies, app);
+    let task = pre_insert_task!(bearer_token, cookies, app);
 
-    // NOTE(alex): Find all
-    let request = test::TestRequest::get().uri("/tasks").to_request();
+    // NOTE(alex): Done
+    let request = test::TestRequest::post()
+        .uri(&format!("/tasks/{}/done", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert_eq!(response.status(), StatusCode::FOUND);
+    assert!(response.status().is_success());
 }
 
 #[actix_rt::test]
-pub async fn test_task_ongoing_tasks() {
+pub async fn test_task_undo() {
     let configure = |cfg: &mut ServiceConfig| {
         cfg.service(task_insert);
         cfg.service(task_done);
-        cfg.service(find_ongoing);
+        cfg.service(task_undo);
     };
 
     let (mut app, bearer_token, cookies) = setup_app!(configure);
-    let _ = pre_insert_task!(bearer_token, cookies, app);
     let task = pre_insert_task!(bearer_token, cookies, app);
 
     // NOTE(alex): Done
@@ -350,83 +650,837 @@ pub async fn test_task_ongoing_tasks() {
     let task_done_response = test::call_service(&mut app, task_done_request).await;
     assert!(task_done_response.status().is_success());
 
-    // NOTE(alex): Find ongoing tasks only
-    let request = test::TestRequest::get().uri("/tasks/ongoing").to_request();
+    // NOTE(alex): Undo
+    let request = test::TestRequest::delete()
+        .uri(&format!("/tasks/{}/undo", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert_eq!(response.status(), StatusCode::FOUND);
+    assert!(response.status().is_success());
 }
 
 #[actix_rt::test]
-pub async fn test_task_find_by_pattern() {
+pub async fn test_task_undo_when_no_tasks_are_done() {
     let configure = |cfg: &mut ServiceConfig| {
         cfg.service(task_insert);
-        cfg.service(task_find_by_pattern);
+        cfg.service(task_undo);
     };
 
     let (mut app, bearer_token, cookies) = setup_app!(configure);
-    let _ = pre_insert_task!(bearer_token, cookies, app);
+    let task = pre_insert_task!(bearer_token, cookies, app);
 
-    let title_pattern = "?title=Watch&details=.";
-    // NOTE(alex): Find tasks with title pattern
-    let request = test::TestRequest::get()
-        .uri(&format!("/tasks{}", title_pattern))
+    // NOTE(alex): Undo
+    let request = test::TestRequest::delete()
+        .uri(&format!("/tasks/{}/undo", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
         .to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
 }
 
 #[actix_rt::test]
-pub async fn test_task_find_by_id() {
+pub async fn test_task_find_all() {
     let configure = |cfg: &mut ServiceConfig| {
         cfg.service(task_insert);
-        cfg.service(task_find_by_id);
+        cfg.service(task_find_all);
     };
 
     let (mut app, bearer_token, cookies) = setup_app!(configure);
-    let task = pre_insert_task!(bearer_token, cookies, app);
+    let _ = pre_insert_task!(bearer_token, cookies, app);
 
-    // NOTE(alex): Find with id
+    // NOTE(alex): Find all
     let request = test::TestRequest::get()
-        .uri(&format!("/tasks/{}", task.id))
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
         .to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let page: Page<Task> = test::read_body_json(response).await;
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.total, 1);
+    assert!(page.next_cursor.is_none());
 }
 
 #[actix_rt::test]
-pub async fn test_task_favorite() {
+pub async fn test_task_find_all_empty() {
     let configure = |cfg: &mut ServiceConfig| {
-        cfg.service(task_insert);
-        cfg.service(favorite);
+        cfg.service(task_find_all);
+        cfg.service(find_ongoing);
     };
 
     let (mut app, bearer_token, cookies) = setup_app!(configure);
-    let task = pre_insert_task!(bearer_token, cookies, app);
 
-    // NOTE(alex): Favorite
-    let request = test::TestRequest::post()
-        .uri(&format!("/tasks/favorite/{}", task.id))
+    // NOTE(alex): Nothing to list is still a list.
+    let request = test::TestRequest::get()
+        .uri("/tasks")
         .insert_header(("Authorization".to_string(), bearer_token.clone()))
         .cookie(cookies.clone())
         .to_request();
     let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let page: Page<Task> = test::read_body_json(response).await;
+    assert!(page.items.is_empty());
+    assert_eq!(page.total, 0);
+
+    let request = test::TestRequest::get()
+        .uri("/tasks/ongoing")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
 
-    assert_eq!(response.status(), StatusCode::FOUND);
+    let tasks: Vec<Task> = test::read_body_json(response).await;
+    assert!(tasks.is_empty());
 }
 
 #[actix_rt::test]
-pub async fn test_task_find_favorite() {
+pub async fn test_task_find_all_pages() {
     let configure = |cfg: &mut ServiceConfig| {
         cfg.service(task_insert);
-        cfg.service(favorite);
-        cfg.service(find_favorite);
+        cfg.service(task_find_all);
     };
 
     let (mut app, bearer_token, cookies) = setup_app!(configure);
-    let task = pre_insert_task!(bearer_token, cookies, app);
+    for title in [
+        "Cowboy Bebop",
+        "Trigun",
+        "Outlaw Star",
+        "Samurai Champloo",
+        "Planetes",
+    ] {
+        let _ = pre_insert_task!(title, bearer_token, cookies, app);
+    }
+
+    // NOTE(alex): Follows `next_cursor` until the last page.
+    let mut titles = Vec::new();
+    let mut uri = "/tasks?limit=2&sort=title".to_string();
+    loop {
+        let request = test::TestRequest::get()
+            .uri(&uri)
+            .insert_header(("Authorization".to_string(), bearer_token.clone()))
+            .cookie(cookies.clone())
+            .to_request();
+        let response = test::call_service(&mut app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let page: Page<Task> = test::read_body_json(response).await;
+        assert_eq!(page.total, 5);
+        assert!(page.items.len() <= 2);
+        titles.extend(page.items.into_iter().map(|task| task.title));
+
+        match page.next_cursor {
+            Some(next_cursor) => {
+                uri = format!("/tasks?limit=2&sort=title&after_id={}", next_cursor)
+            }
+            None => break,
+        }
+    }
+
+    assert_eq!(
+        titles,
+        vec![
+            "Cowboy Bebop",
+            "Outlaw Star",
+            "Planetes",
+            "Samurai Champloo",
+            "Trigun"
+        ]
+    );
+}
+
+#[actix_rt::test]
+pub async fn test_task_find_all_sort_and_status() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_done);
+        cfg.service(task_find_all);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let first = pre_insert_task!("Cowboy Bebop", bearer_token, cookies, app);
+    let second = pre_insert_task!("Trigun", bearer_token, cookies, app);
+    let third = pre_insert_task!("Outlaw Star", bearer_token, cookies, app);
+
+    let task_done_request = test::TestRequest::post()
+        .uri(&format!("/tasks/{}/done", second.id))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let task_done_response = test::call_service(&mut app, task_done_request).await;
+    assert!(task_done_response.status().is_success());
+
+    let request = test::TestRequest::get()
+        .uri("/tasks?sort=-created_at&status=ongoing")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let page: Page<Task> = test::read_body_json(response).await;
+    let ids = page.items.iter().map(|task| task.id).collect::<Vec<_>>();
+    assert_eq!(ids, vec![third.id, first.id]);
+    assert_eq!(page.total, 2);
+
+    let request = test::TestRequest::get()
+        .uri("/tasks?status=done")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    let page: Page<Task> = test::read_body_json(response).await;
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.items[0].id, second.id);
+}
+
+#[actix_rt::test]
+pub async fn test_task_find_all_invalid_query() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_find_all);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let _ = pre_insert_task!(bearer_token, cookies, app);
+
+    let request = test::TestRequest::get()
+        .uri("/tasks?limit=0")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    // NOTE(alex): Not an empty page, the client would take it for the end of the list.
+    let request = test::TestRequest::get()
+        .uri("/tasks?limit=2&after_id=1000")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let problem: Problem = test::read_body_json(response).await;
+    assert_eq!(problem.code, "unknown_cursor");
+
+    let request = test::TestRequest::get()
+        .uri("/tasks?sort=details")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_rt::test]
+pub async fn test_task_schedule() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_find_all);
+        cfg.service(find_due);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let low = pre_insert_task!("Cowboy Bebop", bearer_token, cookies, app);
+
+    // NOTE(alex): Raw JSON, to check that dates go in (and come out) as RFC 3339.
+    let due_at = (OffsetDateTime::now_utc() + Duration::days(2))
+        .replace_nanosecond(0)
+        .unwrap();
+    let request = test::TestRequest::post()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .set_json(&serde_json::json!({
+            "non_empty_title": "Trigun",
+            "details": "Love and peace.",
+            "priority": "urgent",
+            "due_at": due_at.format(&Rfc3339).unwrap(),
+        }))
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert!(response.status().is_success());
+
+    let urgent: serde_json::Value = test::read_body_json(response).await;
+    assert_eq!(urgent["priority"], "urgent");
+    assert_eq!(urgent["due_at"], due_at.format(&Rfc3339).unwrap());
+    assert!(OffsetDateTime::parse(urgent["created_at"].as_str().unwrap(), &Rfc3339).is_ok());
+    assert_eq!(urgent["created_at"], urgent["updated_at"]);
+    assert!(urgent["completed_at"].is_null());
+
+    let request = test::TestRequest::get()
+        .uri("/tasks?sort=-priority")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    let page: Page<Task> = test::read_body_json(response).await;
+    assert_eq!(page.items[0].priority, Priority::Urgent);
+    assert_eq!(page.items[0].due_at, Some(due_at.unix_timestamp()));
+    assert_eq!(page.items[1].id, low.id);
+
+    let request = test::TestRequest::get()
+        .uri("/tasks/due?days=3")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let tasks: Vec<Task> = test::read_body_json(response).await;
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].title, "Trigun");
+
+    let request = test::TestRequest::get()
+        .uri("/tasks/due?days=1")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let tasks: Vec<Task> = test::read_body_json(response).await;
+    assert!(tasks.is_empty());
+
+    let request = test::TestRequest::get()
+        .uri("/tasks/due?days=0")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[actix_rt::test]
+pub async fn test_task_due_in_past() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_update);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = pre_insert_task!(bearer_token, cookies, app);
+    let yesterday = (OffsetDateTime::now_utc() - Duration::days(1)).unix_timestamp();
+
+    let insert_task = InsertTask {
+        non_empty_title: "Re-watch Cowboy Bebop".to_string(),
+        details: "Good show.".to_string(),
+        priority: Priority::High,
+        due_at: Some(yesterday),
+        recurrence: None,
+    };
+    let request = test::TestRequest::post()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .set_json(&insert_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let update_task = UpdateTask {
+        id: task.id,
+        new_title: task.title,
+        details: task.details,
+        priority: Some(Priority::High),
+        due_at: Some(Some(yesterday)),
+        recurrence: None,
+    };
+    let request = test::TestRequest::put()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .set_json(&update_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[actix_rt::test]
+pub async fn test_task_update_overdue() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_update);
+        cfg.service(task_find_by_id);
+    };
+
+    let data = setup_data().await;
+    let (mut app, bearer_token, cookies) = setup_app!(configure, data.clone());
+    let task = pre_insert_task!(bearer_token, cookies, app);
+    let yesterday = (OffsetDateTime::now_utc() - Duration::days(1)).unix_timestamp();
+
+    sqlx::query("update Task set due_at = $1, priority = 2 where id = $2")
+        .bind(yesterday)
+        .bind(task.id)
+        .execute(data.get_ref())
+        .await
+        .unwrap();
+
+    // NOTE(alex): The fields that are left out keep their values, even the `due_at` in the past.
+    let update_task = json!({
+        "id": task.id,
+        "new_title": "Re-watch Trigun",
+        "details": task.details,
+    });
+    let request = test::TestRequest::put()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .set_json(&update_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert!(response.status().is_success());
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/tasks/{}", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    let updated: Task = test::read_body_json(response).await;
+    assert_eq!(updated.title, "Re-watch Trigun");
+    assert_eq!(updated.priority, Priority::High);
+    assert_eq!(updated.due_at, Some(yesterday));
+
+    // NOTE(alex): Moving it somewhere else in the past is still refused, clearing it is fine.
+    let update_task = json!({
+        "id": task.id,
+        "new_title": "Re-watch Trigun",
+        "details": task.details,
+        "due_at": "2001-09-11T00:00:00Z",
+    });
+    let request = test::TestRequest::put()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .set_json(&update_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let problem: Problem = test::read_body_json(response).await;
+    assert_eq!(problem.code, "due_in_past");
+
+    let update_task = json!({
+        "id": task.id,
+        "new_title": "Re-watch Trigun",
+        "details": task.details,
+        "due_at": null,
+    });
+    let request = test::TestRequest::put()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .set_json(&update_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert!(response.status().is_success());
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/tasks/{}", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    let updated: Task = test::read_body_json(response).await;
+    assert_eq!(updated.due_at, None);
+}
+
+// NOTE(alex): The routes refuse a `due_at` in the past, so the overdue tasks are written straight
+// into the database.
+#[actix_rt::test]
+pub async fn test_task_find_overdue() {
+    let data = setup_data().await;
+    let db_pool = data.get_ref();
+    let now = OffsetDateTime::now_utc();
+
+    sqlx::query("insert into User (username, password_hash) values ('spike', '')")
+        .execute(db_pool)
+        .await
+        .unwrap();
+
+    let schedule = [
+        ("Cowboy Bebop", Some(now - Duration::days(1))),
+        ("Trigun", Some(now - Duration::days(3))),
+        ("Outlaw Star", Some(now + Duration::days(1))),
+        ("Yu Yu Hakusho", None),
+        ("Samurai Champloo", Some(now - Duration::days(2))),
+    ];
+    for (title, due_at) in schedule.iter() {
+        sqlx::query("insert into Task (title, details, owner_id, due_at) values ($1, '', 1, $2)")
+            .bind(*title)
+            .bind(due_at.map(|due_at| due_at.unix_timestamp()))
+            .execute(db_pool)
+            .await
+            .unwrap();
+    }
+    sqlx::query("insert into Done (task_id, completed_at) values (5, $1)")
+        .bind(now.unix_timestamp())
+        .execute(db_pool)
+        .await
+        .unwrap();
+
+    let overdue = Task::find_overdue(db_pool, 1).await.unwrap();
+    let titles = overdue
+        .iter()
+        .map(|task| task.title.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(titles, vec!["Trigun", "Cowboy Bebop"]);
+
+    let done = Task::find_by_id(db_pool, 5, 1).await.unwrap().unwrap();
+    assert_eq!(done.completed_at, Some(now.unix_timestamp()));
+}
+
+macro_rules! search {
+    ($query: expr, $bearer_token: expr, $cookies: expr, $app: expr) => {{
+        let request = test::TestRequest::get()
+            .uri(&format!("/tasks/search?q={}", $query))
+            .insert_header(("Authorization".to_string(), $bearer_token.clone()))
+            .cookie($cookies.clone())
+            .to_request();
+        test::call_service(&mut $app, request).await
+    }};
+}
+
+#[actix_rt::test]
+pub async fn test_task_search() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_update);
+        cfg.service(task_delete);
+        cfg.service(task_search);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let bebop = pre_insert_task!("Re-watch Cowboy Bebop", bearer_token, cookies, app);
+    let trigun = pre_insert_task!("Watch Trigun", bearer_token, cookies, app);
+    let _ = pre_insert_task!("Read the Cowboy Bebop manga", bearer_token, cookies, app);
+
+    // NOTE(alex): Matches `details` too ("Good show."), but the title match ranks first.
+    let response = search!("bebop", bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::OK);
+    let results: Vec<TaskSearchResult> = test::read_body_json(response).await;
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].task.id, bebop.id);
+    assert_eq!(
+        results[0].title_highlight,
+        "Re-watch Cowboy <mark>Bebop</mark>"
+    );
+
+    // NOTE(alex): Phrase, the words must be next to each other.
+    let response = search!("%22manga%20bebop%22", bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::OK);
+    let results: Vec<TaskSearchResult> = test::read_body_json(response).await;
+    assert!(results.is_empty());
+
+    let response = search!("%22the%20cowboy%22", bearer_token, cookies, app);
+    let results: Vec<TaskSearchResult> = test::read_body_json(response).await;
+    assert_eq!(results.len(), 1);
+
+    // NOTE(alex): Prefix.
+    let response = search!("trig*", bearer_token, cookies, app);
+    let results: Vec<TaskSearchResult> = test::read_body_json(response).await;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].task.id, trigun.id);
+
+    // NOTE(alex): The index follows updates, and deletes.
+    let update_task = UpdateTask {
+        id: trigun.id,
+        new_title: "Watch Samurai Champloo".to_string(),
+        details: "Also by Watanabe.".to_string(),
+        priority: Some(Priority::Normal),
+        due_at: None,
+        recurrence: None,
+    };
+    let request = test::TestRequest::put()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .set_json(&update_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert!(response.status().is_success());
+
+    let response = search!("trigun", bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = search!("watanabe", bearer_token, cookies, app);
+    let results: Vec<TaskSearchResult> = test::read_body_json(response).await;
+    assert_eq!(results[0].details_snippet, "Also by <mark>Watanabe</mark>.");
+
+    // NOTE(alex): Only the `<mark>`s are HTML, what the user wrote is escaped.
+    let _ = pre_insert_task!("<b>Watch</b> Planetes & ROD", bearer_token, cookies, app);
+    let response = search!("planetes", bearer_token, cookies, app);
+    let results: Vec<TaskSearchResult> = test::read_body_json(response).await;
+    assert_eq!(
+        results[0].title_highlight,
+        "&lt;b&gt;Watch&lt;/b&gt; <mark>Planetes</mark> &amp; ROD"
+    );
+
+    let request = test::TestRequest::delete()
+        .uri(&format!("/tasks/{}", bebop.id))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert!(response.status().is_success());
+
+    let response = search!("bebop", bearer_token, cookies, app);
+    let results: Vec<TaskSearchResult> = test::read_body_json(response).await;
+    assert_eq!(results.len(), 1);
+}
+
+#[actix_rt::test]
+pub async fn test_task_search_invalid_query() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_search);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let _ = pre_insert_task!(bearer_token, cookies, app);
+
+    let response = search!("%22%20%22", bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    // NOTE(alex): FTS5 syntax is taken literally, instead of failing the query.
+    let response = search!("bebop%20AND%20NEAR(", bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[actix_rt::test]
+pub async fn test_task_ongoing_tasks() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_done);
+        cfg.service(find_ongoing);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let _ = pre_insert_task!(bearer_token, cookies, app);
+    let task = pre_insert_task!(bearer_token, cookies, app);
+
+    // NOTE(alex): Done
+    let task_done_request = test::TestRequest::post()
+        .uri(&format!("/tasks/{}/done", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let task_done_response = test::call_service(&mut app, task_done_request).await;
+    assert!(task_done_response.status().is_success());
+
+    // NOTE(alex): Find ongoing tasks only
+    let request = test::TestRequest::get()
+        .uri("/tasks/ongoing")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[actix_rt::test]
+pub async fn test_task_find_by_pattern() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_find_all);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let _ = pre_insert_task!(bearer_token, cookies, app);
+    let _ = pre_insert_task!("Read 100%_done", bearer_token, cookies, app);
+
+    // NOTE(alex): Find tasks with title pattern
+    let request = test::TestRequest::get()
+        .uri("/tasks?q=watch")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let page: Page<Task> = test::read_body_json(response).await;
+    assert_eq!(page.total, 1);
+
+    // NOTE(alex): `%` and `_` are matched literally, not as wildcards.
+    let request = test::TestRequest::get()
+        .uri("/tasks?q=%25_")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    let page: Page<Task> = test::read_body_json(response).await;
+    assert_eq!(page.total, 1);
+    assert_eq!(page.items[0].title, "Read 100%_done");
+}
+
+#[actix_rt::test]
+pub async fn test_task_find_by_id() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_find_by_id);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = pre_insert_task!(bearer_token, cookies, app);
+
+    // NOTE(alex): Find with id
+    let request = test::TestRequest::get()
+        .uri(&format!("/tasks/{}", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[actix_rt::test]
+pub async fn test_task_find_by_id_of_another_user() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_find_by_id);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = pre_insert_task!(bearer_token, cookies, app);
+
+    let (other_cookies, other_bearer_token) = register_and_login!(app, "jet", "black");
+
+    // NOTE(alex): Find with id, as a user that doesn't own the task
+    let request = test::TestRequest::get()
+        .uri(&format!("/tasks/{}", task.id))
+        .insert_header(("Authorization".to_string(), other_bearer_token))
+        .cookie(other_cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+pub async fn test_task_update_of_another_user() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_update);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = pre_insert_task!(bearer_token, cookies, app);
+
+    let (other_cookies, other_bearer_token) = register_and_login!(app, "jet", "black");
+
+    let update_task = UpdateTask {
+        id: task.id,
+        new_title: "Feed Ein".to_string(),
+        details: "Not a good show.".to_string(),
+        priority: Some(Priority::Normal),
+        due_at: None,
+        recurrence: None,
+    };
+
+    // NOTE(alex): Update, as a user that doesn't own the task
+    let request = test::TestRequest::put()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), other_bearer_token))
+        .cookie(other_cookies)
+        .set_json(&update_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+pub async fn test_task_delete_of_another_user() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_delete);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = pre_insert_task!(bearer_token, cookies, app);
+
+    let (other_cookies, other_bearer_token) = register_and_login!(app, "jet", "black");
+
+    // NOTE(alex): Delete, as a user that doesn't own the task
+    let request = test::TestRequest::delete()
+        .uri(&format!("/tasks/{}", task.id))
+        .insert_header(("Authorization".to_string(), other_bearer_token))
+        .cookie(other_cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+pub async fn test_task_mark_as_done_of_another_user() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_done);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = pre_insert_task!(bearer_token, cookies, app);
+
+    let (other_cookies, other_bearer_token) = register_and_login!(app, "jet", "black");
+
+    // NOTE(alex): Done, as a user that doesn't own the task
+    let request = test::TestRequest::post()
+        .uri(&format!("/tasks/{}/done", task.id))
+        .insert_header(("Authorization".to_string(), other_bearer_token))
+        .cookie(other_cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+pub async fn test_task_favorite() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(favorite);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = pre_insert_task!(bearer_token, cookies, app);
+
+    // NOTE(alex): Favorite
+    let request = test::TestRequest::post()
+        .uri(&format!("/tasks/favorite/{}", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[actix_rt::test]
+pub async fn test_task_find_favorite() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(favorite);
+        cfg.service(find_favorite);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = pre_insert_task!(bearer_token, cookies, app);
 
     // NOTE(alex): Favorite
     let task_favorite_request = test::TestRequest::post()
@@ -435,7 +1489,7 @@ pub async fn test_task_find_favorite() {
         .cookie(cookies.clone())
         .to_request();
     let task_favorite_response = test::call_service(&mut app, task_favorite_request).await;
-    assert_eq!(task_favorite_response.status(), StatusCode::FOUND);
+    assert_eq!(task_favorite_response.status(), StatusCode::OK);
 
     // NOTE(alex): Retrieve the session cookies to insert them into the find favorite request.
     let session_cookies = task_favorite_response.response().cookies();
@@ -451,5 +1505,295 @@ pub async fn test_task_find_favorite() {
         .to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[actix_rt::test]
+pub async fn test_task_recurrence_rules() {
+    let at = |year, month, day| {
+        Date::from_calendar_date(year, month, day)
+            .unwrap()
+            .with_hms(9, 30, 0)
+            .unwrap()
+            .assume_utc()
+    };
+
+    let rule: Recurrence = "rrule:freq=weekly;interval=2;byday=fr,mo,fr"
+        .parse()
+        .unwrap();
+    assert_eq!(rule.to_string(), "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,FR");
+    assert_eq!(
+        rule.occurrences(at(2024, Month::January, 1), 3),
+        vec![
+            at(2024, Month::January, 5),
+            at(2024, Month::January, 15),
+            at(2024, Month::January, 19),
+        ]
+    );
+
+    // NOTE(alex): February (and April) have no 31st.
+    let rule: Recurrence = "FREQ=MONTHLY".parse().unwrap();
+    assert_eq!(
+        rule.occurrences(at(2024, Month::January, 31), 2),
+        vec![at(2024, Month::March, 31), at(2024, Month::May, 31)]
+    );
+
+    let rule: Recurrence = "FREQ=DAILY;INTERVAL=3".parse().unwrap();
+    assert_eq!(
+        rule.next_after(at(2024, Month::February, 28)),
+        Some(at(2024, Month::March, 2))
+    );
+
+    for invalid in [
+        "",
+        "INTERVAL=2",
+        "FREQ=YEARLY",
+        "FREQ=DAILY;INTERVAL=0",
+        "FREQ=DAILY;FREQ=WEEKLY",
+        "FREQ=WEEKLY;BYDAY=1MO",
+        "FREQ=WEEKLY;COUNT=3",
+    ]
+    .iter()
+    {
+        assert!(invalid.parse::<Recurrence>().is_err(), "{}", invalid);
+    }
+}
+
+#[actix_rt::test]
+pub async fn test_task_done_recurring() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_find_by_id);
+        cfg.service(task_done);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let due_at = (OffsetDateTime::now_utc() + Duration::days(2)).unix_timestamp();
+
+    let insert_task = InsertTask {
+        non_empty_title: "Feed Ein".to_string(),
+        details: "Every week.".to_string(),
+        priority: Priority::High,
+        due_at: Some(due_at),
+        recurrence: Some("freq=weekly".to_string()),
+    };
+    let request = test::TestRequest::post()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .set_json(&insert_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert!(response.status().is_success());
+
+    let task: Task = test::read_body_json(response).await;
+    assert_eq!(task.recurrence.as_deref(), Some("FREQ=WEEKLY;INTERVAL=1"));
+
+    let request = test::TestRequest::post()
+        .uri(&format!("/tasks/{}/done", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let location = response
+        .headers()
+        .get("Location")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let request = test::TestRequest::get()
+        .uri(&location)
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let next: Task = test::read_body_json(response).await;
+    assert_ne!(next.id, task.id);
+    assert_eq!(next.title, task.title);
+    assert_eq!(next.priority, Priority::High);
+    assert_eq!(
+        next.due_at,
+        Some(due_at + Duration::weeks(1).whole_seconds())
+    );
+    assert_eq!(next.recurrence, task.recurrence);
+    assert!(next.completed_at.is_none());
+
+    // NOTE(alex): Done again, it's refused instead of creating another occurrence.
+    let request = test::TestRequest::post()
+        .uri(&format!("/tasks/{}/done", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let problem: Problem = test::read_body_json(response).await;
+    assert_eq!(problem.code, "task_already_done");
+}
+
+#[actix_rt::test]
+pub async fn test_task_done_undo_done_recurring() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_find_all);
+        cfg.service(task_done);
+        cfg.service(task_undo);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let due_at = (OffsetDateTime::now_utc() + Duration::days(2)).unix_timestamp();
+
+    let insert_task = InsertTask {
+        non_empty_title: "Feed Ein".to_string(),
+        details: "Every week.".to_string(),
+        priority: Priority::High,
+        due_at: Some(due_at),
+        recurrence: Some("freq=weekly".to_string()),
+    };
+    let request = test::TestRequest::post()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .set_json(&insert_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    let task: Task = test::read_body_json(response).await;
+
+    let mut statuses = Vec::new();
+    for _ in 0..2 {
+        let request = test::TestRequest::post()
+            .uri(&format!("/tasks/{}/done", task.id))
+            .insert_header(("Authorization".to_string(), bearer_token.clone()))
+            .cookie(cookies.clone())
+            .to_request();
+        let response = test::call_service(&mut app, request).await;
+        statuses.push(response.status());
+
+        let request = test::TestRequest::delete()
+            .uri(&format!("/tasks/{}/undo", task.id))
+            .insert_header(("Authorization".to_string(), bearer_token.clone()))
+            .cookie(cookies.clone())
+            .to_request();
+        let response = test::call_service(&mut app, request).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+
+    // NOTE(alex): Done the second time, the occurrence from the first is already there.
+    assert_eq!(statuses, vec![StatusCode::CREATED, StatusCode::NO_CONTENT]);
+
+    let request = test::TestRequest::get()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    let page: Page<Task> = test::read_body_json(response).await;
+    assert_eq!(page.total, 2);
+}
+
+#[actix_rt::test]
+pub async fn test_task_invalid_recurrence() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let tomorrow = (OffsetDateTime::now_utc() + Duration::days(1)).unix_timestamp();
+
+    for (due_at, recurrence) in [
+        (Some(tomorrow), "FREQ=HOURLY"),
+        (Some(tomorrow), "FREQ=WEEKLY;BYDAY=XX"),
+        (None, "FREQ=DAILY"),
+    ]
+    .iter()
+    {
+        let insert_task = InsertTask {
+            non_empty_title: "Feed Ein".to_string(),
+            details: "".to_string(),
+            priority: Priority::Normal,
+            due_at: *due_at,
+            recurrence: Some(recurrence.to_string()),
+        };
+        let request = test::TestRequest::post()
+            .uri("/tasks")
+            .insert_header(("Authorization".to_string(), bearer_token.clone()))
+            .cookie(cookies.clone())
+            .set_json(&insert_task)
+            .to_request();
+        let response = test::call_service(&mut app, request).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+}
+
+#[actix_rt::test]
+pub async fn test_task_find_occurrences() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(find_occurrences);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let plain_task = pre_insert_task!(bearer_token, cookies, app);
+    let due_at = (OffsetDateTime::now_utc() + Duration::days(1)).unix_timestamp();
+
+    let insert_task = InsertTask {
+        non_empty_title: "Feed Ein".to_string(),
+        details: "".to_string(),
+        priority: Priority::Normal,
+        due_at: Some(due_at),
+        recurrence: Some("FREQ=DAILY;INTERVAL=2".to_string()),
+    };
+    let request = test::TestRequest::post()
+        .uri("/tasks")
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .set_json(&insert_task)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    let task: Task = test::read_body_json(response).await;
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/tasks/{}/occurrences?count=3", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let occurrences: Vec<Occurrence> = test::read_body_json(response).await;
+    let due_dates = occurrences
+        .iter()
+        .map(|occurrence| occurrence.due_at)
+        .collect::<Vec<_>>();
+    let two_days = Duration::days(2).whole_seconds();
+    assert_eq!(
+        due_dates,
+        vec![
+            due_at + two_days,
+            due_at + 2 * two_days,
+            due_at + 3 * two_days
+        ]
+    );
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/tasks/{}/occurrences?count=0", task.id))
+        .insert_header(("Authorization".to_string(), bearer_token.clone()))
+        .cookie(cookies.clone())
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let request = test::TestRequest::get()
+        .uri(&format!("/tasks/{}/occurrences", plain_task.id))
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
 }
@@ -0,0 +1,244 @@
+mod common;
+
+use actix_identity::{CookieIdentityPolicy, IdentityService};
+use actix_session::CookieSession;
+use actix_web::{cookie::Cookie, dev::Service, http::StatusCode, test, web::ServiceConfig, App};
+use common::{setup_data, setup_token_config};
+use time::Duration;
+use tls_lib::{
+    tasks::{
+        models::{InsertTask, Page, Task},
+        routes::{
+            delete as task_delete, done as task_done, find_all as task_find_all,
+            find_by_id as task_find_by_id, insert as task_insert, restore as task_restore,
+        },
+    },
+    trash::{models::Trash, routes::find_all as trash_find_all},
+    users::{
+        models::{InsertUser, LoggedUser, LoginUser, User},
+        routes::{delete as user_delete, insert as user_insert, login, restore as user_restore},
+    },
+};
+
+#[actix_rt::test]
+pub async fn test_trash_task_delete_and_restore() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_delete);
+        cfg.service(task_restore);
+        cfg.service(task_find_by_id);
+        cfg.service(trash_find_all);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let task = insert_task!(bearer_token, cookies, app);
+    let task_uri = format!("/tasks/{}", task.id);
+    let restore_uri = format!("/tasks/{}/restore", task.id);
+
+    let response = call!(delete, task_uri, bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = call!(delete, task_uri, bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = call!(get, task_uri, bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = call!(get, "/trash", bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let trash: Trash = test::read_body_json(response).await;
+    assert_eq!(trash.tasks.len(), 1);
+    assert_eq!(trash.tasks[0].id, task.id);
+    assert!(trash.tasks[0].deleted_at.is_some());
+    assert!(trash.users.is_empty());
+
+    let response = call!(post, restore_uri, bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = call!(post, restore_uri, bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = call!(get, task_uri, bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let found: Task = test::read_body_json(response).await;
+    assert!(found.deleted_at.is_none());
+}
+
+#[actix_rt::test]
+pub async fn test_trash_hides_trashed_tasks() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_delete);
+        cfg.service(task_done);
+        cfg.service(task_find_all);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let trashed = insert_task!(bearer_token, cookies, app);
+    let kept = insert_task!(bearer_token, cookies, app);
+
+    let response = call!(
+        delete,
+        format!("/tasks/{}", trashed.id),
+        bearer_token,
+        cookies,
+        app
+    );
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = call!(get, "/tasks", bearer_token, cookies, app);
+    let page: Page<Task> = test::read_body_json(response).await;
+    assert_eq!(page.total, 1);
+    assert_eq!(page.items[0].id, kept.id);
+
+    let response = call!(
+        post,
+        format!("/tasks/{}/done", trashed.id),
+        bearer_token,
+        cookies,
+        app
+    );
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+pub async fn test_trash_purge() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(task_insert);
+        cfg.service(task_delete);
+        cfg.service(task_done);
+        cfg.service(trash_find_all);
+    };
+
+    let data = setup_data().await;
+    let (mut app, bearer_token, cookies) = setup_app!(configure, data.clone());
+    let old = insert_task!(bearer_token, cookies, app);
+    let recent = insert_task!(bearer_token, cookies, app);
+
+    for task in [&old, &recent].iter() {
+        let response = call!(
+            post,
+            format!("/tasks/{}/done", task.id),
+            bearer_token,
+            cookies,
+            app
+        );
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let response = call!(
+            delete,
+            format!("/tasks/{}", task.id),
+            bearer_token,
+            cookies,
+            app
+        );
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+
+    let long_ago = (time::OffsetDateTime::now_utc() - Duration::days(31)).unix_timestamp();
+    sqlx::query("update Task set deleted_at = $1 where id = $2")
+        .bind(long_ago)
+        .bind(old.id)
+        .execute(data.get_ref())
+        .await
+        .unwrap();
+
+    let purged = tls_lib::trash::models::purge(data.get_ref(), 30)
+        .await
+        .unwrap();
+    assert_eq!(purged, (1, 0));
+
+    let response = call!(get, "/trash", bearer_token, cookies, app);
+    let trash: Trash = test::read_body_json(response).await;
+    let ids = trash.tasks.iter().map(|task| task.id).collect::<Vec<_>>();
+    assert_eq!(ids, vec![recent.id]);
+
+    // NOTE(alex): The cascade took its `Done` with it.
+    let (num_done,): (i64,) = sqlx::query_as("select count(*) from Done where task_id = $1")
+        .bind(old.id)
+        .fetch_one(data.get_ref())
+        .await
+        .unwrap();
+    assert_eq!(num_done, 0);
+}
+
+#[actix_rt::test]
+pub async fn test_trash_user_restore() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(user_delete);
+        cfg.service(user_restore);
+        cfg.service(trash_find_all);
+    };
+
+    let data = setup_data().await;
+    let (mut app, bearer_token, cookies) = setup_app!(configure, data.clone());
+    let (_, other_bearer_token) = register_and_login!(app, "jet", "black");
+
+    let response = call!(get, "/trash", other_bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let (jet_id,): (i64,) = sqlx::query_as("select id from User where username = 'jet'")
+        .fetch_one(data.get_ref())
+        .await
+        .unwrap();
+
+    let response = call!(
+        delete,
+        format!("/users/{}", jet_id),
+        bearer_token,
+        cookies,
+        app
+    );
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    // NOTE(alex): Trashed users can't use their tokens, or login again.
+    let request = test::TestRequest::get()
+        .uri("/trash")
+        .insert_header(("Authorization".to_string(), other_bearer_token))
+        .cookie(cookies.clone())
+        .to_request();
+    let error = app.call(request).await.unwrap_err();
+    assert_eq!(
+        error.as_response_error().status_code(),
+        StatusCode::UNAUTHORIZED
+    );
+
+    let login_request = test::TestRequest::post()
+        .uri("/users/login")
+        .set_json(&LoginUser {
+            username: "jet".to_string(),
+            password: "black".to_string(),
+        })
+        .to_request();
+    let response = test::call_service(&mut app, login_request).await;
+    assert!(!response.status().is_success());
+
+    let restore_uri = format!("/users/{}/restore", jet_id);
+    let response = call!(post, restore_uri, bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    sqlx::query("update User set is_admin = 1 where username = 'spike'")
+        .execute(data.get_ref())
+        .await
+        .unwrap();
+
+    let response = call!(get, "/trash", bearer_token, cookies, app);
+    let trash: Trash = test::read_body_json(response).await;
+    assert_eq!(trash.users.len(), 1);
+    assert_eq!(trash.users[0].username, "jet");
+
+    let response = call!(post, restore_uri, bearer_token, cookies, app);
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let login_request = test::TestRequest::post()
+        .uri("/users/login")
+        .set_json(&LoginUser {
+            username: "jet".to_string(),
+            password: "black".to_string(),
+        })
+        .to_request();
+    let response = test::call_service(&mut app, login_request).await;
+    assert!(response.status().is_success());
+}
@@ -2,15 +2,25 @@ mod common;
 
 use actix_identity::{CookieIdentityPolicy, IdentityService};
 use actix_session::CookieSession;
-use actix_web::{cookie::Cookie, http::StatusCode, test, web::ServiceConfig, App};
-use common::setup_data;
+use actix_web::{
+    cookie::Cookie,
+    http::{header, StatusCode},
+    test,
+    web::ServiceConfig,
+    App,
+};
+use common::{setup_data, setup_token_config};
 use time::Duration;
-use tls_lib::users::{
-    models::{InsertUser, LoggedUser, LoginUser, UpdateUser, User},
-    routes::{
-        delete as user_delete, find_all as user_find_all, find_by_id as user_find_by_id,
-        insert as user_insert, login, logout, update as user_update,
+use tls_lib::{
+    errors::Problem,
+    users::{
+        models::{InsertUser, LoggedUser, LoginUser, UpdateUser, User},
+        routes::{
+            delete as user_delete, find_all as user_find_all, find_by_id as user_find_by_id,
+            insert as user_insert, login, logout, update as user_update,
+        },
     },
+    validation::{PasswordComplexity, UsernameCharset, ValidationConfig},
 };
 
 macro_rules! pre_insert_user {
@@ -51,7 +61,14 @@ pub async fn test_user_insert_valid_user() {
         .to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert!(response.status().is_success());
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let location = response.headers().get(header::LOCATION).unwrap().clone();
+
+    // NOTE(alex): The password hash must never leave the server.
+    let body: serde_json::Value = test::read_body_json(response).await;
+    assert!(body.get("password").is_none());
+    assert!(body.get("password_hash").is_none());
+    assert_eq!(location, format!("/users/{}", body["id"]).as_str());
 }
 
 #[actix_rt::test]
@@ -76,6 +93,118 @@ pub async fn test_user_insert_invalid_username() {
     assert!(response.status().is_client_error());
 }
 
+#[actix_rt::test]
+pub async fn test_user_insert_reports_every_invalid_field() {
+    let data = setup_data().await;
+    let app = App::new().app_data(data.clone()).configure(|cfg| {
+        cfg.service(user_insert);
+    });
+    let mut app = test::init_service(app).await;
+
+    let invalid_insert_user = InsertUser {
+        valid_username: "yu suke".to_string(),
+        valid_password: "".to_string(),
+    };
+
+    let request = test::TestRequest::post()
+        .uri("/users/register")
+        .set_json(&invalid_insert_user)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let problem: Problem = test::read_body_json(response).await;
+    assert_eq!(problem.code, "validation_failed");
+    let codes = problem
+        .errors
+        .iter()
+        .map(|error| (error.field.as_str(), error.code.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        codes,
+        vec![
+            ("username", "invalid_username_character"),
+            ("password", "empty_password")
+        ]
+    );
+}
+
+#[actix_rt::test]
+pub async fn test_user_insert_with_stricter_rules() {
+    let data = setup_data().await;
+    let validation_config = ValidationConfig {
+        username_charset: UsernameCharset::Ascii,
+        min_password_length: 8,
+        password_complexity: PasswordComplexity::Strong,
+        ..Default::default()
+    };
+    let app = App::new()
+        .app_data(data.clone())
+        .app_data(actix_web::web::Data::new(validation_config))
+        .configure(|cfg| {
+            cfg.service(user_insert);
+        });
+    let mut app = test::init_service(app).await;
+
+    let weak_insert_user = InsertUser {
+        valid_username: "yusuke!".to_string(),
+        valid_password: "toguro-brothers".to_string(),
+    };
+
+    let request = test::TestRequest::post()
+        .uri("/users/register")
+        .set_json(&weak_insert_user)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let problem: Problem = test::read_body_json(response).await;
+    let codes = problem
+        .errors
+        .iter()
+        .map(|error| error.code.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(codes, vec!["invalid_username_character", "weak_password"]);
+
+    let strong_insert_user = InsertUser {
+        valid_username: "yusuke".to_string(),
+        valid_password: "Toguro-100".to_string(),
+    };
+
+    let request = test::TestRequest::post()
+        .uri("/users/register")
+        .set_json(&strong_insert_user)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert!(response.status().is_success());
+}
+
+#[actix_rt::test]
+pub async fn test_user_insert_taken_username() {
+    let data = setup_data().await;
+    let app = App::new().app_data(data.clone()).configure(|cfg| {
+        cfg.service(user_insert);
+    });
+    let mut app = test::init_service(app).await;
+
+    pre_insert_user!(app);
+
+    let insert_user = InsertUser {
+        valid_username: "yusuke".to_string(),
+        valid_password: "sensui".to_string(),
+    };
+    let request = test::TestRequest::post()
+        .uri("/users/register")
+        .set_json(&insert_user)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let problem: Problem = test::read_body_json(response).await;
+    assert_eq!(problem.code, "username_taken");
+    assert_eq!(problem.errors[0].field, "username");
+}
+
 #[actix_rt::test]
 pub async fn test_user_update_valid_user() {
     let configure = |cfg: &mut ServiceConfig| {
@@ -89,7 +218,7 @@ pub async fn test_user_update_valid_user() {
     let update_user = UpdateUser {
         id: user.id,
         valid_username: format!("{}_urameshi", user.username),
-        valid_password: format!("{}_young", user.password),
+        valid_password: "toguro_young".to_string(),
     };
 
     // NOTE(alex): Update
@@ -117,7 +246,7 @@ pub async fn test_user_update_with_invalid_username() {
     let update_user = UpdateUser {
         id: user.id,
         valid_username: " \n\t".to_string(),
-        valid_password: format!("{}_young", user.password),
+        valid_password: "toguro_young".to_string(),
     };
 
     // NOTE(alex): Update
@@ -132,6 +261,36 @@ pub async fn test_user_update_with_invalid_username() {
     assert!(response.status().is_client_error());
 }
 
+#[actix_rt::test]
+pub async fn test_user_update_to_taken_username() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(user_insert);
+        cfg.service(user_update);
+    };
+
+    let (mut app, bearer_token, cookies) = setup_app!(configure);
+    let user = pre_insert_user!(app);
+
+    // NOTE(alex): `spike` is the user `setup_app` registered.
+    let update_user = UpdateUser {
+        id: user.id,
+        valid_username: "spike".to_string(),
+        valid_password: "toguro_young".to_string(),
+    };
+
+    let request = test::TestRequest::put()
+        .uri("/users")
+        .insert_header(("Authorization".to_string(), bearer_token))
+        .cookie(cookies)
+        .set_json(&update_user)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let problem: Problem = test::read_body_json(response).await;
+    assert_eq!(problem.code, "username_taken");
+}
+
 #[actix_rt::test]
 pub async fn test_user_delete_existing_user() {
     let configure = |cfg: &mut ServiceConfig| {
@@ -171,7 +330,7 @@ pub async fn test_user_delete_non_existant_user() {
         .to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
 }
 
 #[actix_rt::test]
@@ -188,7 +347,7 @@ pub async fn test_user_find_all() {
     let request = test::TestRequest::get().uri("/users").to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(response.status(), StatusCode::OK);
 }
 
 #[actix_rt::test]
@@ -207,7 +366,7 @@ pub async fn test_user_find_by_id() {
         .to_request();
     let response = test::call_service(&mut app, request).await;
 
-    assert_eq!(response.status(), StatusCode::FOUND);
+    assert_eq!(response.status(), StatusCode::OK);
 }
 
 #[actix_rt::test]
@@ -220,16 +379,46 @@ pub async fn test_user_login() {
     let (mut app, _, _) = setup_app!(configure);
     let user = pre_insert_user!(app);
 
+    let login_user = LoginUser {
+        username: user.username,
+        password: "toguro".to_string(),
+    };
+
     // NOTE(alex): Login
     let request = test::TestRequest::post()
         .uri("/users/login")
-        .set_json(&user)
+        .set_json(&login_user)
         .to_request();
     let response = test::call_service(&mut app, request).await;
 
     assert!(response.status().is_success());
 }
 
+#[actix_rt::test]
+pub async fn test_user_login_with_wrong_password() {
+    let configure = |cfg: &mut ServiceConfig| {
+        cfg.service(user_insert);
+        cfg.service(login);
+    };
+
+    let (mut app, _, _) = setup_app!(configure);
+    let user = pre_insert_user!(app);
+
+    let login_user = LoginUser {
+        username: user.username,
+        password: "sensui".to_string(),
+    };
+
+    // NOTE(alex): Login
+    let request = test::TestRequest::post()
+        .uri("/users/login")
+        .set_json(&login_user)
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+
+    assert!(response.status().is_client_error());
+}
+
 #[actix_rt::test]
 pub async fn test_user_logout() {
     let configure = |cfg: &mut ServiceConfig| {
@@ -241,6 +430,7 @@ pub async fn test_user_logout() {
     let data = setup_data().await;
     let app = App::new()
         .app_data(data.clone())
+        .app_data(setup_token_config())
         .configure(configure)
         .wrap(IdentityService::new(
             CookieIdentityPolicy::new(&[0; 32])
@@ -265,7 +455,7 @@ pub async fn test_user_logout() {
 
     let login_user = LoginUser {
         username: user.username,
-        password: user.password,
+        password: new_user.valid_password,
     };
     let login_request = test::TestRequest::post()
         .uri("/users/login")